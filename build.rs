@@ -0,0 +1,307 @@
+//! Build script generating `ClashClass` from the dataset description with the
+//! `codegen-classes` feature, so that the class names, colors and groups of the model are
+//! declared once in `dataset.yaml` instead of being edited by hand in several files
+
+fn main() {
+    #[cfg(feature = "codegen-classes")]
+    if let Err(e) = codegen::generate() {
+        panic!("Failed to generate ClashClass: {e}");
+    }
+}
+
+#[cfg(feature = "codegen-classes")]
+mod codegen {
+    use serde_yaml_ng::Value;
+    use std::fmt::Write as _;
+    use std::path::PathBuf;
+    use std::{env, fs};
+
+    /// Environment variable pointing to another dataset description than `dataset.yaml`
+    const DATASET_ENV: &str = "CLASHVISION_DATASET";
+
+    /// Groups of `ClassGroup`, as written in the `groups` map
+    const GROUPS: [&str; 5] = ["buildings", "resources", "defenses", "troops", "spells"];
+
+    /// Class described by the dataset
+    struct Class {
+        variant: String,
+        name: String,
+        color: (u8, u8, u8, u8),
+        group: String,
+        footprint: u32,
+    }
+
+    /// Writes `clash_class.rs` into `OUT_DIR` from the dataset description
+    pub fn generate() -> Result<(), String> {
+        println!("cargo:rerun-if-env-changed={DATASET_ENV}");
+        let path = env::var_os(DATASET_ENV).map_or_else(
+            || PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap()).join("dataset.yaml"),
+            PathBuf::from,
+        );
+        println!("cargo:rerun-if-changed={}", path.display());
+
+        let yaml = fs::read_to_string(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+        let dataset: Value = serde_yaml_ng::from_str(&yaml).map_err(|e| e.to_string())?;
+        let classes = parse_classes(&dataset)?;
+
+        let out = PathBuf::from(env::var("OUT_DIR").unwrap()).join("clash_class.rs");
+        fs::write(out, render(&classes, &path.display().to_string())).map_err(|e| e.to_string())
+    }
+
+    /// Reads the classes from the `names` list or map and the optional per-class maps
+    fn parse_classes(dataset: &Value) -> Result<Vec<Class>, String> {
+        let names: Vec<String> = match dataset.get("names") {
+            Some(Value::Sequence(names)) => names.iter().map(to_text).collect::<Result<_, _>>()?,
+            Some(Value::Mapping(names)) => {
+                let mut names: Vec<(usize, String)> = names
+                    .iter()
+                    .map(|(id, name)| Ok((class_id(id)?, to_text(name)?)))
+                    .collect::<Result<_, String>>()?;
+                names.sort_by_key(|(id, _)| *id);
+                for (expected, (id, _)) in names.iter().enumerate() {
+                    if *id != expected {
+                        return Err(format!(
+                            "class ids must be contiguous from 0, missing {expected}"
+                        ));
+                    }
+                }
+                names.into_iter().map(|(_, name)| name).collect()
+            }
+            _ => return Err("missing `names` list or map".to_string()),
+        };
+
+        let per_class = |key: &str| -> Result<Vec<(usize, Value)>, String> {
+            match dataset.get(key) {
+                Some(Value::Mapping(map)) => map
+                    .iter()
+                    .map(|(id, value)| Ok((class_id(id)?, value.clone())))
+                    .collect(),
+                None | Some(Value::Null) => Ok(Vec::new()),
+                Some(_) => Err(format!("`{key}` must map class ids to values")),
+            }
+        };
+        let lookup = |entries: &[(usize, Value)], id: usize| {
+            entries
+                .iter()
+                .find(|(key, _)| *key == id)
+                .map(|(_, value)| value.clone())
+        };
+        let (colors, groups, footprints) = (
+            per_class("colors")?,
+            per_class("groups")?,
+            per_class("footprints")?,
+        );
+
+        let mut classes = Vec::with_capacity(names.len());
+        for (id, name) in names.into_iter().enumerate() {
+            let variant = variant_name(&name)?;
+            if classes.iter().any(|class: &Class| class.variant == variant) {
+                return Err(format!("two classes are named {variant}"));
+            }
+            let color = match lookup(&colors, id) {
+                Some(color) => to_color(&color)?,
+                None => distinct_color(id),
+            };
+            let group = match lookup(&groups, id) {
+                Some(group) => to_text(&group)?.to_lowercase(),
+                None => "buildings".to_string(),
+            };
+            if !GROUPS.contains(&group.as_str()) {
+                return Err(format!("unknown group {group} of {name}"));
+            }
+            let footprint = match lookup(&footprints, id) {
+                Some(footprint) => footprint
+                    .as_u64()
+                    .and_then(|side| u32::try_from(side).ok())
+                    .ok_or_else(|| format!("invalid footprint of {name}"))?,
+                None => 3,
+            };
+            classes.push(Class {
+                variant,
+                name,
+                color,
+                group,
+                footprint,
+            });
+        }
+        if classes.is_empty() {
+            return Err("the dataset has no classes".to_string());
+        }
+        Ok(classes)
+    }
+
+    /// Renders the enum and its data methods, completed by the hand-written ones
+    fn render(classes: &[Class], source: &str) -> String {
+        let mut code = format!("// Generated by build.rs from {source}; do not edit.\n\n");
+        code.push_str("/// This file is part of a Clash of Clans related project.\n");
+        code.push_str(
+            "#[derive(PartialEq, Eq)]\n#[must_use]\n#[repr(u8)]\npub enum ClashClass {\n",
+        );
+        for (id, class) in classes.iter().enumerate() {
+            let _ = writeln!(code, "    {} = {id},", class.variant);
+        }
+        code.push_str("}\n\nimpl ClashClass {\n");
+
+        let arms = |value: &dyn Fn(&Class) -> String| {
+            classes.iter().fold(String::new(), |mut arms, class| {
+                let _ = writeln!(
+                    arms,
+                    "            Self::{} => {},",
+                    class.variant,
+                    value(class)
+                );
+                arms
+            })
+        };
+        let method = |code: &mut String, doc: &str, signature: &str, arms: String| {
+            let _ = write!(
+                code,
+                "    /// {doc}\n    #[inline]\n    #[must_use]\n    pub const fn {signature} {{\n        match self {{\n{arms}        }}\n    }}\n\n"
+            );
+        };
+        method(
+            &mut code,
+            "Returns the string representation of the `ClashClass` variant.",
+            "as_str(&self) -> &'static str",
+            arms(&|class| format!("{:?}", class.name)),
+        );
+        method(
+            &mut code,
+            "Returns the RGB color associated with the `ClashClass` variant.",
+            "to_rgba(&self) -> (u8, u8, u8, u8)",
+            arms(&|class| format!("{:?}", class.color)),
+        );
+        method(
+            &mut code,
+            "Returns the class group of the `ClashClass` variant.",
+            "group(&self) -> ClassGroup",
+            arms(&|class| format!("ClassGroup::{}", variant_name(&class.group).unwrap())),
+        );
+        method(
+            &mut code,
+            "Returns the side of the square footprint of the `ClashClass` variant, in tiles.",
+            "footprint(&self) -> u32",
+            arms(&|class| class.footprint.to_string()),
+        );
+
+        let count = classes.len();
+        let variants: Vec<String> = classes
+            .iter()
+            .map(|class| format!("ClashClass::{}", class.variant))
+            .collect();
+        let colors: Vec<String> = classes
+            .iter()
+            .map(|class| format!("{:?}", class.color))
+            .collect();
+        let (variants, colors) = (variants.join(", "), colors.join(", "));
+        let _ = write!(
+            code,
+            "    /// Returns a static slice of all `ClashClass` variants.
+    pub fn values() -> &'static [Self] {{
+        static VALUES: [ClashClass; {count}] = [{variants}];
+        &VALUES
+    }}
+
+    /// Returns a static slice of RGB colors corresponding to the `ClashClass` variants.
+    #[must_use]
+    pub fn rgb_colors() -> &'static [(u8, u8, u8, u8)] {{
+        static COLORS: [(u8, u8, u8, u8); {count}] = [{colors}];
+        &COLORS
+    }}
+}}
+
+impl TryFrom<usize> for ClashClass {{
+    type Error = ();
+
+    fn try_from(value: usize) -> Result<Self, Self::Error> {{
+        match value {{
+"
+        );
+        for (id, class) in classes.iter().enumerate() {
+            let _ = writeln!(code, "            {id} => Ok(Self::{}),", class.variant);
+        }
+        code.push_str("            _ => Err(()),\n        }\n    }\n}\n");
+        code
+    }
+
+    /// Returns the `PascalCase` variant of a class name, e.g. `GoldStorage` for `Gold Storage`
+    /// or `gold_storage`
+    fn variant_name(name: &str) -> Result<String, String> {
+        let variant: String = name
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(|word| {
+                let mut chars = word.chars();
+                chars.next().map_or_else(String::new, |first| {
+                    first.to_ascii_uppercase().to_string() + chars.as_str()
+                })
+            })
+            .collect();
+        match variant.chars().next() {
+            Some(first) if first.is_ascii_alphabetic() => Ok(variant),
+            _ => Err(format!("class name {name:?} does not start with a letter")),
+        }
+    }
+
+    fn class_id(key: &Value) -> Result<usize, String> {
+        match key {
+            Value::Number(id) => id.as_u64().and_then(|id| usize::try_from(id).ok()),
+            Value::String(id) => id.trim().parse().ok(),
+            _ => None,
+        }
+        .ok_or_else(|| format!("invalid class id {key:?}"))
+    }
+
+    fn to_text(value: &Value) -> Result<String, String> {
+        match value {
+            Value::String(text) => Ok(text.clone()),
+            Value::Number(number) => Ok(number.to_string()),
+            _ => Err(format!("expected a string, got {value:?}")),
+        }
+    }
+
+    /// Parses `[r, g, b]`, `[r, g, b, a]` or `"#rrggbb"`
+    fn to_color(value: &Value) -> Result<(u8, u8, u8, u8), String> {
+        let invalid = || format!("invalid color {value:?}");
+        let channels: Vec<u8> = match value {
+            Value::Sequence(channels) => channels
+                .iter()
+                .map(|channel| channel.as_u64().and_then(|c| u8::try_from(c).ok()))
+                .collect::<Option<_>>()
+                .ok_or_else(invalid)?,
+            Value::String(hex) => {
+                let hex = hex.trim_start_matches('#');
+                (0..hex.len())
+                    .step_by(2)
+                    .map(|i| {
+                        hex.get(i..i + 2)
+                            .and_then(|c| u8::from_str_radix(c, 16).ok())
+                    })
+                    .collect::<Option<_>>()
+                    .ok_or_else(invalid)?
+            }
+            _ => return Err(invalid()),
+        };
+        match channels[..] {
+            [r, g, b] => Ok((r, g, b, 255)),
+            [r, g, b, a] => Ok((r, g, b, a)),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Color of a class without one in the dataset, hues spread by the golden angle
+    fn distinct_color(id: usize) -> (u8, u8, u8, u8) {
+        let hue = (id as f32 * 137.508) % 360.0;
+        let x = 1.0 - ((hue / 60.0) % 2.0 - 1.0).abs();
+        let (r, g, b) = match (hue / 60.0) as u32 {
+            0 => (1.0, x, 0.0),
+            1 => (x, 1.0, 0.0),
+            2 => (0.0, 1.0, x),
+            3 => (0.0, x, 1.0),
+            4 => (x, 0.0, 1.0),
+            _ => (1.0, 0.0, x),
+        };
+        let channel = |c: f32| (35.0 + c * 185.0) as u8;
+        (channel(r), channel(g), channel(b), 255)
+    }
+}
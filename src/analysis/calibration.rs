@@ -0,0 +1,196 @@
+//! Keypoint-free calibration of the tile grid, estimated from detections of known-size buildings
+
+use crate::analysis::device_profile::DeviceProfile;
+use crate::class::clash_class::ClashClass;
+use crate::detection::BoundingBox;
+use std::collections::BTreeMap;
+use std::f32::consts::TAU;
+
+/// Calibration key of the tile size, as a fraction of the image width
+pub const TILE_SIZE_KEY: &str = "tile_size";
+/// Calibration key of the horizontal grid offset, as a fraction of the image width
+pub const OFFSET_X_KEY: &str = "tile_offset_x";
+/// Calibration key of the vertical grid offset, as a fraction of the image height
+pub const OFFSET_Y_KEY: &str = "tile_offset_y";
+
+/// Configuration of the calibration
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalibrationConfig {
+    pub footprints: BTreeMap<usize, u32>, // Footprint side in tiles by class id
+    pub min_samples: usize,               // Known-size boxes required for an estimate
+}
+
+impl Default for CalibrationConfig {
+    fn default() -> Self {
+        Self {
+            footprints: ClashClass::values()
+                .iter()
+                .enumerate()
+                .map(|(class_id, class)| (class_id, class.footprint()))
+                .collect(),
+            min_samples: 1,
+        }
+    }
+}
+
+/// Box expressed in tiles of the calibrated grid
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TileBox {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// Scale and offset mapping image pixels to tiles
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Calibration {
+    pub pixels_per_tile: f32,
+    pub offset: (f32, f32),
+}
+
+impl Calibration {
+    /// Estimates the calibration from the boxes of classes with a known footprint.
+    ///
+    /// The scale is the median of box width over footprint, which ignores the odd mis-sized box.
+    /// Buildings sit on the grid, so the offset is the circular mean of the left and bottom
+    /// edges modulo the tile size. Returns `None` below `min_samples` known-size boxes.
+    #[must_use]
+    pub fn estimate(boxes: &[BoundingBox], config: &CalibrationConfig) -> Option<Self> {
+        let known: Vec<(&BoundingBox, f32)> = boxes
+            .iter()
+            .filter_map(|bbox| {
+                let footprint = *config.footprints.get(&bbox.class_id)?;
+                (footprint > 0).then(|| (bbox, bbox.dimensions().0 / footprint as f32))
+            })
+            .collect();
+        if known.is_empty() || known.len() < config.min_samples {
+            return None;
+        }
+
+        let mut scales: Vec<f32> = known.iter().map(|&(_, scale)| scale).collect();
+        scales.sort_by(f32::total_cmp);
+        let middle = scales.len() / 2;
+        let pixels_per_tile = if scales.len().is_multiple_of(2) {
+            (scales[middle - 1] + scales[middle]) / 2.0
+        } else {
+            scales[middle]
+        };
+        if pixels_per_tile <= 0.0 {
+            return None;
+        }
+
+        let offset_x = circular_mean(known.iter().map(|(bbox, _)| bbox.x1), pixels_per_tile);
+        let offset_y = circular_mean(known.iter().map(|(bbox, _)| bbox.y2), pixels_per_tile);
+        Some(Self {
+            pixels_per_tile,
+            offset: (offset_x, offset_y),
+        })
+    }
+
+    /// Reads the calibration stored in a profile, scaled to an image of `image_size`
+    #[must_use]
+    pub fn from_profile(profile: &DeviceProfile, image_size: (u32, u32)) -> Option<Self> {
+        let (width, height) = (image_size.0 as f32, image_size.1 as f32);
+        Some(Self {
+            pixels_per_tile: profile.calibration(TILE_SIZE_KEY)? * width,
+            offset: (
+                profile.calibration(OFFSET_X_KEY).unwrap_or(0.0) * width,
+                profile.calibration(OFFSET_Y_KEY).unwrap_or(0.0) * height,
+            ),
+        })
+    }
+
+    /// Stores the calibration in a profile, normalized by the `image_size` it was estimated on
+    pub fn store(&self, profile: &mut DeviceProfile, image_size: (u32, u32)) {
+        let (width, height) = (image_size.0.max(1) as f32, image_size.1.max(1) as f32);
+        profile
+            .calibration
+            .insert(TILE_SIZE_KEY.to_string(), self.pixels_per_tile / width);
+        profile
+            .calibration
+            .insert(OFFSET_X_KEY.to_string(), self.offset.0 / width);
+        profile
+            .calibration
+            .insert(OFFSET_Y_KEY.to_string(), self.offset.1 / height);
+    }
+
+    /// Converts a box to approximate tile coordinates and dimensions
+    #[must_use]
+    pub fn to_tiles(&self, bbox: &BoundingBox) -> TileBox {
+        let (width, height) = bbox.dimensions();
+        TileBox {
+            x: (bbox.x1 - self.offset.0) / self.pixels_per_tile,
+            y: (bbox.y1 - self.offset.1) / self.pixels_per_tile,
+            width: width / self.pixels_per_tile,
+            height: height / self.pixels_per_tile,
+        }
+    }
+}
+
+/// Mean of values on a circle of circumference `period`, in `[0, period)`
+fn circular_mean(values: impl Iterator<Item = f32>, period: f32) -> f32 {
+    let (sin, cos) = values.fold((0.0f32, 0.0f32), |(sin, cos), value| {
+        let angle = value.rem_euclid(period) / period * TAU;
+        (sin + angle.sin(), cos + angle.cos())
+    });
+    (sin.atan2(cos) / TAU * period).rem_euclid(period)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Storage of 3x3 tiles at tile `(col, row)` of a 20 px grid offset by `(5, 7)`
+    fn storage(col: f32, row: f32, class_id: usize) -> BoundingBox {
+        let (x1, y2) = (5.0 + 20.0 * col, 7.0 + 20.0 * row);
+        BoundingBox::new(x1, y2 - 50.0, x1 + 60.0, y2, class_id, 0.9)
+    }
+
+    #[test]
+    fn test_estimate_scale_and_offset() {
+        let mut boxes = vec![
+            storage(1.0, 4.0, 0),
+            storage(6.0, 9.0, 1),
+            storage(12.0, 5.0, 0),
+            BoundingBox::new(0.0, 0.0, 400.0, 400.0, 7, 0.9),
+        ];
+        // A box detected a bit too wide does not move the median
+        boxes.push(BoundingBox::new(305.0, 300.0, 385.0, 347.0, 1, 0.9));
+
+        let calibration = Calibration::estimate(&boxes, &CalibrationConfig::default()).unwrap();
+        assert!((calibration.pixels_per_tile - 20.0).abs() < 1e-3);
+        assert!((calibration.offset.0 - 5.0).abs() < 1.0);
+        assert!((calibration.offset.1 - 7.0).abs() < 1.0);
+
+        let tiles = calibration.to_tiles(&storage(6.0, 9.0, 1));
+        assert!((tiles.x - 6.0).abs() < 0.1);
+        assert!((tiles.width - 3.0).abs() < 1e-3);
+
+        assert_eq!(
+            Calibration::estimate(&boxes[3..4], &CalibrationConfig::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_store_in_profile_scales_with_resolution() {
+        let calibration = Calibration {
+            pixels_per_tile: 20.0,
+            offset: (5.0, 7.0),
+        };
+        let mut profile = DeviceProfile::new("emulator-720p", (1280, 720));
+        calibration.store(&mut profile, (1280, 720));
+        assert_eq!(profile.calibration(TILE_SIZE_KEY), Some(20.0 / 1280.0));
+
+        let restored = Calibration::from_profile(&profile, (2560, 1440)).unwrap();
+        assert!((restored.pixels_per_tile - 40.0).abs() < 1e-3);
+        assert!((restored.offset.0 - 10.0).abs() < 1e-3);
+        assert!((restored.offset.1 - 14.0).abs() < 1e-3);
+
+        assert_eq!(
+            Calibration::from_profile(&DeviceProfile::new("blank", (1280, 720)), (1280, 720)),
+            None
+        );
+    }
+}
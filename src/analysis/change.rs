@@ -0,0 +1,347 @@
+//! Changes between two screenshots of the same base taken at different times.
+//!
+//! The screenshots are aligned from their detections, as the camera zoom and scroll differ
+//! between scouts: a scale and offset mapping the older centers onto the newer ones is refined
+//! iteratively from the buildings matched so far. Matched buildings whose estimated level went
+//! up are reported as upgraded, unmatched ones as added or removed.
+
+use crate::detection::BoundingBox;
+
+/// Building center in image coordinates
+type Point = (f32, f32);
+
+/// Configuration of the change detection
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChangeConfig {
+    pub match_distance: f32,
+    pub iterations: usize,
+}
+
+impl Default for ChangeConfig {
+    fn default() -> Self {
+        Self {
+            match_distance: 0.1, // Largest center distance of a match, as a fraction of the base radius
+            iterations: 5,       // Alignment refinements, the first ones matching up to 4x farther
+        }
+    }
+}
+
+/// Scale and offset mapping the coordinates of the older screenshot onto the newer one
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Alignment {
+    pub scale: f32,
+    pub offset: (f32, f32),
+}
+
+impl Default for Alignment {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            offset: (0.0, 0.0),
+        }
+    }
+}
+
+impl Alignment {
+    /// Maps a point of the older screenshot to the newer one
+    #[inline]
+    #[must_use]
+    pub fn apply(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        (
+            x * self.scale + self.offset.0,
+            y * self.scale + self.offset.1,
+        )
+    }
+
+    /// Aligns the centroids and spreads of two point sets
+    fn from_moments(before: &[(f32, f32)], after: &[(f32, f32)]) -> Self {
+        let (before_center, before_spread) = moments(before);
+        let (after_center, after_spread) = moments(after);
+        let scale = if before_spread > 0.0 && after_spread > 0.0 {
+            after_spread / before_spread
+        } else {
+            1.0
+        };
+        Self {
+            scale,
+            offset: (
+                after_center.0 - scale * before_center.0,
+                after_center.1 - scale * before_center.1,
+            ),
+        }
+    }
+
+    /// Least-squares scale and offset mapping the first point of each pair onto the second
+    fn fit(pairs: &[(Point, Point)]) -> Option<Self> {
+        if pairs.len() < 2 {
+            return None;
+        }
+        let (before, after): (Vec<_>, Vec<_>) = pairs.iter().copied().unzip();
+        let (before_center, _) = moments(&before);
+        let (after_center, _) = moments(&after);
+
+        let (mut covariance, mut variance) = (0.0, 0.0);
+        for (p, q) in pairs {
+            let (px, py) = (p.0 - before_center.0, p.1 - before_center.1);
+            covariance += px * (q.0 - after_center.0) + py * (q.1 - after_center.1);
+            variance += px * px + py * py;
+        }
+        let scale = covariance / variance;
+        (variance > 0.0 && scale > 0.0).then_some(Self {
+            scale,
+            offset: (
+                after_center.0 - scale * before_center.0,
+                after_center.1 - scale * before_center.1,
+            ),
+        })
+    }
+}
+
+/// Change of a building between the two screenshots
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BuildingChange {
+    /// Same building whose estimated level increased
+    Upgraded {
+        before: BoundingBox,
+        after: BoundingBox,
+        from_level: u32,
+        to_level: u32,
+    },
+    /// Building only found in the newer screenshot
+    Added(BoundingBox),
+    /// Building only found in the older screenshot
+    Removed(BoundingBox),
+}
+
+impl BuildingChange {
+    /// Returns the string representation of the kind of change.
+    #[inline]
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Upgraded { .. } => "upgraded",
+            Self::Added(_) => "added",
+            Self::Removed(_) => "removed",
+        }
+    }
+}
+
+/// Differences between the detections of two screenshots of a base
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangeReport {
+    pub alignment: Alignment,
+    /// Buildings found in both screenshots without a level increase
+    pub unchanged: usize,
+    pub changes: Vec<BuildingChange>,
+}
+
+impl ChangeReport {
+    /// Returns true if no building was upgraded, added or removed
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Returns the number of changes of a kind, e.g. `"upgraded"`
+    #[must_use]
+    pub fn count(&self, kind: &str) -> usize {
+        self.changes
+            .iter()
+            .filter(|change| change.as_str() == kind)
+            .count()
+    }
+}
+
+/// Aligns the detections of two screenshots of a base and reports the buildings that changed
+#[must_use]
+pub fn diff_detections(
+    before: &[BoundingBox],
+    after: &[BoundingBox],
+    config: &ChangeConfig,
+) -> ChangeReport {
+    let before_centers: Vec<(f32, f32)> = before.iter().map(BoundingBox::center).collect();
+    let after_centers: Vec<(f32, f32)> = after.iter().map(BoundingBox::center).collect();
+    let max_distance = config.match_distance * moments(&after_centers).1.max(1.0);
+
+    // Coarse to fine: early matches tolerate the error of the initial moment-based alignment
+    let mut alignment = Alignment::from_moments(&before_centers, &after_centers);
+    let mut search_distance = max_distance * 4.0;
+    for _ in 0..config.iterations {
+        let matches = match_buildings(before, after, &alignment, search_distance);
+        let pairs: Vec<_> = matches
+            .iter()
+            .map(|&(b, a)| (before_centers[b], after_centers[a]))
+            .collect();
+        if let Some(refined) = Alignment::fit(&pairs) {
+            alignment = refined;
+        }
+        search_distance = (search_distance / 2.0).max(max_distance);
+    }
+    let matches = match_buildings(before, after, &alignment, max_distance);
+
+    let mut changes = Vec::new();
+    let mut unchanged = 0;
+    for &(b, a) in &matches {
+        match (before[b].level, after[a].level) {
+            (Some(from), Some(to)) if to.level > from.level => {
+                changes.push(BuildingChange::Upgraded {
+                    before: before[b],
+                    after: after[a],
+                    from_level: from.level,
+                    to_level: to.level,
+                });
+            }
+            _ => unchanged += 1,
+        }
+    }
+    changes.extend(
+        (0..after.len())
+            .filter(|a| !matches.iter().any(|m| m.1 == *a))
+            .map(|a| BuildingChange::Added(after[a])),
+    );
+    changes.extend(
+        (0..before.len())
+            .filter(|b| !matches.iter().any(|m| m.0 == *b))
+            .map(|b| BuildingChange::Removed(before[b])),
+    );
+
+    ChangeReport {
+        alignment,
+        unchanged,
+        changes,
+    }
+}
+
+/// Greedily matches same-class buildings by increasing distance once aligned
+fn match_buildings(
+    before: &[BoundingBox],
+    after: &[BoundingBox],
+    alignment: &Alignment,
+    max_distance: f32,
+) -> Vec<(usize, usize)> {
+    let mut candidates = Vec::new();
+    for (b, old) in before.iter().enumerate() {
+        let (x, y) = alignment.apply(old.center());
+        for (a, new) in after.iter().enumerate() {
+            if old.class_id != new.class_id {
+                continue;
+            }
+            let (nx, ny) = new.center();
+            let distance = (nx - x).hypot(ny - y);
+            if distance <= max_distance {
+                candidates.push((distance, b, a));
+            }
+        }
+    }
+    candidates.sort_by(|x, y| x.0.total_cmp(&y.0).then((x.1, x.2).cmp(&(y.1, y.2))));
+
+    let mut before_matched = vec![false; before.len()];
+    let mut after_matched = vec![false; after.len()];
+    let mut matches = Vec::new();
+    for (_, b, a) in candidates {
+        if !before_matched[b] && !after_matched[a] {
+            before_matched[b] = true;
+            after_matched[a] = true;
+            matches.push((b, a));
+        }
+    }
+    matches.sort_unstable();
+    matches
+}
+
+/// Returns the centroid of points and their root mean square distance to it
+fn moments(points: &[(f32, f32)]) -> ((f32, f32), f32) {
+    if points.is_empty() {
+        return ((0.0, 0.0), 0.0);
+    }
+    let count = points.len() as f32;
+    let center = (
+        points.iter().map(|p| p.0).sum::<f32>() / count,
+        points.iter().map(|p| p.1).sum::<f32>() / count,
+    );
+    let spread = (points
+        .iter()
+        .map(|p| (p.0 - center.0).powi(2) + (p.1 - center.1).powi(2))
+        .sum::<f32>()
+        / count)
+        .sqrt();
+    (center, spread)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detection::LevelEstimate;
+
+    fn building(x: f32, y: f32, class_id: usize, level: u32) -> BoundingBox {
+        let mut bbox = BoundingBox::new(x - 10.0, y - 10.0, x + 10.0, y + 10.0, class_id, 0.9);
+        bbox.level = Some(LevelEstimate {
+            level,
+            confidence: 0.8,
+        });
+        bbox
+    }
+
+    /// Base of six buildings and the same base zoomed in twice and scrolled by (30, -20)
+    fn scouts() -> (Vec<BoundingBox>, Vec<BoundingBox>) {
+        let base = [
+            (100.0, 100.0, 0),
+            (200.0, 110.0, 1),
+            (150.0, 200.0, 0),
+            (260.0, 220.0, 1),
+            (120.0, 300.0, 0),
+            (240.0, 320.0, 1),
+        ];
+        let before = base
+            .iter()
+            .map(|&(x, y, class_id)| building(x, y, class_id, 10))
+            .collect();
+        let after = base
+            .iter()
+            .map(|&(x, y, class_id)| building(2.0 * x + 30.0, 2.0 * y - 20.0, class_id, 10))
+            .collect();
+        (before, after)
+    }
+
+    #[test]
+    fn test_aligned_scouts_without_changes() {
+        let (before, after) = scouts();
+        let report = diff_detections(&before, &after, &ChangeConfig::default());
+        assert!(report.is_empty());
+        assert_eq!(report.unchanged, 6);
+        assert!((report.alignment.scale - 2.0).abs() < 1e-4);
+        assert!((report.alignment.offset.0 - 30.0).abs() < 1e-2);
+        assert!((report.alignment.offset.1 + 20.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_upgraded_added_and_removed() {
+        let (before, mut after) = scouts();
+        after[1].level = Some(LevelEstimate {
+            level: 11,
+            confidence: 0.7,
+        });
+        let removed = after.remove(4);
+        let added = building(500.0, 500.0, 1, 1);
+        after.push(added);
+
+        let report = diff_detections(&before, &after, &ChangeConfig::default());
+        assert_eq!(report.unchanged, 4);
+        assert_eq!(report.count("upgraded"), 1);
+        assert!(matches!(
+            report.changes[0],
+            BuildingChange::Upgraded {
+                from_level: 10,
+                to_level: 11,
+                ..
+            }
+        ));
+        assert_eq!(report.changes[1], BuildingChange::Added(added));
+        assert_eq!(report.changes[2], BuildingChange::Removed(before[4]));
+
+        // The removed building is where the alignment expects it in the newer scout
+        let (x, y) = report.alignment.apply(before[4].center());
+        assert!((x - removed.center().0).abs() < 0.1 && (y - removed.center().1).abs() < 0.1);
+    }
+}
@@ -0,0 +1,393 @@
+//! Base compartments enclosed by wall detections, and the graph of compartments sharing a wall.
+//!
+//! Wall boxes are rasterized onto a grid of roughly one wall per cell. Cells that cannot be
+//! reached from the border of the grid without crossing a wall form the compartments, and the
+//! other buildings are assigned to the compartment under their center.
+
+use crate::detection::BoundingBox;
+use serde::Serialize;
+use std::collections::{BTreeSet, VecDeque};
+
+/// Largest number of grid cells along a side, the cell size growing for larger bases
+const MAX_GRID_SIDE: usize = 512;
+
+/// Configuration of the compartment extraction
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompartmentConfig {
+    pub wall_classes: Vec<usize>,
+    pub cell_size: Option<f32>,
+    pub min_cells: usize,
+}
+
+impl Default for CompartmentConfig {
+    fn default() -> Self {
+        Self {
+            wall_classes: Vec::new(), // Class ids of wall segments, none in the built-in model
+            cell_size: None,          // Grid cell in pixels, defaults to the median wall side
+            min_cells: 1,             // Smaller enclosed pockets are ignored
+        }
+    }
+}
+
+/// Area enclosed by walls
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Compartment {
+    pub id: usize,
+    /// Convex outline of the compartment cells, in image coordinates
+    pub polygon: Vec<(f32, f32)>,
+    pub area: f32,
+    /// Indices of the buildings inside, in the input boxes
+    pub buildings: Vec<usize>,
+}
+
+/// Compartments of a base and the pairs of compartments separated by a single wall
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CompartmentGraph {
+    pub compartments: Vec<Compartment>,
+    pub edges: Vec<(usize, usize)>,
+    /// Indices of the buildings outside every compartment
+    pub outside: Vec<usize>,
+}
+
+impl CompartmentGraph {
+    /// Returns the compartment of a building, by its index in the input boxes
+    #[must_use]
+    pub fn compartment_of(&self, building: usize) -> Option<&Compartment> {
+        self.compartments
+            .iter()
+            .find(|compartment| compartment.buildings.contains(&building))
+    }
+
+    /// Returns the ids of the compartments sharing a wall with `id`
+    pub fn neighbors(&self, id: usize) -> impl Iterator<Item = usize> + '_ {
+        self.edges.iter().filter_map(move |&(a, b)| match id {
+            _ if a == id => Some(b),
+            _ if b == id => Some(a),
+            _ => None,
+        })
+    }
+}
+
+/// Grid cell state during the extraction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cell {
+    Wall,
+    Outside,
+    Open,
+    Compartment(usize),
+}
+
+/// Grid covering the detections with a margin of one cell
+struct Grid {
+    origin: (f32, f32),
+    cell_size: f32,
+    width: usize,
+    height: usize,
+    cells: Vec<Cell>,
+}
+
+impl Grid {
+    fn new(boxes: &[BoundingBox], cell_size: f32) -> Self {
+        let (min_x, min_y, max_x, max_y) = boxes.iter().fold(
+            (f32::MAX, f32::MAX, f32::MIN, f32::MIN),
+            |(min_x, min_y, max_x, max_y), bbox| {
+                (
+                    min_x.min(bbox.x1),
+                    min_y.min(bbox.y1),
+                    max_x.max(bbox.x2),
+                    max_y.max(bbox.y2),
+                )
+            },
+        );
+        let cell_size = cell_size
+            .max((max_x - min_x) / (MAX_GRID_SIDE - 2) as f32)
+            .max((max_y - min_y) / (MAX_GRID_SIDE - 2) as f32);
+        let width = ((max_x - min_x) / cell_size).ceil() as usize + 2;
+        let height = ((max_y - min_y) / cell_size).ceil() as usize + 2;
+
+        Self {
+            origin: (min_x - cell_size, min_y - cell_size),
+            cell_size,
+            width,
+            height,
+            cells: vec![Cell::Open; width * height],
+        }
+    }
+
+    /// Returns the cell containing a point
+    fn cell_at(&self, (x, y): (f32, f32)) -> (usize, usize) {
+        let column = ((x - self.origin.0) / self.cell_size).floor().max(0.0) as usize;
+        let row = ((y - self.origin.1) / self.cell_size).floor().max(0.0) as usize;
+        (column.min(self.width - 1), row.min(self.height - 1))
+    }
+
+    /// Marks the cells overlapped by a wall box
+    fn mark_wall(&mut self, bbox: &BoundingBox) {
+        // Boxes ending exactly on a cell border do not spill into the next cell
+        let margin = self.cell_size * 1e-3;
+        let (x1, y1) = self.cell_at((bbox.x1, bbox.y1));
+        let (x2, y2) = self.cell_at((bbox.x2 - margin, bbox.y2 - margin));
+        for row in y1..=y2.max(y1) {
+            for column in x1..=x2.max(x1) {
+                self.cells[row * self.width + column] = Cell::Wall;
+            }
+        }
+    }
+
+    /// Returns the 4-connected neighbors of a cell index
+    fn neighbors(&self, index: usize) -> impl Iterator<Item = usize> {
+        let (column, row) = (index % self.width, index / self.width);
+        let width = self.width;
+        [
+            (column > 0).then(|| index - 1),
+            (column + 1 < width).then(|| index + 1),
+            (row > 0).then(|| index - width),
+            (row + 1 < self.height).then(|| index + width),
+        ]
+        .into_iter()
+        .flatten()
+    }
+
+    /// Fills the open cells 4-connected to `start` with `value`, returning the filled cells
+    fn flood(&mut self, start: usize, value: Cell) -> Vec<usize> {
+        let mut filled = vec![start];
+        let mut queue = VecDeque::from([start]);
+        self.cells[start] = value;
+        while let Some(index) = queue.pop_front() {
+            for next in self.neighbors(index).collect::<Vec<_>>() {
+                if self.cells[next] == Cell::Open {
+                    self.cells[next] = value;
+                    filled.push(next);
+                    queue.push_back(next);
+                }
+            }
+        }
+        filled
+    }
+}
+
+/// Extracts the compartments enclosed by the wall boxes and assigns the other boxes to them
+#[must_use]
+pub fn extract_compartments(boxes: &[BoundingBox], config: &CompartmentConfig) -> CompartmentGraph {
+    let is_wall = |bbox: &BoundingBox| config.wall_classes.contains(&bbox.class_id);
+    let walls: Vec<&BoundingBox> = boxes.iter().filter(|bbox| is_wall(bbox)).collect();
+    let buildings = || (0..boxes.len()).filter(|&i| !is_wall(&boxes[i]));
+
+    let Some(cell_size) = config
+        .cell_size
+        .or_else(|| median_wall_side(&walls))
+        .filter(|size| size.is_finite() && *size > 0.0)
+    else {
+        return CompartmentGraph {
+            outside: buildings().collect(),
+            ..CompartmentGraph::default()
+        };
+    };
+
+    let mut grid = Grid::new(boxes, cell_size);
+    for wall in &walls {
+        grid.mark_wall(wall);
+    }
+
+    // Everything reachable from the border without crossing a wall is outside the base
+    let border: Vec<usize> = (0..grid.cells.len())
+        .filter(|&i| {
+            let (column, row) = (i % grid.width, i / grid.width);
+            column == 0 || row == 0 || column == grid.width - 1 || row == grid.height - 1
+        })
+        .collect();
+    for index in border {
+        if grid.cells[index] == Cell::Open {
+            grid.flood(index, Cell::Outside);
+        }
+    }
+
+    let mut compartments = Vec::new();
+    for index in 0..grid.cells.len() {
+        if grid.cells[index] != Cell::Open {
+            continue;
+        }
+        let id = compartments.len();
+        let cells = grid.flood(index, Cell::Compartment(id));
+        if cells.len() < config.min_cells {
+            for cell in cells {
+                grid.cells[cell] = Cell::Outside;
+            }
+            continue;
+        }
+        compartments.push(Compartment {
+            id,
+            polygon: outline(&grid, &cells),
+            area: cells.len() as f32 * grid.cell_size * grid.cell_size,
+            buildings: Vec::new(),
+        });
+    }
+
+    let mut outside = Vec::new();
+    for i in buildings() {
+        let (column, row) = grid.cell_at(boxes[i].center());
+        match grid.cells[row * grid.width + column] {
+            Cell::Compartment(id) => compartments[id].buildings.push(i),
+            _ => outside.push(i),
+        }
+    }
+
+    CompartmentGraph {
+        compartments,
+        edges: shared_walls(&grid),
+        outside,
+    }
+}
+
+/// Returns the median of the shorter sides of the wall boxes
+fn median_wall_side(walls: &[&BoundingBox]) -> Option<f32> {
+    let mut sides: Vec<f32> = walls
+        .iter()
+        .map(|wall| {
+            let (width, height) = wall.dimensions();
+            width.min(height)
+        })
+        .collect();
+    sides.sort_by(f32::total_cmp);
+    sides.get(sides.len() / 2).copied()
+}
+
+/// Returns the pairs of compartments around the same wall cell, 8-connected
+fn shared_walls(grid: &Grid) -> Vec<(usize, usize)> {
+    let mut edges = BTreeSet::new();
+    for (index, _) in grid
+        .cells
+        .iter()
+        .enumerate()
+        .filter(|(_, cell)| **cell == Cell::Wall)
+    {
+        let (column, row) = (index % grid.width, index / grid.width);
+        let mut around = BTreeSet::new();
+        for next_row in row.saturating_sub(1)..=(row + 1).min(grid.height - 1) {
+            for next_column in column.saturating_sub(1)..=(column + 1).min(grid.width - 1) {
+                if let Cell::Compartment(id) = grid.cells[next_row * grid.width + next_column] {
+                    around.insert(id);
+                }
+            }
+        }
+        let around: Vec<usize> = around.into_iter().collect();
+        for (i, &a) in around.iter().enumerate() {
+            for &b in &around[i + 1..] {
+                edges.insert((a, b));
+            }
+        }
+    }
+    edges.into_iter().collect()
+}
+
+/// Returns the convex hull of the corners of the cells, clockwise on screen from the top-left
+fn outline(grid: &Grid, cells: &[usize]) -> Vec<(f32, f32)> {
+    let mut corners: Vec<(f32, f32)> = cells
+        .iter()
+        .flat_map(|&index| {
+            let x = grid.origin.0 + (index % grid.width) as f32 * grid.cell_size;
+            let y = grid.origin.1 + (index / grid.width) as f32 * grid.cell_size;
+            let size = grid.cell_size;
+            [(x, y), (x + size, y), (x + size, y + size), (x, y + size)]
+        })
+        .collect();
+    corners.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)));
+    corners.dedup();
+
+    // Andrew's monotone chain
+    let cross = |o: (f32, f32), a: (f32, f32), b: (f32, f32)| {
+        (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+    };
+    let mut hull: Vec<(f32, f32)> = Vec::with_capacity(corners.len() * 2);
+    for pass in [corners.clone(), corners.into_iter().rev().collect()] {
+        let start = hull.len();
+        for point in pass {
+            while hull.len() >= start + 2
+                && cross(hull[hull.len() - 2], hull[hull.len() - 1], point) <= 0.0
+            {
+                hull.pop();
+            }
+            hull.push(point);
+        }
+        hull.pop();
+    }
+    hull
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WALL: usize = 9;
+
+    /// Square ring of 10 px walls from (0, 0) to (70, 70), split in two by a wall at x = 30
+    fn walled_base() -> Vec<BoundingBox> {
+        let mut boxes = Vec::new();
+        for step in 0..=6 {
+            let offset = step as f32 * 10.0;
+            for (x, y) in [(offset, 0.0), (offset, 60.0), (0.0, offset), (60.0, offset)] {
+                boxes.push(BoundingBox::new(x, y, x + 10.0, y + 10.0, WALL, 0.9));
+            }
+            if (1..6).contains(&step) {
+                boxes.push(BoundingBox::new(
+                    30.0,
+                    offset,
+                    40.0,
+                    offset + 10.0,
+                    WALL,
+                    0.9,
+                ));
+            }
+        }
+        boxes
+    }
+
+    fn config() -> CompartmentConfig {
+        CompartmentConfig {
+            wall_classes: vec![WALL],
+            ..CompartmentConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_split_base_has_two_neighboring_compartments() {
+        let mut boxes = walled_base();
+        let storage = boxes.len();
+        boxes.push(BoundingBox::new(12.0, 12.0, 28.0, 28.0, 1, 0.8));
+        let cannon = boxes.len();
+        boxes.push(BoundingBox::new(42.0, 42.0, 58.0, 58.0, 0, 0.8));
+        let outer = boxes.len();
+        boxes.push(BoundingBox::new(100.0, 100.0, 110.0, 110.0, 0, 0.8));
+
+        let graph = extract_compartments(&boxes, &config());
+        assert_eq!(graph.compartments.len(), 2);
+        assert_eq!(graph.edges, vec![(0, 1)]);
+        assert_eq!(graph.neighbors(1).collect::<Vec<_>>(), vec![0]);
+        assert_eq!(graph.outside, vec![outer]);
+
+        let left = graph.compartment_of(storage).unwrap();
+        let right = graph.compartment_of(cannon).unwrap();
+        assert_ne!(left.id, right.id);
+        assert_eq!(left.area, 2.0 * 5.0 * 100.0);
+        assert_eq!(
+            left.polygon,
+            vec![(10.0, 10.0), (30.0, 10.0), (30.0, 60.0), (10.0, 60.0)]
+        );
+    }
+
+    #[test]
+    fn test_open_walls_enclose_nothing() {
+        let mut boxes = walled_base();
+        // A missing wall on the left side lets the outside into the left compartment
+        boxes.retain(|bbox| (bbox.x1, bbox.y1) != (0.0, 30.0));
+        boxes.push(BoundingBox::new(12.0, 12.0, 28.0, 28.0, 1, 0.8));
+
+        let graph = extract_compartments(&boxes, &config());
+        assert_eq!(graph.compartments.len(), 1);
+        assert_eq!(graph.outside, vec![boxes.len() - 1]);
+
+        let graph = extract_compartments(&boxes, &CompartmentConfig::default());
+        assert!(graph.compartments.is_empty());
+        assert_eq!(graph.outside.len(), boxes.len());
+    }
+}
@@ -0,0 +1,245 @@
+//! Device profiles matching screenshot resolutions to UI masks, ROI and calibration constants
+
+use crate::detection::BoundingBox;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Maximum relative aspect ratio difference for a profile to match by aspect ratio
+pub const ASPECT_RATIO_TOLERANCE: f32 = 0.02;
+
+/// Approximate home village UI overlays, as normalized `[x1, y1, x2, y2]` rectangles
+const HOME_VILLAGE_UI_MASK: [[f32; 4]; 4] = [
+    [0.0, 0.0, 0.3, 0.12], // Player name and experience level
+    [0.7, 0.0, 1.0, 0.2],  // Resource counters
+    [0.0, 0.8, 0.15, 1.0], // Attack button
+    [0.85, 0.8, 1.0, 1.0], // Shop and layout buttons
+];
+
+/// Errors that can occur while loading device profiles
+#[derive(Debug, thiserror::Error)]
+pub enum ProfileError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid device profiles JSON: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Screen layout of a device or emulator resolution.
+///
+/// Rectangles are normalized `[x1, y1, x2, y2]` in `[0, 1]`, so a profile also applies to
+/// screenshots of the same aspect ratio at another resolution.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeviceProfile {
+    pub name: String,
+    pub resolution: (u32, u32),
+    #[serde(default)]
+    pub ui_mask: Vec<[f32; 4]>,
+    #[serde(default = "full_frame")]
+    pub roi: [f32; 4],
+    #[serde(default)]
+    pub calibration: BTreeMap<String, f32>,
+}
+
+const fn full_frame() -> [f32; 4] {
+    [0.0, 0.0, 1.0, 1.0]
+}
+
+impl DeviceProfile {
+    /// Creates a profile with the home village UI mask and the full frame as ROI
+    #[must_use]
+    pub fn new(name: impl Into<String>, resolution: (u32, u32)) -> Self {
+        Self {
+            name: name.into(),
+            resolution,
+            ui_mask: HOME_VILLAGE_UI_MASK.to_vec(),
+            roi: full_frame(),
+            calibration: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the width / height ratio of the profile resolution
+    #[inline]
+    #[must_use]
+    pub fn aspect_ratio(&self) -> f32 {
+        self.resolution.0 as f32 / self.resolution.1.max(1) as f32
+    }
+
+    /// Returns a calibration constant by name
+    #[inline]
+    #[must_use]
+    pub fn calibration(&self, key: &str) -> Option<f32> {
+        self.calibration.get(key).copied()
+    }
+
+    /// Returns true if the box center lies inside the ROI and outside every UI mask rectangle.
+    ///
+    /// The box is expressed in the pixel space of an image of `image_size`.
+    #[must_use]
+    pub fn accepts(&self, bbox: &BoundingBox, image_size: (u32, u32)) -> bool {
+        let (cx, cy) = bbox.center();
+        let point = (
+            cx / image_size.0.max(1) as f32,
+            cy / image_size.1.max(1) as f32,
+        );
+        contains(self.roi, point) && !self.ui_mask.iter().any(|&rect| contains(rect, point))
+    }
+
+    /// Drops the boxes rejected by `accepts`
+    pub fn filter_boxes(&self, boxes: &mut Vec<BoundingBox>, image_size: (u32, u32)) {
+        boxes.retain(|bbox| self.accepts(bbox, image_size));
+    }
+}
+
+/// Returns true if the normalized point lies inside the half-open rectangle
+fn contains(rect: [f32; 4], (x, y): (f32, f32)) -> bool {
+    x >= rect[0] && x < rect[2] && y >= rect[1] && y < rect[3]
+}
+
+/// Registry of device profiles, detected from screenshot dimensions
+#[derive(Debug, Clone, Default)]
+pub struct DeviceProfiles {
+    profiles: Vec<DeviceProfile>,
+}
+
+impl DeviceProfiles {
+    /// Returns the profiles of common emulator and device resolutions
+    #[must_use]
+    pub fn builtin() -> Self {
+        let profiles = [
+            ("emulator-720p", (1280, 720)),
+            ("emulator-900p", (1600, 900)),
+            ("emulator-1080p", (1920, 1080)),
+            ("emulator-1440p", (2560, 1440)),
+            ("emulator-16x10", (1280, 800)),
+            ("phone-20x9", (2400, 1080)),
+            ("tablet-4x3", (2048, 1536)),
+        ];
+        Self {
+            profiles: profiles
+                .into_iter()
+                .map(|(name, resolution)| DeviceProfile::new(name, resolution))
+                .collect(),
+        }
+    }
+
+    /// Parses a JSON array of profiles
+    pub fn from_json(json: &str) -> Result<Vec<DeviceProfile>, ProfileError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Registers the profiles of a JSON file, taking precedence over those already registered
+    pub fn load(&mut self, path: &Path) -> Result<usize, ProfileError> {
+        let profiles = Self::from_json(&fs::read_to_string(path)?)?;
+        let count = profiles.len();
+        for profile in profiles {
+            self.register(profile);
+        }
+        Ok(count)
+    }
+
+    /// Registers a profile, replacing any profile with the same name.
+    ///
+    /// Later registrations take precedence when several profiles match an image.
+    pub fn register(&mut self, profile: DeviceProfile) {
+        self.profiles
+            .retain(|existing| existing.name != profile.name);
+        self.profiles.push(profile);
+    }
+
+    /// Returns the registered profiles
+    #[inline]
+    #[must_use]
+    pub fn profiles(&self) -> &[DeviceProfile] {
+        &self.profiles
+    }
+
+    /// Returns the profile matching the image dimensions.
+    ///
+    /// An exact resolution match wins; otherwise the closest aspect ratio within
+    /// `ASPECT_RATIO_TOLERANCE` is selected.
+    #[must_use]
+    pub fn detect(&self, image_size: (u32, u32)) -> Option<&DeviceProfile> {
+        if let Some(profile) = self
+            .profiles
+            .iter()
+            .rev()
+            .find(|profile| profile.resolution == image_size)
+        {
+            return Some(profile);
+        }
+
+        let aspect_ratio = image_size.0 as f32 / image_size.1.max(1) as f32;
+        self.profiles
+            .iter()
+            .rev()
+            .map(|profile| {
+                let difference = (profile.aspect_ratio() - aspect_ratio).abs() / aspect_ratio;
+                (profile, difference)
+            })
+            .filter(|&(_, difference)| difference <= ASPECT_RATIO_TOLERANCE)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(profile, _)| profile)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_detect_exact_resolution() {
+        let profiles = DeviceProfiles::builtin();
+        assert_eq!(profiles.detect((1600, 900)).unwrap().name, "emulator-900p");
+        assert_eq!(profiles.detect((2048, 1536)).unwrap().name, "tablet-4x3");
+    }
+
+    #[test]
+    fn test_detect_by_aspect_ratio() {
+        let profiles = DeviceProfiles::builtin();
+        // 16:9 at an unlisted resolution matches the last registered 16:9 profile
+        assert_eq!(
+            profiles.detect((3840, 2160)).unwrap().aspect_ratio(),
+            16.0 / 9.0
+        );
+        assert_eq!(profiles.detect((1024, 768)).unwrap().name, "tablet-4x3");
+        assert!(profiles.detect((1000, 1000)).is_none());
+    }
+
+    #[test]
+    fn test_ui_mask_and_roi() {
+        let mut profile = DeviceProfile::new("test", (100, 100));
+        let center = BoundingBox::new(40.0, 40.0, 60.0, 60.0, 0, 0.9);
+        let resources = BoundingBox::new(80.0, 2.0, 90.0, 10.0, 1, 0.9);
+        assert!(profile.accepts(&center, (100, 100)));
+        assert!(!profile.accepts(&resources, (100, 100)));
+
+        profile.roi = [0.0, 0.0, 0.4, 1.0];
+        let mut boxes = vec![center, resources];
+        profile.filter_boxes(&mut boxes, (100, 100));
+        assert!(boxes.is_empty());
+    }
+
+    #[test]
+    fn test_load_profiles_from_file() -> Result<(), ProfileError> {
+        let dir = tempdir()?;
+        let path = dir.path().join("profiles.json");
+        fs::write(
+            &path,
+            r#"[{"name": "custom-1080p", "resolution": [1920, 1080],
+                 "ui_mask": [[0.0, 0.0, 1.0, 0.1]], "calibration": {"tile_size": 0.018}}]"#,
+        )?;
+
+        let mut profiles = DeviceProfiles::builtin();
+        assert_eq!(profiles.load(&path)?, 1);
+
+        let profile = profiles.detect((1920, 1080)).unwrap();
+        assert_eq!(profile.name, "custom-1080p");
+        assert_eq!(profile.roi, [0.0, 0.0, 1.0, 1.0]);
+        assert_eq!(profile.ui_mask.len(), 1);
+        assert_eq!(profile.calibration("tile_size"), Some(0.018));
+        Ok(())
+    }
+}
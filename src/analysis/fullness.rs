@@ -0,0 +1,321 @@
+//! Storage fullness estimation from hue statistics inside detected boxes.
+
+use crate::class::clash_class::ClashClass;
+use crate::class::registry::ClassRegistry;
+use crate::detection::BoundingBox;
+use crate::image::image_util::rgb_to_hsv;
+use image::RgbImage;
+use serde::Serialize;
+
+/// Number of bins used for hue histograms (10 degrees per bin)
+pub const HUE_BINS: usize = 36;
+
+/// Estimated fill level of a resource storage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FillLevel {
+    Empty,
+    Partial,
+    Full,
+}
+
+impl FillLevel {
+    /// Returns the string representation of the `FillLevel` variant.
+    #[inline]
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Empty => "empty",
+            Self::Partial => "partial",
+            Self::Full => "full",
+        }
+    }
+}
+
+/// Hue histogram of the saturated pixels inside a bounding box
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HueHistogram {
+    pub bins: [u32; HUE_BINS],
+    pub total_pixels: u32,
+}
+
+impl HueHistogram {
+    /// Builds the hue histogram of the pixels inside `bbox`, ignoring dull pixels
+    /// whose saturation or value fall below the given minimums.
+    #[must_use]
+    pub fn from_region(
+        image: &RgbImage,
+        bbox: &BoundingBox,
+        min_saturation: f32,
+        min_value: f32,
+    ) -> Self {
+        let mut bins = [0u32; HUE_BINS];
+        let mut total_pixels = 0u32;
+
+        let x_start = bbox.x1.max(0.0).floor() as u32;
+        let y_start = bbox.y1.max(0.0).floor() as u32;
+        let x_end = (bbox.x2.ceil().max(0.0) as u32).min(image.width());
+        let y_end = (bbox.y2.ceil().max(0.0) as u32).min(image.height());
+
+        for y in y_start..y_end {
+            for x in x_start..x_end {
+                total_pixels += 1;
+
+                let [r, g, b] = image.get_pixel(x, y).0;
+                let (hue, saturation, value) = rgb_to_hsv(r, g, b);
+                if saturation < min_saturation || value < min_value {
+                    continue;
+                }
+
+                let bin = ((hue / 360.0) * HUE_BINS as f32) as usize;
+                bins[bin.min(HUE_BINS - 1)] += 1;
+            }
+        }
+
+        Self { bins, total_pixels }
+    }
+
+    /// Returns the fraction of all region pixels whose hue lies within `[start, end)` degrees
+    #[must_use]
+    pub fn ratio_in_range(&self, start: f32, end: f32) -> f32 {
+        if self.total_pixels == 0 {
+            return 0.0;
+        }
+
+        let bin_width = 360.0 / HUE_BINS as f32;
+        let in_range: u32 = self
+            .bins
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| {
+                let bin_center = (*i as f32 + 0.5) * bin_width;
+                bin_center >= start && bin_center < end
+            })
+            .map(|(_, &count)| count)
+            .sum();
+
+        in_range as f32 / self.total_pixels as f32
+    }
+}
+
+/// A storage detection annotated with its estimated fill level
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StorageFullness {
+    pub bbox: BoundingBox,
+    pub level: FillLevel,
+    pub resource_ratio: f32,
+}
+
+/// Configuration for the storage fullness analysis pass.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FullnessConfig {
+    pub min_saturation: f32,
+    pub min_value: f32,
+    pub partial_ratio: f32,
+    pub full_ratio: f32,
+}
+
+impl Default for FullnessConfig {
+    fn default() -> Self {
+        Self {
+            min_saturation: 0.35, // Ignore grayish pixels (walls, ground, shadows)
+            min_value: 0.35,      // Ignore dark pixels
+            partial_ratio: 0.08,  // Resource-colored share above which a storage is not empty
+            full_ratio: 0.35,     // Resource-colored share above which a storage is full
+        }
+    }
+}
+
+impl FullnessConfig {
+    /// Estimates the fill level of every storage detection.
+    ///
+    /// Boxes must be expressed in the pixel space of `image`. Detections whose class is not a
+    /// resource storage in `classes` are skipped.
+    #[must_use]
+    pub fn estimate_fullness(
+        image: &RgbImage,
+        boxes: &[BoundingBox],
+        config: Option<FullnessConfig>,
+        classes: &ClassRegistry,
+    ) -> Vec<StorageFullness> {
+        let config = config.unwrap_or_default();
+        boxes
+            .iter()
+            .filter_map(|bbox| config.estimate_single(image, bbox, classes))
+            .collect()
+    }
+
+    /// Estimates the fill level of a single storage detection, resolving its class through
+    /// `classes`
+    #[must_use]
+    pub fn estimate_single(
+        &self,
+        image: &RgbImage,
+        bbox: &BoundingBox,
+        classes: &ClassRegistry,
+    ) -> Option<StorageFullness> {
+        let (hue_start, hue_end) = resource_hue_range(classes.clash_class(bbox.class_id)?);
+
+        let histogram = HueHistogram::from_region(image, bbox, self.min_saturation, self.min_value);
+        let resource_ratio = histogram.ratio_in_range(hue_start, hue_end);
+
+        Some(StorageFullness {
+            bbox: *bbox,
+            level: self.classify(resource_ratio),
+            resource_ratio,
+        })
+    }
+
+    /// Sets the fill level of every storage detection, leaving the other boxes unchanged.
+    ///
+    /// Boxes must be expressed in the pixel space of `image`.
+    pub fn annotate(&self, image: &RgbImage, boxes: &mut [BoundingBox], classes: &ClassRegistry) {
+        for bbox in boxes {
+            if let Some(estimate) = self.estimate_single(image, bbox, classes) {
+                bbox.fullness = Some(estimate.level);
+            }
+        }
+    }
+
+    /// Maps a resource-colored pixel ratio to a fill level
+    #[inline]
+    #[must_use]
+    pub fn classify(&self, resource_ratio: f32) -> FillLevel {
+        if resource_ratio >= self.full_ratio {
+            FillLevel::Full
+        } else if resource_ratio >= self.partial_ratio {
+            FillLevel::Partial
+        } else {
+            FillLevel::Empty
+        }
+    }
+}
+
+/// Returns the hue range (in degrees) of the resource held by a storage class
+const fn resource_hue_range(class: &ClashClass) -> (f32, f32) {
+    match class {
+        ClashClass::ElixirStorage => (270.0, 340.0), // Pink to purple
+        ClashClass::GoldStorage => (30.0, 70.0),     // Orange to yellow
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    const GRAY: Rgb<u8> = Rgb([112, 112, 112]);
+    const GOLD: Rgb<u8> = Rgb([212, 175, 55]);
+    const ELIXIR: Rgb<u8> = Rgb([255, 0, 255]);
+
+    /// Creates a gray image whose first `filled_rows` rows are painted with `color`
+    fn striped_image(color: Rgb<u8>, filled_rows: u32) -> RgbImage {
+        RgbImage::from_fn(10, 10, |_, y| if y < filled_rows { color } else { GRAY })
+    }
+
+    #[test]
+    fn test_hue_histogram_ignores_gray() {
+        let image = striped_image(GOLD, 0);
+        let bbox = BoundingBox::new(0.0, 0.0, 10.0, 10.0, ClashClass::GoldStorage.into(), 0.9);
+        let histogram = HueHistogram::from_region(&image, &bbox, 0.35, 0.35);
+        assert_eq!(histogram.total_pixels, 100);
+        assert_eq!(histogram.bins.iter().sum::<u32>(), 0);
+    }
+
+    #[test]
+    fn test_hue_histogram_clamps_to_image() {
+        let image = striped_image(GOLD, 10);
+        let bbox = BoundingBox::new(-5.0, -5.0, 50.0, 50.0, ClashClass::GoldStorage.into(), 0.9);
+        let histogram = HueHistogram::from_region(&image, &bbox, 0.35, 0.35);
+        assert_eq!(histogram.total_pixels, 100);
+        assert!((histogram.ratio_in_range(30.0, 70.0) - 1.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_gold_storage_levels() {
+        let bbox = BoundingBox::new(0.0, 0.0, 10.0, 10.0, ClashClass::GoldStorage.into(), 0.9);
+
+        let empty = FullnessConfig::estimate_fullness(
+            &striped_image(GOLD, 0),
+            &[bbox],
+            None,
+            &ClassRegistry::default(),
+        );
+        assert_eq!(empty[0].level, FillLevel::Empty);
+
+        let partial = FullnessConfig::estimate_fullness(
+            &striped_image(GOLD, 2),
+            &[bbox],
+            None,
+            &ClassRegistry::default(),
+        );
+        assert_eq!(partial[0].level, FillLevel::Partial);
+        assert!((partial[0].resource_ratio - 0.2).abs() < 0.001);
+
+        let full = FullnessConfig::estimate_fullness(
+            &striped_image(GOLD, 8),
+            &[bbox],
+            None,
+            &ClassRegistry::default(),
+        );
+        assert_eq!(full[0].level, FillLevel::Full);
+    }
+
+    #[test]
+    fn test_elixir_does_not_count_as_gold() {
+        let image = striped_image(ELIXIR, 10);
+        let gold = BoundingBox::new(0.0, 0.0, 10.0, 10.0, ClashClass::GoldStorage.into(), 0.9);
+        let elixir = BoundingBox::new(0.0, 0.0, 10.0, 10.0, ClashClass::ElixirStorage.into(), 0.9);
+
+        let result = FullnessConfig::estimate_fullness(
+            &image,
+            &[gold, elixir],
+            None,
+            &ClassRegistry::default(),
+        );
+        assert_eq!(result[0].level, FillLevel::Empty);
+        assert_eq!(result[1].level, FillLevel::Full);
+    }
+
+    #[test]
+    fn test_unknown_class_is_skipped() {
+        let image = striped_image(GOLD, 10);
+        let bbox = BoundingBox::new(0.0, 0.0, 10.0, 10.0, 42, 0.9);
+        assert!(
+            FullnessConfig::estimate_fullness(&image, &[bbox], None, &ClassRegistry::default())
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_storage_classes_come_from_registry() {
+        let image = striped_image(GOLD, 10);
+        let classes = ClassRegistry::from_json(r#"["Town Hall", "Gold Storage"]"#).unwrap();
+        let town_hall = BoundingBox::new(0.0, 0.0, 10.0, 10.0, 0, 0.9);
+        let gold = BoundingBox::new(0.0, 0.0, 10.0, 10.0, 1, 0.9);
+
+        let result = FullnessConfig::estimate_fullness(&image, &[town_hall, gold], None, &classes);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].bbox.class_id, 1);
+        assert_eq!(result[0].level, FillLevel::Full);
+    }
+
+    #[test]
+    fn test_annotate_sets_storage_fullness() {
+        let image = striped_image(GOLD, 10);
+        let mut boxes = [
+            BoundingBox::new(0.0, 0.0, 10.0, 10.0, ClashClass::GoldStorage.into(), 0.9),
+            BoundingBox::new(0.0, 0.0, 10.0, 10.0, 42, 0.9),
+        ];
+        FullnessConfig::default().annotate(&image, &mut boxes, &ClassRegistry::default());
+        assert_eq!(boxes[0].fullness, Some(FillLevel::Full));
+        assert_eq!(boxes[1].fullness, None);
+    }
+
+    #[test]
+    fn test_fill_level_as_str() {
+        assert_eq!(FillLevel::Empty.as_str(), "empty");
+        assert_eq!(FillLevel::Partial.as_str(), "partial");
+        assert_eq!(FillLevel::Full.as_str(), "full");
+    }
+}
@@ -0,0 +1,257 @@
+//! Layout descriptors of a base (centroid, spread, symmetry, rings) computed from its detections,
+//! exported as a fixed-length feature vector to cluster base archetypes without the raw images.
+
+use crate::detection::BoundingBox;
+use serde::Serialize;
+
+/// Number of concentric rings the buildings are counted in, from the centroid outwards
+pub const RING_COUNT: usize = 4;
+
+/// Names of the entries of `LayoutFeatures::to_vector`, in order
+pub const FEATURE_NAMES: [&str; 10 + RING_COUNT] = [
+    "buildings",
+    "centroid_x",
+    "centroid_y",
+    "spread_x",
+    "spread_y",
+    "spread_radial",
+    "symmetry_vertical",
+    "symmetry_horizontal",
+    "symmetry_diagonal",
+    "symmetry_anti_diagonal",
+    "ring_0",
+    "ring_1",
+    "ring_2",
+    "ring_3",
+];
+
+/// Mirror axis through the centroid of a base
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymmetryAxis {
+    Vertical,
+    Horizontal,
+    Diagonal,
+    AntiDiagonal,
+}
+
+impl SymmetryAxis {
+    /// Returns the string representation of the `SymmetryAxis` variant.
+    #[inline]
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Vertical => "vertical",
+            Self::Horizontal => "horizontal",
+            Self::Diagonal => "diagonal",
+            Self::AntiDiagonal => "anti_diagonal",
+        }
+    }
+
+    /// Returns a static slice of all `SymmetryAxis` variants.
+    #[must_use]
+    pub const fn values() -> &'static [Self] {
+        &[
+            Self::Vertical,
+            Self::Horizontal,
+            Self::Diagonal,
+            Self::AntiDiagonal,
+        ]
+    }
+
+    /// Mirrors an offset from the centroid across the axis
+    #[inline]
+    #[must_use]
+    pub const fn reflect(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        match self {
+            Self::Vertical => (-x, y),
+            Self::Horizontal => (x, -y),
+            Self::Diagonal => (y, x),
+            Self::AntiDiagonal => (-y, -x),
+        }
+    }
+}
+
+/// Layout descriptors of a base, with positions normalized by the image dimensions
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LayoutFeatures {
+    pub buildings: usize,
+    pub centroid: (f32, f32),
+    /// Standard deviation of the building centers along each axis
+    pub spread: (f32, f32),
+    /// Root mean square distance of the building centers to the centroid
+    pub radial_spread: f32,
+    /// Mirror symmetry score in `[0, 1]` of every axis, in `SymmetryAxis::values` order
+    pub symmetry: [f32; 4],
+    /// Fraction of the buildings in each ring, the outer ring ending at the farthest building
+    pub rings: [f32; RING_COUNT],
+}
+
+impl LayoutFeatures {
+    /// Computes the layout descriptors of the detections of a `width` x `height` image
+    #[must_use]
+    pub fn from_detections(boxes: &[BoundingBox], width: u32, height: u32) -> Self {
+        let (width, height) = (width.max(1) as f32, height.max(1) as f32);
+        let centers: Vec<(f32, f32, usize)> = boxes
+            .iter()
+            .map(|bbox| {
+                let (x, y) = bbox.center();
+                (x / width, y / height, bbox.class_id)
+            })
+            .collect();
+        if centers.is_empty() {
+            return Self {
+                buildings: 0,
+                centroid: (0.0, 0.0),
+                spread: (0.0, 0.0),
+                radial_spread: 0.0,
+                symmetry: [0.0; 4],
+                rings: [0.0; RING_COUNT],
+            };
+        }
+
+        let count = centers.len() as f32;
+        let centroid = (
+            centers.iter().map(|c| c.0).sum::<f32>() / count,
+            centers.iter().map(|c| c.1).sum::<f32>() / count,
+        );
+        let offsets: Vec<(f32, f32, usize)> = centers
+            .iter()
+            .map(|&(x, y, class_id)| (x - centroid.0, y - centroid.1, class_id))
+            .collect();
+
+        let variance = |axis: fn(&(f32, f32, usize)) -> f32| {
+            offsets.iter().map(|o| axis(o).powi(2)).sum::<f32>() / count
+        };
+        let spread = (variance(|o| o.0).sqrt(), variance(|o| o.1).sqrt());
+        let radial_spread = (spread.0.powi(2) + spread.1.powi(2)).sqrt();
+
+        let distances: Vec<f32> = offsets.iter().map(|o| o.0.hypot(o.1)).collect();
+        let max_distance = distances.iter().copied().fold(0.0, f32::max);
+        let mut rings = [0.0; RING_COUNT];
+        for distance in &distances {
+            let ring = if max_distance > 0.0 {
+                ((distance / max_distance * RING_COUNT as f32) as usize).min(RING_COUNT - 1)
+            } else {
+                0
+            };
+            rings[ring] += 1.0 / count;
+        }
+
+        let mut symmetry = [0.0; 4];
+        for (score, axis) in symmetry.iter_mut().zip(SymmetryAxis::values()) {
+            *score = symmetry_score(&offsets, *axis, max_distance);
+        }
+
+        Self {
+            buildings: centers.len(),
+            centroid,
+            spread,
+            radial_spread,
+            symmetry,
+            rings,
+        }
+    }
+
+    /// Returns the axis the base is the most symmetric about, with its score
+    #[must_use]
+    pub fn main_symmetry_axis(&self) -> Option<(SymmetryAxis, f32)> {
+        SymmetryAxis::values()
+            .iter()
+            .copied()
+            .zip(self.symmetry)
+            .filter(|_| self.buildings > 0)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    /// Returns the descriptors as a feature vector, named by `FEATURE_NAMES`
+    #[must_use]
+    pub fn to_vector(&self) -> Vec<f32> {
+        let mut vector = vec![
+            self.buildings as f32,
+            self.centroid.0,
+            self.centroid.1,
+            self.spread.0,
+            self.spread.1,
+            self.radial_spread,
+        ];
+        vector.extend(self.symmetry);
+        vector.extend(self.rings);
+        vector
+    }
+}
+
+/// Scores how well the buildings mirror onto buildings of the same class across an axis.
+///
+/// Every mirrored center is matched to the nearest same-class center; the score is one minus
+/// the mean match distance relative to the base radius, so 1 means a perfect mirror image.
+fn symmetry_score(offsets: &[(f32, f32, usize)], axis: SymmetryAxis, radius: f32) -> f32 {
+    if radius <= 0.0 {
+        return 1.0;
+    }
+    let total: f32 = offsets
+        .iter()
+        .map(|&(x, y, class_id)| {
+            let (mx, my) = axis.reflect((x, y));
+            let nearest = offsets
+                .iter()
+                .filter(|other| other.2 == class_id)
+                .map(|other| (other.0 - mx).hypot(other.1 - my))
+                .fold(f32::MAX, f32::min);
+            (nearest / radius).min(1.0)
+        })
+        .sum();
+    1.0 - total / offsets.len() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn building(x: f32, y: f32, class_id: usize) -> BoundingBox {
+        BoundingBox::new(x - 5.0, y - 5.0, x + 5.0, y + 5.0, class_id, 0.9)
+    }
+
+    #[test]
+    fn test_mirrored_base() {
+        // Left-right mirror image around x = 50, not mirrored top to bottom
+        let boxes = [
+            building(20.0, 30.0, 0),
+            building(80.0, 30.0, 0),
+            building(35.0, 70.0, 1),
+            building(65.0, 70.0, 1),
+        ];
+        let features = LayoutFeatures::from_detections(&boxes, 100, 100);
+
+        assert_eq!(features.buildings, 4);
+        assert!((features.centroid.0 - 0.5).abs() < 1e-6);
+        assert!((features.centroid.1 - 0.5).abs() < 1e-6);
+        assert!((features.symmetry[0] - 1.0).abs() < 1e-6);
+        assert!(features.symmetry[1] < 0.9);
+        assert_eq!(
+            features.main_symmetry_axis().map(|(axis, _)| axis),
+            Some(SymmetryAxis::Vertical)
+        );
+        assert!((features.rings.iter().sum::<f32>() - 1.0).abs() < 1e-6);
+        assert!(features.spread.0 > features.spread.1);
+    }
+
+    #[test]
+    fn test_feature_vector() {
+        let boxes = [
+            building(50.0, 50.0, 0),
+            building(10.0, 50.0, 0),
+            building(90.0, 50.0, 0),
+        ];
+        let features = LayoutFeatures::from_detections(&boxes, 100, 100);
+        let vector = features.to_vector();
+        assert_eq!(vector.len(), FEATURE_NAMES.len());
+        assert_eq!(vector[0], 3.0);
+        // The center building is in the inner ring, the two others in the outer one
+        assert_eq!(&vector[10..], &[1.0 / 3.0, 0.0, 0.0, 2.0 / 3.0]);
+
+        let empty = LayoutFeatures::from_detections(&[], 100, 100);
+        assert_eq!(empty.to_vector(), vec![0.0; FEATURE_NAMES.len()]);
+        assert_eq!(empty.main_symmetry_axis(), None);
+    }
+}
@@ -0,0 +1,6 @@
+pub mod calibration;
+pub mod change;
+pub mod compartments;
+pub mod device_profile;
+pub mod fullness;
+pub mod layout;
@@ -1,6 +1,13 @@
+use crate::class::group::ClassGroup;
 use std::fmt::Debug;
 
+// With the `codegen-classes` feature, the enum and its data methods are generated by build.rs
+// from dataset.yaml instead.
+#[cfg(feature = "codegen-classes")]
+include!(concat!(env!("OUT_DIR"), "/clash_class.rs"));
+
 /// This file is part of a Clash of Clans related project.
+#[cfg(not(feature = "codegen-classes"))]
 #[derive(PartialEq, Eq)]
 #[must_use]
 #[repr(u8)]
@@ -9,6 +16,7 @@ pub enum ClashClass {
     GoldStorage = 1,
 }
 
+#[cfg(not(feature = "codegen-classes"))]
 impl ClashClass {
     /// Returns the string representation of the `ClashClass` variant.
     #[inline]
@@ -36,6 +44,24 @@ impl ClashClass {
         }
     }
 
+    /// Returns the class group of the `ClashClass` variant.
+    #[inline]
+    #[must_use]
+    pub const fn group(&self) -> ClassGroup {
+        match self {
+            Self::ElixirStorage | Self::GoldStorage => ClassGroup::Resources,
+        }
+    }
+
+    /// Returns the side of the square footprint of the `ClashClass` variant, in tiles.
+    #[inline]
+    #[must_use]
+    pub const fn footprint(&self) -> u32 {
+        match self {
+            Self::ElixirStorage | Self::GoldStorage => 3,
+        }
+    }
+
     /// Returns a static slice of RGB colors corresponding to the `ClashClass` variants.
     #[must_use]
     pub fn rgb_colors() -> &'static [(u8, u8, u8, u8)] {
@@ -45,6 +71,17 @@ impl ClashClass {
         ];
         &COLORS
     }
+}
+
+impl ClashClass {
+    /// Returns the display name of a class id, falling back to `Class <id>` for unknown ids.
+    #[must_use]
+    pub fn display_name(class_id: usize) -> String {
+        Self::try_from(class_id).map_or_else(
+            |()| format!("Class {class_id}"),
+            |class| class.as_str().to_string(),
+        )
+    }
 
     /// Returns the number of `ClashClass` variants.
     #[inline]
@@ -66,9 +103,22 @@ impl Into<usize> for ClashClass {
     }
 }
 
+#[cfg(not(feature = "codegen-classes"))]
+impl TryFrom<usize> for ClashClass {
+    type Error = ();
+
+    fn try_from(value: usize) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::ElixirStorage),
+            1 => Ok(Self::GoldStorage),
+            _ => Err(()),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::ClashClass;
+    use super::{ClashClass, ClassGroup};
 
     #[test]
     fn test_as_str() {
@@ -104,6 +154,25 @@ mod tests {
         assert_eq!(colors[1], (212, 175, 55, 255));
     }
 
+    #[test]
+    fn test_try_from_usize() {
+        assert_eq!(ClashClass::try_from(0).unwrap(), ClashClass::ElixirStorage);
+        assert_eq!(ClashClass::try_from(1).unwrap(), ClashClass::GoldStorage);
+        assert!(ClashClass::try_from(2).is_err());
+    }
+
+    #[test]
+    fn test_group() {
+        assert_eq!(ClashClass::ElixirStorage.group(), ClassGroup::Resources);
+        assert_eq!(ClashClass::GoldStorage.group(), ClassGroup::Resources);
+    }
+
+    #[test]
+    fn test_display_name() {
+        assert_eq!(ClashClass::display_name(1), "Gold Storage");
+        assert_eq!(ClashClass::display_name(7), "Class 7");
+    }
+
     #[test]
     fn test_num_classes() {
         assert_eq!(ClashClass::num_classes(), 2);
@@ -0,0 +1,216 @@
+//! Class groups (buildings, resources, defenses, troops, spells) and their hierarchy
+
+use crate::class::clash_class::ClashClass;
+use crate::detection::BoundingBox;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::str::FromStr;
+
+/// Errors that can occur while parsing class groups
+#[derive(Debug, thiserror::Error)]
+pub enum GroupError {
+    #[error("Invalid group JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Invalid class id in group map: {0}")]
+    InvalidClassId(String),
+    #[error("Unknown class group: {0}")]
+    UnknownGroup(String),
+}
+
+/// Group of classes, nested under a parent group where one exists
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClassGroup {
+    Buildings,
+    Resources,
+    Defenses,
+    Troops,
+    Spells,
+}
+
+impl ClassGroup {
+    /// Returns the string representation of the `ClassGroup` variant.
+    #[inline]
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Buildings => "buildings",
+            Self::Resources => "resources",
+            Self::Defenses => "defenses",
+            Self::Troops => "troops",
+            Self::Spells => "spells",
+        }
+    }
+
+    /// Returns a static slice of all `ClassGroup` variants.
+    #[must_use]
+    pub const fn values() -> &'static [Self] {
+        &[
+            Self::Buildings,
+            Self::Resources,
+            Self::Defenses,
+            Self::Troops,
+            Self::Spells,
+        ]
+    }
+
+    /// Returns the group this group is nested under
+    #[inline]
+    #[must_use]
+    pub const fn parent(&self) -> Option<Self> {
+        match self {
+            Self::Resources | Self::Defenses => Some(Self::Buildings),
+            Self::Buildings | Self::Troops | Self::Spells => None,
+        }
+    }
+
+    /// Returns true if this group is `group` or nested under it
+    #[must_use]
+    pub fn is_within(&self, group: Self) -> bool {
+        std::iter::successors(Some(*self), Self::parent).any(|g| g == group)
+    }
+}
+
+impl fmt::Display for ClassGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ClassGroup {
+    type Err = GroupError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::values()
+            .iter()
+            .copied()
+            .find(|group| group.as_str().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| GroupError::UnknownGroup(s.to_string()))
+    }
+}
+
+/// Group metadata of a label map, from class id to group.
+///
+/// Group maps are JSON objects from class id to group name, e.g. `{"0": "resources"}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassGroups {
+    groups: HashMap<usize, ClassGroup>,
+}
+
+impl Default for ClassGroups {
+    fn default() -> Self {
+        let groups = ClashClass::values()
+            .iter()
+            .enumerate()
+            .map(|(class_id, class)| (class_id, class.group()))
+            .collect();
+        Self { groups }
+    }
+}
+
+impl ClassGroups {
+    /// Parses a JSON group map
+    pub fn from_json(json: &str) -> Result<Self, GroupError> {
+        let raw: HashMap<String, String> = serde_json::from_str(json)?;
+        let groups = raw
+            .into_iter()
+            .map(|(id, group)| {
+                let id = id
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|_| GroupError::InvalidClassId(id))?;
+                Ok((id, group.parse()?))
+            })
+            .collect::<Result<_, GroupError>>()?;
+        Ok(Self { groups })
+    }
+
+    /// Sets the group of a class id
+    pub fn insert(&mut self, class_id: usize, group: ClassGroup) {
+        self.groups.insert(class_id, group);
+    }
+
+    /// Returns the group of a class id, if known
+    #[inline]
+    #[must_use]
+    pub fn group(&self, class_id: usize) -> Option<ClassGroup> {
+        self.groups.get(&class_id).copied()
+    }
+
+    /// Returns true if the class of `bbox` belongs to `group` or a group nested under it
+    #[must_use]
+    pub fn contains(&self, group: ClassGroup, bbox: &BoundingBox) -> bool {
+        self.group(bbox.class_id)
+            .is_some_and(|class_group| class_group.is_within(group))
+    }
+
+    /// Keeps the boxes whose class belongs to `group`
+    #[must_use]
+    pub fn filter(&self, boxes: &[BoundingBox], group: ClassGroup) -> Vec<BoundingBox> {
+        boxes
+            .iter()
+            .filter(|bbox| self.contains(group, bbox))
+            .copied()
+            .collect()
+    }
+
+    /// Counts the boxes of each group, counting nested groups towards their parents too
+    #[must_use]
+    pub fn counts(&self, boxes: &[BoundingBox]) -> BTreeMap<ClassGroup, usize> {
+        let mut counts = BTreeMap::new();
+        for group in boxes.iter().filter_map(|bbox| self.group(bbox.class_id)) {
+            for group in std::iter::successors(Some(group), ClassGroup::parent) {
+                *counts.entry(group).or_insert(0) += 1;
+            }
+        }
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hierarchy() {
+        assert!(ClassGroup::Resources.is_within(ClassGroup::Buildings));
+        assert!(ClassGroup::Resources.is_within(ClassGroup::Resources));
+        assert!(!ClassGroup::Buildings.is_within(ClassGroup::Resources));
+        assert!(!ClassGroup::Troops.is_within(ClassGroup::Buildings));
+        assert_eq!(
+            "Defenses".parse::<ClassGroup>().unwrap(),
+            ClassGroup::Defenses
+        );
+        assert!("walls".parse::<ClassGroup>().is_err());
+    }
+
+    #[test]
+    fn test_builtin_groups_filter_and_count() {
+        let groups = ClassGroups::default();
+        let boxes = [
+            BoundingBox::new(0.0, 0.0, 1.0, 1.0, 0, 0.9),
+            BoundingBox::new(0.0, 0.0, 1.0, 1.0, 1, 0.8),
+            BoundingBox::new(0.0, 0.0, 1.0, 1.0, 7, 0.7),
+        ];
+
+        assert_eq!(groups.group(0), Some(ClassGroup::Resources));
+        assert_eq!(groups.filter(&boxes, ClassGroup::Buildings).len(), 2);
+        assert!(groups.filter(&boxes, ClassGroup::Troops).is_empty());
+
+        let counts = groups.counts(&boxes);
+        assert_eq!(counts[&ClassGroup::Resources], 2);
+        assert_eq!(counts[&ClassGroup::Buildings], 2);
+        assert_eq!(counts.len(), 2);
+    }
+
+    #[test]
+    fn test_from_json() {
+        let groups = ClassGroups::from_json(r#"{"2": "troops", "3": "Spells"}"#).unwrap();
+        assert_eq!(groups.group(2), Some(ClassGroup::Troops));
+        assert_eq!(groups.group(3), Some(ClassGroup::Spells));
+        assert_eq!(groups.group(0), None);
+        assert!(ClassGroups::from_json(r#"{"x": "troops"}"#).is_err());
+        assert!(ClassGroups::from_json(r#"{"1": "walls"}"#).is_err());
+    }
+}
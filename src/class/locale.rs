@@ -0,0 +1,175 @@
+//! Localized class display names loaded from per-locale JSON maps
+
+use crate::class::registry::ClassRegistry;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Locales bundled with the crate, as `(locale, JSON name map)`
+const BUILTIN_LOCALES: [(&str, &str); 3] = [
+    ("de", include_str!("../../locales/de.json")),
+    ("es", include_str!("../../locales/es.json")),
+    ("fr", include_str!("../../locales/fr.json")),
+];
+
+/// Errors that can occur while loading a locale
+#[derive(Debug, thiserror::Error)]
+pub enum LocaleError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid locale JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Invalid class id in locale: {0}")]
+    InvalidClassId(String),
+    #[error("Unknown locale: {0}")]
+    UnknownLocale(String),
+}
+
+/// Class display names of a locale.
+///
+/// Name maps are JSON objects from class id to name, e.g. `{"0": "Elixierspeicher"}`.
+/// Classes missing from the map fall back to their English name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassNames {
+    locale: String,
+    names: HashMap<usize, String>,
+}
+
+impl Default for ClassNames {
+    fn default() -> Self {
+        Self {
+            locale: "en".to_string(),
+            names: HashMap::new(),
+        }
+    }
+}
+
+impl ClassNames {
+    /// Parses a JSON name map for `locale`
+    pub fn from_json(locale: impl Into<String>, json: &str) -> Result<Self, LocaleError> {
+        let raw: HashMap<String, String> = serde_json::from_str(json)?;
+        let names = raw
+            .into_iter()
+            .map(|(id, name)| {
+                id.trim()
+                    .parse::<usize>()
+                    .map(|id| (id, name))
+                    .map_err(|_| LocaleError::InvalidClassId(id))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            locale: locale.into(),
+            names,
+        })
+    }
+
+    /// Loads a JSON name map, named after its locale (e.g. `fr.json`)
+    pub fn load(path: &Path) -> Result<Self, LocaleError> {
+        let locale = path
+            .file_stem()
+            .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+        Self::from_json(locale, &fs::read_to_string(path)?)
+    }
+
+    /// Returns the names of a bundled locale (`en`, `de`, `es` or `fr`)
+    pub fn builtin(locale: &str) -> Result<Self, LocaleError> {
+        if locale == "en" {
+            return Ok(Self::default());
+        }
+        BUILTIN_LOCALES
+            .iter()
+            .find(|(name, _)| *name == locale)
+            .ok_or_else(|| LocaleError::UnknownLocale(locale.to_string()))
+            .and_then(|(name, json)| Self::from_json(*name, json))
+    }
+
+    /// Resolves a bundled locale name, or else a path to a JSON name map
+    pub fn resolve(locale_or_path: &str) -> Result<Self, LocaleError> {
+        match Self::builtin(locale_or_path) {
+            Err(LocaleError::UnknownLocale(_)) => Self::load(Path::new(locale_or_path)),
+            result => result,
+        }
+    }
+
+    /// Returns the locale of the names
+    #[inline]
+    #[must_use]
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// Returns the localized display name of a class id
+    #[must_use]
+    pub fn name(&self, class_id: usize) -> String {
+        self.name_or(class_id, &ClassRegistry::default())
+    }
+
+    /// Returns the localized display name of a class id, falling back to its name in `classes`
+    #[must_use]
+    pub fn name_or(&self, class_id: usize, classes: &ClassRegistry) -> String {
+        self.names
+            .get(&class_id)
+            .cloned()
+            .unwrap_or_else(|| classes.name(class_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::class::clash_class::ClashClass;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_builtin_locales_cover_every_class() {
+        for (locale, _) in BUILTIN_LOCALES {
+            let names = ClassNames::builtin(locale).unwrap();
+            assert_eq!(names.locale(), locale);
+            for class_id in 0..ClashClass::num_classes() {
+                assert_ne!(
+                    names.name(class_id),
+                    ClashClass::display_name(class_id),
+                    "{locale}"
+                );
+            }
+        }
+        assert!(matches!(
+            ClassNames::builtin("xx"),
+            Err(LocaleError::UnknownLocale(_))
+        ));
+    }
+
+    #[test]
+    fn test_fallback_to_english() {
+        let names = ClassNames::from_json("fr", r#"{"1": "Réserve d'or"}"#).unwrap();
+        assert_eq!(names.name(1), "Réserve d'or");
+        assert_eq!(names.name(0), "Elixir Storage");
+        assert_eq!(names.name(9), "Class 9");
+        assert_eq!(ClassNames::default().name(0), "Elixir Storage");
+        let classes = ClassRegistry::new(["Town Hall", "Cannon"]);
+        assert_eq!(names.name_or(0, &classes), "Town Hall");
+        assert_eq!(names.name_or(1, &classes), "Réserve d'or");
+    }
+
+    #[test]
+    fn test_invalid_class_id() {
+        assert!(matches!(
+            ClassNames::from_json("fr", r#"{"gold": "Réserve d'or"}"#),
+            Err(LocaleError::InvalidClassId(id)) if id == "gold"
+        ));
+    }
+
+    #[test]
+    fn test_resolve_path() -> Result<(), LocaleError> {
+        let dir = tempdir()?;
+        let path = dir.path().join("it.json");
+        fs::write(&path, r#"{"0": "Deposito di elisir"}"#)?;
+
+        let names = ClassNames::resolve(path.to_str().unwrap())?;
+        assert_eq!(names.locale(), "it");
+        assert_eq!(names.name(0), "Deposito di elisir");
+        assert_eq!(ClassNames::resolve("de")?.name(1), "Goldspeicher");
+        Ok(())
+    }
+}
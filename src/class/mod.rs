@@ -1 +1,6 @@
 pub mod clash_class;
+pub mod group;
+pub mod locale;
+pub mod overrides;
+pub mod palette;
+pub mod registry;
@@ -0,0 +1,185 @@
+//! Runtime overrides of class names and colors, applied on top of the compiled defaults or a
+//! names file without rebuilding the crate
+//!
+//! Override files are JSON or TOML objects with optional `names` and `colors` tables keyed by
+//! class id, e.g. `{"names": {"1": "Gold"}, "colors": {"1": "#ffd700"}}` or:
+//!
+//! ```toml
+//! [names]
+//! 1 = "Gold"
+//!
+//! [colors]
+//! 1 = [255, 215, 0]
+//! ```
+
+use crate::class::registry::ClassRegistry;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Environment variable pointing to an overrides file
+pub const OVERRIDES_ENV: &str = "CLASHVISION_CLASS_OVERRIDES";
+
+/// Errors that can occur while loading class overrides
+#[derive(Debug, thiserror::Error)]
+pub enum OverrideError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid overrides JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Invalid overrides TOML: {0}")]
+    Toml(#[from] toml::de::Error),
+    #[error("Invalid class id in overrides: {0}")]
+    InvalidClassId(String),
+    #[error("Invalid color of class {0}, expected \"#rrggbb\" or [r, g, b]")]
+    InvalidColor(usize),
+}
+
+/// Color written as `"#rrggbb"`, `"#rrggbbaa"` or a list of 3 or 4 channels
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawColor {
+    Hex(String),
+    Channels(Vec<u8>),
+}
+
+impl RawColor {
+    /// Parses the color as RGBA, opaque unless an alpha channel is given
+    fn parse(self) -> Option<(u8, u8, u8, u8)> {
+        let channels = match self {
+            Self::Hex(hex) => {
+                let hex = hex.trim().trim_start_matches('#');
+                (0..hex.len())
+                    .step_by(2)
+                    .map(|i| {
+                        hex.get(i..i + 2)
+                            .and_then(|c| u8::from_str_radix(c, 16).ok())
+                    })
+                    .collect::<Option<Vec<u8>>>()?
+            }
+            Self::Channels(channels) => channels,
+        };
+        match channels[..] {
+            [r, g, b] => Some((r, g, b, 255)),
+            [r, g, b, a] => Some((r, g, b, a)),
+            _ => None,
+        }
+    }
+}
+
+/// Overrides file as written, keyed by class id strings
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct RawOverrides {
+    names: HashMap<String, String>,
+    colors: HashMap<String, RawColor>,
+}
+
+/// Names and colors replacing the ones of some class ids
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClassOverrides {
+    pub names: BTreeMap<usize, String>,
+    pub colors: BTreeMap<usize, (u8, u8, u8, u8)>,
+}
+
+impl ClassOverrides {
+    /// Parses JSON overrides
+    pub fn from_json(json: &str) -> Result<Self, OverrideError> {
+        Self::from_raw(serde_json::from_str(json)?)
+    }
+
+    /// Parses TOML overrides
+    pub fn from_toml(toml: &str) -> Result<Self, OverrideError> {
+        Self::from_raw(toml::from_str(toml)?)
+    }
+
+    /// Loads an overrides file, parsed as TOML for a `.toml` extension and as JSON otherwise
+    pub fn load(path: &Path) -> Result<Self, OverrideError> {
+        let content = fs::read_to_string(path)?;
+        if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("toml"))
+        {
+            Self::from_toml(&content)
+        } else {
+            Self::from_json(&content)
+        }
+    }
+
+    /// Returns the path of the overrides file named by `CLASHVISION_CLASS_OVERRIDES`, if set
+    #[must_use]
+    pub fn env_path() -> Option<PathBuf> {
+        std::env::var_os(OVERRIDES_ENV)
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+    }
+
+    /// Renames and recolors the classes of `registry`, ignoring unknown class ids
+    #[must_use]
+    pub fn apply(&self, registry: ClassRegistry) -> ClassRegistry {
+        let registry = self.names.iter().fold(registry, |registry, (&id, name)| {
+            registry.with_name(id, name)
+        });
+        self.colors
+            .iter()
+            .fold(registry, |registry, (&id, &color)| {
+                registry.with_color(id, color)
+            })
+    }
+
+    fn from_raw(raw: RawOverrides) -> Result<Self, OverrideError> {
+        let class_id = |id: String| {
+            id.trim()
+                .parse::<usize>()
+                .map_err(|_| OverrideError::InvalidClassId(id))
+        };
+        let names = raw
+            .names
+            .into_iter()
+            .map(|(id, name)| Ok((class_id(id)?, name)))
+            .collect::<Result<_, OverrideError>>()?;
+        let colors = raw
+            .colors
+            .into_iter()
+            .map(|(id, color)| {
+                let id = class_id(id)?;
+                let color = color.parse().ok_or(OverrideError::InvalidColor(id))?;
+                Ok((id, color))
+            })
+            .collect::<Result<_, OverrideError>>()?;
+        Ok(Self { names, colors })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overrides_from_json_and_toml() -> Result<(), OverrideError> {
+        let json = ClassOverrides::from_json(
+            r##"{"names": {"1": "Gold"}, "colors": {"0": "#ff000080", "1": [255, 215, 0]}}"##,
+        )?;
+        let toml = ClassOverrides::from_toml(
+            "[names]\n1 = \"Gold\"\n\n[colors]\n0 = \"#ff000080\"\n1 = \"#ffd700\"\n",
+        )?;
+        assert_eq!(json, toml);
+        assert_eq!(json.colors[&0], (255, 0, 0, 128));
+
+        let registry = json.apply(ClassRegistry::default());
+        assert_eq!(registry.name(0), "Elixir Storage");
+        assert_eq!(registry.name(1), "Gold");
+        assert_eq!(registry.color(1), (255, 215, 0, 255));
+
+        assert!(matches!(
+            ClassOverrides::from_json(r#"{"names": {"gold": "Gold"}}"#),
+            Err(OverrideError::InvalidClassId(_))
+        ));
+        assert!(matches!(
+            ClassOverrides::from_json(r##"{"colors": {"1": "#ffd7"}}"##),
+            Err(OverrideError::InvalidColor(1))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,192 @@
+//! Class color palettes, including presets safe for red-green color blindness
+
+use std::fmt;
+use std::str::FromStr;
+
+/// Okabe-Ito colors, distinguishable under the common color vision deficiencies
+const SAFE_COLORS: [(u8, u8, u8); 8] = [
+    (230, 159, 0),   // Orange
+    (86, 180, 233),  // Sky blue
+    (0, 158, 115),   // Bluish green
+    (240, 228, 66),  // Yellow
+    (0, 114, 178),   // Blue
+    (213, 94, 0),    // Vermillion
+    (204, 121, 167), // Reddish purple
+    (255, 255, 255), // White
+];
+
+/// Viénot, Brettel and Mollon (1999) projection of linear RGB for protanopes
+const PROTANOPIA: [[f32; 3]; 3] = [
+    [0.112_38, 0.887_62, 0.0],
+    [0.112_38, 0.887_62, 0.0],
+    [0.004_01, -0.004_01, 1.0],
+];
+
+/// Viénot, Brettel and Mollon (1999) projection of linear RGB for deuteranopes
+const DEUTERANOPIA: [[f32; 3]; 3] = [
+    [0.292_75, 0.707_25, 0.0],
+    [0.292_75, 0.707_25, 0.0],
+    [-0.022_34, 0.022_34, 1.0],
+];
+
+/// Colors used for the classes of drawn boxes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Palette {
+    /// Colors of the class registry
+    #[default]
+    Registry,
+    /// Colors kept apart for viewers with deuteranopia (green-blind)
+    Deuteranopia,
+    /// Colors kept apart for viewers with protanopia (red-blind)
+    Protanopia,
+}
+
+impl Palette {
+    /// Returns the string representation of the `Palette` variant.
+    #[inline]
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Registry => "default",
+            Self::Deuteranopia => "deuteranopia",
+            Self::Protanopia => "protanopia",
+        }
+    }
+
+    /// Returns a static slice of all `Palette` variants.
+    #[must_use]
+    pub const fn values() -> &'static [Self] {
+        &[Self::Registry, Self::Deuteranopia, Self::Protanopia]
+    }
+
+    /// Returns the colors of `count` classes, or `None` to keep the registry colors.
+    ///
+    /// Each color is the safe color farthest from those already picked as seen with the
+    /// deficiency, so that the first classes get the most distinguishable colors.
+    #[must_use]
+    pub fn colors(&self, count: usize) -> Option<Vec<(u8, u8, u8, u8)>> {
+        let projection = match self {
+            Self::Registry => return None,
+            Self::Deuteranopia => &DEUTERANOPIA,
+            Self::Protanopia => &PROTANOPIA,
+        };
+
+        let mut remaining: Vec<(u8, u8, u8)> = SAFE_COLORS.to_vec();
+        let mut ordered = vec![remaining.remove(0)];
+        while !remaining.is_empty() {
+            let (index, _) = remaining
+                .iter()
+                .enumerate()
+                .map(|(i, &candidate)| {
+                    let nearest = ordered
+                        .iter()
+                        .map(|&picked| perceived_distance(projection, candidate, picked))
+                        .fold(f32::MAX, f32::min);
+                    (i, nearest)
+                })
+                .fold(
+                    (0, f32::MIN),
+                    |best, item| {
+                        if item.1 > best.1 { item } else { best }
+                    },
+                );
+            ordered.push(remaining.remove(index));
+        }
+
+        Some(
+            ordered
+                .iter()
+                .cycle()
+                .take(count)
+                .map(|&(r, g, b)| (r, g, b, 255))
+                .collect(),
+        )
+    }
+}
+
+impl fmt::Display for Palette {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Palette {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::values()
+            .iter()
+            .copied()
+            .find(|palette| palette.as_str().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| format!("Unknown palette: {s}"))
+    }
+}
+
+/// Distance between two sRGB colors as perceived through a color vision deficiency projection
+#[must_use]
+pub fn perceived_distance(projection: &[[f32; 3]; 3], a: (u8, u8, u8), b: (u8, u8, u8)) -> f32 {
+    let (a, b) = (simulate(projection, a), simulate(projection, b));
+    a.iter()
+        .zip(&b)
+        .map(|(x, y)| (x - y).powi(2))
+        .sum::<f32>()
+        .sqrt()
+}
+
+/// Simulates how a color is seen through a projection, as gamma-encoded channels in `[0, 1]`
+fn simulate(projection: &[[f32; 3]; 3], (r, g, b): (u8, u8, u8)) -> [f32; 3] {
+    let linear = [r, g, b].map(|c| {
+        let c = f32::from(c) / 255.0;
+        if c <= 0.040_45 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    });
+    projection.map(|row| {
+        let c = (row[0] * linear[0] + row[1] * linear[1] + row[2] * linear[2]).clamp(0.0, 1.0);
+        if c <= 0.003_130_8 {
+            c * 12.92
+        } else {
+            1.055 * c.powf(1.0 / 2.4) - 0.055
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::class::clash_class::ClashClass;
+
+    fn rgb((r, g, b, _): (u8, u8, u8, u8)) -> (u8, u8, u8) {
+        (r, g, b)
+    }
+
+    #[test]
+    fn test_presets_separate_classes_better_than_builtin_colors() {
+        let builtin = ClashClass::rgb_colors();
+        for (palette, projection) in [
+            (Palette::Deuteranopia, &DEUTERANOPIA),
+            (Palette::Protanopia, &PROTANOPIA),
+        ] {
+            let colors = palette.colors(2).unwrap();
+            assert_eq!(colors.len(), 2);
+            assert!(
+                perceived_distance(projection, rgb(colors[0]), rgb(colors[1]))
+                    > perceived_distance(projection, rgb(builtin[0]), rgb(builtin[1]))
+            );
+        }
+    }
+
+    #[test]
+    fn test_colors_cycle_and_parse() {
+        assert_eq!(Palette::Registry.colors(3), None);
+        let colors = Palette::Protanopia.colors(10).unwrap();
+        assert_eq!(colors.len(), 10);
+        assert_eq!(colors[0], colors[SAFE_COLORS.len()]);
+
+        assert_eq!("Deuteranopia".parse(), Ok(Palette::Deuteranopia));
+        assert_eq!("default".parse(), Ok(Palette::Registry));
+        assert!("tritanopia".parse::<Palette>().is_err());
+    }
+}
@@ -0,0 +1,321 @@
+//! Class names and colors of the model, built-in or loaded from a names file
+
+use crate::class::clash_class::ClashClass;
+use crate::class::locale::ClassNames;
+use crate::class::palette::Palette;
+use crate::image::image_util::generate_distinct_colors;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+/// Color of classes without a registered color
+pub const FALLBACK_COLOR: (u8, u8, u8, u8) = (128, 16, 64, 255);
+
+/// Errors that can occur while loading a class registry
+#[derive(Debug, thiserror::Error)]
+pub enum RegistryError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid names JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Invalid names YAML: {0}")]
+    Yaml(#[from] serde_yaml_ng::Error),
+    #[error("Invalid class id in names: {0}")]
+    InvalidClassId(String),
+    #[error("Class ids must be contiguous from 0, missing {0}")]
+    MissingClassId(usize),
+}
+
+/// Names listed as a sequence or as a map from class id to name
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawNames {
+    List(Vec<String>),
+    Map(HashMap<RawClassId, String>),
+}
+
+/// Class id key, an integer in YAML and a string in JSON
+#[derive(Deserialize, PartialEq, Eq, Hash)]
+#[serde(untagged)]
+enum RawClassId {
+    Index(usize),
+    Text(String),
+}
+
+impl RawClassId {
+    /// Parses the key as a class id
+    fn parse(self) -> Result<usize, RegistryError> {
+        match self {
+            Self::Index(id) => Ok(id),
+            Self::Text(id) => id
+                .trim()
+                .parse()
+                .map_err(|_| RegistryError::InvalidClassId(id)),
+        }
+    }
+}
+
+/// Ultralytics `data.yaml` dataset description, of which only the names are used
+#[derive(Deserialize)]
+struct DataYaml {
+    names: RawNames,
+}
+
+/// Names and colors of the classes a model detects, indexed by class id.
+///
+/// Names files are either JSON (`["Elixir Storage", "Gold Storage"]` or `{"0": "Elixir Storage"}`)
+/// or an Ultralytics `data.yaml` with a `names` list or map.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassRegistry {
+    names: Vec<String>,
+    colors: Vec<(u8, u8, u8, u8)>,
+    translations: Vec<ClassNames>,
+}
+
+impl Default for ClassRegistry {
+    fn default() -> Self {
+        Self {
+            names: ClashClass::values()
+                .iter()
+                .map(|class| class.as_str().to_string())
+                .collect(),
+            colors: ClashClass::rgb_colors().to_vec(),
+            translations: Vec::new(),
+        }
+    }
+}
+
+impl ClassRegistry {
+    /// Creates a registry from class names ordered by id, with evenly spread colors
+    pub fn new<S: Into<String>>(names: impl IntoIterator<Item = S>) -> Self {
+        let names: Vec<String> = names.into_iter().map(Into::into).collect();
+        let colors = generate_distinct_colors(names.len())
+            .into_iter()
+            .map(|color| (color.r, color.g, color.b, color.a))
+            .collect();
+        Self {
+            names,
+            colors,
+            translations: Vec::new(),
+        }
+    }
+
+    /// Renames a class id, ignored for unknown ids
+    #[must_use]
+    pub fn with_name(mut self, class_id: usize, name: impl Into<String>) -> Self {
+        if let Some(slot) = self.names.get_mut(class_id) {
+            *slot = name.into();
+        }
+        self
+    }
+
+    /// Sets the color of a class id, ignored for unknown ids
+    #[must_use]
+    pub fn with_color(mut self, class_id: usize, color: (u8, u8, u8, u8)) -> Self {
+        if let Some(slot) = self.colors.get_mut(class_id) {
+            *slot = color;
+        }
+        self
+    }
+
+    /// Adds the localized names of a locale, written alongside the names in the COCO
+    /// categories; replaces the names added before for the same locale
+    #[must_use]
+    pub fn with_translation(mut self, names: ClassNames) -> Self {
+        self.translations
+            .retain(|translation| translation.locale() != names.locale());
+        self.translations.push(names);
+        self
+    }
+
+    /// Recolors the classes after a palette preset, keeping the colors for `Palette::Registry`
+    #[must_use]
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        if let Some(colors) = palette.colors(self.names.len()) {
+            self.colors = colors;
+        }
+        self
+    }
+
+    /// Parses a JSON names list or map
+    pub fn from_json(json: &str) -> Result<Self, RegistryError> {
+        Self::from_raw(serde_json::from_str(json)?)
+    }
+
+    /// Parses the `names` of an Ultralytics `data.yaml`
+    pub fn from_yaml(yaml: &str) -> Result<Self, RegistryError> {
+        let data: DataYaml = serde_yaml_ng::from_str(yaml)?;
+        Self::from_raw(data.names)
+    }
+
+    /// Loads a names file, parsed as JSON for a `.json` extension and as YAML otherwise
+    pub fn load(path: &Path) -> Result<Self, RegistryError> {
+        let content = fs::read_to_string(path)?;
+        if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+        {
+            Self::from_json(&content)
+        } else {
+            Self::from_yaml(&content)
+        }
+    }
+
+    /// Builds a registry from parsed names, requiring map ids to be contiguous from 0
+    fn from_raw(raw: RawNames) -> Result<Self, RegistryError> {
+        let names: Vec<String> = match raw {
+            RawNames::List(names) => names,
+            RawNames::Map(map) => map
+                .into_iter()
+                .map(|(id, name)| Ok((id.parse()?, name)))
+                .collect::<Result<BTreeMap<usize, String>, RegistryError>>()?
+                .into_iter()
+                .enumerate()
+                .map(|(expected, (id, name))| {
+                    if id == expected {
+                        Ok(name)
+                    } else {
+                        Err(RegistryError::MissingClassId(expected))
+                    }
+                })
+                .collect::<Result<_, _>>()?,
+        };
+        Ok(Self::new(names))
+    }
+
+    /// Returns the number of classes
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Returns true if the registry has no classes
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Returns the class names ordered by id
+    #[inline]
+    #[must_use]
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Returns the localized names added with `with_translation`
+    #[inline]
+    #[must_use]
+    pub fn translations(&self) -> &[ClassNames] {
+        &self.translations
+    }
+
+    /// Returns the name of a class id, if known
+    #[inline]
+    #[must_use]
+    pub fn get(&self, class_id: usize) -> Option<&str> {
+        self.names.get(class_id).map(String::as_str)
+    }
+
+    /// Returns the display name of a class id, falling back to `Class <id>` for unknown ids
+    #[must_use]
+    pub fn name(&self, class_id: usize) -> String {
+        self.get(class_id)
+            .map_or_else(|| format!("Class {class_id}"), str::to_string)
+    }
+
+    /// Returns the built-in class a class id stands for, matched by name, so models trained
+    /// on other label sets still resolve the classes the built-in analyses know about
+    #[must_use]
+    pub fn clash_class(&self, class_id: usize) -> Option<&'static ClashClass> {
+        let name = self.get(class_id)?.replace('_', " ");
+        ClashClass::values()
+            .iter()
+            .find(|class| class.as_str().eq_ignore_ascii_case(&name))
+    }
+
+    /// Returns true if any class id stands for a built-in resource storage
+    #[must_use]
+    pub fn has_storage_classes(&self) -> bool {
+        (0..self.len()).any(|class_id| self.clash_class(class_id).is_some())
+    }
+
+    /// Returns the RGBA color of a class id, falling back to `FALLBACK_COLOR`
+    #[inline]
+    #[must_use]
+    pub fn color(&self, class_id: usize) -> (u8, u8, u8, u8) {
+        self.colors.get(class_id).copied().unwrap_or(FALLBACK_COLOR)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_default_matches_builtin_classes() {
+        let registry = ClassRegistry::default();
+        assert_eq!(registry.len(), ClashClass::num_classes());
+        assert_eq!(registry.name(1), "Gold Storage");
+        assert_eq!(registry.name(7), "Class 7");
+        assert_eq!(registry.color(0), ClashClass::ElixirStorage.to_rgba());
+        assert_eq!(registry.color(7), FALLBACK_COLOR);
+        assert_eq!(registry.clash_class(1), Some(&ClashClass::GoldStorage));
+        assert!(registry.has_storage_classes());
+    }
+
+    #[test]
+    fn test_clash_class_matches_names() {
+        let registry = ClassRegistry::from_json(r#"["Town Hall", "gold_storage"]"#).unwrap();
+        assert_eq!(registry.clash_class(0), None);
+        assert_eq!(registry.clash_class(1), Some(&ClashClass::GoldStorage));
+        assert_eq!(registry.clash_class(7), None);
+
+        let registry = ClassRegistry::from_json(r#"["Town Hall", "Cannon"]"#).unwrap();
+        assert!(!registry.has_storage_classes());
+    }
+
+    #[test]
+    fn test_from_json_list_and_map() {
+        let list = ClassRegistry::from_json(r#"["Town Hall", "Cannon", "Mortar"]"#).unwrap();
+        assert_eq!(list.names(), ["Town Hall", "Cannon", "Mortar"]);
+        assert_ne!(list.color(0), list.color(1));
+
+        let map = ClassRegistry::from_json(r#"{"1": "Cannon", "0": "Town Hall"}"#).unwrap();
+        assert_eq!(map.names(), ["Town Hall", "Cannon"]);
+        assert!(matches!(
+            ClassRegistry::from_json(r#"{"0": "Town Hall", "2": "Mortar"}"#),
+            Err(RegistryError::MissingClassId(1))
+        ));
+        assert!(matches!(
+            ClassRegistry::from_json(r#"{"gold": "Gold Storage"}"#),
+            Err(RegistryError::InvalidClassId(id)) if id == "gold"
+        ));
+    }
+
+    #[test]
+    fn test_load_data_yaml() -> Result<(), RegistryError> {
+        let dir = tempdir()?;
+        let path = dir.path().join("data.yaml");
+        fs::write(
+            &path,
+            "path: ../datasets/coc\ntrain: images/train\nnc: 2\nnames:\n  0: Town Hall\n  1: Cannon\n",
+        )?;
+        let registry = ClassRegistry::load(&path)?;
+        assert_eq!(registry.names(), ["Town Hall", "Cannon"]);
+
+        let registry = ClassRegistry::from_yaml("names: [Archer Tower, Wizard Tower]")?
+            .with_color(1, (1, 2, 3, 255));
+        assert_eq!(registry.get(0), Some("Archer Tower"));
+        assert_eq!(registry.color(1), (1, 2, 3, 255));
+
+        let recolored = registry.clone().with_palette(Palette::Deuteranopia);
+        assert_eq!(recolored.names(), registry.names());
+        assert_ne!(recolored.color(1), registry.color(1));
+        assert_eq!(registry.clone().with_palette(Palette::Registry), registry);
+        Ok(())
+    }
+}
@@ -0,0 +1,99 @@
+//! `clashvision.toml` configuration file seeding the CLI defaults
+
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of the configuration file looked up in the working directory
+pub const DEFAULT_CONFIG_PATH: &str = "clashvision.toml";
+
+/// Errors that can occur while loading the configuration file
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("Failed to read {0}: {1}")]
+    Io(PathBuf, std::io::Error),
+    #[error("Invalid config file {0}: {1}")]
+    Parse(PathBuf, toml::de::Error),
+}
+
+/// Values read from the configuration file, all optional
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FileConfig {
+    pub model: Option<PathBuf>,
+    pub model_type: Option<String>,
+    pub provider: Option<String>,
+    pub output_dir: Option<String>,
+    pub confidence_threshold: Option<f32>,
+    pub nms_threshold: Option<f32>,
+    pub locale: Option<String>,
+    pub classes: Option<PathBuf>,
+    pub class_overrides: Option<PathBuf>,
+    pub palette: Option<String>,
+    pub usage_stats: Option<PathBuf>,
+    pub watermark: Option<bool>,
+    pub output_format: Option<String>,
+    pub image_timeout: Option<f64>,
+}
+
+impl FileConfig {
+    /// Parses a configuration file
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let content =
+            fs::read_to_string(path).map_err(|e| ConfigError::Io(path.to_path_buf(), e))?;
+        toml::from_str(&content).map_err(|e| ConfigError::Parse(path.to_path_buf(), e))
+    }
+
+    /// Loads the explicitly requested file, or `clashvision.toml` if present, or an empty config
+    pub fn discover(explicit: Option<&Path>) -> Result<Self, ConfigError> {
+        match explicit {
+            Some(path) => Self::load(path),
+            None if Path::new(DEFAULT_CONFIG_PATH).is_file() => {
+                Self::load(Path::new(DEFAULT_CONFIG_PATH))
+            }
+            None => Ok(Self::default()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_load_config() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("clashvision.toml");
+        fs::write(
+            &path,
+            "model = \"models/custom.onnx\"\nconfidence_threshold = 0.4\noutput_dir = \"out\"\n",
+        )
+        .unwrap();
+
+        let config = FileConfig::load(&path).unwrap();
+        assert_eq!(config.model, Some(PathBuf::from("models/custom.onnx")));
+        assert_eq!(config.confidence_threshold, Some(0.4));
+        assert_eq!(config.output_dir.as_deref(), Some("out"));
+        assert!(config.nms_threshold.is_none());
+    }
+
+    #[test]
+    fn test_unknown_key_is_rejected() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("clashvision.toml");
+        fs::write(&path, "confidence = 0.4\n").unwrap();
+        assert!(matches!(
+            FileConfig::load(&path),
+            Err(ConfigError::Parse(..))
+        ));
+    }
+
+    #[test]
+    fn test_missing_explicit_file() {
+        assert!(matches!(
+            FileConfig::discover(Some(Path::new("missing.toml"))),
+            Err(ConfigError::Io(..))
+        ));
+    }
+}
@@ -0,0 +1,679 @@
+//! Command line interface of the `clashvision` binary
+
+pub mod config;
+#[cfg(feature = "tui")]
+pub mod tui;
+
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use clashvision::class::locale::ClassNames;
+use clashvision::class::overrides::ClassOverrides;
+use clashvision::class::palette::Palette;
+use clashvision::class::registry::ClassRegistry;
+use clashvision::detection::output::OutputFormat;
+use clashvision::model::yolo_type::YoloType;
+use clashvision::session::execution::ExecutionProvider;
+#[cfg(feature = "http")]
+use clashvision::sink::alert::AlertRule;
+use clashvision::source::file::DirectoryOptions;
+use clashvision::source::shard::Shard;
+use config::FileConfig;
+use std::io;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Detects Clash of Clans buildings in images with the embedded YOLO model
+#[derive(Debug, Parser)]
+#[command(name = "clashvision", version, about, subcommand_negates_reqs = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Images to process, as with `detect`
+    #[arg(required_unless_present_any = ["completions", "diff_manifests", "clipboard"])]
+    pub images: Vec<PathBuf>,
+
+    /// Process the image in the system clipboard, saved into the output directory first
+    /// (requires the `clipboard` feature)
+    #[arg(long)]
+    pub clipboard: bool,
+
+    /// Configuration file seeding the defaults [default: ./clashvision.toml if present]
+    #[arg(short, long, value_name = "PATH", global = true)]
+    pub config: Option<PathBuf>,
+
+    /// ONNX model to use instead of the embedded one
+    #[arg(short, long, value_name = "PATH", global = true)]
+    pub model: Option<PathBuf>,
+
+    /// Model architecture (yolov5, yolov8, yolov10, yolov11)
+    #[arg(long, global = true)]
+    pub model_type: Option<String>,
+
+    /// Execution provider (cpu, cuda, tensorrt, coreml, directml, openvino), falling back to the CPU [default: cpu]
+    #[arg(long, value_name = "PROVIDER", global = true)]
+    pub provider: Option<String>,
+
+    /// Directory receiving annotated images and detection files [default: output]
+    #[arg(short, long, global = true)]
+    pub output_dir: Option<String>,
+
+    /// Minimum confidence for detections [default: 0.25]
+    #[arg(long, value_name = "THRESHOLD", global = true)]
+    pub confidence: Option<f32>,
+
+    /// IoU threshold for non-maximum suppression [default: 0.45]
+    #[arg(long, value_name = "THRESHOLD", global = true)]
+    pub nms_threshold: Option<f32>,
+
+    /// Format of the detection files written next to annotated images (yolo, json, csv, voc, or a
+    /// registered custom format) [default: json]
+    #[arg(short, long, value_name = "FORMAT", global = true)]
+    pub format: Option<OutputFormat>,
+
+    /// Class names file of a retrained model (JSON list or map, or Ultralytics data.yaml)
+    #[arg(long, value_name = "PATH", global = true)]
+    pub classes: Option<PathBuf>,
+
+    /// Class colors (default, deuteranopia, protanopia), the presets staying distinguishable with color blindness [default: default]
+    #[arg(long)]
+    pub palette: Option<String>,
+
+    /// JSON or TOML file overriding the names and colors of some class ids, e.g.
+    /// '{"names": {"1": "Gold"}, "colors": {"1": "#ffd700"}}' [env: CLASHVISION_CLASS_OVERRIDES]
+    #[arg(long, value_name = "PATH")]
+    pub class_overrides: Option<PathBuf>,
+
+    /// Language of the class names (en, de, es, fr) or path to a JSON name map [default: en]
+    #[arg(long, value_name = "LOCALE")]
+    pub locale: Option<String>,
+
+    /// Rhai script run on the detections of every image, printing the values it emits as JSON lines
+    #[cfg(feature = "rhai")]
+    #[arg(long, value_name = "PATH")]
+    pub script: Option<PathBuf>,
+
+    /// Webhook (Discord or Slack) receiving an alert with the annotated image when an --alert rule fires
+    #[cfg(feature = "http")]
+    #[arg(long, value_name = "URL", requires = "alert")]
+    pub webhook: Option<String>,
+
+    /// Alert rule '<class> >= <count> [@ <confidence>]', e.g. 'Gold Storage>=4@0.6'; repeatable
+    #[cfg(feature = "http")]
+    #[arg(long, value_name = "RULE", requires = "webhook")]
+    pub alert: Vec<AlertRule>,
+
+    /// Collect the detections of the run into a Parquet file, one row per detection
+    #[cfg(feature = "arrow")]
+    #[arg(long, value_name = "PATH", conflicts_with = "resume")]
+    pub parquet: Option<PathBuf>,
+
+    /// Raise a desktop notification summarizing the detections once all images are processed
+    #[cfg(feature = "notify")]
+    #[arg(long)]
+    pub notify: bool,
+
+    /// Encrypt the detection files, summary, COCO exports and manifest with AES-256-GCM, using the
+    /// key of --key-file or of the CLASHVISION_OUTPUT_KEY environment variable (64 hex digits)
+    #[cfg(feature = "encryption")]
+    #[arg(long)]
+    pub encrypt: bool,
+
+    /// File holding the key of --encrypt and `decrypt`, as 64 hex digits or 32 raw bytes
+    #[cfg(feature = "encryption")]
+    #[arg(long, value_name = "PATH")]
+    pub key_file: Option<PathBuf>,
+
+    /// Follow the run in a terminal UI showing progress, class counts, recent errors and latencies
+    #[cfg(feature = "tui")]
+    #[arg(long, conflicts_with = "verbose")]
+    pub tui: bool,
+
+    /// Slice images larger than this many pixels into overlapping square tiles, each run through
+    /// the model, so small buildings of large screenshots are not lost to downscaling
+    #[arg(long, value_name = "PIXELS", value_parser = clap::value_parser!(u32).range(32..))]
+    pub tile: Option<u32>,
+
+    /// Fraction of a tile shared with its neighbours
+    #[arg(
+        long,
+        value_name = "FRACTION",
+        default_value_t = 0.2,
+        requires = "tile"
+    )]
+    pub tile_overlap: f32,
+
+    /// Draw the tile boundaries, detections per tile and the tile of every box on annotated images
+    #[arg(long, requires = "tile")]
+    pub tile_debug: bool,
+
+    /// Keep at most this many detections per image after NMS, the most confident first
+    #[arg(long, value_name = "N")]
+    pub max_detections: Option<usize>,
+
+    /// Keep at most this many detections of each class per image after NMS
+    #[arg(long, value_name = "K")]
+    pub top_k_per_class: Option<usize>,
+
+    /// Give up on an image once it has taken this many seconds, counting it as timed out
+    #[arg(long, value_name = "SECS")]
+    pub image_timeout: Option<f64>,
+
+    /// Estimate the fill level of gold and elixir storages, written as `fullness` in JSON outputs
+    #[arg(long)]
+    pub fullness: bool,
+
+    /// Stamp annotated images with a footer naming the model, time and configuration hash
+    #[arg(long)]
+    pub watermark: bool,
+
+    /// Only print errors
+    #[arg(short, long, conflicts_with = "verbose", global = true)]
+    pub quiet: bool,
+
+    /// Print the detections and step timings of every image
+    #[arg(short, long, global = true)]
+    pub verbose: bool,
+
+    /// Write a machine-readable run summary to this path
+    #[arg(long, value_name = "PATH")]
+    pub summary_json: Option<PathBuf>,
+
+    /// Write the per-image manifest (detections, timing, errors) of the run to this path
+    #[arg(long, value_name = "PATH")]
+    pub manifest: Option<PathBuf>,
+
+    /// Checkpoint the manifest every N images, so that an interrupted run can be resumed
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 100,
+        value_parser = clap::value_parser!(u64).range(1..),
+        requires = "manifest"
+    )]
+    pub checkpoint_every: u64,
+
+    /// Continue an interrupted run from its manifest checkpoint, skipping the images it already
+    /// processed whose content is unchanged. The COCO exports need the detections of every image,
+    /// so they cannot be combined with it
+    #[arg(long, requires = "manifest", conflicts_with_all = ["coco_results", "coco_dataset"])]
+    pub resume: bool,
+
+    /// Write the detections of the run as a COCO results JSON array, scorable with pycocotools
+    #[arg(long, value_name = "PATH")]
+    pub coco_results: Option<PathBuf>,
+
+    /// Write the detections of the run as a full COCO dataset (images, annotations, categories)
+    #[arg(long, value_name = "PATH")]
+    pub coco_dataset: Option<PathBuf>,
+
+    /// Compare the detections with reference outputs exported from Ultralytics (JSON object of
+    /// image name to `Results.to_json()` boxes) and exit with failure beyond 1px / 0.01 deviation
+    #[arg(long, value_name = "PATH")]
+    pub parity_reference: Option<PathBuf>,
+
+    /// Only process shard i of n of the images, partitioned by path hash, and suffix the
+    /// summary and manifest names with the shard
+    #[arg(long, value_name = "I/N")]
+    pub shard: Option<Shard>,
+
+    /// Compare the manifests of two runs and exit with failure if they differ
+    #[arg(long, num_args = 2, value_names = ["BASELINE", "NEWER"], conflicts_with = "images")]
+    pub diff_manifests: Option<Vec<PathBuf>>,
+
+    /// Opt in to local usage statistics (runs, latency, model hash) accumulated in this JSON file
+    #[arg(long, value_name = "PATH")]
+    pub usage_stats: Option<PathBuf>,
+
+    /// Print the completion script for a shell and exit
+    #[arg(long, value_name = "SHELL")]
+    pub completions: Option<Shell>,
+}
+
+/// Subcommands of the binary; without one, the images given directly are processed as `detect`
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Detect buildings in images
+    Detect {
+        #[arg(required = true)]
+        images: Vec<PathBuf>,
+    },
+    /// Detect buildings in every image of a directory
+    Batch {
+        dir: PathBuf,
+
+        /// Also process the images of subdirectories
+        #[arg(short, long)]
+        recursive: bool,
+
+        /// Only process files whose name matches this glob (`*` and `?`), e.g. 'base_*.png'
+        #[arg(long, value_name = "GLOB")]
+        pattern: Option<String>,
+    },
+    /// Score the detections of images against ground truth, printing per-class precision,
+    /// recall and AP
+    Eval {
+        #[arg(required = true)]
+        images: Vec<PathBuf>,
+
+        /// Ground truth: a COCO JSON file, or a directory of YOLO labels named after the images
+        #[arg(long, value_name = "PATH")]
+        ground_truth: PathBuf,
+
+        /// JSON map from class id to group, e.g. `{"0": "resources"}`, used for the group
+        /// metrics instead of the built-in groups
+        #[arg(long, value_name = "PATH")]
+        class_groups: Option<PathBuf>,
+    },
+    /// Serve the output directory as a browsable gallery of annotated images and their JSON
+    /// detections, filterable by class and confidence
+    Serve {
+        /// Address to listen on; the default makes the gallery reachable from the LAN
+        #[arg(long, default_value = "0.0.0.0:8080")]
+        addr: SocketAddr,
+    },
+    /// Decrypt a file written with --encrypt to standard output
+    #[cfg(feature = "encryption")]
+    Decrypt { file: PathBuf },
+}
+
+impl Cli {
+    /// Returns the images to process, from the subcommand or given directly
+    pub fn input_images(&self) -> Result<Vec<PathBuf>, String> {
+        match &self.command {
+            Some(Command::Detect { images } | Command::Eval { images, .. }) => Ok(images.clone()),
+            Some(Command::Batch {
+                dir,
+                recursive,
+                pattern,
+            }) => DirectoryOptions {
+                recursive: *recursive,
+                pattern: pattern.clone(),
+                ..DirectoryOptions::default()
+            }
+            .find(dir)
+            .map_err(|e| format!("Failed to read directory {}: {e}", dir.display())),
+            Some(Command::Serve { .. }) => Ok(Vec::new()),
+            #[cfg(feature = "encryption")]
+            Some(Command::Decrypt { .. }) => Ok(Vec::new()),
+            None => Ok(self.images.clone()),
+        }
+    }
+}
+
+/// Run settings resolved from CLI flags, then the config file, then built-in defaults
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    pub model: Option<PathBuf>,
+    pub model_type: YoloType,
+    pub provider: ExecutionProvider,
+    pub output_dir: String,
+    pub confidence_threshold: Option<f32>,
+    pub nms_threshold: Option<f32>,
+    pub classes: ClassRegistry,
+    pub palette: Palette,
+    pub class_names: ClassNames,
+    pub usage_stats: Option<PathBuf>,
+    pub watermark: bool,
+    pub output_format: OutputFormat,
+    pub image_timeout: Option<Duration>,
+}
+
+impl Settings {
+    /// Merges the CLI flags over the config file values
+    pub fn resolve(cli: &Cli, file: FileConfig) -> Result<Self, String> {
+        // Skipped images would count as predicting nothing against their ground truth
+        if cli.resume && matches!(cli.command, Some(Command::Eval { .. })) {
+            return Err(
+                "--resume cannot be combined with eval, which needs the detections of every image"
+                    .to_string(),
+            );
+        }
+        let model_type = match cli.model_type.as_deref().or(file.model_type.as_deref()) {
+            Some(name) => {
+                YoloType::try_from(name).map_err(|()| format!("Unknown model type: {name}"))?
+            }
+            None => YoloType::YoloV8,
+        };
+        let provider = match cli.provider.as_deref().or(file.provider.as_deref()) {
+            Some(name) => name.parse()?,
+            None => ExecutionProvider::Cpu,
+        };
+        let class_names = match cli.locale.as_deref().or(file.locale.as_deref()) {
+            Some(locale) => ClassNames::resolve(locale).map_err(|e| e.to_string())?,
+            None => ClassNames::default(),
+        };
+        let palette = match cli.palette.as_deref().or(file.palette.as_deref()) {
+            Some(name) => name.parse()?,
+            None => Palette::Registry,
+        };
+        let output_format = match cli.format {
+            Some(format) => format,
+            None => match file.output_format.as_deref() {
+                Some(name) => name.parse()?,
+                None => OutputFormat::Json,
+            },
+        };
+        let image_timeout = match cli.image_timeout.or(file.image_timeout) {
+            Some(secs) if secs > 0.0 => Some(
+                Duration::try_from_secs_f64(secs)
+                    .map_err(|e| format!("Invalid image timeout {secs}: {e}"))?,
+            ),
+            Some(secs) => return Err(format!("Invalid image timeout {secs}: must be positive")),
+            None => None,
+        };
+        let classes = match cli.classes.as_deref().or(file.classes.as_deref()) {
+            Some(path) => ClassRegistry::load(path)
+                .map_err(|e| format!("Failed to load classes {}: {e}", path.display()))?,
+            None => ClassRegistry::default(),
+        };
+        let overrides = cli
+            .class_overrides
+            .clone()
+            .or(file.class_overrides)
+            .or_else(ClassOverrides::env_path);
+        let classes = match overrides {
+            Some(path) => ClassOverrides::load(&path)
+                .map_err(|e| format!("Failed to load class overrides {}: {e}", path.display()))?
+                .apply(classes),
+            None => classes,
+        };
+        let classes = if class_names.locale() == "en" {
+            classes
+        } else {
+            classes.with_translation(class_names.clone())
+        };
+
+        Ok(Self {
+            model: cli.model.clone().or(file.model),
+            model_type,
+            provider,
+            output_dir: cli
+                .output_dir
+                .clone()
+                .or(file.output_dir)
+                .unwrap_or_else(|| "output".to_string()),
+            confidence_threshold: cli.confidence.or(file.confidence_threshold),
+            nms_threshold: cli.nms_threshold.or(file.nms_threshold),
+            classes,
+            palette,
+            class_names,
+            usage_stats: cli.usage_stats.clone().or(file.usage_stats),
+            watermark: cli.watermark || file.watermark.unwrap_or(false),
+            output_format,
+            image_timeout,
+        })
+    }
+}
+
+/// Writes the completion script of `shell` to stdout
+pub fn print_completions(shell: Shell) {
+    let mut command = Cli::command();
+    let name = command.get_name().to_string();
+    clap_complete::generate(shell, &mut command, name, &mut io::stdout());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_cli_flags_override_file() {
+        let cli = Cli::parse_from(["clashvision", "--confidence", "0.6", "a.png"]);
+        let file = FileConfig {
+            output_dir: Some("from_file".to_string()),
+            confidence_threshold: Some(0.3),
+            nms_threshold: Some(0.5),
+            ..FileConfig::default()
+        };
+
+        let settings = Settings::resolve(&cli, file).unwrap();
+        assert_eq!(settings.confidence_threshold, Some(0.6));
+        assert_eq!(settings.nms_threshold, Some(0.5));
+        assert_eq!(settings.output_dir, "from_file");
+        assert_eq!(settings.model_type, YoloType::YoloV8);
+        assert_eq!(settings.class_names.locale(), "en");
+    }
+
+    #[test]
+    fn test_image_timeout() {
+        let file = FileConfig {
+            image_timeout: Some(30.0),
+            ..FileConfig::default()
+        };
+        let cli = Cli::parse_from(["clashvision", "a.png"]);
+        let settings = Settings::resolve(&cli, file.clone()).unwrap();
+        assert_eq!(settings.image_timeout, Some(Duration::from_secs(30)));
+
+        let cli = Cli::parse_from(["clashvision", "--image-timeout", "1.5", "a.png"]);
+        let settings = Settings::resolve(&cli, file).unwrap();
+        assert_eq!(settings.image_timeout, Some(Duration::from_millis(1500)));
+
+        let cli = Cli::parse_from(["clashvision", "--image-timeout", "0", "a.png"]);
+        assert!(Settings::resolve(&cli, FileConfig::default()).is_err());
+    }
+
+    #[test]
+    fn test_locale_from_file() {
+        let cli = Cli::parse_from(["clashvision", "a.png"]);
+        let file = FileConfig {
+            locale: Some("de".to_string()),
+            ..FileConfig::default()
+        };
+        let settings = Settings::resolve(&cli, file).unwrap();
+        assert_eq!(settings.class_names.name(1), "Goldspeicher");
+        assert_eq!(settings.classes.translations()[0].locale(), "de");
+    }
+
+    #[test]
+    fn test_defaults_without_file() {
+        let cli = Cli::parse_from(["clashvision", "a.png"]);
+        let settings = Settings::resolve(&cli, FileConfig::default()).unwrap();
+        assert_eq!(settings.output_dir, "output");
+        assert!(settings.model.is_none());
+        assert!(settings.usage_stats.is_none());
+        assert!(!settings.watermark);
+        assert_eq!(settings.palette, Palette::Registry);
+        assert_eq!(settings.classes, ClassRegistry::default());
+    }
+
+    #[test]
+    fn test_classes_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("names.json");
+        std::fs::write(&path, r#"["Town Hall", "Cannon"]"#).unwrap();
+
+        let cli = Cli::parse_from(["clashvision", "a.png"]);
+        let file = FileConfig {
+            classes: Some(path),
+            ..FileConfig::default()
+        };
+        let settings = Settings::resolve(&cli, file).unwrap();
+        assert_eq!(settings.classes.name(1), "Cannon");
+
+        let cli = Cli::parse_from(["clashvision", "--classes", "missing.yaml", "a.png"]);
+        assert!(Settings::resolve(&cli, FileConfig::default()).is_err());
+    }
+
+    #[test]
+    fn test_class_overrides_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("overrides.toml");
+        std::fs::write(
+            &path,
+            "[names]\n0 = \"Elixir\"\n\n[colors]\n0 = \"#00ff00\"\n",
+        )
+        .unwrap();
+
+        let overrides = path.to_string_lossy().into_owned();
+        let cli = Cli::parse_from(["clashvision", "--class-overrides", &overrides, "a.png"]);
+        let settings = Settings::resolve(&cli, FileConfig::default()).unwrap();
+        assert_eq!(settings.classes.name(0), "Elixir");
+        assert_eq!(settings.classes.name(1), "Gold Storage");
+        assert_eq!(settings.classes.color(0), (0, 255, 0, 255));
+    }
+
+    #[test]
+    fn test_provider() {
+        let cli = Cli::parse_from(["clashvision", "--provider", "cuda", "a.png"]);
+        let file = FileConfig {
+            provider: Some("coreml".to_string()),
+            ..FileConfig::default()
+        };
+        let settings = Settings::resolve(&cli, file).unwrap();
+        assert_eq!(settings.provider, ExecutionProvider::Cuda);
+
+        let cli = Cli::parse_from(["clashvision", "--provider", "tpu", "a.png"]);
+        assert!(Settings::resolve(&cli, FileConfig::default()).is_err());
+    }
+
+    #[test]
+    fn test_resume_rejects_run_wide_exports() {
+        let resume = ["clashvision", "--manifest", "m.json", "--resume"];
+        #[cfg(feature = "arrow")]
+        let flags = ["--coco-results", "--coco-dataset", "--parquet"];
+        #[cfg(not(feature = "arrow"))]
+        let flags = ["--coco-results", "--coco-dataset"];
+        for flag in flags {
+            let args = resume.iter().copied().chain([flag, "coco.json", "a.png"]);
+            assert!(Cli::try_parse_from(args).is_err());
+        }
+        assert!(Cli::try_parse_from(resume.iter().copied().chain(["a.png"])).is_ok());
+    }
+
+    #[test]
+    fn test_resume_rejects_eval() {
+        let cli = Cli::parse_from([
+            "clashvision",
+            "--manifest",
+            "m.json",
+            "--resume",
+            "eval",
+            "--ground-truth",
+            "gt.json",
+            "a.png",
+        ]);
+        assert!(Settings::resolve(&cli, FileConfig::default()).is_err());
+    }
+
+    #[test]
+    fn test_unknown_model_type() {
+        let cli = Cli::parse_from(["clashvision", "--model-type", "yolo99", "a.png"]);
+        assert!(Settings::resolve(&cli, FileConfig::default()).is_err());
+    }
+
+    #[test]
+    fn test_diff_manifests_without_images() {
+        let cli = Cli::parse_from(["clashvision", "--diff-manifests", "old.json", "new.json"]);
+        assert_eq!(cli.diff_manifests.unwrap().len(), 2);
+        assert!(Cli::try_parse_from(["clashvision", "--diff-manifests", "old.json"]).is_err());
+    }
+
+    #[test]
+    fn test_resume_requires_manifest() {
+        let cli = Cli::parse_from(["clashvision", "--manifest", "m.json", "--resume", "a.png"]);
+        assert!(cli.resume);
+        assert_eq!(cli.checkpoint_every, 100);
+        assert!(Cli::try_parse_from(["clashvision", "--resume", "a.png"]).is_err());
+        assert!(
+            Cli::try_parse_from([
+                "clashvision",
+                "--manifest",
+                "m.json",
+                "--checkpoint-every",
+                "0",
+                "a.png"
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_subcommands() {
+        let cli = Cli::parse_from(["clashvision", "detect", "a.png", "--confidence", "0.5"]);
+        assert_eq!(cli.input_images().unwrap(), [PathBuf::from("a.png")]);
+        assert_eq!(cli.confidence, Some(0.5));
+        assert!(Cli::try_parse_from(["clashvision", "detect"]).is_err());
+
+        let cli = Cli::parse_from([
+            "clashvision",
+            "eval",
+            "a.png",
+            "--ground-truth",
+            "gt.json",
+            "-f",
+            "voc",
+        ]);
+        assert!(matches!(cli.command, Some(Command::Eval { .. })));
+        let settings = Settings::resolve(&cli, FileConfig::default()).unwrap();
+        assert_eq!(settings.output_format, OutputFormat::PascalVoc);
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("b.png"), b"").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), b"").unwrap();
+        let cli = Cli::parse_from([
+            std::ffi::OsStr::new("clashvision"),
+            std::ffi::OsStr::new("batch"),
+            dir.path().as_os_str(),
+        ]);
+        assert_eq!(cli.input_images().unwrap(), [dir.path().join("b.png")]);
+
+        let cli = Cli::parse_from(["clashvision", "batch", "shots", "-r", "--pattern", "b*"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Batch {
+                recursive: true,
+                pattern: Some(_),
+                ..
+            })
+        ));
+
+        let cli = Cli::parse_from(["clashvision", "-o", "out", "serve"]);
+        assert!(cli.input_images().unwrap().is_empty());
+        assert!(matches!(
+            cli.command,
+            Some(Command::Serve { addr }) if addr.port() == 8080
+        ));
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn test_decrypt_without_images() {
+        let cli = Cli::parse_from([
+            "clashvision",
+            "--key-file",
+            "key.hex",
+            "decrypt",
+            "a.json.enc",
+        ]);
+        assert!(
+            matches!(cli.command, Some(Command::Decrypt { ref file }) if file.ends_with("a.json.enc"))
+        );
+        assert!(cli.input_images().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_clipboard_without_images() {
+        let cli = Cli::parse_from(["clashvision", "--clipboard"]);
+        assert!(cli.clipboard && cli.images.is_empty());
+    }
+
+    #[test]
+    fn test_shard() {
+        let cli = Cli::parse_from(["clashvision", "--shard", "2/3", "a.png"]);
+        assert_eq!(cli.shard, Shard::new(2, 3));
+        assert!(Cli::try_parse_from(["clashvision", "--shard", "4/3", "a.png"]).is_err());
+    }
+
+    #[test]
+    fn test_completions_without_images() {
+        let cli = Cli::parse_from(["clashvision", "--completions", "bash"]);
+        assert_eq!(cli.completions, Some(Shell::Bash));
+    }
+}
@@ -0,0 +1,232 @@
+//! Terminal UI following a batch run: progress, running class counts, recent errors and latencies
+
+use clashvision::class::registry::ClassRegistry;
+use clashvision::detection::BoundingBox;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Style};
+use ratatui::widgets::{Block, Gauge, List, Paragraph, Sparkline};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::{BTreeMap, VecDeque};
+use std::io;
+use std::time::{Duration, Instant};
+
+/// Number of errors kept on screen
+const RECENT_ERRORS: usize = 5;
+
+/// Number of image latencies drawn in the sparkline
+const LATENCY_HISTORY: usize = 120;
+
+/// State of a batch run shown by the terminal UI
+#[derive(Debug, Clone)]
+pub struct BatchMonitor {
+    total: usize,
+    processed: usize,
+    failed: usize,
+    detections_per_class: BTreeMap<String, usize>,
+    recent_errors: VecDeque<String>,
+    latencies_ms: VecDeque<u64>,
+    started: Instant,
+}
+
+impl BatchMonitor {
+    /// Creates the state of a run over `total` images
+    pub fn new(total: usize) -> Self {
+        Self {
+            total,
+            processed: 0,
+            failed: 0,
+            detections_per_class: BTreeMap::new(),
+            recent_errors: VecDeque::with_capacity(RECENT_ERRORS),
+            latencies_ms: VecDeque::with_capacity(LATENCY_HISTORY),
+            started: Instant::now(),
+        }
+    }
+
+    /// Records an image processed in `latency`
+    pub fn record_success(
+        &mut self,
+        boxes: &[BoundingBox],
+        classes: &ClassRegistry,
+        latency: Duration,
+    ) {
+        self.processed += 1;
+        for bbox in boxes {
+            *self
+                .detections_per_class
+                .entry(classes.name(bbox.class_id))
+                .or_insert(0) += 1;
+        }
+        self.record_latency(latency);
+    }
+
+    /// Records an image that failed with `error`
+    pub fn record_failure(&mut self, image: &str, error: &str, latency: Duration) {
+        self.processed += 1;
+        self.failed += 1;
+        if self.recent_errors.len() == RECENT_ERRORS {
+            self.recent_errors.pop_front();
+        }
+        self.recent_errors.push_back(format!("{image}: {error}"));
+        self.record_latency(latency);
+    }
+
+    /// Records an image skipped without processing, e.g. completed before a checkpoint
+    pub fn record_skipped(&mut self) {
+        self.processed += 1;
+    }
+
+    fn record_latency(&mut self, latency: Duration) {
+        if self.latencies_ms.len() == LATENCY_HISTORY {
+            self.latencies_ms.pop_front();
+        }
+        let latency_ms = u64::try_from(latency.as_millis()).unwrap_or(u64::MAX);
+        self.latencies_ms.push_back(latency_ms);
+    }
+
+    /// Returns the share of the images processed so far, in [0, 1]
+    fn ratio(&self) -> f64 {
+        if self.total == 0 {
+            1.0
+        } else {
+            (self.processed as f64 / self.total as f64).min(1.0)
+        }
+    }
+
+    /// Draws the state on the whole frame
+    pub fn render(&self, frame: &mut Frame) {
+        let [progress, middle, errors, help] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(6),
+            Constraint::Length(RECENT_ERRORS as u16 + 2),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [counts, latency] =
+            Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .areas(middle);
+
+        let gauge = Gauge::default()
+            .block(Block::bordered().title(" Progress "))
+            .gauge_style(Style::default().fg(Color::Green))
+            .ratio(self.ratio())
+            .label(format!(
+                "{}/{} images, {} failed, {:.0}s",
+                self.processed,
+                self.total,
+                self.failed,
+                self.started.elapsed().as_secs_f64()
+            ));
+        frame.render_widget(gauge, progress);
+
+        let class_lines = self
+            .detections_per_class
+            .iter()
+            .map(|(name, count)| format!("{count:>6}  {name}"));
+        frame.render_widget(
+            List::new(class_lines).block(Block::bordered().title(" Detections ")),
+            counts,
+        );
+
+        let latencies: Vec<u64> = self.latencies_ms.iter().copied().collect();
+        let title = latencies.last().map_or_else(
+            || " Latency ".to_string(),
+            |last| format!(" Latency (last {last}ms) "),
+        );
+        frame.render_widget(
+            Sparkline::default()
+                .block(Block::bordered().title(title))
+                .style(Style::default().fg(Color::Cyan))
+                .data(&latencies),
+            latency,
+        );
+
+        frame.render_widget(
+            List::new(self.recent_errors.iter().map(String::as_str))
+                .style(Style::default().fg(Color::Red))
+                .block(Block::bordered().title(" Recent errors ")),
+            errors,
+        );
+        frame.render_widget(Paragraph::new("q: stop after the current image"), help);
+    }
+}
+
+/// Terminal switched to the UI, restored when dropped
+pub struct Tui {
+    terminal: DefaultTerminal,
+    pub monitor: BatchMonitor,
+}
+
+impl Tui {
+    /// Switches the terminal to the UI for a run over `total` images
+    pub fn start(total: usize) -> Self {
+        Self {
+            terminal: ratatui::init(),
+            monitor: BatchMonitor::new(total),
+        }
+    }
+
+    /// Redraws the UI
+    pub fn draw(&mut self) -> io::Result<()> {
+        self.terminal
+            .draw(|frame| self.monitor.render(frame))
+            .map(|_| ())
+    }
+
+    /// Returns true if `q` or Esc was pressed since the last call, without blocking
+    pub fn quit_requested(&self) -> io::Result<bool> {
+        while event::poll(Duration::ZERO)? {
+            if let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+                && matches!(key.code, KeyCode::Char('q') | KeyCode::Esc)
+            {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+impl Drop for Tui {
+    fn drop(&mut self) {
+        ratatui::restore();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::Terminal;
+    use ratatui::backend::TestBackend;
+
+    #[test]
+    fn test_monitor_renders_progress_counts_and_errors() {
+        let classes = ClassRegistry::default();
+        let mut monitor = BatchMonitor::new(8);
+        let boxes = [
+            BoundingBox::new(0.0, 0.0, 4.0, 4.0, 1, 0.9),
+            BoundingBox::new(8.0, 0.0, 12.0, 4.0, 1, 0.9),
+        ];
+        monitor.record_success(&boxes, &classes, Duration::from_millis(40));
+        monitor.record_failure("b.png", "unreadable", Duration::from_millis(5));
+        for i in 0..RECENT_ERRORS {
+            monitor.record_failure(&format!("{i}.png"), "unreadable", Duration::ZERO);
+        }
+        assert_eq!(monitor.recent_errors.len(), RECENT_ERRORS);
+        assert!(!monitor.recent_errors[0].starts_with("b.png"));
+        assert!((monitor.ratio() - 0.875).abs() < 1e-9);
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+        terminal.draw(|frame| monitor.render(frame)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("7/8 images, 6 failed"));
+        assert!(screen.contains("2  Gold Storage"));
+        assert!(screen.contains("4.png: unreadable"));
+    }
+}
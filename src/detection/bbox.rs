@@ -1,5 +1,16 @@
 //! Bounding box utilities and operations.
 
+use crate::analysis::fullness::FillLevel;
+use crate::image::letterbox::LetterboxTransform;
+use serde::Serialize;
+
+/// Building level predicted by a secondary classifier for a detection.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct LevelEstimate {
+    pub level: u32,
+    pub confidence: f32,
+}
+
 /// Struct representing a bounding box with coordinates, class ID, and confidence score.
 #[must_use]
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -10,6 +21,11 @@ pub struct BoundingBox {
     pub y2: f32,
     pub class_id: usize,
     pub confidence: f32,
+    pub level: Option<LevelEstimate>,
+    /// Fill level of a resource storage, estimated after NMS when configured
+    pub fullness: Option<FillLevel>,
+    /// Index of the tile of a tiled inference the box was detected on, `None` for the whole image
+    pub tile: Option<usize>,
 }
 
 impl BoundingBox {
@@ -23,6 +39,9 @@ impl BoundingBox {
             y2,
             class_id,
             confidence,
+            level: None,
+            fullness: None,
+            tile: None,
         }
     }
 
@@ -112,6 +131,23 @@ impl BoundingBox {
         bbox.scale(scale_x, scale_y);
         bbox
     }
+
+    /// Maps a box from the letterboxed model input back to the original image, clamped to its bounds
+    pub fn to_original_space(&self, transform: &LetterboxTransform) -> Self {
+        let (width, height) = (
+            transform.original_size.width as f32,
+            transform.original_size.height as f32,
+        );
+        let (x1, y1) = transform.to_original(self.x1, self.y1);
+        let (x2, y2) = transform.to_original(self.x2, self.y2);
+        Self {
+            x1: x1.clamp(0.0, width),
+            y1: y1.clamp(0.0, height),
+            x2: x2.clamp(0.0, width),
+            y2: y2.clamp(0.0, height),
+            ..*self
+        }
+    }
 }
 
 #[cfg(test)]
@@ -198,9 +234,115 @@ mod tests {
         assert_eq!(bbox.area(), 2400.0);
     }
 
+    #[test]
+    fn test_level_defaults_to_none() {
+        let bbox = BoundingBox::from_center(30.0, 50.0, 40.0, 60.0, 1, 0.9);
+        assert_eq!(bbox.level, None);
+        assert_eq!(bbox.fullness, None);
+    }
+
     #[test]
     fn test_center() {
         let bbox = BoundingBox::new(10.0, 20.0, 50.0, 80.0, 1, 0.9);
         assert_eq!(bbox.center(), (30.0, 50.0));
     }
+
+    #[test]
+    fn test_to_original_space() {
+        use crate::image::image_size::ImageSize;
+
+        let transform =
+            LetterboxTransform::new(ImageSize::new(1280, 720), ImageSize::new(640, 640));
+        let bbox = BoundingBox::new(100.0, 150.0, 200.0, 520.0, 1, 0.9);
+        let original = bbox.to_original_space(&transform);
+        assert_eq!(
+            (original.x1, original.y1, original.x2, original.y2),
+            (200.0, 20.0, 400.0, 720.0)
+        );
+        assert_eq!((original.class_id, original.confidence), (1, 0.9));
+    }
+
+    mod properties {
+        use super::*;
+        use proptest::prelude::*;
+
+        /// Relative tolerance for comparisons of recomputed coordinates and areas
+        const EPSILON: f32 = 1e-4;
+
+        fn arb_box() -> impl Strategy<Value = BoundingBox> {
+            (
+                -500.0f32..500.0,
+                -500.0f32..500.0,
+                0.0f32..300.0,
+                0.0f32..300.0,
+            )
+                .prop_map(|(x, y, w, h)| BoundingBox::new(x, y, x + w, y + h, 0, 0.5))
+        }
+
+        fn approx_eq(a: f32, b: f32) -> bool {
+            (a - b).abs() <= EPSILON * a.abs().max(b.abs()).max(1.0)
+        }
+
+        proptest! {
+            #[test]
+            fn iou_is_symmetric(a in arb_box(), b in arb_box()) {
+                prop_assert_eq!(a.iou(&b), b.iou(&a));
+                prop_assert_eq!(a.intersection(&b), b.intersection(&a));
+            }
+
+            #[test]
+            fn iou_is_within_unit_interval(a in arb_box(), b in arb_box()) {
+                let iou = a.iou(&b);
+                prop_assert!((0.0..=1.0 + EPSILON).contains(&iou), "iou = {}", iou);
+            }
+
+            #[test]
+            fn iou_with_itself_is_one(a in arb_box()) {
+                prop_assume!(a.area() > 0.0);
+                prop_assert!(approx_eq(a.iou(&a), 1.0));
+            }
+
+            #[test]
+            fn intersection_is_bounded_by_areas(a in arb_box(), b in arb_box()) {
+                let intersection = a.intersection(&b);
+                prop_assert!(intersection >= 0.0);
+                prop_assert!(intersection <= a.area().min(b.area()) * (1.0 + EPSILON));
+                prop_assert!(a.union(&b) >= a.area().max(b.area()) * (1.0 - EPSILON));
+            }
+
+            #[test]
+            fn center_round_trip(
+                cx in -500.0f32..500.0,
+                cy in -500.0f32..500.0,
+                w in 0.0f32..300.0,
+                h in 0.0f32..300.0,
+            ) {
+                let bbox = BoundingBox::from_center(cx, cy, w, h, 0, 0.5);
+                let (center_x, center_y) = bbox.center();
+                let (width, height) = bbox.dimensions();
+                prop_assert!(approx_eq(center_x, cx) && approx_eq(center_y, cy));
+                prop_assert!(approx_eq(width, w) && approx_eq(height, h));
+
+                let rebuilt = BoundingBox::from_center(center_x, center_y, width, height, 0, 0.5);
+                prop_assert!(approx_eq(rebuilt.x1, bbox.x1) && approx_eq(rebuilt.y1, bbox.y1));
+                prop_assert!(approx_eq(rebuilt.x2, bbox.x2) && approx_eq(rebuilt.y2, bbox.y2));
+            }
+
+            #[test]
+            fn scale_matches_scaled(a in arb_box(), sx in 0.01f32..10.0, sy in 0.01f32..10.0) {
+                let mut scaled_in_place = a;
+                scaled_in_place.scale(sx, sy);
+                prop_assert_eq!(scaled_in_place, a.scaled(sx, sy));
+
+                let restored = scaled_in_place.scaled(1.0 / sx, 1.0 / sy);
+                prop_assert!(approx_eq(restored.x1, a.x1) && approx_eq(restored.y1, a.y1));
+                prop_assert!(approx_eq(restored.x2, a.x2) && approx_eq(restored.y2, a.y2));
+            }
+
+            #[test]
+            fn scaling_preserves_iou(a in arb_box(), b in arb_box(), s in 0.1f32..10.0) {
+                prop_assert!((a.scaled(s, s).iou(&b.scaled(s, s)) - a.iou(&b)).abs() <= 1e-3);
+            }
+        }
+    }
 }
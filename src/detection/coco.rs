@@ -0,0 +1,224 @@
+//! COCO results and dataset export of the detections of a batch of images, scorable with
+//! pycocotools
+
+use super::bbox::BoundingBox;
+use crate::class::registry::ClassRegistry;
+#[cfg(feature = "encryption")]
+use crate::report::encryption::{OutputKey, encrypted_path};
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Detection in the COCO results format, with its box as `[x, y, width, height]`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CocoResult {
+    pub image_id: u64,
+    pub category_id: usize,
+    pub bbox: [f32; 4],
+    pub score: f32,
+}
+
+/// Image entry of a COCO dataset
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CocoImage {
+    pub id: u64,
+    pub file_name: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Annotation entry of a COCO dataset, keeping the score of the detection it comes from
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CocoAnnotation {
+    pub id: u64,
+    pub image_id: u64,
+    pub category_id: usize,
+    pub bbox: [f32; 4],
+    pub area: f32,
+    pub iscrowd: u8,
+    pub score: f32,
+}
+
+/// Category entry of a COCO dataset
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CocoCategory {
+    pub id: usize,
+    pub name: String,
+}
+
+/// Full COCO dataset with `images`, `annotations` and `categories`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CocoDataset {
+    pub images: Vec<CocoImage>,
+    pub annotations: Vec<CocoAnnotation>,
+    pub categories: Vec<CocoCategory>,
+}
+
+/// Accumulates the detections of a batch of images for COCO export.
+///
+/// Images are numbered from 1 in the order they are added, unless an id is given to match the
+/// ground truth the results are scored against. Category ids are the model class ids.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CocoExport {
+    images: Vec<CocoImage>,
+    results: Vec<CocoResult>,
+}
+
+impl CocoExport {
+    /// Adds the detections of an image under the next free image id, which is returned
+    pub fn add_image(
+        &mut self,
+        file_name: &str,
+        image_dimensions: (u32, u32),
+        boxes: &[BoundingBox],
+    ) -> u64 {
+        let id = self.images.iter().map(|image| image.id).max().unwrap_or(0) + 1;
+        self.add_image_with_id(id, file_name, image_dimensions, boxes);
+        id
+    }
+
+    /// Adds the detections of an image under a given image id
+    pub fn add_image_with_id(
+        &mut self,
+        id: u64,
+        file_name: &str,
+        image_dimensions: (u32, u32),
+        boxes: &[BoundingBox],
+    ) {
+        self.images.push(CocoImage {
+            id,
+            file_name: file_name.to_string(),
+            width: image_dimensions.0,
+            height: image_dimensions.1,
+        });
+        self.results.extend(boxes.iter().map(|bbox| {
+            let (width, height) = bbox.dimensions();
+            CocoResult {
+                image_id: id,
+                category_id: bbox.class_id,
+                bbox: [bbox.x1, bbox.y1, width, height],
+                score: bbox.confidence,
+            }
+        }));
+    }
+
+    /// Returns the images added so far
+    #[inline]
+    #[must_use]
+    pub fn images(&self) -> &[CocoImage] {
+        &self.images
+    }
+
+    /// Returns the detections in the COCO results format
+    #[inline]
+    #[must_use]
+    pub fn results(&self) -> &[CocoResult] {
+        &self.results
+    }
+
+    /// Builds the full dataset, with a category per class of `classes`
+    #[must_use]
+    pub fn to_dataset(&self, classes: &ClassRegistry) -> CocoDataset {
+        CocoDataset {
+            images: self.images.clone(),
+            annotations: self
+                .results
+                .iter()
+                .zip(1..)
+                .map(|(result, id)| CocoAnnotation {
+                    id,
+                    image_id: result.image_id,
+                    category_id: result.category_id,
+                    bbox: result.bbox,
+                    area: result.bbox[2] * result.bbox[3],
+                    iscrowd: 0,
+                    score: result.score,
+                })
+                .collect(),
+            categories: classes
+                .names()
+                .iter()
+                .enumerate()
+                .map(|(id, name)| CocoCategory {
+                    id,
+                    name: name.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Writes the detections as a COCO results JSON array, as loaded by `COCO.loadRes`
+    pub fn write_results(&self, output_path: &Path) -> io::Result<()> {
+        let json = serde_json::to_string(&self.results).map_err(io::Error::other)?;
+        fs::write(output_path, json)
+    }
+
+    /// Writes the full COCO dataset JSON
+    pub fn write_dataset(&self, output_path: &Path, classes: &ClassRegistry) -> io::Result<()> {
+        let json =
+            serde_json::to_string_pretty(&self.to_dataset(classes)).map_err(io::Error::other)?;
+        fs::write(output_path, json)
+    }
+
+    /// Writes the detection results like `write_results`, encrypted with `key` to `output_path`
+    /// with `.enc` appended
+    #[cfg(feature = "encryption")]
+    pub fn write_encrypted_results(&self, output_path: &Path, key: &OutputKey) -> io::Result<()> {
+        let json = serde_json::to_string(&self.results).map_err(io::Error::other)?;
+        key.write_sealed(&encrypted_path(output_path), json.as_bytes())
+    }
+
+    /// Writes the full COCO dataset like `write_dataset`, encrypted with `key` to `output_path`
+    /// with `.enc` appended
+    #[cfg(feature = "encryption")]
+    pub fn write_encrypted_dataset(
+        &self,
+        output_path: &Path,
+        classes: &ClassRegistry,
+        key: &OutputKey,
+    ) -> io::Result<()> {
+        let json =
+            serde_json::to_string_pretty(&self.to_dataset(classes)).map_err(io::Error::other)?;
+        key.write_sealed(&encrypted_path(output_path), json.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_results_and_dataset() {
+        let mut export = CocoExport::default();
+        let first = export.add_image(
+            "a.png",
+            (640, 480),
+            &[
+                BoundingBox::new(10.0, 20.0, 50.0, 80.0, 1, 0.9),
+                BoundingBox::new(0.0, 0.0, 5.0, 5.0, 0, 0.5),
+            ],
+        );
+        export.add_image_with_id(42, "b.png", (100, 100), &[]);
+        let third = export.add_image("c.png", (100, 100), &[]);
+        assert_eq!((first, third), (1, 43));
+
+        let results = serde_json::to_value(export.results()).unwrap();
+        assert_eq!(
+            results[0],
+            serde_json::json!({
+                "image_id": 1,
+                "category_id": 1,
+                "bbox": [10.0, 20.0, 40.0, 60.0],
+                "score": 0.9f32,
+            })
+        );
+
+        let dataset = export.to_dataset(&ClassRegistry::new(["Town Hall", "Cannon"]));
+        assert_eq!(dataset.images.len(), 3);
+        assert_eq!(dataset.annotations.len(), 2);
+        assert_eq!(dataset.annotations[1].id, 2);
+        assert_eq!(dataset.annotations[0].area, 2400.0);
+        assert_eq!(dataset.categories[1].name, "Cannon");
+    }
+}
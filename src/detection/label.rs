@@ -0,0 +1,144 @@
+//! Text labels drawn next to bounding boxes, rasterized with the bundled DejaVu Sans font
+
+use ab_glyph::{Font, FontRef, Glyph, PxScale, ScaleFont, point};
+use raqote::Mask;
+use std::sync::OnceLock;
+
+/// DejaVu Sans, see `assets/fonts/LICENSE-DejaVu.txt`
+static FONT_DATA: &[u8] = include_bytes!("../../assets/fonts/DejaVuSans.ttf");
+
+/// Returns the bundled font, parsed once
+fn font() -> &'static FontRef<'static> {
+    static FONT: OnceLock<FontRef<'static>> = OnceLock::new();
+    FONT.get_or_init(|| FontRef::try_from_slice(FONT_DATA).expect("Bundled font is valid"))
+}
+
+/// Where a label is placed relative to its bounding box
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LabelPosition {
+    /// On top of the box, moved inside when it would leave the image
+    #[default]
+    Above,
+    /// In the top-left corner of the box
+    Inside,
+    /// Under the box, moved inside when it would leave the image
+    Below,
+}
+
+impl LabelPosition {
+    /// Returns the string representation of the `LabelPosition` variant.
+    #[inline]
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Above => "above",
+            Self::Inside => "inside",
+            Self::Below => "below",
+        }
+    }
+
+    /// Returns a static slice of all `LabelPosition` variants.
+    #[must_use]
+    pub const fn values() -> &'static [Self] {
+        &[Self::Above, Self::Inside, Self::Below]
+    }
+
+    /// Returns the top of a label of `label_height` for a box spanning `top..bottom`
+    #[must_use]
+    pub fn label_top(&self, top: f32, bottom: f32, label_height: f32, image_height: f32) -> f32 {
+        match self {
+            Self::Above if top - label_height >= 0.0 => top - label_height,
+            Self::Below if bottom + label_height <= image_height => bottom,
+            Self::Above | Self::Inside => top.max(0.0),
+            Self::Below => (bottom - label_height).max(0.0),
+        }
+    }
+}
+
+/// Rasterizes a single line of text into a coverage mask of `font_size` pixels high
+#[must_use]
+pub fn rasterize(text: &str, font_size: f32) -> Mask {
+    let font = font().as_scaled(PxScale::from(font_size.max(1.0)));
+
+    // Lay the glyphs out on the baseline, kerning each pair
+    let mut caret = 0.0;
+    let mut previous = None;
+    let glyphs: Vec<Glyph> = text
+        .chars()
+        .map(|c| {
+            let id = font.glyph_id(c);
+            if let Some(previous) = previous {
+                caret += font.kern(previous, id);
+            }
+            previous = Some(id);
+            let glyph = id.with_scale_and_position(font.scale(), point(caret, font.ascent()));
+            caret += font.h_advance(id);
+            glyph
+        })
+        .collect();
+
+    let width = caret.ceil().max(0.0) as i32;
+    let height = (font.ascent() - font.descent()).ceil() as i32;
+    let mut data = vec![0u8; (width * height) as usize];
+
+    for glyph in glyphs {
+        let Some(outlined) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outlined.px_bounds();
+        outlined.draw(|x, y, coverage| {
+            let px = bounds.min.x as i32 + x as i32;
+            let py = bounds.min.y as i32 + y as i32;
+            if (0..width).contains(&px) && (0..height).contains(&py) {
+                let pixel = &mut data[(py * width + px) as usize];
+                *pixel = (*pixel).max((coverage * 255.0).round() as u8);
+            }
+        });
+    }
+
+    Mask {
+        width,
+        height,
+        data,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rasterize_covers_text() {
+        let short = rasterize("Gold", 12.0);
+        assert!(short.width > 0);
+        assert_eq!(short.data.len(), (short.width * short.height) as usize);
+        assert!(short.data.iter().any(|&c| c > 0));
+
+        let long = rasterize("Gold Storage 0.87", 12.0);
+        assert!(long.width > short.width);
+        assert_eq!(long.height, short.height);
+        assert!(rasterize("Gold", 24.0).height > short.height);
+        assert_eq!(rasterize("", 12.0).width, 0);
+    }
+
+    #[test]
+    fn test_label_top_stays_in_image() {
+        assert_eq!(
+            LabelPosition::Above.label_top(50.0, 80.0, 14.0, 100.0),
+            36.0
+        );
+        assert_eq!(LabelPosition::Above.label_top(5.0, 80.0, 14.0, 100.0), 5.0);
+        assert_eq!(
+            LabelPosition::Inside.label_top(50.0, 80.0, 14.0, 100.0),
+            50.0
+        );
+        assert_eq!(
+            LabelPosition::Below.label_top(50.0, 80.0, 14.0, 100.0),
+            80.0
+        );
+        assert_eq!(
+            LabelPosition::Below.label_top(50.0, 95.0, 14.0, 100.0),
+            81.0
+        );
+    }
+}
@@ -1,9 +1,14 @@
 mod bbox;
+pub mod coco;
+pub mod label;
 pub mod nms;
 pub mod output;
+pub mod tile_overlay;
 pub mod visualization;
+pub mod watermark;
+pub mod wbf;
 
-pub use bbox::BoundingBox;
+pub use bbox::{BoundingBox, LevelEstimate};
 
 /// Errors that can occur during detection operations
 #[derive(Debug, thiserror::Error)]
@@ -1,6 +1,9 @@
 //! Non-Maximum Suppression implementation
 
 use super::bbox::BoundingBox;
+use crate::class::group::{ClassGroup, ClassGroups};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
 
 /// Performs Non-Maximum Suppression (NMS) on a list of bounding boxes.
 ///
@@ -9,20 +12,16 @@ use super::bbox::BoundingBox;
 /// * `iou_threshold` - `IoU` threshold for suppression (typically 0.4-0.5)
 ///
 /// # Returns
-/// Vector of filtered bounding boxes
+/// Vector of filtered bounding boxes, in `detection_order`.
+/// Boxes with a NaN or infinite confidence or coordinate are dropped.
 #[must_use]
 pub fn nms(boxes: &[BoundingBox], iou_threshold: f32) -> Vec<BoundingBox> {
-    if boxes.is_empty() {
+    let mut sorted_boxes: Vec<BoundingBox> = boxes.iter().copied().filter(is_finite_box).collect();
+    if sorted_boxes.is_empty() {
         return Vec::new();
     }
 
-    // Sort by confidence in descending order
-    let mut sorted_boxes = boxes.to_vec();
-    sorted_boxes.sort_by(|a, b| {
-        b.confidence
-            .partial_cmp(&a.confidence)
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
+    sorted_boxes.sort_by(detection_order);
 
     let mut result = Vec::with_capacity(boxes.len());
     let mut suppressed = vec![false; sorted_boxes.len()];
@@ -54,6 +53,94 @@ pub fn nms_class_agnostic(boxes: &[BoundingBox], iou_threshold: f32) -> Vec<Boun
 /// Performs per-class NMS
 #[must_use]
 pub fn nms_per_class(boxes: &[BoundingBox], iou_threshold: f32) -> Vec<BoundingBox> {
+    per_class(boxes, |boxes_for_class| nms(boxes_for_class, iou_threshold))
+}
+
+/// How overlapping detections are suppressed
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum NmsStrategy {
+    /// Drops boxes overlapping a kept box above the IoU threshold
+    #[default]
+    Hard,
+    /// Decays the confidence of overlapping boxes, see `soft_nms`
+    Soft { sigma: f32, method: SoftNmsMethod },
+}
+
+/// Score decay applied by Soft-NMS to boxes overlapping a kept box
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SoftNmsMethod {
+    /// Scales scores by `1 - IoU` for overlaps above `iou_threshold`
+    Linear { iou_threshold: f32 },
+    /// Scales scores by `exp(-IoU² / sigma)`
+    Gaussian,
+}
+
+impl SoftNmsMethod {
+    /// Returns the factor applied to the score of a box overlapping a kept box by `iou`
+    #[inline]
+    #[must_use]
+    pub fn decay(&self, iou: f32, sigma: f32) -> f32 {
+        match self {
+            _ if iou <= 0.0 => 1.0,
+            Self::Linear { iou_threshold } if iou > *iou_threshold => 1.0 - iou,
+            Self::Linear { .. } => 1.0,
+            Self::Gaussian => (-(iou * iou) / sigma).exp(),
+        }
+    }
+}
+
+/// Performs Soft-NMS: instead of discarding boxes overlapping a kept box, their confidence
+/// decays with the overlap, and boxes are only dropped once it falls under `score_threshold`.
+///
+/// Keeps more of the genuinely overlapping objects of dense bases than hard NMS.
+/// Returns the kept boxes with their decayed confidences, in `detection_order`.
+#[must_use]
+pub fn soft_nms(
+    boxes: &[BoundingBox],
+    sigma: f32,
+    score_threshold: f32,
+    method: SoftNmsMethod,
+) -> Vec<BoundingBox> {
+    let mut remaining: Vec<BoundingBox> = boxes
+        .iter()
+        .copied()
+        .filter(|bbox| is_finite_box(bbox) && bbox.confidence >= score_threshold)
+        .collect();
+    let mut result = Vec::with_capacity(remaining.len());
+
+    // Scores only ever decay, so picking the best remaining box keeps the result sorted
+    while let Some(best) =
+        (0..remaining.len()).min_by(|&a, &b| detection_order(&remaining[a], &remaining[b]))
+    {
+        let current = remaining.swap_remove(best);
+        for other in &mut remaining {
+            other.confidence *= method.decay(current.iou(other), sigma);
+        }
+        remaining.retain(|bbox| bbox.confidence >= score_threshold);
+        result.push(current);
+    }
+
+    result
+}
+
+/// Performs per-class Soft-NMS
+#[must_use]
+pub fn soft_nms_per_class(
+    boxes: &[BoundingBox],
+    sigma: f32,
+    score_threshold: f32,
+    method: SoftNmsMethod,
+) -> Vec<BoundingBox> {
+    per_class(boxes, |boxes_for_class| {
+        soft_nms(boxes_for_class, sigma, score_threshold, method)
+    })
+}
+
+/// Applies `suppress` to the boxes of every class separately
+fn per_class(
+    boxes: &[BoundingBox],
+    suppress: impl Fn(&[BoundingBox]) -> Vec<BoundingBox>,
+) -> Vec<BoundingBox> {
     use std::collections::HashMap;
 
     let mut class_boxes: HashMap<usize, Vec<BoundingBox>> = HashMap::new();
@@ -67,23 +154,158 @@ pub fn nms_per_class(boxes: &[BoundingBox], iou_threshold: f32) -> Vec<BoundingB
 
     // Apply NMS per class
     for boxes_for_class in class_boxes.values() {
-        result.extend(nms(boxes_for_class, iou_threshold));
+        result.extend(suppress(boxes_for_class));
+    }
+
+    // Sort the final result, independent of the map iteration order
+    result.sort_by(detection_order);
+
+    result
+}
+
+/// Group-level NMS settings: IoU thresholds of the groups whose classes suppress each other
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupNms {
+    pub groups: ClassGroups,
+    pub thresholds: BTreeMap<ClassGroup, f32>,
+}
+
+impl GroupNms {
+    /// Creates group NMS settings over a group map, with no group pooled yet
+    #[must_use]
+    pub fn new(groups: ClassGroups) -> Self {
+        Self {
+            groups,
+            thresholds: BTreeMap::new(),
+        }
     }
 
-    // Sort final result by confidence
-    result.sort_by(|a, b| {
-        b.confidence
-            .partial_cmp(&a.confidence)
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
+    /// Pools the classes of `group`, including nested groups, under one NMS at `iou_threshold`
+    #[must_use]
+    pub fn with_threshold(mut self, group: ClassGroup, iou_threshold: f32) -> Self {
+        self.thresholds.insert(group, iou_threshold);
+        self
+    }
+
+    /// Returns the most specific configured group of a class and its threshold
+    fn pool_of(&self, class_id: usize) -> Option<(ClassGroup, f32)> {
+        let group = self.groups.group(class_id)?;
+        std::iter::successors(Some(group), ClassGroup::parent)
+            .find_map(|group| Some((group, *self.thresholds.get(&group)?)))
+    }
+}
+
+impl Default for GroupNms {
+    fn default() -> Self {
+        Self::new(ClassGroups::default())
+    }
+}
+
+/// Performs group-level NMS.
+///
+/// Boxes of classes in a configured group are suppressed across classes of that group at
+/// its threshold, e.g. level variants of a building detected as different classes.
+/// Classes outside every configured group fall back to per-class NMS at `iou_threshold`.
+#[must_use]
+pub fn nms_per_group(
+    boxes: &[BoundingBox],
+    config: &GroupNms,
+    iou_threshold: f32,
+) -> Vec<BoundingBox> {
+    let mut pooled: BTreeMap<ClassGroup, (f32, Vec<BoundingBox>)> = BTreeMap::new();
+    let mut ungrouped = Vec::new();
+    for &bbox in boxes {
+        match config.pool_of(bbox.class_id) {
+            Some((group, threshold)) => pooled
+                .entry(group)
+                .or_insert_with(|| (threshold, Vec::new()))
+                .1
+                .push(bbox),
+            None => ungrouped.push(bbox),
+        }
+    }
 
+    let mut result = nms_per_class(&ungrouped, iou_threshold);
+    for (threshold, group_boxes) in pooled.values() {
+        result.extend(nms(group_boxes, *threshold));
+    }
+    result.sort_by(detection_order);
     result
 }
 
+/// Keeps at most `top_k_per_class` boxes of each class and `max_detections` boxes overall,
+/// the most confident first, bounding the output of noisy low-threshold runs.
+///
+/// # Returns
+/// Vector of kept boxes, in `detection_order`.
+#[must_use]
+pub fn limit_detections(
+    boxes: &[BoundingBox],
+    max_detections: Option<usize>,
+    top_k_per_class: Option<usize>,
+) -> Vec<BoundingBox> {
+    let mut kept = boxes.to_vec();
+    kept.sort_by(detection_order);
+
+    if let Some(top_k) = top_k_per_class {
+        let mut per_class: BTreeMap<usize, usize> = BTreeMap::new();
+        kept.retain(|bbox| {
+            let count = per_class.entry(bbox.class_id).or_insert(0);
+            *count += 1;
+            *count <= top_k
+        });
+    }
+    if let Some(max_detections) = max_detections {
+        kept.truncate(max_detections);
+    }
+    kept
+}
+
+/// Total order used to rank detections: highest confidence first, ties broken by
+/// larger area, then by the smallest `x1`, `y1`, `x2`, `y2` and `class_id`.
+#[must_use]
+pub fn detection_order(a: &BoundingBox, b: &BoundingBox) -> Ordering {
+    b.confidence
+        .total_cmp(&a.confidence)
+        .then_with(|| b.area().total_cmp(&a.area()))
+        .then_with(|| a.x1.total_cmp(&b.x1))
+        .then_with(|| a.y1.total_cmp(&b.y1))
+        .then_with(|| a.x2.total_cmp(&b.x2))
+        .then_with(|| a.y2.total_cmp(&b.y2))
+        .then_with(|| a.class_id.cmp(&b.class_id))
+}
+
+/// Returns true if the confidence and all coordinates of the box are finite
+#[inline]
+pub(crate) fn is_finite_box(bbox: &BoundingBox) -> bool {
+    [bbox.x1, bbox.y1, bbox.x2, bbox.y2, bbox.confidence]
+        .iter()
+        .all(|v| v.is_finite())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_limit_detections() {
+        let boxes = vec![
+            BoundingBox::new(0.0, 0.0, 10.0, 10.0, 0, 0.5),
+            BoundingBox::new(20.0, 0.0, 30.0, 10.0, 1, 0.9),
+            BoundingBox::new(40.0, 0.0, 50.0, 10.0, 0, 0.8),
+            BoundingBox::new(60.0, 0.0, 70.0, 10.0, 0, 0.7),
+        ];
+        assert_eq!(limit_detections(&boxes, None, None).len(), 4);
+
+        let top = limit_detections(&boxes, Some(2), None);
+        assert_eq!(top, vec![boxes[1], boxes[2]]);
+
+        let per_class = limit_detections(&boxes, None, Some(1));
+        assert_eq!(per_class, vec![boxes[1], boxes[2]]);
+        let both = limit_detections(&boxes, Some(3), Some(2));
+        assert_eq!(both, vec![boxes[1], boxes[2], boxes[3]]);
+    }
+
     #[test]
     fn test_nms_empty() {
         let boxes = [];
@@ -110,4 +332,195 @@ mod tests {
         assert_eq!(result[0].confidence, 0.9);
         assert_eq!(result[1].confidence, 0.7);
     }
+
+    #[test]
+    fn test_nms_filters_nan() {
+        let boxes = [
+            BoundingBox::new(0.0, 0.0, 10.0, 10.0, 0, f32::NAN),
+            BoundingBox::new(f32::NAN, 0.0, 10.0, 10.0, 0, 0.9),
+            BoundingBox::new(0.0, 0.0, f32::INFINITY, 10.0, 0, 0.9),
+            BoundingBox::new(1.0, 1.0, 11.0, 11.0, 0, 0.5),
+        ];
+        let result = nms(&boxes, 0.5);
+        assert_eq!(result, vec![boxes[3]]);
+    }
+
+    #[test]
+    fn test_ties_broken_by_area_then_coordinates() {
+        let small = BoundingBox::new(0.0, 0.0, 10.0, 10.0, 0, 0.8);
+        let large = BoundingBox::new(50.0, 50.0, 70.0, 70.0, 0, 0.8);
+        let left = BoundingBox::new(100.0, 0.0, 110.0, 10.0, 1, 0.8);
+
+        for input in [
+            [small, large, left],
+            [left, small, large],
+            [large, left, small],
+        ] {
+            assert_eq!(nms(&input, 0.5), vec![large, small, left]);
+            assert_eq!(nms_per_class(&input, 0.5), vec![large, small, left]);
+        }
+    }
+
+    #[test]
+    fn test_tied_overlapping_boxes_keep_larger() {
+        let inner = BoundingBox::new(1.0, 1.0, 10.0, 10.0, 0, 0.8);
+        let outer = BoundingBox::new(0.0, 0.0, 10.0, 10.0, 0, 0.8);
+        assert_eq!(nms(&[inner, outer], 0.5), vec![outer]);
+        assert_eq!(nms(&[outer, inner], 0.5), vec![outer]);
+    }
+
+    #[test]
+    fn test_nms_per_group_suppresses_across_classes() {
+        let mut groups = ClassGroups::default();
+        groups.insert(5, ClassGroup::Defenses);
+        let boxes = [
+            BoundingBox::new(0.0, 0.0, 10.0, 10.0, 0, 0.9),
+            BoundingBox::new(1.0, 1.0, 11.0, 11.0, 1, 0.8), // Same group, other class
+            BoundingBox::new(1.0, 1.0, 11.0, 11.0, 5, 0.7), // Other group
+            BoundingBox::new(1.0, 1.0, 11.0, 11.0, 9, 0.6), // Ungrouped
+        ];
+
+        let config = GroupNms::new(groups.clone()).with_threshold(ClassGroup::Resources, 0.5);
+        let result = nms_per_group(&boxes, &config, 0.5);
+        let classes: Vec<usize> = result.iter().map(|bbox| bbox.class_id).collect();
+        assert_eq!(classes, vec![0, 5, 9]);
+
+        // A parent group pools every nested group
+        let config = GroupNms::new(groups).with_threshold(ClassGroup::Buildings, 0.5);
+        let result = nms_per_group(&boxes, &config, 0.5);
+        let classes: Vec<usize> = result.iter().map(|bbox| bbox.class_id).collect();
+        assert_eq!(classes, vec![0, 9]);
+    }
+
+    #[test]
+    fn test_nms_per_group_without_groups_is_per_class() {
+        let boxes = [
+            BoundingBox::new(0.0, 0.0, 10.0, 10.0, 0, 0.9),
+            BoundingBox::new(1.0, 1.0, 11.0, 11.0, 1, 0.8),
+        ];
+        assert_eq!(
+            nms_per_group(&boxes, &GroupNms::default(), 0.5),
+            nms_per_class(&boxes, 0.5)
+        );
+    }
+
+    #[test]
+    fn test_soft_nms_decays_instead_of_suppressing() {
+        let boxes = [
+            BoundingBox::new(0.0, 0.0, 10.0, 10.0, 0, 0.9),
+            BoundingBox::new(2.0, 0.0, 12.0, 10.0, 0, 0.8), // IoU 2/3 with the first box
+            BoundingBox::new(20.0, 20.0, 30.0, 30.0, 0, 0.7),
+        ];
+        assert_eq!(nms(&boxes, 0.5).len(), 2);
+
+        let result = soft_nms(&boxes, 0.5, 0.1, SoftNmsMethod::Gaussian);
+        assert_eq!(result.len(), 3);
+        assert_eq!(result[0], boxes[0]);
+        assert_eq!(result[1], boxes[2]);
+        let expected = 0.8 * (-(4.0f32 / 9.0) / 0.5).exp();
+        assert!((result[2].confidence - expected).abs() < 1e-5);
+
+        let result = soft_nms(
+            &boxes,
+            0.5,
+            0.3,
+            SoftNmsMethod::Linear { iou_threshold: 0.5 },
+        );
+        assert_eq!(result, vec![boxes[0], boxes[2]]);
+        let result = soft_nms(
+            &boxes,
+            0.5,
+            0.2,
+            SoftNmsMethod::Linear { iou_threshold: 0.5 },
+        );
+        assert_eq!(result.len(), 3);
+        assert!((result[2].confidence - 0.8 / 3.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_soft_nms_per_class_keeps_other_classes() {
+        let boxes = [
+            BoundingBox::new(0.0, 0.0, 10.0, 10.0, 0, 0.9),
+            BoundingBox::new(0.0, 0.0, 10.0, 10.0, 1, 0.8),
+            BoundingBox::new(0.0, 0.0, 10.0, 10.0, 0, f32::NAN),
+        ];
+        let result = soft_nms_per_class(&boxes, 0.5, 0.1, SoftNmsMethod::Gaussian);
+        assert_eq!(result, vec![boxes[0], boxes[1]]);
+
+        // Identical boxes of one class decay to exp(-1 / sigma)
+        let result = soft_nms(&boxes[..2], 0.5, 0.1, SoftNmsMethod::Gaussian);
+        assert!((result[1].confidence - 0.8 * (-2.0f32).exp()).abs() < 1e-5);
+    }
+
+    mod properties {
+        use super::*;
+        use proptest::prelude::*;
+
+        fn arb_box() -> impl Strategy<Value = BoundingBox> {
+            (
+                0.0f32..100.0,
+                0.0f32..100.0,
+                1.0f32..50.0,
+                1.0f32..50.0,
+                0usize..3,
+                prop_oneof![9 => 0.0f32..1.0, 1 => Just(f32::NAN), 1 => Just(0.5f32)],
+            )
+                .prop_map(|(x, y, w, h, class_id, confidence)| {
+                    BoundingBox::new(x, y, x + w, y + h, class_id, confidence)
+                })
+        }
+
+        proptest! {
+            #[test]
+            fn nms_output_is_finite_subset(boxes in prop::collection::vec(arb_box(), 0..40)) {
+                let result = nms(&boxes, 0.5);
+                prop_assert!(result.len() <= boxes.len());
+                for bbox in &result {
+                    prop_assert!(bbox.confidence.is_finite());
+                    prop_assert!(boxes.contains(bbox));
+                }
+            }
+
+            #[test]
+            fn nms_kept_boxes_do_not_overlap(
+                boxes in prop::collection::vec(arb_box(), 0..40),
+                threshold in 0.1f32..0.9,
+            ) {
+                let result = nms(&boxes, threshold);
+                for (i, a) in result.iter().enumerate() {
+                    for b in &result[i + 1..] {
+                        prop_assert!(a.iou(b) <= threshold);
+                    }
+                }
+            }
+
+            #[test]
+            fn nms_is_sorted_and_order_independent(boxes in prop::collection::vec(arb_box(), 0..40)) {
+                let result = nms(&boxes, 0.5);
+                prop_assert!(result.windows(2).all(|w| detection_order(&w[0], &w[1]) != Ordering::Greater));
+
+                let mut reversed = boxes.clone();
+                reversed.reverse();
+                prop_assert_eq!(nms(&reversed, 0.5), result);
+                prop_assert_eq!(
+                    nms_per_class(&reversed, 0.5),
+                    nms_per_class(&boxes, 0.5)
+                );
+            }
+
+            #[test]
+            fn nms_is_idempotent(boxes in prop::collection::vec(arb_box(), 0..40)) {
+                let once = nms(&boxes, 0.5);
+                prop_assert_eq!(nms(&once, 0.5), once.clone());
+            }
+
+            #[test]
+            fn nms_per_class_keeps_every_class(boxes in prop::collection::vec(arb_box(), 0..40)) {
+                let result = nms_per_class(&boxes, 0.5);
+                for bbox in boxes.iter().filter(|b| b.confidence.is_finite()) {
+                    prop_assert!(result.iter().any(|kept| kept.class_id == bbox.class_id));
+                }
+            }
+        }
+    }
 }
@@ -1,18 +1,48 @@
 //! Output utilities for saving detection results
 
 use super::bbox::BoundingBox;
+use crate::class::group::ClassGroups;
+use crate::class::registry::ClassRegistry;
 use serde::Serialize;
 use std::fmt::Write as _;
-use std::fs;
-use std::io::{self};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write as _};
 use std::path::Path;
+use std::str::FromStr;
+use std::sync::RwLock;
+
+/// Header row of the CSV output
+pub const CSV_HEADER: &str = "image,class_id,class_name,x1,y1,x2,y2,confidence";
+
+/// Builds the detections file of an image from its boxes, dimensions, name and classes
+pub type RenderFn = fn(&[BoundingBox], (u32, u32), &str, &ClassRegistry) -> String;
+
+/// Output format registered by a downstream crate with `OutputFormat::register`
+#[derive(Debug)]
+pub struct CustomFormat {
+    pub name: &'static str,
+    pub extension: &'static str,
+    pub render: RenderFn,
+}
+
+/// Custom formats registered so far, leaked so that `OutputFormat` stays `Copy`
+static CUSTOM_FORMATS: RwLock<Vec<&'static CustomFormat>> = RwLock::new(Vec::new());
 
 /// Output format options
-#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[derive(Debug, Clone, Copy, Default)]
 pub enum OutputFormat {
     #[default]
     Yolo,
     Json,
+    Csv,
+    PascalVoc,
+    Custom(&'static CustomFormat),
+}
+
+impl PartialEq for OutputFormat {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
 }
 
 impl Serialize for OutputFormat {
@@ -20,11 +50,24 @@ impl Serialize for OutputFormat {
     where
         S: serde::Serializer,
     {
-        let s = match self {
-            Self::Yolo => "yolo",
-            Self::Json => "json",
-        };
-        serializer.serialize_str(s)
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::registered()
+            .into_iter()
+            .find(|format| format.as_str().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::registered().iter().map(Self::as_str).collect();
+                format!(
+                    "Unknown output format {s}, expected one of {}",
+                    names.join(", ")
+                )
+            })
     }
 }
 
@@ -35,31 +78,209 @@ impl OutputFormat {
         image_dimensions: (u32, u32),
         output_path: &Path,
         format: Option<Self>,
+        classes: &ClassRegistry,
     ) -> io::Result<()> {
+        let contents =
+            Self::render_detections(boxes, image_dimensions, output_path, format, classes);
+        fs::write(output_path, contents)
+    }
+
+    /// Builds the contents of the detections file written to `output_path`, which names the
+    /// image in the JSON, CSV and Pascal VOC formats
+    #[must_use]
+    pub fn render_detections(
+        boxes: &[BoundingBox],
+        image_dimensions: (u32, u32),
+        output_path: &Path,
+        format: Option<Self>,
+        classes: &ClassRegistry,
+    ) -> String {
         let format: Self = format.unwrap_or_default();
+        let stem = output_path
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy();
         match format {
-            Self::Yolo => Self::output_to_yolo_txt_normalized(
-                boxes,
-                image_dimensions.0,
-                image_dimensions.1,
-                output_path.to_str().unwrap(),
-            ),
-            Self::Json => Self::output_to_coco_json(boxes, image_dimensions, output_path),
+            Self::Yolo => {
+                Self::to_yolo_txt_normalized(boxes, image_dimensions.0, image_dimensions.1)
+            }
+            Self::Json => {
+                let output = Self::to_coco_json(boxes, image_dimensions, &stem, classes);
+                serde_json::to_string_pretty(&output).unwrap()
+            }
+            Self::Csv => {
+                let mut csv = format!("{CSV_HEADER}\n");
+                csv.push_str(&Self::to_csv_rows(boxes, &stem, classes));
+                csv
+            }
+            Self::Custom(custom) => (custom.render)(boxes, image_dimensions, &stem, classes),
+            Self::PascalVoc => {
+                let folder = output_path
+                    .parent()
+                    .and_then(Path::file_name)
+                    .unwrap_or_default()
+                    .to_string_lossy();
+                Self::to_pascal_voc(
+                    boxes,
+                    image_dimensions,
+                    &folder,
+                    &format!("{stem}.jpg"),
+                    classes,
+                )
+            }
         }
     }
 
-    /// Outputs in COCO JSON format to a json file
-    fn output_to_coco_json(
+    /// Builds the Pascal VOC XML annotation of an image, as read by labeling tools such as
+    /// LabelImg or CVAT. Boxes touching the image border are marked as truncated.
+    #[must_use]
+    pub fn to_pascal_voc(
         boxes: &[BoundingBox],
         image_dimensions: (u32, u32),
+        folder: &str,
+        file_name: &str,
+        classes: &ClassRegistry,
+    ) -> String {
+        let (width, height) = image_dimensions;
+        let mut xml = String::with_capacity(256 + boxes.len() * 256);
+        let _ = write!(
+            xml,
+            "<annotation>\n\
+             \t<folder>{}</folder>\n\
+             \t<filename>{}</filename>\n\
+             \t<size>\n\
+             \t\t<width>{width}</width>\n\
+             \t\t<height>{height}</height>\n\
+             \t\t<depth>3</depth>\n\
+             \t</size>\n\
+             \t<segmented>0</segmented>\n",
+            escape_xml(folder),
+            escape_xml(file_name)
+        );
+        for bbox in boxes {
+            let xmin = bbox.x1.round().max(0.0) as u32;
+            let ymin = bbox.y1.round().max(0.0) as u32;
+            let xmax = (bbox.x2.round().max(0.0) as u32).min(width);
+            let ymax = (bbox.y2.round().max(0.0) as u32).min(height);
+            let truncated = xmin == 0 || ymin == 0 || xmax >= width || ymax >= height;
+            let _ = write!(
+                xml,
+                "\t<object>\n\
+                 \t\t<name>{}</name>\n\
+                 \t\t<pose>Unspecified</pose>\n\
+                 \t\t<truncated>{}</truncated>\n\
+                 \t\t<difficult>0</difficult>\n\
+                 \t\t<bndbox>\n\
+                 \t\t\t<xmin>{xmin}</xmin>\n\
+                 \t\t\t<ymin>{ymin}</ymin>\n\
+                 \t\t\t<xmax>{xmax}</xmax>\n\
+                 \t\t\t<ymax>{ymax}</ymax>\n\
+                 \t\t</bndbox>\n\
+                 \t</object>\n",
+                escape_xml(&classes.name(bbox.class_id)),
+                u8::from(truncated)
+            );
+        }
+        xml.push_str("</annotation>\n");
+        xml
+    }
+
+    /// Appends the detections of an image to a CSV file, writing the header first if the file
+    /// is new or empty, so that a whole batch can be collected in a single file
+    pub fn append_to_csv(
+        boxes: &[BoundingBox],
+        image: &str,
         output_path: &Path,
+        classes: &ClassRegistry,
     ) -> io::Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(output_path)?;
+        if file.metadata()?.len() == 0 {
+            writeln!(file, "{CSV_HEADER}")?;
+        }
+        file.write_all(Self::to_csv_rows(boxes, image, classes).as_bytes())
+    }
+
+    /// Formats one CSV row per detection, without the header
+    #[must_use]
+    pub fn to_csv_rows(boxes: &[BoundingBox], image: &str, classes: &ClassRegistry) -> String {
+        let image = csv_field(image);
+        let mut rows = String::with_capacity(boxes.len() * 64);
+        for bbox in boxes {
+            let _ = writeln!(
+                rows,
+                "{image},{},{},{:.2},{:.2},{:.2},{:.2},{:.4}",
+                bbox.class_id,
+                csv_field(&classes.name(bbox.class_id)),
+                bbox.x1,
+                bbox.y1,
+                bbox.x2,
+                bbox.y2,
+                bbox.confidence
+            );
+        }
+        rows
+    }
+
+    /// Builds the COCO categories of a label map, one per class with its group as
+    /// supercategory, or its own name for classes without a group.
+    ///
+    /// Classes get a `localized_names` object from locale to name when `classes` has
+    /// translations, e.g. `{"de": "Goldspeicher"}`.
+    #[must_use]
+    pub fn coco_categories(
+        classes: &ClassRegistry,
+        groups: &ClassGroups,
+    ) -> Vec<serde_json::Value> {
+        classes
+            .names()
+            .iter()
+            .enumerate()
+            .map(|(id, name)| {
+                let supercategory = groups.group(id).map_or(name.as_str(), |g| g.as_str());
+                let mut category = serde_json::json!({
+                    "id": id,
+                    "name": name,
+                    "supercategory": supercategory,
+                });
+                if !classes.translations().is_empty() {
+                    let localized: serde_json::Map<String, serde_json::Value> = classes
+                        .translations()
+                        .iter()
+                        .map(|names| {
+                            (
+                                names.locale().to_string(),
+                                names.name_or(id, classes).into(),
+                            )
+                        })
+                        .collect();
+                    category["localized_names"] = serde_json::Value::Object(localized);
+                }
+                category
+            })
+            .collect()
+    }
+
+    /// Builds the COCO JSON document describing the detections of an image, naming their
+    /// categories after `classes`
+    #[must_use]
+    pub fn to_coco_json(
+        boxes: &[BoundingBox],
+        image_dimensions: (u32, u32),
+        file_name: &str,
+        classes: &ClassRegistry,
+    ) -> serde_json::Value {
+        let groups = ClassGroups::default();
+        let categories = Self::coco_categories(classes, &groups);
         let stub = serde_json::json!({
             "images": [{
                 "width": image_dimensions.0,
                 "height": image_dimensions.1,
-                "file_name": output_path.file_stem().unwrap().to_str().unwrap()
+                "file_name": file_name
             }],
+            "categories": categories,
             "detections": [],
         });
 
@@ -67,9 +288,10 @@ impl OutputFormat {
         let mut detections = Vec::new();
         for (i, bbox) in boxes.iter().enumerate() {
             let (width, height) = bbox.dimensions();
-            detections.push(serde_json::json!({
+            let mut detection = serde_json::json!({
                 "id": i + 1,
                 "category_id": bbox.class_id,
+                "category_name": classes.name(bbox.class_id),
                 "x1": bbox.x1,
                 "y1": bbox.y1,
                 "x2": bbox.x2,
@@ -77,24 +299,32 @@ impl OutputFormat {
                 "width": width,
                 "height": height,
                 "score": bbox.confidence,
-            }));
+            });
+            if let Some(group) = groups.group(bbox.class_id) {
+                detection["group"] = serde_json::json!(group.as_str());
+            }
+            if let Some(level) = bbox.level {
+                detection["level"] = serde_json::json!(level.level);
+                detection["level_confidence"] = serde_json::json!(level.confidence);
+            }
+            if let Some(fullness) = bbox.fullness {
+                detection["fullness"] = serde_json::json!(fullness.as_str());
+            }
+            detections.push(detection);
         }
         let mut output = stub;
         output["detections"] = serde_json::Value::Array(detections);
-        fs::write(output_path, serde_json::to_string_pretty(&output).unwrap())?;
-
-        Ok(())
+        output
     }
 
-    /// Outputs normalized YOLO format with error handling
-    fn output_to_yolo_txt_normalized(
+    /// Builds the normalized YOLO format, one line per box
+    fn to_yolo_txt_normalized(
         boxes: &[BoundingBox],
         image_width: u32,
         image_height: u32,
-        output_path: &str,
-    ) -> io::Result<()> {
+    ) -> String {
         if boxes.is_empty() {
-            return fs::write(output_path, "");
+            return String::new();
         }
 
         let img_width_f = image_width as f32;
@@ -122,7 +352,57 @@ impl OutputFormat {
             );
         }
 
-        fs::write(output_path, yolo_output)
+        yolo_output
+    }
+
+    /// Returns the name of the output format
+    #[inline]
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Yolo => "yolo",
+            Self::Json => "json",
+            Self::Csv => "csv",
+            Self::PascalVoc => "voc",
+            Self::Custom(custom) => custom.name,
+        }
+    }
+
+    /// Returns all built-in output formats
+    #[inline]
+    #[must_use]
+    pub const fn values() -> [Self; 4] {
+        [Self::Yolo, Self::Json, Self::Csv, Self::PascalVoc]
+    }
+
+    /// Returns the built-in output formats followed by the registered custom ones
+    #[must_use]
+    pub fn registered() -> Vec<Self> {
+        let custom = CUSTOM_FORMATS.read().unwrap_or_else(|e| e.into_inner());
+        Self::values()
+            .into_iter()
+            .chain(custom.iter().map(|&format| Self::Custom(format)))
+            .collect()
+    }
+
+    /// Registers a custom format, parsed by name like the built-in ones (e.g. by `--format`)
+    /// and written by `output_detections` and the file sink, failing if the name is taken
+    pub fn register(format: CustomFormat) -> Result<Self, String> {
+        let mut custom = CUSTOM_FORMATS.write().unwrap_or_else(|e| e.into_inner());
+        let taken = Self::values()
+            .iter()
+            .map(Self::as_str)
+            .chain(custom.iter().map(|registered| registered.name))
+            .any(|name| name.eq_ignore_ascii_case(format.name));
+        if taken {
+            return Err(format!(
+                "Output format {} is already registered",
+                format.name
+            ));
+        }
+        let format: &'static CustomFormat = Box::leak(Box::new(format));
+        custom.push(format);
+        Ok(Self::Custom(format))
     }
 
     /// Returns the file extension for the output format
@@ -132,15 +412,37 @@ impl OutputFormat {
         match self {
             Self::Yolo => "txt",
             Self::Json => "json",
+            Self::Csv => "csv",
+            Self::PascalVoc => "xml",
+            Self::Custom(custom) => custom.extension,
         }
     }
 }
 
+/// Quotes a CSV field containing a separator, quote or line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Escapes the characters that are significant in XML text
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::analysis::fullness::FillLevel;
     use crate::class::clash_class::ClashClass;
-    use tempfile::NamedTempFile;
+    use crate::class::locale::ClassNames;
+    use crate::detection::LevelEstimate;
+    use tempfile::{NamedTempFile, tempdir};
 
     #[test]
     fn test_yolo_output_yolo_format() -> io::Result<()> {
@@ -150,11 +452,12 @@ mod tests {
             BoundingBox::new(30.0, 40.0, 70.0, 90.0, 2, 0.8),
         ];
 
-        OutputFormat::output_to_yolo_txt_normalized(
+        OutputFormat::output_detections(
             &boxes,
-            100,
-            100,
-            temp_file.path().to_str().unwrap(),
+            (100, 100),
+            temp_file.path(),
+            Some(OutputFormat::Yolo),
+            &ClassRegistry::default(),
         )?;
 
         let content = fs::read_to_string(temp_file.path())?;
@@ -166,8 +469,7 @@ mod tests {
     }
 
     #[test]
-    fn test_yolo_output_single_box() -> io::Result<()> {
-        let temp_file = NamedTempFile::new()?;
+    fn test_yolo_output_single_box() {
         let boxes = vec![BoundingBox::new(
             10.0,
             20.0,
@@ -177,17 +479,9 @@ mod tests {
             1.0,
         )];
 
-        OutputFormat::output_to_yolo_txt_normalized(
-            &boxes,
-            100,
-            100,
-            temp_file.path().to_str().unwrap(),
-        )?;
-
-        let content = fs::read_to_string(temp_file.path())?;
+        let content = OutputFormat::to_yolo_txt_normalized(&boxes, 100, 100);
         assert_eq!(content.trim(), "1 0.300000 0.500000 0.400000 0.600000");
-
-        Ok(())
+        assert!(OutputFormat::to_yolo_txt_normalized(&[], 100, 100).is_empty());
     }
 
     #[test]
@@ -202,7 +496,13 @@ mod tests {
             1.0,
         )];
 
-        OutputFormat::output_to_coco_json(&boxes, (100, 100), temp_file.path())?;
+        OutputFormat::output_detections(
+            &boxes,
+            (100, 100),
+            temp_file.path(),
+            Some(OutputFormat::Json),
+            &ClassRegistry::default(),
+        )?;
 
         let content = fs::read_to_string(temp_file.path())?;
         let json: serde_json::Value = serde_json::from_str(&content)?;
@@ -213,12 +513,165 @@ mod tests {
             ClashClass::GoldStorage as usize
         );
         assert_eq!(json["detections"][0]["score"], 1.0);
+        assert_eq!(json["detections"][0]["category_name"], "Gold Storage");
+        assert_eq!(json["detections"][0]["group"], "resources");
+        assert_eq!(json["categories"][0]["name"], "Elixir Storage");
         Ok(())
     }
 
+    #[test]
+    fn test_json_output_includes_level() -> io::Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        let mut bbox = BoundingBox::new(10.0, 20.0, 50.0, 80.0, 1, 1.0);
+        bbox.level = Some(LevelEstimate {
+            level: 12,
+            confidence: 0.5,
+        });
+        bbox.fullness = Some(FillLevel::Partial);
+
+        OutputFormat::output_detections(
+            &[bbox],
+            (100, 100),
+            temp_file.path(),
+            Some(OutputFormat::Json),
+            &ClassRegistry::default(),
+        )?;
+
+        let content = fs::read_to_string(temp_file.path())?;
+        let json: serde_json::Value = serde_json::from_str(&content)?;
+        assert_eq!(json["detections"][0]["level"], 12);
+        assert_eq!(json["detections"][0]["level_confidence"], 0.5);
+        assert_eq!(json["detections"][0]["fullness"], "partial");
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_output_custom_classes() {
+        let classes = ClassRegistry::new(["Town Hall", "Cannon", "Mortar"]);
+        let bbox = BoundingBox::new(10.0, 20.0, 50.0, 80.0, 2, 0.7);
+        let json = OutputFormat::to_coco_json(&[bbox], (100, 100), "village", &classes);
+        assert_eq!(json["categories"].as_array().unwrap().len(), 3);
+        assert_eq!(json["detections"][0]["category_name"], "Mortar");
+    }
+
+    #[test]
+    fn test_coco_categories_groups_and_translations() {
+        let categories = OutputFormat::coco_categories(
+            &ClassRegistry::default(),
+            &ClassGroups::from_json(r#"{"0": "resources"}"#).unwrap(),
+        );
+        assert_eq!(categories[0]["supercategory"], "resources");
+        assert_eq!(categories[1]["supercategory"], "Gold Storage");
+        assert!(categories[0].get("localized_names").is_none());
+
+        let classes = ClassRegistry::default()
+            .with_translation(ClassNames::builtin("de").unwrap())
+            .with_translation(ClassNames::builtin("fr").unwrap())
+            .with_translation(ClassNames::builtin("de").unwrap());
+        let json = OutputFormat::to_coco_json(&[], (100, 100), "village", &classes);
+        let localized = &json["categories"][1]["localized_names"];
+        assert_eq!(localized["de"], "Goldspeicher");
+        assert_eq!(localized.as_object().unwrap().len(), 2);
+        assert_eq!(json["categories"][1]["supercategory"], "resources");
+    }
+
     #[test]
     fn test_output_format_extension() {
         assert_eq!(OutputFormat::Yolo.extension(), "txt");
         assert_eq!(OutputFormat::Json.extension(), "json");
+        assert_eq!(OutputFormat::Csv.extension(), "csv");
+        assert_eq!(OutputFormat::PascalVoc.extension(), "xml");
+        assert_eq!("VOC".parse(), Ok(OutputFormat::PascalVoc));
+        assert!("xml".parse::<OutputFormat>().is_err());
+    }
+
+    #[test]
+    fn test_custom_output_format() -> io::Result<()> {
+        fn render(boxes: &[BoundingBox], _: (u32, u32), image: &str, _: &ClassRegistry) -> String {
+            format!("{image}\t{}", boxes.len())
+        }
+        let tsv = OutputFormat::register(CustomFormat {
+            name: "tsv",
+            extension: "tsv",
+            render,
+        })
+        .unwrap();
+        assert_eq!("TSV".parse(), Ok(tsv));
+        assert!(OutputFormat::registered().contains(&tsv));
+        assert!(
+            OutputFormat::register(CustomFormat {
+                name: "json",
+                extension: "json",
+                render,
+            })
+            .is_err()
+        );
+
+        let dir = tempdir()?;
+        let path = dir.path().join(format!("village.{}", tsv.extension()));
+        let boxes = [BoundingBox::new(0.0, 0.0, 1.0, 1.0, 0, 0.9)];
+        let classes = ClassRegistry::default();
+        OutputFormat::output_detections(&boxes, (10, 10), &path, Some(tsv), &classes)?;
+        assert_eq!(fs::read_to_string(path)?, "village\t1");
+        Ok(())
+    }
+
+    #[test]
+    fn test_csv_output_and_append() -> io::Result<()> {
+        let dir = tempdir()?;
+        let classes = ClassRegistry::new(["Town Hall", "Cannon, lvl 2"]);
+        let boxes = vec![BoundingBox::new(10.0, 20.0, 50.0, 80.0, 1, 0.875)];
+
+        let single = dir.path().join("village.csv");
+        OutputFormat::output_detections(
+            &boxes,
+            (100, 100),
+            &single,
+            Some(OutputFormat::Csv),
+            &classes,
+        )?;
+        assert_eq!(
+            fs::read_to_string(&single)?,
+            format!("{CSV_HEADER}\nvillage,1,\"Cannon, lvl 2\",10.00,20.00,50.00,80.00,0.8750\n")
+        );
+
+        let batch = dir.path().join("batch.csv");
+        OutputFormat::append_to_csv(&boxes, "a.png", &batch, &classes)?;
+        OutputFormat::append_to_csv(&boxes, "b.png", &batch, &classes)?;
+        let content = fs::read_to_string(&batch)?;
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], CSV_HEADER);
+        assert!(lines[2].starts_with("b.png,1,"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_pascal_voc_output() -> io::Result<()> {
+        let dir = tempdir()?;
+        let classes = ClassRegistry::new(["Town Hall", "Cannon & Mortar"]);
+        let boxes = vec![
+            BoundingBox::new(10.4, 20.0, 50.0, 80.6, 1, 0.9),
+            BoundingBox::new(0.0, 60.0, 30.0, 100.0, 0, 0.8),
+        ];
+
+        let path = dir.path().join("village.xml");
+        OutputFormat::output_detections(
+            &boxes,
+            (100, 100),
+            &path,
+            Some(OutputFormat::PascalVoc),
+            &classes,
+        )?;
+        let xml = fs::read_to_string(&path)?;
+        assert!(xml.contains("<filename>village.jpg</filename>"));
+        assert!(xml.contains("<width>100</width>"));
+        assert_eq!(xml.matches("<object>").count(), 2);
+        assert!(xml.contains("<name>Cannon &amp; Mortar</name>"));
+        assert!(xml.contains(
+            "<xmin>10</xmin>\n\t\t\t<ymin>20</ymin>\n\t\t\t<xmax>50</xmax>\n\t\t\t<ymax>81</ymax>"
+        ));
+        assert_eq!(xml.matches("<truncated>1</truncated>").count(), 1);
+        Ok(())
     }
 }
@@ -0,0 +1,154 @@
+//! Debug overlay of tiled inference: tile boundaries, final detections per tile and the tile
+//! each box originated from, to tune the tile size and overlap
+
+use super::bbox::BoundingBox;
+use super::label;
+use crate::image::tiling::Tile;
+use image::{Rgb, RgbImage};
+use raqote::Mask;
+
+/// Colors cycled through the tiles, so that overlapping neighbours stand apart
+const TILE_COLORS: [[u8; 3]; 6] = [
+    [255, 64, 64],
+    [64, 160, 255],
+    [64, 220, 96],
+    [255, 200, 0],
+    [220, 96, 255],
+    [0, 220, 220],
+];
+
+/// Color of the tags of boxes detected on the whole image
+const FULL_IMAGE_COLOR: [u8; 3] = [255, 255, 255];
+
+/// Font size of the tile labels and box tags
+const FONT_SIZE: f32 = 12.0;
+
+/// Returns the number of final boxes that originated from each tile
+#[must_use]
+pub fn tile_counts(tile_count: usize, boxes: &[BoundingBox]) -> Vec<usize> {
+    let mut counts = vec![0; tile_count];
+    for index in boxes.iter().filter_map(|bbox| bbox.tile) {
+        if let Some(count) = counts.get_mut(index) {
+            *count += 1;
+        }
+    }
+    counts
+}
+
+/// Draws the boundary of every tile labelled with its final detections, e.g. `tile 3: 5`, and
+/// tags every box with the tile it originated from, `t3`, or `full` for the whole image.
+///
+/// Tiles and boxes must be expressed in the pixel space of `image`.
+pub fn draw_tile_overlay(image: &mut RgbImage, tiles: &[Tile], boxes: &[BoundingBox]) {
+    let counts = tile_counts(tiles.len(), boxes);
+    for (index, (tile, count)) in tiles.iter().zip(counts).enumerate() {
+        let color = tile_color(index);
+        draw_outline(image, tile, color);
+        let mask = label::rasterize(&format!("tile {index}: {count}"), FONT_SIZE);
+        draw_text(image, &mask, (tile.x + 2, tile.y + 2), color);
+    }
+
+    for bbox in boxes {
+        let (tag, color) = match bbox.tile {
+            Some(index) => (format!("t{index}"), tile_color(index)),
+            None => ("full".to_string(), FULL_IMAGE_COLOR),
+        };
+        // Bottom-left corner of the box, away from the class label drawn on top
+        let mask = label::rasterize(&tag, FONT_SIZE);
+        let left = bbox.x1.max(0.0) as u32;
+        let top = (bbox.y2 - mask.height as f32 - 2.0).max(0.0) as u32;
+        draw_text(image, &mask, (left, top), color);
+    }
+}
+
+/// Returns the color of the tile at `index`
+const fn tile_color(index: usize) -> [u8; 3] {
+    TILE_COLORS[index % TILE_COLORS.len()]
+}
+
+/// Draws the 2-pixel boundary of a tile, clipped to the image
+fn draw_outline(image: &mut RgbImage, tile: &Tile, color: [u8; 3]) {
+    let right = (tile.x + tile.width).min(image.width());
+    let bottom = (tile.y + tile.height).min(image.height());
+    if tile.x >= right || tile.y >= bottom {
+        return;
+    }
+    for y in tile.y..bottom {
+        for x in tile.x..right {
+            let on_edge = x < tile.x + 2 || x + 2 >= right || y < tile.y + 2 || y + 2 >= bottom;
+            if on_edge {
+                image.put_pixel(x, y, Rgb(color));
+            }
+        }
+    }
+}
+
+/// Draws rasterized text on a black backdrop with its top-left corner at `(left, top)`,
+/// clipped to the image
+fn draw_text(image: &mut RgbImage, mask: &Mask, (left, top): (u32, u32), color: [u8; 3]) {
+    let (width, height) = (mask.width.max(0) as u32, mask.height.max(0) as u32);
+    for y in top..(top + height + 2).min(image.height()) {
+        for x in left..(left + width + 2).min(image.width()) {
+            image.put_pixel(x, y, Rgb([0, 0, 0]));
+        }
+    }
+    for (y, row) in mask.data.chunks(width.max(1) as usize).enumerate() {
+        for (x, &coverage) in row.iter().enumerate() {
+            let (px, py) = (left + 1 + x as u32, top + 1 + y as u32);
+            if coverage == 0 || px >= image.width() || py >= image.height() {
+                continue;
+            }
+            let pixel = image.get_pixel_mut(px, py);
+            for (channel, &text) in pixel.0.iter_mut().zip(&color) {
+                let a = u32::from(coverage);
+                *channel = ((u32::from(text) * a + u32::from(*channel) * (255 - a)) / 255) as u8;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tile(x: u32, y: u32) -> Tile {
+        Tile {
+            x,
+            y,
+            width: 60,
+            height: 60,
+        }
+    }
+
+    #[test]
+    fn test_tile_counts() {
+        let mut boxes = [BoundingBox::new(0.0, 0.0, 10.0, 10.0, 0, 0.9); 4];
+        boxes[0].tile = Some(1);
+        boxes[1].tile = Some(1);
+        boxes[2].tile = Some(0);
+        assert_eq!(tile_counts(2, &boxes), [1, 2]);
+        boxes[3].tile = Some(5);
+        assert_eq!(tile_counts(2, &boxes), [1, 2]);
+    }
+
+    #[test]
+    fn test_overlay_draws_tile_boundaries() {
+        let mut image = RgbImage::new(100, 100);
+        let tiles = [tile(0, 0), tile(40, 40)];
+        let mut bbox = BoundingBox::new(50.0, 50.0, 90.0, 90.0, 0, 0.9);
+        bbox.tile = Some(1);
+        draw_tile_overlay(&mut image, &tiles, &[bbox]);
+
+        // Right and bottom edges of the first tile, bottom-right corner of the second
+        assert_eq!(image.get_pixel(59, 30).0, TILE_COLORS[0]);
+        assert_eq!(image.get_pixel(30, 58).0, TILE_COLORS[0]);
+        assert_eq!(image.get_pixel(99, 99).0, TILE_COLORS[1]);
+        // Inside the tiles, away from labels and tags
+        assert_eq!(image.get_pixel(30, 40).0, [0, 0, 0]);
+        assert_eq!(image.get_pixel(80, 60).0, [0, 0, 0]);
+        // The tag of the box is drawn in the (blue) color of its tile
+        let image = &image;
+        let mut tag = (51..70).flat_map(|x| (70..88).map(move |y| image.get_pixel(x, y).0));
+        assert!(tag.any(|[red, _, blue]| blue > 128 && red < blue / 2));
+    }
+}
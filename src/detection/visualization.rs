@@ -1,7 +1,10 @@
 //! Visualization utilities for drawing bounding boxes on images.
 
 use super::bbox::BoundingBox;
-use crate::image::image_util::generate_class_colors;
+use super::label::{self, LabelPosition};
+use crate::class::group::{ClassGroup, ClassGroups};
+use crate::class::palette::Palette;
+use crate::class::registry::ClassRegistry;
 use image::{DynamicImage, RgbImage};
 use raqote::{DrawOptions, DrawTarget, LineJoin, PathBuilder, SolidSource, Source, StrokeStyle};
 use std::collections::HashMap;
@@ -11,8 +14,16 @@ use std::collections::HashMap;
 pub struct DrawConfig {
     pub line_width: f32,
     pub alpha_blend: bool,
+    pub show_labels: bool,
     pub show_confidence: bool,
     pub font_size: f32,
+    pub label_position: LabelPosition,
+    pub label_background: bool,
+    pub text_color: (u8, u8, u8, u8),
+    pub palette: Palette,
+    pub adaptive_density: bool,
+    pub density_threshold: usize,
+    pub groups: Option<Vec<ClassGroup>>,
 }
 
 impl Default for DrawConfig {
@@ -20,13 +31,35 @@ impl Default for DrawConfig {
         Self {
             line_width: 4.0,
             alpha_blend: true,
+            show_labels: true,
             show_confidence: false,
             font_size: 12.0,
+            label_position: LabelPosition::Above,
+            label_background: true,
+            text_color: (255, 255, 255, 255),
+            palette: Palette::Registry,
+            adaptive_density: true,
+            density_threshold: 100,
+            groups: None,
         }
     }
 }
 
 impl DrawConfig {
+    /// Returns the factor thinning and fading strokes when `box_count` boxes are drawn.
+    ///
+    /// Above `density_threshold` boxes (e.g. walls), strokes shrink with the square root of the
+    /// density so that the screenshot stays visible, down to a quarter of their width and opacity.
+    #[must_use]
+    pub fn density_scale(&self, box_count: usize) -> f32 {
+        if !self.adaptive_density || box_count <= self.density_threshold {
+            return 1.0;
+        }
+        (self.density_threshold as f32 / box_count as f32)
+            .sqrt()
+            .max(0.25)
+    }
+
     /// Draws bounding boxes on an image with improved performance and customization.
     #[must_use]
     pub fn draw_bounding_boxes(
@@ -34,6 +67,18 @@ impl DrawConfig {
         boxes: &[BoundingBox],
         input_size: (u32, u32),
         config: Option<DrawConfig>,
+    ) -> RgbImage {
+        Self::draw_with_classes(image, boxes, input_size, config, &ClassRegistry::default())
+    }
+
+    /// Draws bounding boxes on an image, coloring them after the classes of `classes`.
+    #[must_use]
+    pub fn draw_with_classes(
+        image: &DynamicImage,
+        boxes: &[BoundingBox],
+        input_size: (u32, u32),
+        config: Option<DrawConfig>,
+        classes: &ClassRegistry,
     ) -> RgbImage {
         let config = config.unwrap_or_default();
         let (img_width, img_height) = (image.width(), image.height());
@@ -42,14 +87,35 @@ impl DrawConfig {
             return image.to_rgb8();
         }
 
+        let recolored;
+        let classes = if config.palette == Palette::Registry {
+            classes
+        } else {
+            recolored = classes.clone().with_palette(config.palette);
+            &recolored
+        };
+
         let mut draw_target = DrawTarget::new(img_width as i32, img_height as i32);
-        let class_colors: HashMap<usize, SolidSource> = Self::generate_colors_for_boxes(boxes);
+        let class_colors: HashMap<usize, SolidSource> =
+            Self::generate_colors_for_boxes(boxes, classes);
 
         // Pre-calculate scaling factors
         let scale_x = img_width as f32 / input_size.0 as f32;
         let scale_y = img_height as f32 / input_size.1 as f32;
 
-        for bbox in boxes {
+        let class_groups = ClassGroups::default();
+        let shown: Vec<&BoundingBox> = boxes
+            .iter()
+            .filter(|bbox| {
+                config.groups.as_ref().is_none_or(|groups| {
+                    groups
+                        .iter()
+                        .any(|&group| class_groups.contains(group, bbox))
+                })
+            })
+            .collect();
+        let density_scale = config.density_scale(shown.len());
+        for bbox in &shown {
             Self::draw_single_box(
                 &mut draw_target,
                 bbox,
@@ -57,9 +123,25 @@ impl DrawConfig {
                 scale_x,
                 scale_y,
                 &config,
+                density_scale,
             );
         }
 
+        // Labels go on top of every box so that neighbouring strokes do not cover them
+        if config.show_labels || config.show_confidence {
+            for bbox in &shown {
+                Self::draw_label(
+                    &mut draw_target,
+                    bbox,
+                    classes,
+                    &class_colors,
+                    scale_x,
+                    scale_y,
+                    &config,
+                );
+            }
+        }
+
         Self::blend_with_original_image(image, draw_target, config.alpha_blend)
     }
 
@@ -71,6 +153,7 @@ impl DrawConfig {
         scale_x: f32,
         scale_y: f32,
         config: &DrawConfig,
+        density_scale: f32,
     ) {
         let mut path_builder = PathBuilder::new();
 
@@ -84,36 +167,102 @@ impl DrawConfig {
         let path = path_builder.finish();
 
         // Get color for this class, with fallback
-        let color = class_colors.get(&bbox.class_id).unwrap_or(&SolidSource {
-            r: 0x80,
-            g: 0x10,
-            b: 0x40,
-            a: 0xFF,
-        });
-
-        #[cfg(debug_assertions)]
-        {
-            println!(
-                "Drawing box: class_id={}, x1={}, y1={}, x2={}, y2={}, color={:?}",
-                bbox.class_id, bbox.x1, bbox.y1, bbox.x2, bbox.y2, color
-            );
-        }
+        let color = Self::box_color(bbox, class_colors);
+
+        #[cfg(feature = "tracing")]
+        tracing::trace!(
+            class_id = bbox.class_id,
+            x1 = bbox.x1,
+            y1 = bbox.y1,
+            x2 = bbox.x2,
+            y2 = bbox.y2,
+            "drawing box"
+        );
 
         let stroke_style = StrokeStyle {
             join: LineJoin::Round,
-            width: config.line_width,
+            width: (config.line_width * density_scale).max(1.0),
             ..StrokeStyle::default()
         };
 
         // Draw the rectangle on the draw target
         draw_target.stroke(
             &path,
-            &Source::Solid(*color),
+            &Source::Solid(color),
             &stroke_style,
-            &DrawOptions::new(),
+            &DrawOptions {
+                alpha: density_scale,
+                ..DrawOptions::new()
+            },
         );
     }
 
+    /// Draws the class name and/or confidence of a box, filled with the class color.
+    fn draw_label(
+        draw_target: &mut DrawTarget,
+        bbox: &BoundingBox,
+        classes: &ClassRegistry,
+        class_colors: &HashMap<usize, SolidSource>,
+        scale_x: f32,
+        scale_y: f32,
+        config: &DrawConfig,
+    ) {
+        let text = match (config.show_labels, config.show_confidence) {
+            (true, true) => format!("{} {:.2}", classes.name(bbox.class_id), bbox.confidence),
+            (true, false) => classes.name(bbox.class_id),
+            (false, true) => format!("{:.2}", bbox.confidence),
+            (false, false) => return,
+        };
+        let mask = label::rasterize(&text, config.font_size);
+        if mask.width == 0 {
+            return;
+        }
+
+        let padding = (config.font_size / 6.0).ceil();
+        let label_width = mask.width as f32 + 2.0 * padding;
+        let label_height = mask.height as f32 + 2.0 * padding;
+        let image_width = draw_target.width() as f32;
+        let x = (bbox.x1 * scale_x).min(image_width - label_width).max(0.0);
+        let y = config.label_position.label_top(
+            bbox.y1 * scale_y,
+            bbox.y2 * scale_y,
+            label_height,
+            draw_target.height() as f32,
+        );
+
+        if config.label_background {
+            draw_target.fill_rect(
+                x,
+                y,
+                label_width,
+                label_height,
+                &Source::Solid(Self::box_color(bbox, class_colors)),
+                &DrawOptions::new(),
+            );
+        }
+
+        let (r, g, b, a) = config.text_color;
+        draw_target.mask(
+            &Source::Solid(SolidSource::from_unpremultiplied_argb(a, r, g, b)),
+            (x + padding) as i32,
+            (y + padding) as i32,
+            &mask,
+        );
+    }
+
+    /// Returns the color of the class of a box, with fallback
+    fn box_color(bbox: &BoundingBox, class_colors: &HashMap<usize, SolidSource>) -> SolidSource {
+        class_colors
+            .get(&bbox.class_id)
+            .copied()
+            .unwrap_or(SolidSource {
+                r: 0x80,
+                g: 0x10,
+                b: 0x40,
+                a: 0xFF,
+            })
+    }
+
     // Backward compatibility function
     #[must_use]
     pub fn draw_boxes(
@@ -125,29 +274,18 @@ impl DrawConfig {
     }
 
     /// Generates colors for all unique classes in the bounding boxes.
-    fn generate_colors_for_boxes(boxes: &[BoundingBox]) -> HashMap<usize, SolidSource> {
-        if boxes.is_empty() {
-            return HashMap::new();
-        }
-
-        // Get all class colors from ClashClass
-        let all_class_colors = generate_class_colors();
-
+    fn generate_colors_for_boxes(
+        boxes: &[BoundingBox],
+        classes: &ClassRegistry,
+    ) -> HashMap<usize, SolidSource> {
         // Only return colors for classes that are actually present in the boxes
-        let unique_classes: std::collections::HashSet<usize> =
-            boxes.iter().map(|bbox| bbox.class_id).collect();
-
-        if unique_classes.is_empty() {
-            return HashMap::new();
-        }
-
-        // Filter to only include colors for classes present in the boxes
-        unique_classes
-            .into_iter()
-            .filter_map(|class_id| {
-                all_class_colors
-                    .get(&class_id)
-                    .map(|&color| (class_id, color))
+        boxes
+            .iter()
+            .map(|bbox| bbox.class_id)
+            .filter(|&class_id| class_id < classes.len())
+            .map(|class_id| {
+                let (r, g, b, a) = classes.color(class_id);
+                (class_id, SolidSource { r, g, b, a })
             })
             .collect()
     }
@@ -188,3 +326,23 @@ impl DrawConfig {
         result
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_density_scale() {
+        let config = DrawConfig::default();
+        assert_eq!(config.density_scale(0), 1.0);
+        assert_eq!(config.density_scale(100), 1.0);
+        assert_eq!(config.density_scale(400), 0.5);
+        assert_eq!(config.density_scale(100_000), 0.25);
+
+        let config = DrawConfig {
+            adaptive_density: false,
+            ..DrawConfig::default()
+        };
+        assert_eq!(config.density_scale(400), 1.0);
+    }
+}
@@ -0,0 +1,146 @@
+//! Provenance footer stamped under annotated images, tracing them back to the run that produced them
+
+use super::label;
+use image::{Rgb, RgbImage};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Provenance footer appended to the bottom of annotated images
+#[derive(Debug, Clone, PartialEq)]
+pub struct Watermark {
+    pub model_version: String,
+    pub font_size: f32,
+    pub background: [u8; 3],
+    pub text_color: [u8; 3],
+}
+
+impl Default for Watermark {
+    fn default() -> Self {
+        Self {
+            model_version: format!("clashvision {}", env!("CARGO_PKG_VERSION")),
+            font_size: 11.0,
+            background: [0, 0, 0],
+            text_color: [220, 220, 220],
+        }
+    }
+}
+
+impl Watermark {
+    /// Creates a watermark naming `model_version`
+    pub fn new(model_version: impl Into<String>) -> Self {
+        Self {
+            model_version: model_version.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Returns the footer text: model version, UTC timestamp and configuration hash
+    #[must_use]
+    pub fn footer_text(&self, timestamp: SystemTime, config_hash: &str) -> String {
+        format!(
+            "{} | {} | config {config_hash}",
+            self.model_version,
+            format_utc(timestamp)
+        )
+    }
+
+    /// Returns a copy of `image` with the provenance footer appended below it
+    #[must_use]
+    pub fn stamp(&self, image: &RgbImage, timestamp: SystemTime, config_hash: &str) -> RgbImage {
+        let mask = label::rasterize(&self.footer_text(timestamp, config_hash), self.font_size);
+        let padding = (self.font_size / 4.0).ceil() as u32;
+        let footer_height = mask.height as u32 + 2 * padding;
+
+        let mut stamped = RgbImage::from_pixel(
+            image.width(),
+            image.height() + footer_height,
+            Rgb(self.background),
+        );
+        image::imageops::replace(&mut stamped, image, 0, 0);
+
+        // Text wider than the image is cut off on the right
+        let (left, top) = (padding, image.height() + padding);
+        for (y, row) in mask.data.chunks(mask.width.max(1) as usize).enumerate() {
+            for (x, &coverage) in row.iter().enumerate() {
+                let px = left + x as u32;
+                if coverage == 0 || px >= stamped.width() {
+                    continue;
+                }
+                let pixel = stamped.get_pixel_mut(px, top + y as u32);
+                for (channel, &text) in pixel.0.iter_mut().zip(&self.text_color) {
+                    let a = u32::from(coverage);
+                    *channel =
+                        ((u32::from(text) * a + u32::from(*channel) * (255 - a)) / 255) as u8;
+                }
+            }
+        }
+        stamped
+    }
+}
+
+/// Formats a timestamp as `YYYY-MM-DD HH:MM:SS UTC`
+#[must_use]
+pub fn format_utc(timestamp: SystemTime) -> String {
+    let seconds = timestamp
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since_epoch| since_epoch.as_secs());
+    let (days, time_of_day) = (seconds / 86_400, seconds % 86_400);
+
+    // Civil date from days since the epoch, after Howard Hinnant's `civil_from_days`
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02} UTC",
+        time_of_day / 3600,
+        time_of_day % 3600 / 60,
+        time_of_day % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::GenericImageView;
+    use std::time::Duration;
+
+    #[test]
+    fn test_format_utc() {
+        assert_eq!(format_utc(UNIX_EPOCH), "1970-01-01 00:00:00 UTC");
+        assert_eq!(
+            format_utc(UNIX_EPOCH + Duration::from_secs(951_827_696)),
+            "2000-02-29 12:34:56 UTC"
+        );
+        assert_eq!(
+            format_utc(UNIX_EPOCH + Duration::from_secs(1_791_936_000)),
+            "2026-10-14 00:00:00 UTC"
+        );
+    }
+
+    #[test]
+    fn test_stamp_appends_footer() {
+        let image = RgbImage::from_pixel(200, 100, Rgb([10, 200, 10]));
+        let watermark = Watermark::new("yolov8 1a2b3c4d");
+        let text = watermark.footer_text(UNIX_EPOCH, "00ff");
+        assert_eq!(
+            text,
+            "yolov8 1a2b3c4d | 1970-01-01 00:00:00 UTC | config 00ff"
+        );
+
+        let stamped = watermark.stamp(&image, UNIX_EPOCH, "00ff");
+        assert_eq!(stamped.width(), 200);
+        assert!(stamped.height() > 100);
+        assert_eq!(stamped.get_pixel(199, 99), &Rgb([10, 200, 10]));
+
+        let footer = stamped.view(0, 100, 200, stamped.height() - 100);
+        assert!(footer.pixels().any(|(_, _, pixel)| pixel.0[0] > 100));
+        assert!(footer.pixels().any(|(_, _, pixel)| pixel.0 == [0, 0, 0]));
+    }
+}
@@ -0,0 +1,127 @@
+//! Weighted Boxes Fusion, merging the overlapping boxes of several models or test-time
+//! augmentations instead of keeping only the most confident one as NMS does
+
+use super::bbox::BoundingBox;
+use super::nms::{detection_order, is_finite_box};
+
+/// Boxes fused into one, with the sums its coordinates are averaged from
+struct Cluster {
+    fused: BoundingBox,
+    weighted: [f32; 4], // Confidence-weighted sums of x1, y1, x2, y2
+    confidence_sum: f32,
+    members: usize,
+}
+
+impl Cluster {
+    fn new(bbox: BoundingBox) -> Self {
+        let mut cluster = Self {
+            fused: bbox,
+            weighted: [0.0; 4],
+            confidence_sum: 0.0,
+            members: 0,
+        };
+        cluster.add(&bbox);
+        cluster
+    }
+
+    /// Adds a box, moving the fused corners to the confidence-weighted mean of the members
+    fn add(&mut self, bbox: &BoundingBox) {
+        let corners = [bbox.x1, bbox.y1, bbox.x2, bbox.y2];
+        for (sum, corner) in self.weighted.iter_mut().zip(corners) {
+            *sum += bbox.confidence * corner;
+        }
+        self.confidence_sum += bbox.confidence;
+        self.members += 1;
+
+        if self.confidence_sum > 0.0 {
+            let [x1, y1, x2, y2] = self.weighted.map(|sum| sum / self.confidence_sum);
+            (self.fused.x1, self.fused.y1, self.fused.x2, self.fused.y2) = (x1, y1, x2, y2);
+        }
+        self.fused.confidence = self.confidence_sum / self.members as f32;
+    }
+}
+
+/// Fuses the boxes predicted for one image by several models (one set per model).
+///
+/// Boxes below `skip_threshold` or with non-finite values are ignored. Going from the most
+/// confident box down, each box joins the cluster of its class whose fused box overlaps it most
+/// with an `IoU` above `iou_threshold`, or starts a new cluster. A fused box has the
+/// confidence-weighted mean corners of its cluster, and the mean confidence of its members
+/// scaled down when fewer sets than given predicted it.
+///
+/// # Returns
+/// Vector of fused boxes, in `detection_order`.
+#[must_use]
+pub fn weighted_boxes_fusion(
+    box_sets: &[Vec<BoundingBox>],
+    iou_threshold: f32,
+    skip_threshold: f32,
+) -> Vec<BoundingBox> {
+    let mut boxes: Vec<BoundingBox> = box_sets
+        .iter()
+        .flatten()
+        .copied()
+        .filter(|bbox| is_finite_box(bbox) && bbox.confidence >= skip_threshold)
+        .collect();
+    boxes.sort_by(detection_order);
+
+    let mut clusters: Vec<Cluster> = Vec::new();
+    for bbox in &boxes {
+        let best = clusters
+            .iter_mut()
+            .filter(|cluster| cluster.fused.class_id == bbox.class_id)
+            .map(|cluster| (cluster.fused.iou(bbox), cluster))
+            .filter(|(iou, _)| *iou > iou_threshold)
+            .max_by(|a, b| a.0.total_cmp(&b.0));
+        match best {
+            Some((_, cluster)) => cluster.add(bbox),
+            None => clusters.push(Cluster::new(*bbox)),
+        }
+    }
+
+    let sets = box_sets.len().max(1) as f32;
+    let mut fused: Vec<BoundingBox> = clusters
+        .into_iter()
+        .map(|cluster| {
+            let mut bbox = cluster.fused;
+            bbox.confidence *= (cluster.members as f32).min(sets) / sets;
+            bbox
+        })
+        .collect();
+    fused.sort_by(detection_order);
+    fused
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuses_overlapping_boxes_by_confidence() {
+        let model_a = vec![
+            BoundingBox::new(0.0, 0.0, 10.0, 10.0, 1, 0.9),
+            BoundingBox::new(50.0, 50.0, 60.0, 60.0, 0, 0.8),
+        ];
+        let model_b = vec![
+            BoundingBox::new(2.0, 0.0, 12.0, 10.0, 1, 0.3),
+            // Same place as model A but another class: not fused
+            BoundingBox::new(50.0, 50.0, 60.0, 60.0, 1, 0.6),
+            // Below the skip threshold
+            BoundingBox::new(100.0, 100.0, 110.0, 110.0, 1, 0.05),
+        ];
+
+        let fused = weighted_boxes_fusion(&[model_a, model_b], 0.55, 0.1);
+        assert_eq!(fused.len(), 3);
+
+        let gold = fused[0];
+        assert_eq!(gold.class_id, 1);
+        // x1 = (0.9 * 0 + 0.3 * 2) / 1.2
+        assert!((gold.x1 - 0.5).abs() < 1e-5 && (gold.x2 - 10.5).abs() < 1e-5);
+        assert!((gold.confidence - 0.6).abs() < 1e-5);
+
+        // Predicted by one model out of two
+        assert!((fused[1].confidence - 0.4).abs() < 1e-5);
+        assert!((fused[2].confidence - 0.3).abs() < 1e-5);
+        assert!(weighted_boxes_fusion(&[], 0.55, 0.0).is_empty());
+    }
+}
@@ -0,0 +1,166 @@
+//! Ground-truth annotations read from YOLO label files or COCO JSON
+
+use crate::detection::BoundingBox;
+use crate::eval::EvalError;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+/// Boxes by image name, used for both ground truth and predictions
+pub type Annotations = BTreeMap<String, Vec<BoundingBox>>;
+
+/// Ground-truth boxes of a set of images, in image pixels
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GroundTruth {
+    pub images: Annotations,
+}
+
+#[derive(Deserialize)]
+struct CocoFile {
+    images: Vec<CocoFileImage>,
+    #[serde(default)]
+    annotations: Vec<CocoFileAnnotation>,
+}
+
+#[derive(Deserialize)]
+struct CocoFileImage {
+    id: u64,
+    file_name: String,
+}
+
+#[derive(Deserialize)]
+struct CocoFileAnnotation {
+    image_id: u64,
+    category_id: usize,
+    bbox: [f32; 4],
+}
+
+impl GroundTruth {
+    /// Sets the ground truth of an image
+    pub fn insert(&mut self, image: impl Into<String>, boxes: Vec<BoundingBox>) {
+        self.images.insert(image.into(), boxes);
+    }
+
+    /// Reads the YOLO label file of an image of `image_dimensions`
+    pub fn insert_yolo_txt(
+        &mut self,
+        image: impl Into<String>,
+        label_path: &Path,
+        image_dimensions: (u32, u32),
+    ) -> Result<(), EvalError> {
+        let boxes = parse_yolo_txt(&fs::read_to_string(label_path)?, image_dimensions)?;
+        self.insert(image, boxes);
+        Ok(())
+    }
+
+    /// Reads a COCO dataset JSON, keying the images by `file_name`. Category ids are taken as
+    /// class ids, and images without annotations have an empty ground truth.
+    pub fn from_coco_json(path: &Path) -> Result<Self, EvalError> {
+        let coco: CocoFile = serde_json::from_str(&fs::read_to_string(path)?)
+            .map_err(|e| EvalError::Parse(format!("{}: {e}", path.display())))?;
+
+        let names: HashMap<u64, &str> = coco
+            .images
+            .iter()
+            .map(|image| (image.id, image.file_name.as_str()))
+            .collect();
+        let mut ground_truth = Self::default();
+        for image in &coco.images {
+            ground_truth.insert(image.file_name.clone(), Vec::new());
+        }
+        for annotation in &coco.annotations {
+            let name = names.get(&annotation.image_id).ok_or_else(|| {
+                EvalError::Parse(format!(
+                    "annotation of unknown image {}",
+                    annotation.image_id
+                ))
+            })?;
+            let [x, y, width, height] = annotation.bbox;
+            if let Some(boxes) = ground_truth.images.get_mut(*name) {
+                boxes.push(BoundingBox::new(
+                    x,
+                    y,
+                    x + width,
+                    y + height,
+                    annotation.category_id,
+                    1.0,
+                ));
+            }
+        }
+        Ok(ground_truth)
+    }
+}
+
+/// Parses YOLO labels (`class cx cy w h`, normalized) into boxes of an image of `image_dimensions`
+pub fn parse_yolo_txt(
+    text: &str,
+    image_dimensions: (u32, u32),
+) -> Result<Vec<BoundingBox>, EvalError> {
+    let (width, height) = (image_dimensions.0 as f32, image_dimensions.1 as f32);
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(number, line)| {
+            let invalid = || EvalError::Parse(format!("line {}: {line}", number + 1));
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [class_id, values @ ..] = fields.as_slice() else {
+                return Err(invalid());
+            };
+            let class_id: usize = class_id.parse().map_err(|_| invalid())?;
+            let values: Vec<f32> = values
+                .iter()
+                .map(|value| value.parse().map_err(|_| invalid()))
+                .collect::<Result<_, _>>()?;
+            let [cx, cy, w, h] = values[..] else {
+                return Err(invalid());
+            };
+            Ok(BoundingBox::new(
+                (cx - w / 2.0) * width,
+                (cy - h / 2.0) * height,
+                (cx + w / 2.0) * width,
+                (cy + h / 2.0) * height,
+                class_id,
+                1.0,
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_yolo_txt() {
+        let boxes = parse_yolo_txt("1 0.3 0.5 0.4 0.6\n\n2 0.5 0.5 1 1\n", (100, 200)).unwrap();
+        assert_eq!(boxes.len(), 2);
+        let (x1, y1, x2, y2) = (boxes[0].x1, boxes[0].y1, boxes[0].x2, boxes[0].y2);
+        assert!((x1 - 10.0).abs() < 1e-4 && (y1 - 40.0).abs() < 1e-4);
+        assert!((x2 - 50.0).abs() < 1e-4 && (y2 - 160.0).abs() < 1e-4);
+        assert_eq!(boxes[1].class_id, 2);
+        assert!(parse_yolo_txt("1 0.3 0.5 0.4\n", (100, 100)).is_err());
+        assert!(parse_yolo_txt("x 0.3 0.5 0.4 0.6\n", (100, 100)).is_err());
+    }
+
+    #[test]
+    fn test_from_coco_json() -> Result<(), EvalError> {
+        let dir = tempdir()?;
+        let path = dir.path().join("gt.json");
+        fs::write(
+            &path,
+            r#"{
+                "images": [{"id": 1, "file_name": "a.png"}, {"id": 2, "file_name": "b.png"}],
+                "annotations": [{"image_id": 1, "category_id": 3, "bbox": [10, 20, 30, 40]}]
+            }"#,
+        )?;
+        let ground_truth = GroundTruth::from_coco_json(&path)?;
+        assert_eq!(
+            ground_truth.images["a.png"],
+            [BoundingBox::new(10.0, 20.0, 40.0, 60.0, 3, 1.0)]
+        );
+        assert!(ground_truth.images["b.png"].is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,532 @@
+//! Evaluation of predictions against ground truth: per-class and per-group precision and
+//! recall, COCO-style average precision and a confusion matrix
+
+pub mod ground_truth;
+
+use crate::class::group::{ClassGroup, ClassGroups};
+use crate::class::registry::ClassRegistry;
+use crate::detection::BoundingBox;
+use ground_truth::{Annotations, GroundTruth};
+use std::fmt::Write as _;
+
+/// Errors that can occur while loading ground truth
+#[derive(Debug, thiserror::Error)]
+pub enum EvalError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid annotations: {0}")]
+    Parse(String),
+}
+
+/// Metrics of one class
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClassMetrics {
+    pub class_id: usize,
+    pub ground_truth: usize,
+    pub predictions: usize,
+    /// Precision over all predictions, matched at `IoU` 0.5
+    pub precision: f32,
+    /// Recall over all predictions, matched at `IoU` 0.5
+    pub recall: f32,
+    pub ap50: f32,
+    /// AP averaged over the `IoU` thresholds 0.5 to 0.95 by 0.05
+    pub ap50_95: f32,
+}
+
+/// Metrics of a class group, where a box predicted as another class of its group is correct
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GroupMetrics {
+    pub group: ClassGroup,
+    pub ground_truth: usize,
+    pub predictions: usize,
+    pub precision: f32,
+    pub recall: f32,
+    pub ap50: f32,
+    pub ap50_95: f32,
+}
+
+/// Counts of ground-truth classes (rows) predicted as each class (columns). The last row and
+/// column stand for the background: missed ground truth and predictions matching nothing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfusionMatrix {
+    pub counts: Vec<Vec<usize>>,
+}
+
+impl ConfusionMatrix {
+    /// Returns the index of the background row and column
+    #[inline]
+    #[must_use]
+    pub fn background(&self) -> usize {
+        self.counts.len() - 1
+    }
+
+    /// Returns how many boxes of class `actual` were predicted as `predicted`
+    #[inline]
+    #[must_use]
+    pub fn get(&self, actual: usize, predicted: usize) -> usize {
+        self.counts[actual][predicted]
+    }
+}
+
+/// Result of an evaluation
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvalReport {
+    /// Metrics of the classes with ground truth or predictions, by class id
+    pub classes: Vec<ClassMetrics>,
+    /// Mean AP@0.5 of the classes with ground truth
+    pub map50: f32,
+    /// Mean AP@[.5:.95] of the classes with ground truth
+    pub map50_95: f32,
+    /// Metrics of the groups with ground truth or predictions, in `ClassGroup` order
+    pub groups: Vec<GroupMetrics>,
+    pub confusion: ConfusionMatrix,
+    /// Confusion matches predicted as another class of the same group, e.g. a Gold Storage
+    /// taken for an Elixir Storage, which matter much less for raiding than full misses.
+    /// Classes of a group and of a group nested under it count as the same group.
+    pub wrong_class_right_group: usize,
+    /// Confusion matches predicted as a class of another group, or of no group
+    pub wrong_group: usize,
+}
+
+impl EvalReport {
+    /// Renders a metrics table, one line per class
+    #[must_use]
+    pub fn render_text(&self, classes: &ClassRegistry) -> String {
+        let mut text = String::new();
+        let _ = writeln!(
+            text,
+            "{:<24} {:>6} {:>6} {:>9} {:>6} {:>6} {:>9}",
+            "Class", "GT", "Pred", "Precision", "Recall", "AP50", "AP50-95"
+        );
+        for metrics in &self.classes {
+            let _ = writeln!(
+                text,
+                "{:<24} {:>6} {:>6} {:>9.3} {:>6.3} {:>6.3} {:>9.3}",
+                classes.name(metrics.class_id),
+                metrics.ground_truth,
+                metrics.predictions,
+                metrics.precision,
+                metrics.recall,
+                metrics.ap50,
+                metrics.ap50_95
+            );
+        }
+        let _ = writeln!(
+            text,
+            "mAP50: {:.3}, mAP50-95: {:.3}",
+            self.map50, self.map50_95
+        );
+        for metrics in &self.groups {
+            let _ = writeln!(
+                text,
+                "{:<24} {:>6} {:>6} {:>9.3} {:>6.3} {:>6.3} {:>9.3}",
+                format!("[{}]", metrics.group),
+                metrics.ground_truth,
+                metrics.predictions,
+                metrics.precision,
+                metrics.recall,
+                metrics.ap50,
+                metrics.ap50_95
+            );
+        }
+        let _ = writeln!(
+            text,
+            "Confusions: {} wrong class in the right group, {} wrong group",
+            self.wrong_class_right_group, self.wrong_group
+        );
+        text
+    }
+}
+
+/// Evaluates predictions against ground truth
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Evaluator {
+    pub confusion_iou: f32,
+    pub confusion_confidence: f32,
+}
+
+impl Default for Evaluator {
+    fn default() -> Self {
+        Self {
+            confusion_iou: 0.5,         // Minimum IoU of a confusion match, across classes
+            confusion_confidence: 0.25, // Predictions below are left out of the matrix
+        }
+    }
+}
+
+/// `IoU` thresholds of AP@[.5:.95]
+const IOU_THRESHOLDS: [f32; 10] = [0.5, 0.55, 0.6, 0.65, 0.7, 0.75, 0.8, 0.85, 0.9, 0.95];
+
+impl Evaluator {
+    /// Evaluates the predictions of the images of the ground truth; predictions of other
+    /// images are ignored and images without predictions count as predicting nothing
+    #[must_use]
+    pub fn evaluate(&self, predictions: &Annotations, ground_truth: &GroundTruth) -> EvalReport {
+        self.evaluate_with_groups(predictions, ground_truth, &ClassGroups::default())
+    }
+
+    /// Evaluates like `evaluate`, aggregating the metrics and confusions over the class groups
+    /// of `groups` instead of the built-in ones
+    #[must_use]
+    pub fn evaluate_with_groups(
+        &self,
+        predictions: &Annotations,
+        ground_truth: &GroundTruth,
+        groups: &ClassGroups,
+    ) -> EvalReport {
+        let none = Vec::new();
+        let images: Vec<(&[BoundingBox], &[BoundingBox])> = ground_truth
+            .images
+            .iter()
+            .map(|(image, truth)| {
+                let predicted = predictions.get(image).unwrap_or(&none);
+                (predicted.as_slice(), truth.as_slice())
+            })
+            .collect();
+
+        let num_classes = images
+            .iter()
+            .flat_map(|(predicted, truth)| predicted.iter().chain(truth.iter()))
+            .map(|bbox| bbox.class_id + 1)
+            .max()
+            .unwrap_or(0);
+
+        let classes: Vec<ClassMetrics> = (0..num_classes)
+            .filter_map(|class_id| class_metrics(&images, class_id))
+            .collect();
+
+        let scored: Vec<&ClassMetrics> = classes.iter().filter(|c| c.ground_truth > 0).collect();
+        let mean = |ap: fn(&ClassMetrics) -> f32| {
+            if scored.is_empty() {
+                0.0
+            } else {
+                scored.iter().map(|c| ap(c)).sum::<f32>() / scored.len() as f32
+            }
+        };
+        let confusion = self.confusion_matrix(&images, num_classes);
+        let (wrong_class_right_group, wrong_group) = confusions_by_group(&confusion, groups);
+        EvalReport {
+            map50: mean(|c| c.ap50),
+            map50_95: mean(|c| c.ap50_95),
+            classes,
+            groups: group_metrics(&images, groups),
+            confusion,
+            wrong_class_right_group,
+            wrong_group,
+        }
+    }
+
+    /// Matches the boxes of every image by decreasing `IoU`, across classes
+    fn confusion_matrix(
+        &self,
+        images: &[(&[BoundingBox], &[BoundingBox])],
+        num_classes: usize,
+    ) -> ConfusionMatrix {
+        let background = num_classes;
+        let mut counts = vec![vec![0; num_classes + 1]; num_classes + 1];
+        for (predicted, truth) in images {
+            let predicted: Vec<&BoundingBox> = predicted
+                .iter()
+                .filter(|b| b.confidence >= self.confusion_confidence)
+                .collect();
+            let mut pairs: Vec<(usize, usize, f32)> = Vec::new();
+            for (t, truth_box) in truth.iter().enumerate() {
+                for (p, predicted_box) in predicted.iter().enumerate() {
+                    let iou = truth_box.iou(predicted_box);
+                    if iou >= self.confusion_iou {
+                        pairs.push((t, p, iou));
+                    }
+                }
+            }
+            pairs.sort_by(|a, b| b.2.total_cmp(&a.2));
+
+            let mut truth_matched = vec![false; truth.len()];
+            let mut predicted_matched = vec![false; predicted.len()];
+            for (t, p, _) in pairs {
+                if !truth_matched[t] && !predicted_matched[p] {
+                    truth_matched[t] = true;
+                    predicted_matched[p] = true;
+                    counts[truth[t].class_id][predicted[p].class_id] += 1;
+                }
+            }
+            for (t, _) in truth_matched.iter().enumerate().filter(|(_, m)| !**m) {
+                counts[truth[t].class_id][background] += 1;
+            }
+            for (p, _) in predicted_matched.iter().enumerate().filter(|(_, m)| !**m) {
+                counts[background][predicted[p].class_id] += 1;
+            }
+        }
+        ConfusionMatrix { counts }
+    }
+}
+
+/// Returns the metrics of a class, or `None` if it has neither ground truth nor predictions
+fn class_metrics(
+    images: &[(&[BoundingBox], &[BoundingBox])],
+    class_id: usize,
+) -> Option<ClassMetrics> {
+    let count = |boxes: &[BoundingBox]| boxes.iter().filter(|b| b.class_id == class_id).count();
+    let ground_truth = images.iter().map(|(_, truth)| count(truth)).sum();
+    let predictions = images.iter().map(|(predicted, _)| count(predicted)).sum();
+    if ground_truth == 0 && predictions == 0 {
+        return None;
+    }
+
+    let curves: Vec<(f32, f32, f32)> = IOU_THRESHOLDS
+        .iter()
+        .map(|&iou| precision_recall(images, class_id, iou, ground_truth))
+        .collect();
+    let (ap50, precision, recall) = curves[0];
+    Some(ClassMetrics {
+        class_id,
+        ground_truth,
+        predictions,
+        precision,
+        recall,
+        ap50,
+        ap50_95: curves.iter().map(|c| c.0).sum::<f32>() / curves.len() as f32,
+    })
+}
+
+/// Returns the metrics of every group, evaluating the boxes relabelled with the index of the
+/// group of their class; boxes of classes without a group are left out
+fn group_metrics(
+    images: &[(&[BoundingBox], &[BoundingBox])],
+    groups: &ClassGroups,
+) -> Vec<GroupMetrics> {
+    let values = ClassGroup::values();
+    let relabel = |boxes: &[BoundingBox]| -> Vec<BoundingBox> {
+        boxes
+            .iter()
+            .filter_map(|bbox| {
+                let group = groups.group(bbox.class_id)?;
+                let index = values.iter().position(|&g| g == group)?;
+                Some(BoundingBox {
+                    class_id: index,
+                    ..*bbox
+                })
+            })
+            .collect()
+    };
+    let relabelled: Vec<(Vec<BoundingBox>, Vec<BoundingBox>)> = images
+        .iter()
+        .map(|(predicted, truth)| (relabel(predicted), relabel(truth)))
+        .collect();
+    let relabelled: Vec<(&[BoundingBox], &[BoundingBox])> = relabelled
+        .iter()
+        .map(|(predicted, truth)| (predicted.as_slice(), truth.as_slice()))
+        .collect();
+
+    values
+        .iter()
+        .enumerate()
+        .filter_map(|(index, &group)| {
+            let metrics = class_metrics(&relabelled, index)?;
+            Some(GroupMetrics {
+                group,
+                ground_truth: metrics.ground_truth,
+                predictions: metrics.predictions,
+                precision: metrics.precision,
+                recall: metrics.recall,
+                ap50: metrics.ap50,
+                ap50_95: metrics.ap50_95,
+            })
+        })
+        .collect()
+}
+
+/// Splits the confusion matches between two classes into those within a group and the others.
+///
+/// Two classes share a group when one's group is the other's or nested under it, so a
+/// resource taken for a class grouped as a plain building stays within its group, while
+/// resources and defenses, both nested under buildings, are different groups.
+fn confusions_by_group(confusion: &ConfusionMatrix, groups: &ClassGroups) -> (usize, usize) {
+    let background = confusion.background();
+    let (mut right_group, mut wrong_group) = (0, 0);
+    for actual in 0..background {
+        for predicted in (0..background).filter(|&predicted| predicted != actual) {
+            let count = confusion.get(actual, predicted);
+            match (groups.group(actual), groups.group(predicted)) {
+                (Some(a), Some(p)) if a.is_within(p) || p.is_within(a) => right_group += count,
+                _ => wrong_group += count,
+            }
+        }
+    }
+    (right_group, wrong_group)
+}
+
+/// Returns the AP (101-point interpolated, as in COCO), precision and recall of a class at an
+/// `IoU` threshold, matching predictions by decreasing confidence to the best unmatched ground
+/// truth of their image
+fn precision_recall(
+    images: &[(&[BoundingBox], &[BoundingBox])],
+    class_id: usize,
+    iou_threshold: f32,
+    ground_truth: usize,
+) -> (f32, f32, f32) {
+    let mut predictions: Vec<(usize, &BoundingBox)> = images
+        .iter()
+        .enumerate()
+        .flat_map(|(image, (predicted, _))| predicted.iter().map(move |bbox| (image, bbox)))
+        .filter(|(_, bbox)| bbox.class_id == class_id)
+        .collect();
+    predictions.sort_by(|a, b| b.1.confidence.total_cmp(&a.1.confidence));
+
+    let mut matched: Vec<Vec<bool>> = images
+        .iter()
+        .map(|(_, truth)| vec![false; truth.len()])
+        .collect();
+    let mut true_positives = 0;
+    let mut curve = Vec::with_capacity(predictions.len());
+    for (rank, (image, bbox)) in predictions.iter().enumerate() {
+        let best = images[*image]
+            .1
+            .iter()
+            .enumerate()
+            .filter(|(t, truth)| truth.class_id == class_id && !matched[*image][*t])
+            .map(|(t, truth)| (t, truth.iou(bbox)))
+            .filter(|&(_, iou)| iou >= iou_threshold)
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((t, _)) = best {
+            matched[*image][t] = true;
+            true_positives += 1;
+        }
+        let precision = true_positives as f32 / (rank + 1) as f32;
+        let recall = true_positives as f32 / ground_truth.max(1) as f32;
+        curve.push((precision, recall));
+    }
+
+    let (precision, recall) = curve.last().copied().unwrap_or((0.0, 0.0));
+    if ground_truth == 0 {
+        return (0.0, precision, recall);
+    }
+
+    // Precision envelope: the best precision at any recall at least as high
+    for i in (0..curve.len().saturating_sub(1)).rev() {
+        curve[i].0 = curve[i].0.max(curve[i + 1].0);
+    }
+    let ap = (0..=100)
+        .map(|step| {
+            let recall_level = step as f32 / 100.0;
+            curve
+                .iter()
+                .find(|(_, recall)| *recall >= recall_level)
+                .map_or(0.0, |(precision, _)| *precision)
+        })
+        .sum::<f32>()
+        / 101.0;
+    (ap, precision, recall)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bbox(x: f32, class_id: usize, confidence: f32) -> BoundingBox {
+        BoundingBox::new(x, 0.0, x + 10.0, 10.0, class_id, confidence)
+    }
+
+    #[test]
+    fn test_perfect_predictions() {
+        let mut ground_truth = GroundTruth::default();
+        ground_truth.insert("a.png", vec![bbox(0.0, 0, 1.0), bbox(20.0, 1, 1.0)]);
+        ground_truth.insert("b.png", vec![bbox(40.0, 1, 1.0)]);
+        let predictions = ground_truth.images.clone();
+
+        let report = Evaluator::default().evaluate(&predictions, &ground_truth);
+        assert_eq!(report.classes.len(), 2);
+        assert!((report.map50 - 1.0).abs() < 1e-6);
+        assert!((report.map50_95 - 1.0).abs() < 1e-6);
+        assert_eq!(report.confusion.get(1, 1), 2);
+        assert_eq!(report.confusion.get(1, report.confusion.background()), 0);
+    }
+
+    #[test]
+    fn test_misses_false_positives_and_confusions() {
+        let mut ground_truth = GroundTruth::default();
+        ground_truth.insert(
+            "a.png",
+            vec![bbox(0.0, 0, 1.0), bbox(20.0, 0, 1.0), bbox(40.0, 1, 1.0)],
+        );
+        let mut predictions = Annotations::new();
+        predictions.insert(
+            "a.png".to_string(),
+            vec![
+                bbox(0.0, 0, 0.9),  // true positive
+                bbox(60.0, 0, 0.8), // false positive
+                bbox(40.0, 0, 0.7), // class 1 predicted as class 0
+            ],
+        );
+
+        let report = Evaluator::default().evaluate(&predictions, &ground_truth);
+        let class0 = report.classes[0];
+        assert_eq!((class0.ground_truth, class0.predictions), (2, 3));
+        assert!((class0.precision - 1.0 / 3.0).abs() < 1e-6);
+        assert!((class0.recall - 0.5).abs() < 1e-6);
+        // Precision 1 up to recall 0.5: 51 of the 101 recall levels
+        assert!((class0.ap50 - 51.0 / 101.0).abs() < 1e-6);
+        assert_eq!(report.classes[1].ap50, 0.0);
+
+        assert_eq!((report.wrong_class_right_group, report.wrong_group), (1, 0));
+        let resources = report.groups[0];
+        assert_eq!(resources.group, ClassGroup::Resources);
+        assert_eq!((resources.ground_truth, resources.predictions), (3, 3));
+        assert!((resources.recall - 2.0 / 3.0).abs() < 1e-6);
+
+        let confusion = &report.confusion;
+        let background = confusion.background();
+        assert_eq!(confusion.get(0, 0), 1);
+        assert_eq!(confusion.get(1, 0), 1);
+        assert_eq!(confusion.get(0, background), 1);
+        assert_eq!(confusion.get(background, 0), 1);
+        assert!(
+            report
+                .render_text(&ClassRegistry::default())
+                .contains("mAP50: 0.252")
+        );
+    }
+
+    #[test]
+    fn test_confusions_across_groups() {
+        let mut groups = ClassGroups::default();
+        groups.insert(2, ClassGroup::Defenses);
+        let mut ground_truth = GroundTruth::default();
+        ground_truth.insert("a.png", vec![bbox(0.0, 0, 1.0), bbox(20.0, 2, 1.0)]);
+        let mut predictions = Annotations::new();
+        predictions.insert(
+            "a.png".to_string(),
+            vec![bbox(0.0, 2, 0.9), bbox(20.0, 2, 0.9), bbox(40.0, 1, 0.9)],
+        );
+
+        let report =
+            Evaluator::default().evaluate_with_groups(&predictions, &ground_truth, &groups);
+        assert_eq!((report.wrong_class_right_group, report.wrong_group), (0, 1));
+        let names: Vec<ClassGroup> = report.groups.iter().map(|g| g.group).collect();
+        assert_eq!(names, [ClassGroup::Resources, ClassGroup::Defenses]);
+        assert_eq!(report.groups[0].recall, 0.0);
+        assert!((report.groups[1].precision - 0.5).abs() < 1e-6);
+        assert!(
+            report
+                .render_text(&ClassRegistry::default())
+                .contains("Confusions: 0 wrong class in the right group, 1 wrong group")
+        );
+    }
+
+    #[test]
+    fn test_confusions_follow_group_nesting() {
+        let mut groups = ClassGroups::default();
+        groups.insert(2, ClassGroup::Defenses);
+        groups.insert(3, ClassGroup::Buildings);
+        let mut ground_truth = GroundTruth::default();
+        ground_truth.insert("a.png", vec![bbox(0.0, 3, 1.0), bbox(20.0, 1, 1.0)]);
+        let mut predictions = Annotations::new();
+        predictions.insert(
+            "a.png".to_string(),
+            vec![bbox(0.0, 1, 0.9), bbox(20.0, 2, 0.9)],
+        );
+
+        let report =
+            Evaluator::default().evaluate_with_groups(&predictions, &ground_truth, &groups);
+        assert_eq!((report.wrong_class_right_group, report.wrong_group), (1, 1));
+    }
+}
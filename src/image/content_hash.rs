@@ -0,0 +1,118 @@
+//! Hash functions behind content fingerprints, chosen at compile time by feature flag
+
+/// Hash function used for content fingerprints
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashBackend {
+    /// 64-bit FNV-1a, used when neither hashing feature is enabled
+    Fnv1a,
+    /// BLAKE3 truncated to 128 bits (`blake3` feature, enabled by default)
+    Blake3,
+    /// 128-bit XXH3 (`xxhash` feature, preferred over `blake3` when both are enabled)
+    Xxh3,
+}
+
+impl HashBackend {
+    /// Returns the backend selected by the enabled features
+    #[inline]
+    #[must_use]
+    pub const fn active() -> Self {
+        if cfg!(feature = "xxhash") {
+            Self::Xxh3
+        } else if cfg!(feature = "blake3") {
+            Self::Blake3
+        } else {
+            Self::Fnv1a
+        }
+    }
+
+    /// Returns the name of the backend
+    #[inline]
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Fnv1a => "fnv1a",
+            Self::Blake3 => "blake3",
+            Self::Xxh3 => "xxh3",
+        }
+    }
+
+    /// Returns the backends available in this build
+    #[must_use]
+    pub fn values() -> Vec<Self> {
+        let mut backends = vec![Self::Fnv1a];
+        if cfg!(feature = "blake3") {
+            backends.push(Self::Blake3);
+        }
+        if cfg!(feature = "xxhash") {
+            backends.push(Self::Xxh3);
+        }
+        backends
+    }
+
+    /// Hashes `bytes` to a hex string, 16 characters long for FNV-1a and 32 otherwise.
+    ///
+    /// # Panics
+    /// If the backend was not compiled in; see `values`.
+    #[must_use]
+    pub fn hex_digest(&self, bytes: &[u8]) -> String {
+        match self {
+            Self::Fnv1a => format!("{:016x}", fnv1a(bytes)),
+            #[cfg(feature = "blake3")]
+            Self::Blake3 => blake3::hash(bytes).as_bytes()[..16]
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect(),
+            #[cfg(feature = "xxhash")]
+            Self::Xxh3 => format!("{:032x}", xxhash_rust::xxh3::xxh3_128(bytes)),
+            #[allow(unreachable_patterns)]
+            backend => panic!("{} hashing is not enabled in this build", backend.as_str()),
+        }
+    }
+
+    /// Hashes `bytes` to 64 bits, the leading bits of `hex_digest`, for bucketing
+    #[must_use]
+    pub fn hash_u64(&self, bytes: &[u8]) -> u64 {
+        u64::from_str_radix(&self.hex_digest(bytes)[..16], 16).unwrap_or_default()
+    }
+}
+
+/// 64-bit FNV-1a hash
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+    bytes.iter().fold(FNV_OFFSET_BASIS, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backends_are_stable_and_distinct() {
+        assert_eq!(HashBackend::Fnv1a.hex_digest(b""), "cbf29ce484222325");
+        assert_eq!(HashBackend::Fnv1a.hex_digest(b"a"), "af63dc4c8601ec8c");
+        assert!(HashBackend::values().contains(&HashBackend::active()));
+
+        for backend in HashBackend::values() {
+            let digest = backend.hex_digest(b"village.png");
+            assert_eq!(digest, backend.hex_digest(b"village.png"));
+            assert_ne!(digest, backend.hex_digest(b"village.jpg"));
+            assert_eq!(
+                backend.hash_u64(b"village.png"),
+                u64::from_str_radix(&digest[..16], 16).unwrap()
+            );
+        }
+    }
+
+    #[cfg(feature = "blake3")]
+    #[test]
+    fn test_blake3_digest() {
+        assert_eq!(
+            HashBackend::Blake3.hex_digest(b""),
+            "af1349b9f5f9a1a6a0404dea36dcc949"
+        );
+    }
+}
@@ -0,0 +1,195 @@
+//! Golden preprocessing tests: tiny fixture images with their expected letterboxed NCHW tensors.
+//!
+//! Expected outputs are byte-exact, so any change to the resize backend, the letterbox rounding
+//! or the padding placement shows up here. Regenerate them only for intentional changes.
+
+use crate::image::image_config::ImageConfig;
+use crate::image::image_size::ImageSize;
+use crate::image::image_util::{normalize_image_f32, preprocess_image_u8};
+use crate::image::{IMAGENET_MEAN, IMAGENET_STD, ULTRALYTICS_PADDING_COLOR};
+use image::imageops::FilterType;
+use image::{DynamicImage, RgbImage};
+
+/// 4x2 RGB ramp, every channel value a multiple of 10
+const RAMP_4X2: [u8; 24] = [
+    0, 10, 20, 30, 40, 50, 60, 70, 80, 90, 100, 110, //
+    120, 130, 140, 150, 160, 170, 180, 190, 200, 210, 220, 230,
+];
+
+/// 3x3 primaries, secondaries and dark grays
+const PALETTE_3X3: [u8; 27] = [
+    255, 0, 0, 0, 255, 0, 0, 0, 255, //
+    255, 255, 0, 0, 255, 255, 255, 0, 255, //
+    10, 20, 30, 40, 50, 60, 70, 80, 90,
+];
+
+/// 2x1 pair of complementary colors
+const PAIR_2X1: [u8; 6] = [200, 100, 50, 50, 100, 200];
+
+/// Builds a fixture image from raw RGB pixels
+fn fixture(width: u32, height: u32, pixels: &[u8]) -> DynamicImage {
+    DynamicImage::ImageRgb8(RgbImage::from_raw(width, height, pixels.to_vec()).unwrap())
+}
+
+/// Letterboxes a fixture with the Ultralytics padding color and returns the flat NCHW tensor
+fn letterbox(image: &DynamicImage, target_size: (u32, u32), filter_type: FilterType) -> Vec<u8> {
+    let config = ImageConfig {
+        target_size: ImageSize::new(target_size.0, target_size.1),
+        filter_type,
+        padding_color: ULTRALYTICS_PADDING_COLOR,
+        ..Default::default()
+    };
+    let loaded = preprocess_image_u8(image, &config);
+    assert_eq!(
+        loaded.shape(),
+        &[1, 3, target_size.1 as usize, target_size.0 as usize]
+    );
+    loaded.image_array.as_slice().unwrap().to_vec()
+}
+
+fn assert_tensor_eq(actual: &[f32], expected: &[f32]) {
+    assert_eq!(actual.len(), expected.len());
+    for (i, (a, e)) in actual.iter().zip(expected).enumerate() {
+        assert!((a - e).abs() < 1e-6, "element {i}: {a} != {e}");
+    }
+}
+
+#[test]
+fn test_downscale_pads_bottom() {
+    // 4x2 -> 2x1 content, padded with one row below
+    let image = fixture(4, 2, &RAMP_4X2);
+    assert_eq!(
+        letterbox(&image, (2, 2), FilterType::Nearest),
+        [150, 210, 114, 114, 160, 220, 114, 114, 170, 230, 114, 114]
+    );
+    assert_eq!(
+        letterbox(&image, (2, 2), FilterType::Triangle),
+        [81, 129, 114, 114, 91, 139, 114, 114, 101, 149, 114, 114]
+    );
+    assert_eq!(
+        letterbox(&image, (2, 2), FilterType::Lanczos3),
+        [76, 134, 114, 114, 86, 144, 114, 114, 96, 154, 114, 114]
+    );
+}
+
+#[test]
+fn test_downscale_pads_sides() {
+    // 3x3 -> 2x2 content, centered with one column on each side
+    let image = fixture(3, 3, &PALETTE_3X3);
+    assert_eq!(
+        letterbox(&image, (4, 2), FilterType::Nearest),
+        [
+            114, 255, 0, 114, 114, 10, 70, 114, //
+            114, 0, 0, 114, 114, 20, 80, 114, //
+            114, 0, 255, 114, 114, 30, 90, 114,
+        ]
+    );
+    assert_eq!(
+        letterbox(&image, (4, 2), FilterType::Triangle),
+        [
+            114, 159, 60, 114, 114, 73, 96, 114, //
+            114, 155, 96, 114, 114, 115, 79, 114, //
+            114, 36, 195, 114, 114, 62, 145, 114,
+        ]
+    );
+    assert_eq!(
+        letterbox(&image, (4, 2), FilterType::Lanczos3),
+        [
+            114, 170, 46, 114, 114, 62, 104, 114, //
+            114, 182, 102, 114, 114, 125, 76, 114, //
+            114, 22, 217, 114, 114, 63, 156, 114,
+        ]
+    );
+}
+
+#[test]
+fn test_upscale_pads_top_and_bottom() {
+    // 2x1 -> 4x2 content, centered with one row above and below
+    let image = fixture(2, 1, &PAIR_2X1);
+    let pad = [114u8; 4];
+    let expected = |r: [u8; 4], g: [u8; 4], b: [u8; 4]| -> Vec<u8> {
+        [pad, r, r, pad, pad, g, g, pad, pad, b, b, pad].concat()
+    };
+
+    assert_eq!(
+        letterbox(&image, (4, 4), FilterType::Nearest),
+        expected([200, 200, 50, 50], [100; 4], [50, 50, 200, 200])
+    );
+    assert_eq!(
+        letterbox(&image, (4, 4), FilterType::Triangle),
+        expected([200, 163, 88, 50], [100; 4], [50, 88, 163, 200])
+    );
+    assert_eq!(
+        letterbox(&image, (4, 4), FilterType::Lanczos3),
+        expected([226, 165, 85, 24], [100; 4], [24, 85, 165, 226])
+    );
+}
+
+#[test]
+fn test_same_size_is_identity() {
+    let image = fixture(2, 1, &PAIR_2X1);
+    assert_eq!(
+        letterbox(&image, (2, 1), FilterType::Lanczos3),
+        [200, 50, 100, 100, 50, 200]
+    );
+}
+
+#[test]
+fn test_unit_interval_tensor() {
+    let config = ImageConfig {
+        target_size: ImageSize::new(2, 2),
+        filter_type: FilterType::Triangle,
+        padding_color: ULTRALYTICS_PADDING_COLOR,
+        ..Default::default()
+    };
+    let loaded = preprocess_image_u8(&fixture(4, 2, &RAMP_4X2), &config);
+    let normalized = normalize_image_f32(&loaded, None, None);
+
+    assert_tensor_eq(
+        normalized.image_array.as_slice().unwrap(),
+        &[
+            0.317_647_07,
+            0.505_882_4,
+            0.447_058_86,
+            0.447_058_86,
+            0.356_862_75,
+            0.545_098_07,
+            0.447_058_86,
+            0.447_058_86,
+            0.396_078_47,
+            0.584_313_75,
+            0.447_058_86,
+            0.447_058_86,
+        ],
+    );
+}
+
+#[test]
+fn test_imagenet_tensor() {
+    let config = ImageConfig {
+        target_size: ImageSize::new(2, 2),
+        filter_type: FilterType::Triangle,
+        padding_color: ULTRALYTICS_PADDING_COLOR,
+        ..Default::default()
+    };
+    let loaded = preprocess_image_u8(&fixture(4, 2, &RAMP_4X2), &config);
+    let normalized = normalize_image_f32(&loaded, Some(IMAGENET_MEAN), Some(IMAGENET_STD));
+
+    assert_tensor_eq(
+        normalized.image_array.as_slice().unwrap(),
+        &[
+            -0.730_798_96,
+            0.091_189_146,
+            -0.165_682_08,
+            -0.165_682_08,
+            -0.442_577,
+            0.397_759_2,
+            -0.039_915_92,
+            -0.039_915_92,
+            -0.044_095_874,
+            0.792_505_4,
+            0.182_483_67,
+            0.182_483_67,
+        ],
+    );
+}
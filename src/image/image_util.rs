@@ -1,6 +1,8 @@
 use crate::class::clash_class::ClashClass;
+use crate::image::content_hash::HashBackend;
 use crate::image::image_config::ImageConfig;
 use crate::image::image_size::ImageSize;
+use crate::image::letterbox::LetterboxTransform;
 use crate::image::loaded_image::{LoadedImageF32, LoadedImageU8};
 use crate::image::{DEFAULT_MEAN, DEFAULT_STD};
 use image::{ImageBuffer, ImageError, Rgb};
@@ -31,10 +33,15 @@ pub fn load_image_u8(
     }
 
     let image = image::open(image_path)?;
-    let resized_padded = resize_and_pad_image(&image, config);
+    Ok(preprocess_image_u8(&image, config))
+}
+
+/// Preprocesses an already decoded image (resize, pad and convert to NCHW)
+pub fn preprocess_image_u8(image: &image::DynamicImage, config: &ImageConfig) -> LoadedImageU8 {
+    let resized_padded = resize_and_pad_image(image, config);
     let array = image_to_array(&resized_padded, config.target_size);
 
-    Ok(LoadedImageU8::new(array, config.target_size))
+    LoadedImageU8::new(array, config.target_size)
 }
 
 /// Convenience function with default configuration
@@ -54,26 +61,22 @@ fn resize_and_pad_image(
     image: &image::DynamicImage,
     config: &ImageConfig,
 ) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
-    let (orig_width, orig_height) = (image.width(), image.height());
     let target_size = config.target_size;
 
-    // Calculate scale to maintain aspect ratio
-    let scale_x = target_size.width as f32 / orig_width as f32;
-    let scale_y = target_size.height as f32 / orig_height as f32;
-    let scale = scale_x.min(scale_y);
-
-    let new_width = (orig_width as f32 * scale).round() as u32;
-    let new_height = (orig_height as f32 * scale).round() as u32;
+    // Calculate scale and padding to maintain aspect ratio
+    let original_size = ImageSize::new(image.width(), image.height());
+    let transform = LetterboxTransform::new(original_size, target_size);
+    let (new_width, new_height) = (
+        transform.resized_size.width,
+        transform.resized_size.height,
+    );
+    let (pad_left, pad_top) = (transform.pad_left, transform.pad_top);
 
     // Resize image
     let resized_image = image
         .resize_exact(new_width, new_height, config.filter_type)
         .to_rgb8();
 
-    // Calculate padding
-    let pad_left = (target_size.width - new_width) / 2;
-    let pad_top = (target_size.height - new_height) / 2;
-
     // Create padded image
     let padding_pixel = Rgb(config.padding_color);
     let mut padded_image =
@@ -157,6 +160,13 @@ pub fn normalize_image_f32(
     }
 }
 
+/// Computes a stable fingerprint (hex encoded, with the `HashBackend` selected by feature flag)
+/// of raw content such as an image file
+#[must_use]
+pub fn content_fingerprint(bytes: &[u8]) -> String {
+    HashBackend::active().hex_digest(bytes)
+}
+
 /// Generates distinct colors for each class using a more sophisticated color scheme
 #[must_use]
 pub fn generate_class_colors() -> HashMap<usize, SolidSource> {
@@ -214,6 +224,32 @@ fn hsv_to_rgb(h: f32, s: f32, v: f32) -> (f32, f32, f32) {
     (r_prime + m, g_prime + m, b_prime + m)
 }
 
+/// Converts RGB color space (0-255 channels) to HSV (hue in degrees, saturation and value in 0-1)
+#[must_use]
+pub fn rgb_to_hsv(r: u8, g: u8, b: u8) -> (f32, f32, f32) {
+    let r = f32::from(r) / 255.0;
+    let g = f32::from(g) / 255.0;
+    let b = f32::from(b) / 255.0;
+
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+
+    let hue = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * (((g - b) / delta).rem_euclid(6.0))
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+
+    let saturation = if max == 0.0 { 0.0 } else { delta / max };
+
+    (hue, saturation, max)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -225,4 +261,28 @@ mod tests {
         assert!(g.abs() < f32::EPSILON);
         assert!(b.abs() < f32::EPSILON);
     }
+
+    #[test]
+    fn test_content_fingerprint() {
+        assert_eq!(
+            content_fingerprint(b"a"),
+            HashBackend::active().hex_digest(b"a")
+        );
+        assert_ne!(content_fingerprint(b"abc"), content_fingerprint(b"abd"));
+    }
+
+    #[test]
+    fn test_rgb_to_hsv() {
+        let (h, s, v) = rgb_to_hsv(255, 0, 0); // Pure red
+        assert!(h.abs() < f32::EPSILON);
+        assert!((s - 1.0).abs() < f32::EPSILON);
+        assert!((v - 1.0).abs() < f32::EPSILON);
+
+        let (h, _, _) = rgb_to_hsv(0, 0, 255); // Pure blue
+        assert!((h - 240.0).abs() < 0.001);
+
+        let (_, s, v) = rgb_to_hsv(0, 0, 0); // Black
+        assert!(s.abs() < f32::EPSILON);
+        assert!(v.abs() < f32::EPSILON);
+    }
 }
@@ -0,0 +1,97 @@
+//! Letterbox geometry mapping coordinates between the original image and the model input
+
+use crate::image::image_size::ImageSize;
+
+/// Scale and padding applied by letterboxing an image into the model input
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LetterboxTransform {
+    pub original_size: ImageSize,
+    pub resized_size: ImageSize,
+    pub pad_left: u32,
+    pub pad_top: u32,
+}
+
+impl LetterboxTransform {
+    /// Computes the letterbox of an `original_size` image into `target_size`, keeping its aspect ratio
+    #[must_use]
+    pub fn new(original_size: ImageSize, target_size: ImageSize) -> Self {
+        let scale_x = target_size.width as f32 / original_size.width as f32;
+        let scale_y = target_size.height as f32 / original_size.height as f32;
+        let scale = scale_x.min(scale_y);
+
+        let resized_size = ImageSize::new(
+            (original_size.width as f32 * scale).round() as u32,
+            (original_size.height as f32 * scale).round() as u32,
+        );
+
+        Self {
+            original_size,
+            resized_size,
+            pad_left: (target_size.width - resized_size.width) / 2,
+            pad_top: (target_size.height - resized_size.height) / 2,
+        }
+    }
+
+    /// Returns the horizontal and vertical scale from original to letterboxed coordinates
+    #[inline]
+    #[must_use]
+    pub fn scale(&self) -> (f32, f32) {
+        (
+            self.resized_size.width as f32 / self.original_size.width as f32,
+            self.resized_size.height as f32 / self.original_size.height as f32,
+        )
+    }
+
+    /// Maps a point of the model input back to the original image
+    #[inline]
+    #[must_use]
+    pub fn to_original(&self, x: f32, y: f32) -> (f32, f32) {
+        let (scale_x, scale_y) = self.scale();
+        (
+            (x - self.pad_left as f32) / scale_x,
+            (y - self.pad_top as f32) / scale_y,
+        )
+    }
+
+    /// Maps a point of the original image into the model input
+    #[inline]
+    #[must_use]
+    pub fn to_letterbox(&self, x: f32, y: f32) -> (f32, f32) {
+        let (scale_x, scale_y) = self.scale();
+        (
+            x * scale_x + self.pad_left as f32,
+            y * scale_y + self.pad_top as f32,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wide_image_padded_vertically() {
+        let transform =
+            LetterboxTransform::new(ImageSize::new(1280, 720), ImageSize::new(640, 640));
+        assert_eq!(transform.resized_size, ImageSize::new(640, 360));
+        assert_eq!((transform.pad_left, transform.pad_top), (0, 140));
+        assert_eq!(transform.scale(), (0.5, 0.5));
+        assert_eq!(transform.to_original(320.0, 140.0), (640.0, 0.0));
+        assert_eq!(transform.to_letterbox(1280.0, 720.0), (640.0, 500.0));
+    }
+
+    #[test]
+    fn test_same_size_is_identity() {
+        let transform = LetterboxTransform::new(ImageSize::new(640, 640), ImageSize::new(640, 640));
+        assert_eq!(transform.to_original(12.5, 600.0), (12.5, 600.0));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let transform =
+            LetterboxTransform::new(ImageSize::new(333, 1000), ImageSize::new(640, 480));
+        let (x, y) = transform.to_letterbox(100.0, 250.0);
+        let (ox, oy) = transform.to_original(x, y);
+        assert!((ox - 100.0).abs() < 1e-3 && (oy - 250.0).abs() < 1e-3);
+    }
+}
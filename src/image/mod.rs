@@ -1,8 +1,15 @@
+pub mod content_hash;
 pub mod image_config;
-mod image_size;
+pub mod image_size;
 pub mod image_util;
+pub mod letterbox;
 pub mod loaded_image;
 mod norm_config;
+pub mod pyramid;
+pub mod tiling;
+
+#[cfg(test)]
+mod golden;
 
 // ImageNet normalization constants - commonly used in computer vision
 const IMAGENET_MEAN: [f32; 3] = [0.485, 0.456, 0.406];
@@ -13,4 +20,7 @@ const DEFAULT_MEAN: [f32; 3] = [0.0, 0.0, 0.0];
 const DEFAULT_STD: [f32; 3] = [1.0, 1.0, 1.0];
 
 // Padding color (gray)
-const PADDING_COLOR: [u8; 3] = [112, 112, 112];
+pub const PADDING_COLOR: [u8; 3] = [112, 112, 112];
+
+// Letterbox padding color used by Ultralytics exports
+pub const ULTRALYTICS_PADDING_COLOR: [u8; 3] = [114, 114, 114];
@@ -0,0 +1,240 @@
+//! Cache of decoded images and their letterboxed resizes, for passes at several scales
+
+use crate::image::image_config::ImageConfig;
+use crate::image::image_util::{ImageLoadError, content_fingerprint, preprocess_image_u8};
+use crate::image::loaded_image::LoadedImageU8;
+use image::DynamicImage;
+use image::imageops::FilterType;
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Key of a cached resize: content fingerprint, target size, padding color and resize filter
+type LevelKey = (String, (u32, u32), [u8; 3], FilterType);
+
+/// Decoded images and letterboxed resizes keyed by content fingerprint.
+///
+/// Reprocessing the same screenshot at several input sizes decodes it once and
+/// resizes it once per size and filter. The least recently inserted images are evicted first.
+#[derive(Default)]
+pub struct PyramidCache {
+    capacity: usize,
+    order: VecDeque<String>,
+    originals: HashMap<String, Arc<DynamicImage>>,
+    levels: HashMap<LevelKey, Arc<LoadedImageU8>>,
+}
+
+impl PyramidCache {
+    /// Creates a cache holding at most `capacity` distinct images with their resizes
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            ..Self::default()
+        }
+    }
+
+    /// Decodes an image file, or returns the cached decode of identical content.
+    ///
+    /// Returns the content fingerprint along with the image.
+    pub fn load(
+        &mut self,
+        image_path: impl AsRef<Path>,
+    ) -> Result<(String, Arc<DynamicImage>), ImageLoadError> {
+        let image_path = image_path.as_ref();
+        let bytes = fs::read(image_path)
+            .map_err(|_| ImageLoadError::InvalidPath(image_path.display().to_string()))?;
+        let fingerprint = content_fingerprint(&bytes);
+
+        if let Some(image) = self.originals.get(&fingerprint) {
+            return Ok((fingerprint, Arc::clone(image)));
+        }
+
+        let image = Arc::new(image::load_from_memory(&bytes)?);
+        self.insert(fingerprint.clone(), Arc::clone(&image));
+        Ok((fingerprint, image))
+    }
+
+    /// Adds an already decoded image under its fingerprint
+    pub fn insert(&mut self, fingerprint: String, image: Arc<DynamicImage>) {
+        if self.originals.contains_key(&fingerprint) {
+            return;
+        }
+        while self.order.len() >= self.capacity.max(1) {
+            self.evict_oldest();
+        }
+        self.order.push_back(fingerprint.clone());
+        self.originals.insert(fingerprint, image);
+    }
+
+    /// Returns the letterboxed resize of a cached image, computing it on first use.
+    ///
+    /// Returns `None` if no image with this fingerprint is cached.
+    pub fn level(&mut self, fingerprint: &str, config: &ImageConfig) -> Option<Arc<LoadedImageU8>> {
+        let image = self.originals.get(fingerprint)?;
+        let size = (config.target_size.width, config.target_size.height);
+        let key = (
+            fingerprint.to_string(),
+            size,
+            config.padding_color,
+            config.filter_type,
+        );
+
+        let level = self
+            .levels
+            .entry(key)
+            .or_insert_with(|| Arc::new(preprocess_image_u8(image, config)));
+        Some(Arc::clone(level))
+    }
+
+    /// Returns the letterboxed resize of an in-memory image, caching the image under its
+    /// pixel fingerprint so later passes at any size reuse it
+    pub fn letterbox(&mut self, image: &DynamicImage, config: &ImageConfig) -> Arc<LoadedImageU8> {
+        let fingerprint = pixel_fingerprint(image);
+        if !self.originals.contains_key(&fingerprint) {
+            self.insert(fingerprint.clone(), Arc::new(image.clone()));
+        }
+        self.level(&fingerprint, config)
+            .unwrap_or_else(|| Arc::new(preprocess_image_u8(image, config)))
+    }
+
+    /// Returns the number of cached images
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.originals.len()
+    }
+
+    /// Returns true if no image is cached
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.originals.is_empty()
+    }
+
+    /// Returns the number of cached resizes across all images
+    #[inline]
+    #[must_use]
+    pub fn level_count(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Drops every cached image and resize
+    pub fn clear(&mut self) {
+        self.order.clear();
+        self.originals.clear();
+        self.levels.clear();
+    }
+
+    fn evict_oldest(&mut self) {
+        if let Some(fingerprint) = self.order.pop_front() {
+            self.originals.remove(&fingerprint);
+            self.levels.retain(|(key, ..), _| *key != fingerprint);
+        }
+    }
+}
+
+/// Fingerprints decoded pixels along with the dimensions they are laid out in
+fn pixel_fingerprint(image: &DynamicImage) -> String {
+    format!(
+        "{}x{}:{}",
+        image.width(),
+        image.height(),
+        content_fingerprint(image.as_bytes())
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::image_size::ImageSize;
+    use image::{Rgb, RgbImage};
+    use tempfile::tempdir;
+
+    fn config(width: u32, height: u32) -> ImageConfig {
+        ImageConfig {
+            target_size: ImageSize::new(width, height),
+            ..Default::default()
+        }
+    }
+
+    fn solid(color: [u8; 3]) -> Arc<DynamicImage> {
+        Arc::new(DynamicImage::ImageRgb8(RgbImage::from_pixel(
+            8,
+            4,
+            Rgb(color),
+        )))
+    }
+
+    #[test]
+    fn test_load_decodes_identical_content_once() -> Result<(), ImageLoadError> {
+        let dir = tempdir().unwrap();
+        let first = dir.path().join("a.png");
+        let copy = dir.path().join("b.png");
+        RgbImage::from_pixel(8, 4, Rgb([10, 20, 30])).save(&first)?;
+        fs::copy(&first, &copy).unwrap();
+
+        let mut cache = PyramidCache::new(4);
+        let (fingerprint, image) = cache.load(&first)?;
+        let (copy_fingerprint, copy_image) = cache.load(&copy)?;
+
+        assert_eq!(fingerprint, copy_fingerprint);
+        assert!(Arc::ptr_eq(&image, &copy_image));
+        assert_eq!(cache.len(), 1);
+        assert!(cache.load(dir.path().join("missing.png")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_levels_are_reused_per_size() {
+        let mut cache = PyramidCache::new(4);
+        cache.insert("a".to_string(), solid([255, 0, 0]));
+
+        let small = cache.level("a", &config(4, 4)).unwrap();
+        let again = cache.level("a", &config(4, 4)).unwrap();
+        let large = cache.level("a", &config(16, 16)).unwrap();
+
+        assert!(Arc::ptr_eq(&small, &again));
+        assert_eq!(large.shape(), &[1, 3, 16, 16]);
+        assert_eq!(cache.level_count(), 2);
+        assert!(cache.level("missing", &config(4, 4)).is_none());
+
+        let nearest = ImageConfig {
+            filter_type: FilterType::Nearest,
+            ..config(4, 4)
+        };
+        let _ = cache.level("a", &nearest);
+        assert_eq!(cache.level_count(), 3);
+    }
+
+    #[test]
+    fn test_letterbox_reuses_in_memory_images() {
+        let mut cache = PyramidCache::new(4);
+        let image = solid([0, 0, 255]);
+
+        let first = cache.letterbox(&image, &config(4, 4));
+        let again = cache.letterbox(&image.as_ref().clone(), &config(4, 4));
+
+        assert!(Arc::ptr_eq(&first, &again));
+        assert_eq!(cache.len(), 1);
+        let _ = cache.letterbox(&solid([0, 255, 0]), &config(4, 4));
+        assert_eq!(cache.level_count(), 2);
+    }
+
+    #[test]
+    fn test_eviction_drops_oldest_image_and_levels() {
+        let mut cache = PyramidCache::new(2);
+        cache.insert("a".to_string(), solid([255, 0, 0]));
+        let _ = cache.level("a", &config(4, 4));
+        cache.insert("b".to_string(), solid([0, 255, 0]));
+        cache.insert("c".to_string(), solid([0, 0, 255]));
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.level("a", &config(4, 4)).is_none());
+        assert_eq!(cache.level_count(), 0);
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+}
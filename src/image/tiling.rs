@@ -0,0 +1,125 @@
+//! Overlapping tiles of large images, run through the model one by one so that small buildings
+//! keep enough pixels once resized to the model input (SAHI-style sliced inference)
+
+use crate::detection::BoundingBox;
+
+/// Tiling of the images larger than a tile
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TileConfig {
+    pub tile_size: (u32, u32), // Width, Height of a tile in original pixels
+    pub overlap: f32,          // Fraction of a tile shared with its neighbour, in [0, 1)
+    pub full_image: bool,      // Also run the whole image to keep the large buildings
+    pub debug_overlay: bool,   // Draw the tiles and box origins on annotated images
+}
+
+impl Default for TileConfig {
+    fn default() -> Self {
+        Self {
+            tile_size: (640, 640),
+            overlap: 0.2,
+            full_image: true,
+            debug_overlay: false,
+        }
+    }
+}
+
+/// Region of an image, in original pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Tile {
+    /// Moves a box of the tile into the coordinates of the whole image
+    pub fn to_image_space(&self, bbox: &BoundingBox) -> BoundingBox {
+        let (x, y) = (self.x as f32, self.y as f32);
+        BoundingBox {
+            x1: bbox.x1 + x,
+            y1: bbox.y1 + y,
+            x2: bbox.x2 + x,
+            y2: bbox.y2 + y,
+            ..*bbox
+        }
+    }
+}
+
+impl TileConfig {
+    /// Returns true if an image of `image_size` spans more than one tile
+    #[must_use]
+    pub const fn applies_to(&self, image_size: (u32, u32)) -> bool {
+        image_size.0 > self.tile_size.0 || image_size.1 > self.tile_size.1
+    }
+
+    /// Splits an image of `image_size` into overlapping tiles covering it, row by row. The last
+    /// tile of a row or column is aligned on the image edge rather than overflowing it.
+    #[must_use]
+    pub fn tiles(&self, image_size: (u32, u32)) -> Vec<Tile> {
+        let xs = positions(image_size.0, self.tile_size.0, self.overlap);
+        let ys = positions(image_size.1, self.tile_size.1, self.overlap);
+        ys.iter()
+            .flat_map(|&y| {
+                xs.iter().map(move |&x| Tile {
+                    x,
+                    y,
+                    width: self.tile_size.0.min(image_size.0),
+                    height: self.tile_size.1.min(image_size.1),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Start offsets of tiles of `tile` pixels covering `length` pixels with the given overlap
+fn positions(length: u32, tile: u32, overlap: f32) -> Vec<u32> {
+    let tile = tile.max(1);
+    if length <= tile {
+        return vec![0];
+    }
+    let stride = ((tile as f32 * (1.0 - overlap.clamp(0.0, 0.95))) as u32).max(1);
+    let last = length - tile;
+    let mut offsets: Vec<u32> = (0..last).step_by(stride as usize).collect();
+    offsets.push(last);
+    offsets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tiles_cover_the_image() {
+        let config = TileConfig {
+            tile_size: (640, 640),
+            overlap: 0.25,
+            full_image: false,
+            debug_overlay: false,
+        };
+        assert!(!config.applies_to((640, 480)));
+        assert_eq!(config.tiles((640, 480)).len(), 1);
+
+        let tiles = config.tiles((1920, 1000));
+        // Stride of 480: x at 0, 480, 960 then 1280 on the edge; y at 0 then 360 on the edge
+        assert_eq!(tiles.len(), 8);
+        assert_eq!(
+            tiles[3],
+            Tile {
+                x: 1280,
+                y: 0,
+                width: 640,
+                height: 640
+            }
+        );
+        assert_eq!((tiles[7].x, tiles[7].y), (1280, 360));
+        assert!(
+            tiles
+                .iter()
+                .all(|t| t.x + t.width <= 1920 && t.y + t.height <= 1000)
+        );
+
+        let bbox = tiles[7].to_image_space(&BoundingBox::new(10.0, 20.0, 30.0, 40.0, 2, 0.8));
+        assert_eq!(bbox, BoundingBox::new(1290.0, 380.0, 1310.0, 400.0, 2, 0.8));
+    }
+}
@@ -3,11 +3,21 @@ extern crate core;
 use crate::model::yolo_type::YoloType;
 use crate::session::yolo_session::YoloSession;
 
+pub mod analysis;
 pub mod class;
 pub mod detection;
+pub mod eval;
 pub mod image;
 pub mod model;
+pub mod report;
 pub mod session;
+pub mod sink;
+pub mod source;
+pub mod store;
+#[cfg(feature = "strategy")]
+pub mod strategy;
+pub mod tracking;
+pub mod video;
 
 // Embed the model at compile time
 pub const MODEL_BYTES: &[u8] = include_bytes!("../models/best.onnx");
@@ -1,21 +1,622 @@
-use clashvision::model::yolo_type::YoloType;
-use clashvision::session::yolo_session::YoloSession;
+mod cli;
+
+use clap::Parser;
 use clashvision::MODEL_BYTES;
+use clashvision::analysis::fullness::FullnessConfig;
+use clashvision::class::group::ClassGroups;
+use clashvision::detection::coco::CocoExport;
+use clashvision::detection::watermark::Watermark;
+use clashvision::eval::ground_truth::{Annotations, GroundTruth};
+use clashvision::eval::{EvalError, Evaluator};
+use clashvision::image::image_util::content_fingerprint;
+use clashvision::image::tiling::TileConfig;
+#[cfg(feature = "encryption")]
+use clashvision::report::encryption::OutputKey;
+use clashvision::report::gallery;
+use clashvision::report::manifest::{DEFAULT_REGRESSION_RATIO, RunManifest};
+use clashvision::report::parity::{ParityReference, ParityReport, ParityTolerance};
+use clashvision::report::summary::RunSummary;
+use clashvision::report::usage::UsageStats;
+use clashvision::session::SessionError;
+use clashvision::session::execution::{ExecutionConfig, ExecutionProvider};
+#[cfg(feature = "rhai")]
+use clashvision::session::script::ScriptMiddleware;
+use clashvision::session::session_config::SessionConfig;
+use clashvision::session::yolo_session::YoloSession;
+#[cfg(feature = "http")]
+use clashvision::sink::alert::WebhookSink;
+#[cfg(feature = "notify")]
+use clashvision::sink::notification::NotificationSink;
+#[cfg(feature = "arrow")]
+use clashvision::sink::parquet::ParquetSink;
+use cli::config::FileConfig;
+use cli::{Cli, Command, Settings};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Instant;
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    if let Some(shell) = cli.completions {
+        cli::print_completions(shell);
+        return ExitCode::SUCCESS;
+    }
+    if let Some([baseline, newer]) = cli.diff_manifests.as_deref() {
+        return diff_manifests(baseline, newer);
+    }
+    #[cfg(feature = "encryption")]
+    if let Some(Command::Decrypt { file }) = &cli.command {
+        return decrypt(file, cli.key_file.as_deref());
+    }
+
+    let settings = match FileConfig::discover(cli.config.as_deref())
+        .map_err(|e| e.to_string())
+        .and_then(|file| Settings::resolve(&cli, file))
+    {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Some(Command::Serve { addr }) = cli.command {
+        println!("Serving {} on http://{addr}", settings.output_dir);
+        if let Err(e) = gallery::serve(&settings.output_dir, addr) {
+            eprintln!("Failed to serve {}: {e}", settings.output_dir);
+            return ExitCode::FAILURE;
+        }
+        return ExitCode::SUCCESS;
+    }
+    let start = Instant::now();
+
+    let mut config = SessionConfig::default();
+    if let Some(threshold) = settings.confidence_threshold {
+        config.confidence_threshold = threshold;
+    }
+    if let Some(threshold) = settings.nms_threshold {
+        config.nms_threshold = threshold;
+    }
+    if settings.provider != ExecutionProvider::Cpu {
+        config.execution = ExecutionConfig::with_provider(settings.provider);
+    }
+    config.classes = settings.classes.clone();
+    config.output_format = settings.output_format;
+    if let Some(tile) = cli.tile {
+        config.tiling = Some(TileConfig {
+            tile_size: (tile, tile),
+            overlap: cli.tile_overlap.clamp(0.0, 0.9),
+            debug_overlay: cli.tile_debug,
+            ..TileConfig::default()
+        });
+    }
+    config.image_timeout = settings.image_timeout;
+    config.max_detections = cli.max_detections;
+    config.top_k_per_class = cli.top_k_per_class;
+    if cli.fullness {
+        config.fullness = Some(FullnessConfig::default());
+    }
+    config.draw_config.palette = settings.palette;
+    if settings.watermark {
+        match model_hash(&settings) {
+            Ok(hash) => {
+                config.watermark = Some(Watermark::new(format!(
+                    "{} {} / clashvision {}",
+                    settings.model_type.as_str(),
+                    &hash[..8],
+                    env!("CARGO_PKG_VERSION")
+                )));
+            }
+            Err(e) => {
+                eprintln!("Failed to read the model for the watermark: {e}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    #[cfg(feature = "encryption")]
+    let output_key = if cli.encrypt {
+        match OutputKey::resolve(cli.key_file.as_deref()) {
+            Ok(key) => Some(key),
+            Err(e) => {
+                eprintln!("Failed to read the output key: {e}");
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        None
+    };
+
+    // Use the embedded model bytes unless a model path is configured
+    let session = match &settings.model {
+        Some(path) => {
+            YoloSession::with_config(&path.to_string_lossy(), &settings.model_type, config)
+        }
+        None => YoloSession::from_bytes_with_config(MODEL_BYTES, &settings.model_type, config),
+    };
+    let mut yolo_model = match session {
+        Ok(model) => model,
+        Err(e) => {
+            eprintln!("Failed to create YOLO model: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let parity_reference = match cli.parity_reference.as_deref().map(ParityReference::load) {
+        Some(Ok(reference)) => Some(reference),
+        Some(Err(e)) => {
+            eprintln!("Failed to read parity reference: {e}");
+            return ExitCode::FAILURE;
+        }
+        None => None,
+    };
+
+    #[cfg(feature = "encryption")]
+    if let Some(key) = &output_key {
+        yolo_model = yolo_model.with_output_key(key.clone());
+    }
+
+    #[cfg(feature = "http")]
+    if let Some(url) = &cli.webhook {
+        yolo_model.add_sink(Box::new(WebhookSink::new(
+            url.clone(),
+            cli.alert.clone(),
+            settings.classes.clone(),
+        )));
+    }
+
+    #[cfg(feature = "notify")]
+    if cli.notify {
+        yolo_model.add_sink(Box::new(NotificationSink::new(settings.classes.clone())));
+    }
+
+    #[cfg(feature = "rhai")]
+    let script_outputs = match &cli.script {
+        Some(path) => match ScriptMiddleware::load(path) {
+            Ok(script) => {
+                let (script, outputs) =
+                    script.with_classes(settings.classes.clone()).with_outputs();
+                yolo_model.add_middleware(Box::new(script));
+                Some(outputs)
+            }
+            Err(e) => {
+                eprintln!("Failed to load script {}: {e}", path.display());
+                return ExitCode::FAILURE;
+            }
+        },
+        None => None,
+    };
+
+    let clipboard_image = if cli.clipboard {
+        match save_clipboard_image(Path::new(&settings.output_dir)) {
+            Ok(path) => Some(path),
+            Err(e) => {
+                eprintln!("Failed to read the clipboard: {e}");
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        None
+    };
+
+    let input_images = match cli.input_images() {
+        Ok(images) => images,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut images = match cli.shard {
+        Some(shard) => shard.select(&input_images),
+        None => input_images.iter().collect(),
+    };
+    images.extend(&clipboard_image);
+    let output_path = |path: &Path| {
+        cli.shard
+            .map_or_else(|| path.to_path_buf(), |shard| shard.output_path(path))
+    };
+
+    #[cfg(feature = "arrow")]
+    if let Some(path) = cli.parquet.as_deref().map(output_path) {
+        let sink = ParquetSink::new(path).with_classes(settings.classes.clone());
+        #[cfg(feature = "encryption")]
+        let sink = match &output_key {
+            Some(key) => sink.with_key(key.clone()),
+            None => sink,
+        };
+        yolo_model.add_sink(Box::new(sink));
+    }
+
+    let manifest_path = cli.manifest.as_deref().map(output_path);
+    let run_fingerprint = match model_hash(&settings) {
+        Ok(hash) => format!("{hash}-{}", yolo_model.run_fingerprint()),
+        Err(e) => {
+            eprintln!("Failed to fingerprint the model: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let write_manifest = |manifest: &RunManifest, path: &Path| {
+        #[cfg(feature = "encryption")]
+        if let Some(key) = &output_key {
+            return manifest.write_encrypted_checkpoint(path, key);
+        }
+        manifest.write_checkpoint(path)
+    };
+    let resume_manifest = |path: &Path| {
+        #[cfg(feature = "encryption")]
+        if let Some(key) = &output_key {
+            return RunManifest::resume_encrypted(path, &run_fingerprint, key);
+        }
+        RunManifest::resume(path, &run_fingerprint)
+    };
+    let mut manifest = match &manifest_path {
+        Some(path) if cli.resume && path.exists() => match resume_manifest(path) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                eprintln!("Failed to resume from {}: {e}", path.display());
+                return ExitCode::FAILURE;
+            }
+        },
+        _ => RunManifest::default(),
+    };
+    manifest.run_fingerprint = Some(run_fingerprint);
+
+    let ground_truth = match &cli.command {
+        Some(Command::Eval { ground_truth, .. }) => {
+            match load_ground_truth(ground_truth, &images) {
+                Ok(ground_truth) => Some(ground_truth),
+                Err(e) => {
+                    eprintln!(
+                        "Failed to load ground truth {}: {e}",
+                        ground_truth.display()
+                    );
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+        _ => None,
+    };
+    let class_groups = match &cli.command {
+        Some(Command::Eval {
+            class_groups: Some(path),
+            ..
+        }) => match load_class_groups(path) {
+            Ok(groups) => groups,
+            Err(e) => {
+                eprintln!("Failed to load class groups {}: {e}", path.display());
+                return ExitCode::FAILURE;
+            }
+        },
+        _ => ClassGroups::default(),
+    };
+    let mut predictions = Annotations::new();
+
+    let mut coco = CocoExport::default();
+    let mut parity = ParityReport::default();
+    let mut summary = RunSummary::default();
+    let mut skipped = 0;
+    let mut since_checkpoint = 0;
+    #[cfg(feature = "tui")]
+    let mut tui = cli.tui.then(|| cli::tui::Tui::start(images.len()));
+    #[cfg(feature = "tui")]
+    let interactive = tui.is_some();
+    #[cfg(not(feature = "tui"))]
+    let interactive = false;
+    let quiet = cli.quiet || interactive;
+    for image in images {
+        let image_path = image.to_string_lossy();
+        let image_start = Instant::now();
+        let fingerprint = manifest_path
+            .as_ref()
+            .and_then(|_| std::fs::read(image).ok())
+            .map(|bytes| content_fingerprint(&bytes));
+
+        if cli.resume
+            && let Some(fingerprint) = &fingerprint
+        {
+            if manifest.is_complete(&image_path, fingerprint) {
+                skipped += 1;
+                #[cfg(feature = "tui")]
+                if let Some(tui) = &mut tui {
+                    tui.monitor.record_skipped();
+                }
+                continue;
+            }
+            if manifest.images.contains_key(image_path.as_ref()) && !quiet {
+                eprintln!("{image_path}: changed or failed since the checkpoint, processing again");
+            }
+        }
+
+        match yolo_model.process_image_with_warnings(&image_path, Some(&settings.output_dir)) {
+            Ok((boxes, warnings)) => {
+                for warning in &warnings {
+                    if !quiet {
+                        eprintln!("{image_path}: warning: {warning}");
+                    }
+                    summary.record_warning(warning);
+                }
+                if cli.verbose && !interactive {
+                    println!(
+                        "{image_path}: {} detection(s) in {:.0}ms",
+                        boxes.len(),
+                        image_start.elapsed().as_secs_f64() * 1000.0
+                    );
+                    println!("  {}", yolo_model.last_metrics());
+                    for bbox in &boxes {
+                        println!(
+                            "  {} {:.2} [{:.0}, {:.0}, {:.0}, {:.0}]",
+                            settings
+                                .class_names
+                                .name_or(bbox.class_id, &settings.classes),
+                            bbox.confidence,
+                            bbox.x1,
+                            bbox.y1,
+                            bbox.x2,
+                            bbox.y2
+                        );
+                    }
+                }
+                summary.record_success(&boxes, &settings.classes);
+                #[cfg(feature = "tui")]
+                if let Some(tui) = &mut tui {
+                    tui.monitor
+                        .record_success(&boxes, &settings.classes, image_start.elapsed());
+                }
+                if ground_truth.is_some() {
+                    predictions.insert(file_name(image), boxes.clone());
+                }
+                if let Some(reference) = &parity_reference {
+                    parity.record(&image_path, &boxes, reference);
+                }
+                if cli.coco_results.is_some() || cli.coco_dataset.is_some() {
+                    let dimensions = image::image_dimensions(image).unwrap_or_default();
+                    coco.add_image(&image_path, dimensions, &boxes);
+                }
+                manifest.record_success(
+                    &image_path,
+                    &boxes,
+                    image_start.elapsed(),
+                    &settings.classes,
+                );
+            }
+            Err(e) => {
+                if !interactive {
+                    eprintln!("{image_path}: {e}");
+                }
+                #[cfg(feature = "tui")]
+                if let Some(tui) = &mut tui {
+                    tui.monitor
+                        .record_failure(&image_path, &e.to_string(), image_start.elapsed());
+                }
+                if matches!(e, SessionError::Timeout(_)) {
+                    summary.record_timeout();
+                    manifest.record_timeout(&image_path, e.to_string(), image_start.elapsed());
+                } else {
+                    summary.record_failure();
+                    manifest.record_failure(&image_path, e.to_string(), image_start.elapsed());
+                }
+            }
+        }
+        if let Some(fingerprint) = fingerprint {
+            manifest.record_fingerprint(&image_path, fingerprint);
+        }
+
+        since_checkpoint += 1;
+        if let Some(path) = &manifest_path
+            && since_checkpoint >= cli.checkpoint_every
+        {
+            since_checkpoint = 0;
+            if let Err(e) = write_manifest(&manifest, path) {
+                eprintln!("Failed to checkpoint manifest to {}: {e}", path.display());
+            }
+        }
+
+        #[cfg(feature = "rhai")]
+        for output in script_outputs.iter().flat_map(|outputs| outputs.try_iter()) {
+            println!(
+                "{}",
+                serde_json::json!({ "image": output.image_name, "output": output.value })
+            );
+        }
+
+        #[cfg(feature = "tui")]
+        if let Some(tui) = &mut tui {
+            if let Err(e) = tui.draw() {
+                eprintln!("Failed to draw the terminal UI: {e}");
+            }
+            if tui.quit_requested().unwrap_or(false) {
+                break;
+            }
+        }
+    }
+    #[cfg(feature = "tui")]
+    drop(tui);
+    // Closing finishes sinks that only write their file at the end, such as Parquet
+    if let Err(e) = yolo_model.shutdown() {
+        eprintln!("Failed to close sinks: {e}");
+    }
+    summary.set_total_time(start.elapsed());
+
+    if !cli.quiet {
+        if skipped > 0 {
+            println!("Resumed: skipped {skipped} image(s) completed before the checkpoint");
+        }
+        print!("{}", summary.render_text());
+    }
+
+    if let Some(ground_truth) = &ground_truth {
+        let report =
+            Evaluator::default().evaluate_with_groups(&predictions, ground_truth, &class_groups);
+        print!("{}", report.render_text(&settings.classes));
+    }
+
+    let parity_tolerance = ParityTolerance::default();
+    if parity_reference.is_some() {
+        print!("{}", parity.render_text(&parity_tolerance));
+    }
+
+    // Under --encrypt, the run-level reports are written encrypted to their path with `.enc`
+    let write_summary = |path: &Path| {
+        #[cfg(feature = "encryption")]
+        if let Some(key) = &output_key {
+            return summary.write_encrypted_json(path, key);
+        }
+        summary.write_json(path)
+    };
+    let write_coco_results = |path: &Path| {
+        #[cfg(feature = "encryption")]
+        if let Some(key) = &output_key {
+            return coco.write_encrypted_results(path, key);
+        }
+        coco.write_results(path)
+    };
+    let write_coco_dataset = |path: &Path| {
+        #[cfg(feature = "encryption")]
+        if let Some(key) = &output_key {
+            return coco.write_encrypted_dataset(path, &settings.classes, key);
+        }
+        coco.write_dataset(path, &settings.classes)
+    };
+
+    if let Some(path) = cli.summary_json.as_deref().map(output_path)
+        && let Err(e) = write_summary(&path)
+    {
+        eprintln!("Failed to write summary to {}: {e}", path.display());
+        return ExitCode::FAILURE;
+    }
+
+    if let Some(path) = cli.coco_results.as_deref().map(output_path)
+        && let Err(e) = write_coco_results(&path)
+    {
+        eprintln!("Failed to write COCO results to {}: {e}", path.display());
+        return ExitCode::FAILURE;
+    }
+
+    if let Some(path) = cli.coco_dataset.as_deref().map(output_path)
+        && let Err(e) = write_coco_dataset(&path)
+    {
+        eprintln!("Failed to write COCO dataset to {}: {e}", path.display());
+        return ExitCode::FAILURE;
+    }
+
+    if let Some(path) = &manifest_path
+        && let Err(e) = write_manifest(&manifest, path)
+    {
+        eprintln!("Failed to write manifest to {}: {e}", path.display());
+        return ExitCode::FAILURE;
+    }
+
+    if let Some(path) = &settings.usage_stats
+        && let Err(e) = model_hash(&settings)
+            .and_then(|hash| UsageStats::update(path, &summary, &hash).map(|_| ()))
+    {
+        eprintln!("Failed to update usage statistics {}: {e}", path.display());
+    }
 
-fn main() {
-    let args: Vec<String> = std::env::args().collect::<Vec<String>>();
-    if args.len() < 2 {
-        eprintln!("Usage cargo run --: {} <image_path>", args[0]);
-        panic!("Not enough arguments");
+    if summary.images_failed > 0 || !parity.is_within(&parity_tolerance) {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
     }
+}
+
+/// Saves the clipboard image into `output_dir` under a timestamped name, returning its path
+#[cfg(feature = "clipboard")]
+fn save_clipboard_image(output_dir: &Path) -> Result<std::path::PathBuf, String> {
+    let image = clashvision::source::clipboard::read_image().map_err(|e| e.to_string())?;
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    std::fs::create_dir_all(output_dir).map_err(|e| e.to_string())?;
+    let path = output_dir.join(format!("clipboard_{timestamp}.png"));
+    image.save(&path).map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+#[cfg(not(feature = "clipboard"))]
+fn save_clipboard_image(_output_dir: &Path) -> Result<std::path::PathBuf, String> {
+    Err("clashvision was built without the clipboard feature".to_string())
+}
 
-    let image_path: String = args[1].clone();
+/// Returns the file name of an image, which keys it in the ground truth
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .into_owned()
+}
 
-    // Use the embedded model bytes
-    let mut yolo_model = YoloSession::from_bytes(MODEL_BYTES, YoloType::YoloV8)
-        .expect("Failed to create YOLO model from embedded bytes");
+/// Loads a COCO JSON ground truth, or the YOLO labels of `images` from a directory
+fn load_ground_truth(path: &Path, images: &[&PathBuf]) -> Result<GroundTruth, EvalError> {
+    if path.is_file() {
+        return GroundTruth::from_coco_json(path);
+    }
+    let mut ground_truth = GroundTruth::default();
+    for image in images {
+        let label = path.join(image.with_extension("txt").file_name().unwrap_or_default());
+        let dimensions =
+            image::image_dimensions(image).map_err(|e| EvalError::Parse(e.to_string()))?;
+        if label.is_file() {
+            ground_truth.insert_yolo_txt(file_name(image), &label, dimensions)?;
+        } else {
+            // An image without a label file has no objects
+            ground_truth.insert(file_name(image), Vec::new());
+        }
+    }
+    Ok(ground_truth)
+}
 
-    yolo_model
-        .process_image(&image_path)
-        .expect("Failed to process image");
+/// Fingerprints the configured model, or the embedded one
+fn load_class_groups(path: &Path) -> Result<ClassGroups, String> {
+    let json = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    ClassGroups::from_json(&json).map_err(|e| e.to_string())
+}
+
+fn model_hash(settings: &Settings) -> std::io::Result<String> {
+    match &settings.model {
+        Some(model) => std::fs::read(model).map(|bytes| content_fingerprint(&bytes)),
+        None => Ok(content_fingerprint(MODEL_BYTES)),
+    }
+}
+
+/// Writes the decrypted contents of a file written with --encrypt to standard output
+#[cfg(feature = "encryption")]
+fn decrypt(file: &Path, key_file: Option<&Path>) -> ExitCode {
+    use std::io::Write as _;
+
+    let plaintext = OutputKey::resolve(key_file).and_then(|key| key.read_opened(file));
+    match plaintext {
+        Ok(plaintext) => {
+            if let Err(e) = std::io::stdout().write_all(&plaintext) {
+                eprintln!("Failed to write {}: {e}", file.display());
+                return ExitCode::FAILURE;
+            }
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("Failed to decrypt {}: {e}", file.display());
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Prints the differences between two run manifests, failing if they differ
+fn diff_manifests(baseline: &Path, newer: &Path) -> ExitCode {
+    let manifests =
+        RunManifest::load(baseline).and_then(|old| Ok((old, RunManifest::load(newer)?)));
+    match manifests {
+        Ok((old, new)) => {
+            let diff = old.diff(&new, DEFAULT_REGRESSION_RATIO);
+            print!("{}", diff.render_text());
+            if diff.is_clean() {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+        Err(e) => {
+            eprintln!("Failed to read manifests: {e}");
+            ExitCode::FAILURE
+        }
+    }
 }
@@ -2,9 +2,33 @@
 
 use crate::detection::BoundingBox;
 use crate::model::yolo_type::YoloType;
+use crate::model::yolov5_inference::Yolov5Inference;
 use crate::model::yolov8_inference::Yolov8Inference;
 use crate::model::yolov10_inference::Yolov10Inference;
+use crate::model::yolov11_inference::Yolov11Inference;
 use ndarray::ArrayViewD;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, PoisonError, RwLock};
+use thiserror::Error;
+
+/// Errors that can occur while parsing the output of a model
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum InferenceError {
+    #[error("Unexpected output shape {shape:?}, expected {expected}")]
+    UnexpectedShape {
+        shape: Vec<usize>,
+        expected: &'static str,
+    },
+
+    #[error("Model output is empty")]
+    EmptyOutput,
+
+    #[error("All {0} candidates of the model output hold NaN or infinite values")]
+    NonFiniteValues(usize),
+
+    #[error("No inference registered for {0}")]
+    Unregistered(String),
+}
 
 /// Trait for YOLO model inference
 pub trait YoloInference {
@@ -13,14 +37,242 @@ pub trait YoloInference {
         &self,
         output: ArrayViewD<'_, f32>,
         confidence_threshold: f32,
-    ) -> Vec<BoundingBox>;
+    ) -> Result<Vec<BoundingBox>, InferenceError>;
 }
 
-/// Factory function to create appropriate inference implementation
+/// Checks that a head output is a non-empty `(1, rows, columns)` tensor with at least `min_rows`
+/// rows and `min_columns` columns, and returns its rows and columns
+pub(crate) fn check_head(
+    output: &ArrayViewD<'_, f32>,
+    min_rows: usize,
+    min_columns: usize,
+    expected: &'static str,
+) -> Result<(usize, usize), InferenceError> {
+    let unexpected = || InferenceError::UnexpectedShape {
+        shape: output.shape().to_vec(),
+        expected,
+    };
+    let &[1, rows, columns] = output.shape() else {
+        return Err(unexpected());
+    };
+    if output.is_empty() {
+        return Err(InferenceError::EmptyOutput);
+    }
+    if rows < min_rows || columns < min_columns {
+        return Err(unexpected());
+    }
+    Ok((rows, columns))
+}
+
+/// Fails when all the `candidates` of a head held NaN or infinite values. Parsers skip such
+/// candidates while parsing, so a few corrupted rows never discard the finite detections.
+pub(crate) const fn check_finite(
+    non_finite: usize,
+    candidates: usize,
+) -> Result<(), InferenceError> {
+    if candidates > 0 && non_finite == candidates {
+        Err(InferenceError::NonFiniteValues(candidates))
+    } else {
+        Ok(())
+    }
+}
+
+/// Constructor of a custom inference implementation
+pub type InferenceFactory = Arc<dyn Fn() -> Box<dyn YoloInference> + Send + Sync>;
+
+/// Returns the process-wide registry of custom inference implementations
+fn registry() -> &'static RwLock<HashMap<String, InferenceFactory>> {
+    static REGISTRY: OnceLock<RwLock<HashMap<String, InferenceFactory>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+/// Registers a custom inference implementation used for `YoloType::Custom(key)`.
+///
+/// Registering an existing key replaces the previous factory.
+pub fn register_inference<F>(key: impl Into<String>, factory: F)
+where
+    F: Fn() -> Box<dyn YoloInference> + Send + Sync + 'static,
+{
+    registry()
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(key.into(), Arc::new(factory));
+}
+
+/// Removes a custom inference implementation, returning true if it was registered
+pub fn unregister_inference(key: &str) -> bool {
+    registry()
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .remove(key)
+        .is_some()
+}
+
+/// Returns true if a custom inference implementation is registered under `key`
 #[must_use]
-pub fn create_inference(model_name: &YoloType) -> Box<dyn YoloInference> {
+pub fn is_inference_registered(key: &str) -> bool {
+    registry()
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .contains_key(key)
+}
+
+/// Returns the keys of all registered custom inference implementations, sorted
+#[must_use]
+pub fn registered_inferences() -> Vec<String> {
+    let mut keys: Vec<String> = registry()
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .keys()
+        .cloned()
+        .collect();
+    keys.sort();
+    keys
+}
+
+/// Factory function to create appropriate inference implementation.
+///
+/// Fails with `InferenceError::Unregistered` for a custom model type without a registered
+/// implementation.
+pub fn create_inference(model_name: &YoloType) -> Result<Box<dyn YoloInference>, InferenceError> {
     match model_name {
-        YoloType::YoloV8 => Box::new(Yolov8Inference),
-        YoloType::YoloV10 => Box::new(Yolov10Inference),
+        YoloType::YoloV5 => Ok(Box::new(Yolov5Inference)),
+        YoloType::YoloV8 => Ok(Box::new(Yolov8Inference)),
+        YoloType::YoloV10 => Ok(Box::new(Yolov10Inference)),
+        YoloType::YoloV11 => Ok(Box::new(Yolov11Inference)),
+        YoloType::Custom(key) => {
+            let factory = registry()
+                .read()
+                .unwrap_or_else(PoisonError::into_inner)
+                .get(key)
+                .cloned()
+                .ok_or_else(|| InferenceError::Unregistered(key.clone()))?;
+            Ok(factory())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FixedInference;
+
+    impl YoloInference for FixedInference {
+        fn parse_output(
+            &self,
+            _: ArrayViewD<'_, f32>,
+            _: f32,
+        ) -> Result<Vec<BoundingBox>, InferenceError> {
+            Ok(vec![BoundingBox::new(1.0, 2.0, 3.0, 4.0, 0, 1.0)])
+        }
+    }
+
+    #[test]
+    fn test_builtin_inferences() {
+        assert!(create_inference(&YoloType::YoloV5).is_ok());
+        assert!(create_inference(&YoloType::YoloV8).is_ok());
+        assert!(create_inference(&YoloType::YoloV10).is_ok());
+        assert!(create_inference(&YoloType::YoloV11).is_ok());
+    }
+
+    #[test]
+    fn test_malformed_outputs_are_errors() {
+        let parse = |model: YoloType, shape: &[usize], fill: f32| {
+            let output = ndarray::ArrayD::<f32>::from_elem(ndarray::IxDyn(shape), fill);
+            create_inference(&model)
+                .unwrap()
+                .parse_output(output.view(), 0.5)
+        };
+        for model in [
+            YoloType::YoloV5,
+            YoloType::YoloV8,
+            YoloType::YoloV10,
+            YoloType::YoloV11,
+        ] {
+            assert!(matches!(
+                parse(model.clone(), &[1, 84], 0.0),
+                Err(InferenceError::UnexpectedShape { .. })
+            ));
+            assert_eq!(
+                parse(model.clone(), &[1, 0, 84], 0.0),
+                Err(InferenceError::EmptyOutput)
+            );
+            assert_eq!(
+                parse(model, &[1, 8, 8], f32::NAN),
+                Err(InferenceError::NonFiniteValues(8))
+            );
+        }
+        assert!(matches!(
+            parse(YoloType::YoloV8, &[1, 4, 100], 0.0),
+            Err(InferenceError::UnexpectedShape { .. })
+        ));
+        assert!(matches!(
+            parse(YoloType::YoloV10, &[1, 300, 5], 0.0),
+            Err(InferenceError::UnexpectedShape { .. })
+        ));
+    }
+
+    #[test]
+    fn test_non_finite_candidates_are_skipped() {
+        // v5 rows of (x, y, w, h, objectness, class 0, class 1), the middle one corrupted
+        let mut output = ndarray::ArrayD::<f32>::zeros(ndarray::IxDyn(&[1, 3, 7]));
+        for row in 0..3 {
+            for (column, value) in [10.0, 10.0, 4.0, 4.0, 0.9, 0.9, 0.1]
+                .into_iter()
+                .enumerate()
+            {
+                output[[0, row, column]] = value;
+            }
+        }
+        output[[0, 1, 2]] = f32::NAN;
+        output[[0, 1, 6]] = f32::INFINITY;
+        let boxes = create_inference(&YoloType::YoloV5)
+            .unwrap()
+            .parse_output(output.view(), 0.5)
+            .unwrap();
+        assert_eq!(boxes.len(), 2);
+        assert!(boxes.iter().all(|bbox| bbox.x2.is_finite()));
+
+        // The same candidates in the v8 layout, one column each
+        let transposed = output.permuted_axes(ndarray::IxDyn(&[0, 2, 1]));
+        let mut v8 = ndarray::ArrayD::<f32>::zeros(ndarray::IxDyn(&[1, 6, 3]));
+        for row in 0..6 {
+            for det in 0..3 {
+                v8[[0, row, det]] = transposed[[0, if row < 4 { row } else { row + 1 }, det]];
+            }
+        }
+        let boxes = create_inference(&YoloType::YoloV8)
+            .unwrap()
+            .parse_output(v8.view(), 0.5)
+            .unwrap();
+        assert_eq!(boxes.len(), 2);
+        assert_eq!(check_finite(2, 3), Ok(()));
+        assert_eq!(check_finite(3, 3), Err(InferenceError::NonFiniteValues(3)));
+    }
+
+    #[test]
+    fn test_register_custom_inference() {
+        let key = "test-fixed-head";
+        let custom = YoloType::Custom(key.to_string());
+        assert_eq!(
+            create_inference(&custom).err(),
+            Some(InferenceError::Unregistered(key.to_string()))
+        );
+
+        register_inference(key, || Box::new(FixedInference));
+        assert!(is_inference_registered(key));
+        assert!(registered_inferences().contains(&key.to_string()));
+        assert_eq!(YoloType::try_from(key), Ok(custom.clone()));
+
+        let output = ndarray::ArrayD::<f32>::zeros(ndarray::IxDyn(&[1]));
+        let boxes = create_inference(&custom)
+            .unwrap()
+            .parse_output(output.view(), 0.5)
+            .unwrap();
+        assert_eq!(boxes.len(), 1);
+
+        assert!(unregister_inference(key));
+        assert!(create_inference(&custom).is_err());
     }
 }
@@ -0,0 +1,217 @@
+//! Secondary classifier estimating building levels from detection crops
+
+use crate::detection::{BoundingBox, LevelEstimate};
+use crate::session::SessionError;
+use crate::session::ort_inference_session::OrtInferenceSession;
+use image::RgbImage;
+use image::imageops::{self, FilterType};
+use ndarray::Array4;
+use std::path::Path;
+
+/// Configuration for the level classifier.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LevelClassifierConfig {
+    pub input_size: (u32, u32),
+    pub input_name: String,
+    pub output_name: String,
+    pub first_level: u32,
+    pub min_confidence: f32,
+    pub apply_softmax: bool,
+}
+
+impl Default for LevelClassifierConfig {
+    fn default() -> Self {
+        Self {
+            input_size: (64, 64),               // Crop size fed to the classifier
+            input_name: "images".to_string(),   // Ultralytics export naming
+            output_name: "output0".to_string(), // Ultralytics export naming
+            first_level: 1,                     // Level of the first output class
+            min_confidence: 0.5,                // Minimum confidence to keep a level
+            apply_softmax: false,               // Ultralytics exports output probabilities
+        }
+    }
+}
+
+/// Secondary ONNX classifier that assigns a level to each detection crop.
+#[must_use]
+pub struct LevelClassifier {
+    session: OrtInferenceSession,
+    config: LevelClassifierConfig,
+}
+
+impl LevelClassifier {
+    /// Creates a new level classifier from the specified model path
+    pub fn new(model_path: &Path, config: LevelClassifierConfig) -> Result<Self, SessionError> {
+        let session = OrtInferenceSession::new(model_path)
+            .map_err(|e| SessionError::Io(std::io::Error::other(e)))?;
+        Ok(Self { session, config })
+    }
+
+    /// Creates a new level classifier from model bytes
+    pub fn from_bytes(
+        model_bytes: &[u8],
+        config: LevelClassifierConfig,
+    ) -> Result<Self, SessionError> {
+        let session = OrtInferenceSession::from_bytes(model_bytes)
+            .map_err(|e| SessionError::Io(std::io::Error::other(e)))?;
+        Ok(Self { session, config })
+    }
+
+    /// Returns the classifier configuration
+    #[inline]
+    #[must_use]
+    pub const fn config(&self) -> &LevelClassifierConfig {
+        &self.config
+    }
+
+    /// Classifies the crop of `image` covered by `bbox`.
+    ///
+    /// Returns `None` when the box is empty or the prediction is below `min_confidence`.
+    pub fn classify(
+        &mut self,
+        image: &RgbImage,
+        bbox: &BoundingBox,
+    ) -> Result<Option<LevelEstimate>, SessionError> {
+        let Some(input_tensor) = crop_to_tensor(image, bbox, self.config.input_size) else {
+            return Ok(None);
+        };
+
+        let outputs = self
+            .session
+            .run_inference_with_input(&self.config.input_name, &input_tensor)
+            .map_err(|e| SessionError::Inference(e.to_string()))?;
+
+        let output = outputs.get(&self.config.output_name).ok_or_else(|| {
+            SessionError::Inference(format!(
+                "Level classifier has no output named {}",
+                self.config.output_name
+            ))
+        })?;
+        let (_, data) = output
+            .try_extract_tensor::<f32>()
+            .map_err(|e| SessionError::Inference(format!("Failed to extract tensor: {e}")))?;
+
+        let scores = if self.config.apply_softmax {
+            softmax(data)
+        } else {
+            data.to_vec()
+        };
+
+        Ok(argmax(&scores)
+            .filter(|&(_, confidence)| confidence >= self.config.min_confidence)
+            .map(|(index, confidence)| LevelEstimate {
+                level: self.config.first_level + index as u32,
+                confidence,
+            }))
+    }
+
+    /// Classifies every detection and stores the result in its `level` field
+    pub fn annotate(
+        &mut self,
+        image: &RgbImage,
+        boxes: &mut [BoundingBox],
+    ) -> Result<(), SessionError> {
+        for bbox in boxes.iter_mut() {
+            bbox.level = self.classify(image, bbox)?;
+        }
+        Ok(())
+    }
+}
+
+/// Crops the box region, resizes it to the classifier input size and converts it
+/// to a NCHW tensor scaled to [0, 1]. Returns `None` for empty regions.
+fn crop_to_tensor(
+    image: &RgbImage,
+    bbox: &BoundingBox,
+    input_size: (u32, u32),
+) -> Option<Array4<f32>> {
+    let x = bbox.x1.max(0.0) as u32;
+    let y = bbox.y1.max(0.0) as u32;
+    let x_end = (bbox.x2.max(0.0) as u32).min(image.width());
+    let y_end = (bbox.y2.max(0.0) as u32).min(image.height());
+
+    if x_end <= x || y_end <= y {
+        return None;
+    }
+
+    let crop = imageops::crop_imm(image, x, y, x_end - x, y_end - y).to_image();
+    let resized = imageops::resize(&crop, input_size.0, input_size.1, FilterType::Triangle);
+
+    let (w, h) = (input_size.0 as usize, input_size.1 as usize);
+    let hw = h * w;
+    let raw = resized.as_raw();
+    let mut data = vec![0.0f32; 3 * hw];
+
+    for i in 0..hw {
+        for c in 0..3 {
+            data[c * hw + i] = f32::from(raw[i * 3 + c]) / 255.0;
+        }
+    }
+
+    Array4::from_shape_vec((1, 3, h, w), data).ok()
+}
+
+/// Applies a numerically stable softmax over the scores
+fn softmax(scores: &[f32]) -> Vec<f32> {
+    let max = scores.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let exps: Vec<f32> = scores.iter().map(|&s| (s - max).exp()).collect();
+    let sum: f32 = exps.iter().sum();
+    exps.into_iter().map(|e| e / sum).collect()
+}
+
+/// Returns the index and value of the highest score
+fn argmax(scores: &[f32]) -> Option<(usize, f32)> {
+    scores
+        .iter()
+        .copied()
+        .enumerate()
+        .filter(|(_, score)| score.is_finite())
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    #[test]
+    fn test_crop_to_tensor_shape_and_range() {
+        let image = RgbImage::from_pixel(20, 20, Rgb([255, 0, 51]));
+        let bbox = BoundingBox::new(2.0, 2.0, 12.0, 10.0, 0, 0.9);
+        let tensor = crop_to_tensor(&image, &bbox, (8, 4)).unwrap();
+
+        assert_eq!(tensor.shape(), &[1, 3, 4, 8]);
+        assert!((tensor[[0, 0, 0, 0]] - 1.0).abs() < f32::EPSILON);
+        assert!(tensor[[0, 1, 0, 0]].abs() < f32::EPSILON);
+        assert!((tensor[[0, 2, 3, 7]] - 0.2).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_crop_to_tensor_empty_box() {
+        let image = RgbImage::from_pixel(20, 20, Rgb([0, 0, 0]));
+        let outside = BoundingBox::new(30.0, 30.0, 40.0, 40.0, 0, 0.9);
+        assert!(crop_to_tensor(&image, &outside, (8, 8)).is_none());
+    }
+
+    #[test]
+    fn test_softmax_sums_to_one() {
+        let probabilities = softmax(&[1.0, 2.0, 3.0]);
+        assert!((probabilities.iter().sum::<f32>() - 1.0).abs() < 0.0001);
+        assert!(probabilities[2] > probabilities[1] && probabilities[1] > probabilities[0]);
+    }
+
+    #[test]
+    fn test_argmax() {
+        assert_eq!(argmax(&[0.1, 0.7, 0.2]), Some((1, 0.7)));
+        assert_eq!(argmax(&[f32::NAN, 0.3]), Some((1, 0.3)));
+        assert_eq!(argmax(&[]), None);
+    }
+
+    #[test]
+    fn test_default_config() {
+        let config = LevelClassifierConfig::default();
+        assert_eq!(config.input_size, (64, 64));
+        assert_eq!(config.first_level, 1);
+        assert!(!config.apply_softmax);
+    }
+}
@@ -1,4 +1,7 @@
 pub mod inference;
+pub mod level_classifier;
 pub mod yolo_type;
 pub mod yolov10_inference;
+pub mod yolov11_inference;
+pub mod yolov5_inference;
 pub mod yolov8_inference;
@@ -1,20 +1,41 @@
+use crate::image::{PADDING_COLOR, ULTRALYTICS_PADDING_COLOR};
+use crate::model::inference::is_inference_registered;
 use std::fmt::Debug;
 
 /// Enum representing different types of YOLO models.
 #[derive(PartialEq, Eq, Clone)]
 pub enum YoloType {
+    YoloV5,
     YoloV8,
     YoloV10,
+    YoloV11,
+    /// Model head handled by an inference registered under this key
+    Custom(String),
 }
 
 impl YoloType {
     /// Returns the string representation of the `YoloType` variant.
     #[inline]
     #[must_use]
-    pub const fn as_str(&self) -> &'static str {
+    pub fn as_str(&self) -> &str {
         match self {
+            Self::YoloV5 => "YoloV5",
             Self::YoloV8 => "YoloV8",
             Self::YoloV10 => "YoloV10",
+            Self::YoloV11 => "YoloV11",
+            Self::Custom(key) => key,
+        }
+    }
+
+    /// Returns the letterbox padding color the model was trained with
+    #[inline]
+    #[must_use]
+    pub const fn default_padding_color(&self) -> [u8; 3] {
+        match self {
+            Self::YoloV5 | Self::YoloV8 | Self::YoloV10 | Self::YoloV11 => {
+                ULTRALYTICS_PADDING_COLOR
+            }
+            Self::Custom(_) => PADDING_COLOR,
         }
     }
 }
@@ -24,8 +45,11 @@ impl TryFrom<&str> for YoloType {
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
         match value.to_lowercase().as_str() {
+            "yolov5" => Ok(Self::YoloV5),
             "yolov8" => Ok(Self::YoloV8),
             "yolov10" => Ok(Self::YoloV10),
+            "yolov11" | "yolo11" => Ok(Self::YoloV11),
+            _ if is_inference_registered(value) => Ok(Self::Custom(value.to_string())),
             _ => Err(()),
         }
     }
@@ -43,18 +67,39 @@ mod tests {
 
     #[test]
     fn test_yolo_type_as_str() {
+        assert_eq!(YoloType::YoloV5.as_str(), "YoloV5");
         assert_eq!(YoloType::YoloV8.as_str(), "YoloV8");
         assert_eq!(YoloType::YoloV10.as_str(), "YoloV10");
+        assert_eq!(YoloType::YoloV11.as_str(), "YoloV11");
     }
 
     #[test]
     fn test_yolo_type_try_from() {
+        assert_eq!(YoloType::try_from("yolov5").unwrap(), YoloType::YoloV5);
         assert_eq!(YoloType::try_from("yolov8").unwrap(), YoloType::YoloV8);
         assert_eq!(YoloType::try_from("YoloV8").unwrap(), YoloType::YoloV8);
         assert_eq!(YoloType::try_from("YOLOV8").unwrap(), YoloType::YoloV8);
         assert_eq!(YoloType::try_from("yolov10").unwrap(), YoloType::YoloV10);
         assert_eq!(YoloType::try_from("YoloV10").unwrap(), YoloType::YoloV10);
         assert_eq!(YoloType::try_from("YOLOV10").unwrap(), YoloType::YoloV10);
+        assert_eq!(YoloType::try_from("yolo11").unwrap(), YoloType::YoloV11);
         assert!(YoloType::try_from("unknown").is_err());
     }
+
+    #[test]
+    fn test_default_padding_color() {
+        assert_eq!(YoloType::YoloV8.default_padding_color(), [114, 114, 114]);
+        assert_eq!(YoloType::YoloV10.default_padding_color(), [114, 114, 114]);
+        assert_eq!(
+            YoloType::Custom("head".to_string()).default_padding_color(),
+            PADDING_COLOR
+        );
+    }
+
+    #[test]
+    fn test_custom_yolo_type() {
+        let custom = YoloType::Custom("my-head".to_string());
+        assert_eq!(custom.as_str(), "my-head");
+        assert_eq!(format!("{custom:?}"), "my-head");
+    }
 }
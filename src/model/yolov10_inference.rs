@@ -1,5 +1,5 @@
 use crate::detection::BoundingBox;
-use crate::model::inference::YoloInference;
+use crate::model::inference::{InferenceError, YoloInference, check_finite, check_head};
 use ndarray::ArrayViewD;
 
 /// `YOLOv10` inference implementation
@@ -10,15 +10,25 @@ impl YoloInference for Yolov10Inference {
         &self,
         output: ArrayViewD<'_, f32>,
         confidence_threshold: f32,
-    ) -> Vec<BoundingBox> {
-        let shape = output.shape();
-        let reshaped_output = output
-            .to_shape((shape[1], shape[2]))
-            .expect("Failed to reshape YOLOv10 output");
+    ) -> Result<Vec<BoundingBox>, InferenceError> {
+        const EXPECTED: &str = "(1, detections, 6)";
+        let (rows, columns) = check_head(&output, 1, 6, EXPECTED)?;
+        let reshaped_output =
+            output
+                .to_shape((rows, columns))
+                .map_err(|_| InferenceError::UnexpectedShape {
+                    shape: output.shape().to_vec(),
+                    expected: EXPECTED,
+                })?;
 
         let mut boxes = Vec::with_capacity(reshaped_output.shape()[0]);
+        let mut non_finite = 0;
 
         for detection in reshaped_output.outer_iter() {
+            if !detection.iter().all(|value| value.is_finite()) {
+                non_finite += 1;
+                continue;
+            }
             let confidence = detection[4];
 
             if confidence >= confidence_threshold {
@@ -34,6 +44,7 @@ impl YoloInference for Yolov10Inference {
             }
         }
 
-        boxes
+        check_finite(non_finite, rows)?;
+        Ok(boxes)
     }
 }
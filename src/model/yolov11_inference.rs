@@ -0,0 +1,77 @@
+use crate::detection::BoundingBox;
+use crate::model::inference::{InferenceError, YoloInference};
+use crate::model::yolov8_inference::Yolov8Inference;
+use ndarray::{ArrayViewD, IxDyn};
+
+/// `YOLOv11` inference implementation.
+///
+/// The head shares the `YOLOv8` layout `(1, 4 + classes, detections)`; exports with the
+/// transposed `(1, detections, 4 + classes)` layout are detected and handled too.
+pub struct Yolov11Inference;
+
+impl YoloInference for Yolov11Inference {
+    fn parse_output(
+        &self,
+        output: ArrayViewD<'_, f32>,
+        confidence_threshold: f32,
+    ) -> Result<Vec<BoundingBox>, InferenceError> {
+        let shape = output.shape();
+        if shape.len() == 3 && shape[1] > shape[2] {
+            // The v8 parser reads the raw buffer, so the transposed head is copied to row-major
+            let transposed = output.permuted_axes(IxDyn(&[0, 2, 1]));
+            let transposed = transposed.as_standard_layout();
+            return Yolov8Inference.parse_output(transposed.view(), confidence_threshold);
+        }
+        Yolov8Inference.parse_output(output, confidence_threshold)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array3;
+
+    /// Eight detections (two above threshold) of two classes, in the `(1, 6, 8)` head layout
+    fn fixture() -> Array3<f32> {
+        let mut output = Array3::zeros((1, 6, 8));
+        for (row, values) in [
+            [100.0, 300.0], // x
+            [100.0, 300.0], // y
+            [20.0, 10.0],   // w
+            [40.0, 10.0],   // h
+            [0.1, 0.9],     // class 0
+            [0.8, 0.2],     // class 1
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            output[[0, row, 0]] = values[0];
+            output[[0, row, 1]] = values[1];
+        }
+        output
+    }
+
+    #[test]
+    fn test_parse_output_matches_v8_layout() {
+        let output = fixture().into_dyn();
+        let boxes = Yolov11Inference.parse_output(output.view(), 0.5).unwrap();
+        assert_eq!(boxes.len(), 2);
+        assert_eq!(boxes[0].class_id, 1);
+        assert_eq!((boxes[0].x1, boxes[0].y1), (90.0, 80.0));
+        assert_eq!(boxes[1].class_id, 0);
+    }
+
+    #[test]
+    fn test_parse_output_transposed_layout() {
+        let output = fixture()
+            .permuted_axes([0, 2, 1])
+            .as_standard_layout()
+            .into_owned()
+            .into_dyn();
+        assert_eq!(output.shape(), &[1, 8, 6]);
+        let boxes = Yolov11Inference.parse_output(output.view(), 0.5).unwrap();
+        assert_eq!(boxes.len(), 2);
+        assert_eq!(boxes[0].class_id, 1);
+        assert_eq!(boxes[1].confidence, 0.9);
+    }
+}
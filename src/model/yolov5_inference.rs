@@ -0,0 +1,90 @@
+use crate::detection::BoundingBox;
+use crate::model::inference::{InferenceError, YoloInference, check_finite, check_head};
+use ndarray::ArrayViewD;
+
+/// `YOLOv5` inference implementation
+pub struct Yolov5Inference;
+
+impl YoloInference for Yolov5Inference {
+    fn parse_output(
+        &self,
+        output: ArrayViewD<'_, f32>,
+        confidence_threshold: f32,
+    ) -> Result<Vec<BoundingBox>, InferenceError> {
+        const EXPECTED: &str = "(1, detections, 5 + classes)";
+        let (rows, columns) = check_head(&output, 1, 6, EXPECTED)?;
+        let reshaped_output =
+            output
+                .to_shape((rows, columns))
+                .map_err(|_| InferenceError::UnexpectedShape {
+                    shape: output.shape().to_vec(),
+                    expected: EXPECTED,
+                })?;
+
+        let mut boxes = Vec::with_capacity(reshaped_output.shape()[0] / 10);
+        let mut non_finite = 0;
+
+        // Each row is (x, y, w, h, objectness, class scores...)
+        for detection in reshaped_output.outer_iter() {
+            if !detection.iter().all(|value| value.is_finite()) {
+                non_finite += 1;
+                continue;
+            }
+            let objectness = detection[4];
+            if objectness <= confidence_threshold {
+                continue;
+            }
+
+            let (class_id, class_prob) = detection.iter().skip(5).copied().enumerate().fold(
+                (0, f32::MIN),
+                |best, (c, prob)| {
+                    if prob > best.1 { (c, prob) } else { best }
+                },
+            );
+
+            let confidence = objectness * class_prob;
+            if confidence > confidence_threshold {
+                boxes.push(BoundingBox::from_center(
+                    detection[0],
+                    detection[1],
+                    detection[2],
+                    detection[3],
+                    class_id,
+                    confidence,
+                ));
+            }
+        }
+
+        check_finite(non_finite, rows)?;
+        Ok(boxes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array3;
+
+    #[test]
+    fn test_parse_output_scales_by_objectness() {
+        let output = Array3::from_shape_vec(
+            (1, 3, 7),
+            vec![
+                100.0, 100.0, 20.0, 40.0, 0.9, 0.2, 0.8, // kept: 0.9 * 0.8
+                50.0, 50.0, 10.0, 10.0, 0.3, 1.0, 0.0, // objectness too low
+                10.0, 10.0, 4.0, 4.0, 0.6, 0.5, 0.1, // 0.6 * 0.5 under threshold
+            ],
+        )
+        .unwrap()
+        .into_dyn();
+
+        let boxes = Yolov5Inference.parse_output(output.view(), 0.5).unwrap();
+        assert_eq!(boxes.len(), 1);
+        assert_eq!(boxes[0].class_id, 1);
+        assert!((boxes[0].confidence - 0.72).abs() < 1e-6);
+        assert_eq!(
+            (boxes[0].x1, boxes[0].y1, boxes[0].x2, boxes[0].y2),
+            (90.0, 80.0, 110.0, 120.0)
+        );
+    }
+}
@@ -1,5 +1,5 @@
 use crate::detection::BoundingBox;
-use crate::model::inference::YoloInference;
+use crate::model::inference::{InferenceError, YoloInference, check_finite, check_head};
 use ndarray::ArrayViewD;
 
 /// `YOLOv8` inference implementation
@@ -10,44 +10,56 @@ impl YoloInference for Yolov8Inference {
         &self,
         output: ArrayViewD<'_, f32>,
         confidence_threshold: f32,
-    ) -> Vec<BoundingBox> {
-        let shape = output.shape();
+    ) -> Result<Vec<BoundingBox>, InferenceError> {
+        const EXPECTED: &str = "(1, 4 + classes, detections)";
+        let (num_rows, num_detections) = check_head(&output, 5, 1, EXPECTED)?;
+        let unexpected = || InferenceError::UnexpectedShape {
+            shape: output.shape().to_vec(),
+            expected: EXPECTED,
+        };
         let reshaped_output = output
-            .to_shape((shape[1], shape[2]))
-            .expect("Failed to reshape YOLOv8 output");
-
-        let num_rows = reshaped_output.shape()[0];
-        let num_detections = reshaped_output.shape()[1];
+            .to_shape((num_rows, num_detections))
+            .map_err(|_| unexpected())?;
         let num_classes = num_rows - 4;
 
         let mut boxes = Vec::with_capacity(num_detections / 10);
+        let mut non_finite = 0;
 
         // Get raw slice for faster access (avoids per-element bounds checking)
-        let raw = reshaped_output.as_slice().unwrap();
+        let raw = reshaped_output.as_slice().ok_or_else(unexpected)?;
         let stride = num_detections; // row-major stride for (num_rows, num_detections) layout
 
         for det in 0..num_detections {
             // Find max class probability with a direct loop (no iterator overhead)
             let mut max_class_id = 0usize;
             let mut max_class_prob = raw[4 * stride + det];
+            let mut finite = max_class_prob.is_finite();
 
             for c in 1..num_classes {
                 let prob = raw[(4 + c) * stride + det];
+                finite &= prob.is_finite();
                 if prob > max_class_prob {
                     max_class_prob = prob;
                     max_class_id = c;
                 }
             }
 
+            let x = raw[det];
+            let y = raw[stride + det];
+            let w = raw[2 * stride + det];
+            let h = raw[3 * stride + det];
+            // Candidates with NaN or infinite values are skipped, not the whole output
+            if !(finite && x.is_finite() && y.is_finite() && w.is_finite() && h.is_finite()) {
+                non_finite += 1;
+                continue;
+            }
+
             if max_class_prob > confidence_threshold {
-                let x = raw[det];
-                let y = raw[stride + det];
-                let w = raw[2 * stride + det];
-                let h = raw[3 * stride + det];
                 boxes.push(BoundingBox::from_center(x, y, w, h, max_class_id, max_class_prob));
             }
         }
 
-        boxes
+        check_finite(non_finite, num_detections)?;
+        Ok(boxes)
     }
 }
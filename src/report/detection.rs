@@ -0,0 +1,116 @@
+//! Structured result of running the detection pipeline on one image
+
+use crate::detection::BoundingBox;
+use crate::store::ImageRecord;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Time spent in each stage of the pipeline for one image
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StageTimings {
+    /// Letterboxing and normalization
+    pub preprocess: Duration,
+    /// Model run and output parsing, shared evenly between the images of a batch
+    pub inference: Duration,
+    /// NMS, level estimation, middleware and mapping back to the original image
+    pub postprocess: Duration,
+}
+
+impl StageTimings {
+    /// Returns the time spent in the three stages together
+    #[inline]
+    #[must_use]
+    pub fn total(&self) -> Duration {
+        self.preprocess + self.inference + self.postprocess
+    }
+}
+
+/// Detections of an image together with its sizes and stage timings
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetectionReport {
+    pub image_path: String,
+    /// Dimensions of the image as decoded
+    pub original_size: (u32, u32),
+    /// Dimensions of the letterboxed model input
+    pub input_size: (u32, u32),
+    /// Final boxes, in original image coordinates
+    pub boxes: Vec<BoundingBox>,
+    /// Number of boxes per class id
+    pub class_counts: BTreeMap<usize, usize>,
+    pub timings: StageTimings,
+}
+
+impl DetectionReport {
+    /// Creates a report, counting the boxes per class
+    #[must_use]
+    pub fn new(
+        image_path: impl Into<String>,
+        original_size: (u32, u32),
+        input_size: (u32, u32),
+        boxes: Vec<BoundingBox>,
+        timings: StageTimings,
+    ) -> Self {
+        let mut class_counts = BTreeMap::new();
+        for bbox in &boxes {
+            *class_counts.entry(bbox.class_id).or_insert(0) += 1;
+        }
+        Self {
+            image_path: image_path.into(),
+            original_size,
+            input_size,
+            boxes,
+            class_counts,
+            timings,
+        }
+    }
+
+    /// Returns the number of boxes of a class
+    #[inline]
+    #[must_use]
+    pub fn count(&self, class_id: usize) -> usize {
+        self.class_counts.get(&class_id).copied().unwrap_or(0)
+    }
+}
+
+impl From<&ImageRecord> for DetectionReport {
+    /// Rebuilds the report of a persisted record, whose input size and stage timings are unknown
+    fn from(record: &ImageRecord) -> Self {
+        Self::new(
+            record.image_path.clone(),
+            record.image_dimensions,
+            record.image_dimensions,
+            record.boxes.clone(),
+            StageTimings::default(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_counts_classes_and_totals_timings() {
+        let timings = StageTimings {
+            preprocess: Duration::from_millis(3),
+            inference: Duration::from_millis(20),
+            postprocess: Duration::from_millis(2),
+        };
+        let report = DetectionReport::new(
+            "village.png",
+            (1920, 1080),
+            (640, 640),
+            vec![
+                BoundingBox::new(0.0, 0.0, 10.0, 10.0, 1, 0.9),
+                BoundingBox::new(20.0, 0.0, 30.0, 10.0, 1, 0.8),
+                BoundingBox::new(40.0, 0.0, 50.0, 10.0, 0, 0.7),
+            ],
+            timings,
+        );
+
+        assert_eq!(report.count(0), 1);
+        assert_eq!(report.count(1), 2);
+        assert_eq!(report.count(5), 0);
+        assert_eq!(report.timings.total(), Duration::from_millis(25));
+    }
+}
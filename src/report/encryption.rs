@@ -0,0 +1,263 @@
+//! AES-256-GCM encryption of the detection files, reports and manifests of a run, for results synced
+//! through shared drives that should not be readable without the key
+//!
+//! An encrypted file holds the `CVENC1` magic, a random 96-bit nonce and the ciphertext followed
+//! by its authentication tag. The key is 32 bytes, given as 64 hex digits in the
+//! `CLASHVISION_OUTPUT_KEY` environment variable or in a key file, e.g. generated with
+//! `openssl rand -hex 32`.
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use std::ffi::OsString;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Environment variable holding the hex key when no key file is given
+pub const KEY_ENV: &str = "CLASHVISION_OUTPUT_KEY";
+
+/// Extension appended to the name of encrypted files
+pub const ENCRYPTED_EXTENSION: &str = "enc";
+
+/// Leading bytes of an encrypted file, also authenticated with the ciphertext
+const MAGIC: &[u8] = b"CVENC1";
+
+/// Length of the nonce stored after the magic
+const NONCE_LEN: usize = 12;
+
+#[derive(Error, Debug)]
+pub enum EncryptionError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Invalid key: {0}")]
+    InvalidKey(String),
+    #[error("Not an encrypted file")]
+    NotEncrypted,
+    #[error("Decryption failed: wrong key or corrupted file")]
+    Decryption,
+}
+
+/// AES-256-GCM key encrypting output files
+#[derive(Clone)]
+pub struct OutputKey {
+    key: [u8; 32],
+}
+
+impl OutputKey {
+    /// Creates a key from its 32 bytes
+    #[must_use]
+    pub const fn new(key: [u8; 32]) -> Self {
+        Self { key }
+    }
+
+    /// Parses a key written as 64 hex digits
+    pub fn from_hex(hex: &str) -> Result<Self, EncryptionError> {
+        let hex = hex.trim();
+        if hex.len() != 64 || !hex.is_ascii() {
+            return Err(EncryptionError::InvalidKey(
+                "expected 64 hex digits".to_string(),
+            ));
+        }
+        let mut key = [0u8; 32];
+        for (byte, digits) in key.iter_mut().zip(hex.as_bytes().chunks(2)) {
+            let digits = std::str::from_utf8(digits).unwrap_or_default();
+            *byte = u8::from_str_radix(digits, 16)
+                .map_err(|_| EncryptionError::InvalidKey(format!("invalid hex digits {digits}")))?;
+        }
+        Ok(Self::new(key))
+    }
+
+    /// Reads a key file holding either 64 hex digits or the 32 raw key bytes
+    pub fn from_file(path: &Path) -> Result<Self, EncryptionError> {
+        let bytes = fs::read(path)?;
+        match <[u8; 32]>::try_from(bytes.as_slice()) {
+            Ok(key) => Ok(Self::new(key)),
+            Err(_) => Self::from_hex(&String::from_utf8_lossy(&bytes)),
+        }
+    }
+
+    /// Reads the key from `key_file` if given, otherwise from the `CLASHVISION_OUTPUT_KEY`
+    /// environment variable
+    pub fn resolve(key_file: Option<&Path>) -> Result<Self, EncryptionError> {
+        match key_file {
+            Some(path) => Self::from_file(path),
+            None => match std::env::var(KEY_ENV) {
+                Ok(hex) => Self::from_hex(&hex),
+                Err(_) => Err(EncryptionError::InvalidKey(format!(
+                    "no key file given and {KEY_ENV} is not set"
+                ))),
+            },
+        }
+    }
+
+    /// Encrypts `plaintext` under a fresh random nonce
+    #[must_use]
+    pub fn seal(&self, plaintext: &[u8]) -> Vec<u8> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let payload = Payload {
+            msg: plaintext,
+            aad: MAGIC,
+        };
+        let ciphertext = self
+            .cipher()
+            .encrypt(&nonce, payload)
+            .expect("AES-GCM encrypts any message shorter than 64 GiB");
+
+        let mut sealed = Vec::with_capacity(MAGIC.len() + NONCE_LEN + ciphertext.len());
+        sealed.extend_from_slice(MAGIC);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+        sealed
+    }
+
+    /// Decrypts the contents of a file written by `seal`, checking it was not altered
+    pub fn open(&self, sealed: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        if !is_encrypted(sealed) || sealed.len() < MAGIC.len() + NONCE_LEN {
+            return Err(EncryptionError::NotEncrypted);
+        }
+        let (nonce, ciphertext) = sealed[MAGIC.len()..].split_at(NONCE_LEN);
+        let payload = Payload {
+            msg: ciphertext,
+            aad: MAGIC,
+        };
+        self.cipher()
+            .decrypt(Nonce::from_slice(nonce), payload)
+            .map_err(|_| EncryptionError::Decryption)
+    }
+
+    /// Encrypts `plaintext` into `path`, through a temporary file renamed over it so that a
+    /// partially written file is never left behind
+    pub fn write_sealed(&self, path: &Path, plaintext: &[u8]) -> io::Result<()> {
+        let mut partial = path.as_os_str().to_owned();
+        partial.push(".part");
+        fs::write(&partial, self.seal(plaintext))?;
+        fs::rename(&partial, path)
+    }
+
+    /// Reads and decrypts a file written by `write_sealed`
+    pub fn read_opened(&self, path: &Path) -> Result<Vec<u8>, EncryptionError> {
+        self.open(&fs::read(path)?)
+    }
+
+    fn cipher(&self) -> Aes256Gcm {
+        Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&self.key))
+    }
+}
+
+impl fmt::Debug for OutputKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("OutputKey(..)")
+    }
+}
+
+/// Returns true if `bytes` start like a file written by `OutputKey::seal`
+#[must_use]
+pub fn is_encrypted(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+/// Returns the path an output is encrypted to, `path` with `.enc` appended
+#[must_use]
+pub fn encrypted_path(path: &Path) -> PathBuf {
+    let mut encrypted = OsString::from(path.as_os_str());
+    encrypted.push(".");
+    encrypted.push(ENCRYPTED_EXTENSION);
+    PathBuf::from(encrypted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const HEX_KEY: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+    #[test]
+    fn test_seal_and_open() -> Result<(), EncryptionError> {
+        let key = OutputKey::from_hex(HEX_KEY)?;
+        let sealed = key.seal(br#"{"detections": []}"#);
+        assert!(is_encrypted(&sealed));
+        assert_ne!(key.seal(b"same"), key.seal(b"same"));
+        assert_eq!(key.open(&sealed)?, br#"{"detections": []}"#);
+
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(matches!(
+            key.open(&tampered),
+            Err(EncryptionError::Decryption)
+        ));
+        let other = OutputKey::new([7; 32]);
+        assert!(matches!(
+            other.open(&sealed),
+            Err(EncryptionError::Decryption)
+        ));
+        assert!(matches!(
+            key.open(b"{}"),
+            Err(EncryptionError::NotEncrypted)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_key_files_and_paths() -> Result<(), EncryptionError> {
+        let dir = tempdir()?;
+        let hex_file = dir.path().join("key.hex");
+        fs::write(&hex_file, format!("{HEX_KEY}\n"))?;
+        let raw_file = dir.path().join("key.bin");
+        fs::write(&raw_file, OutputKey::from_hex(HEX_KEY)?.key)?;
+        assert_eq!(
+            OutputKey::from_file(&hex_file)?.key,
+            OutputKey::from_file(&raw_file)?.key
+        );
+        assert!(OutputKey::from_hex("abcd").is_err());
+        assert!(OutputKey::from_hex(&"zz".repeat(32)).is_err());
+
+        let key = OutputKey::resolve(Some(&hex_file))?;
+        let path = encrypted_path(&dir.path().join("village.json"));
+        assert_eq!(path.file_name().unwrap(), "village.json.enc");
+        key.write_sealed(&path, b"secret")?;
+        assert_eq!(key.read_opened(&path)?, b"secret");
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_reports_leave_no_plaintext() -> Result<(), EncryptionError> {
+        use crate::class::registry::ClassRegistry;
+        use crate::detection::BoundingBox;
+        use crate::detection::coco::CocoExport;
+        use crate::report::summary::RunSummary;
+
+        let key = OutputKey::from_hex(HEX_KEY)?;
+        let boxes = [BoundingBox::new(1.0, 2.0, 11.0, 22.0, 0, 0.9)];
+        let mut summary = RunSummary::default();
+        summary.record_success(&boxes, &ClassRegistry::default());
+        let mut coco = CocoExport::default();
+        coco.add_image("village.png", (640, 480), &boxes);
+
+        let dir = tempdir()?;
+        summary.write_encrypted_json(&dir.path().join("summary.json"), &key)?;
+        coco.write_encrypted_results(&dir.path().join("results.json"), &key)?;
+        coco.write_encrypted_dataset(
+            &dir.path().join("dataset.json"),
+            &ClassRegistry::default(),
+            &key,
+        )?;
+
+        let mut names = Vec::new();
+        for entry in fs::read_dir(dir.path())? {
+            let path = entry?.path();
+            assert!(is_encrypted(&fs::read(&path)?));
+            names.push(path.file_name().unwrap().to_string_lossy().into_owned());
+        }
+        names.sort();
+        assert_eq!(
+            names,
+            ["dataset.json.enc", "results.json.enc", "summary.json.enc"]
+        );
+        let results = key.read_opened(&dir.path().join("results.json.enc"))?;
+        assert!(String::from_utf8_lossy(&results).contains("\"category_id\""));
+        Ok(())
+    }
+}
@@ -0,0 +1,509 @@
+//! Browsable gallery of an output directory, served over HTTP for reviewing results on the LAN
+
+use serde::Deserialize;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+/// Time a client has to send its request before the connection is dropped
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Detection read from the JSON output of an image
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct GalleryDetection {
+    #[serde(rename = "category_name")]
+    pub class_name: String,
+    #[serde(rename = "score")]
+    pub confidence: f32,
+}
+
+#[derive(Deserialize)]
+struct DetectionFile {
+    #[serde(default)]
+    detections: Vec<GalleryDetection>,
+}
+
+/// Annotated image of the output directory with the detections of its JSON output
+#[derive(Debug, Clone, PartialEq)]
+pub struct GalleryItem {
+    /// File name of the annotated image
+    pub image: String,
+    /// File name of the JSON output, if there is one
+    pub json: Option<String>,
+    pub detections: Vec<GalleryDetection>,
+}
+
+/// Items shown by the gallery, read from the query string of a request
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GalleryFilter {
+    /// Only show images with a detection of this class (case-insensitive)
+    pub class: Option<String>,
+    /// Only count detections at or above this confidence
+    pub min_confidence: f32,
+}
+
+impl GalleryFilter {
+    /// Parses `class=...&min_confidence=...`, ignoring unknown or invalid parameters
+    #[must_use]
+    pub fn from_query(query: &str) -> Self {
+        let mut filter = Self::default();
+        for (key, value) in query.split('&').filter_map(|pair| pair.split_once('=')) {
+            let value = decode_query(value);
+            match key {
+                "class" if !value.is_empty() => filter.class = Some(value),
+                "min_confidence" => filter.min_confidence = value.parse().unwrap_or(0.0),
+                _ => {}
+            }
+        }
+        filter
+    }
+
+    /// Returns true if the item has a detection passing the filter, or if the filter is empty
+    #[must_use]
+    pub fn matches(&self, item: &GalleryItem) -> bool {
+        if self.class.is_none() && self.min_confidence <= 0.0 {
+            return true;
+        }
+        item.detections.iter().any(|detection| {
+            detection.confidence >= self.min_confidence
+                && self
+                    .class
+                    .as_ref()
+                    .is_none_or(|class| detection.class_name.eq_ignore_ascii_case(class))
+        })
+    }
+}
+
+/// Annotated images of an output directory
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Gallery {
+    pub items: Vec<GalleryItem>,
+}
+
+impl Gallery {
+    /// Lists the annotated `.jpg` images of `dir` with the detections of their `.json` outputs
+    pub fn scan(dir: &Path) -> io::Result<Self> {
+        let mut items = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if !path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("jpg"))
+            {
+                continue;
+            }
+            let Some(image) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            let json_path = path.with_extension("json");
+            let detections = fs::read_to_string(&json_path)
+                .ok()
+                .and_then(|text| serde_json::from_str::<DetectionFile>(&text).ok());
+            items.push(GalleryItem {
+                image: image.to_string(),
+                json: detections.as_ref().and_then(|_| {
+                    json_path
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .map(str::to_string)
+                }),
+                detections: detections.map(|file| file.detections).unwrap_or_default(),
+            });
+        }
+        items.sort_by(|a, b| a.image.cmp(&b.image));
+        Ok(Self { items })
+    }
+
+    /// Returns the names of the detected classes, sorted and deduplicated
+    #[must_use]
+    pub fn class_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .items
+            .iter()
+            .flat_map(|item| &item.detections)
+            .map(|detection| detection.class_name.as_str())
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+
+    /// Renders the gallery page showing the items passing `filter`
+    #[must_use]
+    pub fn render(&self, filter: &GalleryFilter) -> String {
+        let mut html = String::with_capacity(2048 + self.items.len() * 512);
+        let _ = writeln!(
+            html,
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">"
+        );
+        let _ = writeln!(html, "<title>clashvision gallery</title>");
+        let _ = writeln!(html, "<style>{GALLERY_STYLE}</style>\n</head>\n<body>");
+        let _ = writeln!(html, "<h1>clashvision gallery</h1>");
+
+        let _ = writeln!(html, "<form method=\"get\" action=\"/\">");
+        let _ = writeln!(
+            html,
+            "<select name=\"class\"><option value=\"\">All classes</option>"
+        );
+        for name in self.class_names() {
+            let selected = filter
+                .class
+                .as_ref()
+                .is_some_and(|class| class.eq_ignore_ascii_case(name));
+            let _ = writeln!(
+                html,
+                "<option{}>{}</option>",
+                if selected { " selected" } else { "" },
+                escape_html(name)
+            );
+        }
+        let _ = writeln!(html, "</select>");
+        let _ = writeln!(
+            html,
+            "<label>Min confidence <input type=\"number\" name=\"min_confidence\" min=\"0\" \
+             max=\"1\" step=\"0.05\" value=\"{}\"></label>",
+            filter.min_confidence
+        );
+        let _ = writeln!(html, "<button type=\"submit\">Filter</button>\n</form>");
+
+        let shown: Vec<&GalleryItem> = self
+            .items
+            .iter()
+            .filter(|item| filter.matches(item))
+            .collect();
+        let _ = writeln!(
+            html,
+            "<p>{} of {} image(s)</p>\n<div class=\"grid\">",
+            shown.len(),
+            self.items.len()
+        );
+        for item in shown {
+            let image = escape_html(&encode_path(&item.image));
+            let _ = writeln!(html, "<figure>");
+            let _ = writeln!(
+                html,
+                "<a href=\"/files/{image}\"><img src=\"/files/{image}\" loading=\"lazy\" alt=\"{}\"></a>",
+                escape_html(&item.image)
+            );
+            let _ = write!(
+                html,
+                "<figcaption>{} &middot; {} detection(s)",
+                escape_html(&item.image),
+                item.detections.len()
+            );
+            if let Some(json) = &item.json {
+                let _ = write!(
+                    html,
+                    " &middot; <a href=\"/files/{}\">JSON</a>",
+                    escape_html(&encode_path(json))
+                );
+            }
+            let _ = writeln!(html, "</figcaption>\n</figure>");
+        }
+        let _ = writeln!(html, "</div>\n</body>\n</html>");
+        html
+    }
+}
+
+/// Response to a gallery request
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    fn text(status: u16, body: &str) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    /// Writes the response as HTTP/1.1, closing the connection afterwards
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        let reason = match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            _ => "Internal Server Error",
+        };
+        write!(
+            writer,
+            "HTTP/1.1 {} {reason}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            self.status,
+            self.content_type,
+            self.body.len()
+        )?;
+        writer.write_all(&self.body)?;
+        writer.flush()
+    }
+}
+
+/// Answers the request `request_line` (e.g. `GET /?class=Gold+Storage HTTP/1.1`) from `dir`.
+/// `/` renders the gallery and `/files/<name>` serves a file of `dir`, never one outside it.
+#[must_use]
+pub fn handle_request(dir: &Path, request_line: &str) -> Response {
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Response::text(400, "Bad request");
+    };
+    if method != "GET" {
+        return Response::text(405, "Only GET is supported");
+    }
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    if path == "/" {
+        return match Gallery::scan(dir) {
+            Ok(gallery) => Response {
+                status: 200,
+                content_type: "text/html; charset=utf-8",
+                body: gallery
+                    .render(&GalleryFilter::from_query(query))
+                    .into_bytes(),
+            },
+            Err(e) => Response::text(500, &format!("Failed to read {}: {e}", dir.display())),
+        };
+    }
+    let Some(file) = path
+        .strip_prefix("/files/")
+        .and_then(|name| served_file(dir, &decode_path(name)))
+    else {
+        return Response::text(404, "Not found");
+    };
+    match fs::read(&file) {
+        Ok(body) if file.is_file() => Response {
+            status: 200,
+            content_type: content_type(&file),
+            body,
+        },
+        _ => Response::text(404, "Not found"),
+    }
+}
+
+/// Returns the path of the file `name` of `dir`, or `None` if `name` is not a plain file name,
+/// e.g. a drive-prefixed `C:secret.txt` on Windows, or resolves outside `dir` through a link
+fn served_file(dir: &Path, name: &str) -> Option<PathBuf> {
+    let mut components = Path::new(name).components();
+    let plain_name = matches!(
+        (components.next(), components.next()),
+        (Some(Component::Normal(_)), None)
+    );
+    if !plain_name || name.contains(['/', '\\']) || name.starts_with('.') {
+        return None;
+    }
+    let file = dir.join(name).canonicalize().ok()?;
+    file.starts_with(dir.canonicalize().ok()?).then_some(file)
+}
+
+/// Serves the gallery of `dir` on `addr` until the process is stopped, one request at a time
+pub fn serve(dir: impl Into<PathBuf>, addr: impl ToSocketAddrs) -> io::Result<()> {
+    let dir = dir.into();
+    let listener = TcpListener::bind(addr)?;
+    for stream in listener.incoming() {
+        // A misbehaving client only loses its own connection
+        let _ = stream.and_then(|stream| answer(&dir, stream));
+    }
+    Ok(())
+}
+
+/// Reads the request line of a connection and writes the response
+fn answer(dir: &Path, stream: TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Drain the headers so the client sees the response rather than a reset
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+    handle_request(dir, &request_line).write_to(&mut &stream)
+}
+
+/// Returns the content type of a served file from its extension
+fn content_type(path: &Path) -> &'static str {
+    match path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase)
+        .as_deref()
+    {
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("png") => "image/png",
+        Some("json") => "application/json",
+        Some("html") => "text/html; charset=utf-8",
+        Some("xml") => "application/xml",
+        Some("csv") => "text/csv; charset=utf-8",
+        Some("txt") => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Decodes `%XX` escapes of a URL path
+fn decode_path(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| text.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Decodes a query string value, where `+` stands for a space
+fn decode_query(text: &str) -> String {
+    decode_path(&text.replace('+', " "))
+}
+
+/// Escapes the characters of a file name that are not allowed in a URL path
+fn encode_path(name: &str) -> String {
+    let mut encoded = String::with_capacity(name.len());
+    for byte in name.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-_.~".contains(&byte) {
+            encoded.push(char::from(byte));
+        } else {
+            let _ = write!(encoded, "%{byte:02X}");
+        }
+    }
+    encoded
+}
+
+/// Escapes text for inclusion in HTML content and attributes
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+const GALLERY_STYLE: &str = "\
+body{font-family:sans-serif;margin:2em;background:#fafafa;color:#222}\
+form{display:flex;gap:1em;align-items:center;margin-bottom:1em}\
+.grid{display:grid;grid-template-columns:repeat(auto-fill,minmax(280px,1fr));gap:1em}\
+figure{margin:0;background:#fff;border:1px solid #ddd;border-radius:6px;padding:.5em}\
+figure img{width:100%;height:auto}\
+figcaption{font-size:.9em;margin-top:.3em}";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn write_output(dir: &Path, stem: &str, detections: &str) -> io::Result<()> {
+        fs::write(dir.join(format!("{stem}.jpg")), b"jpeg")?;
+        fs::write(
+            dir.join(format!("{stem}.json")),
+            format!(r#"{{"images": [], "detections": [{detections}]}}"#),
+        )
+    }
+
+    #[test]
+    fn test_gallery_filters_by_class_and_confidence() -> io::Result<()> {
+        let dir = tempdir()?;
+        write_output(
+            dir.path(),
+            "base 1",
+            r#"{"category_name": "Gold Storage", "score": 0.9}"#,
+        )?;
+        write_output(
+            dir.path(),
+            "base2",
+            r#"{"category_name": "Elixir Storage", "score": 0.4}"#,
+        )?;
+        let gallery = Gallery::scan(dir.path())?;
+        assert_eq!(gallery.items.len(), 2);
+        assert_eq!(gallery.class_names(), ["Elixir Storage", "Gold Storage"]);
+
+        let filter = GalleryFilter::from_query("class=gold+storage&min_confidence=0.5");
+        assert_eq!(filter.class.as_deref(), Some("gold storage"));
+        assert!(filter.matches(&gallery.items[0]));
+        assert!(!filter.matches(&gallery.items[1]));
+        assert!(!GalleryFilter::from_query("min_confidence=0.5").matches(&gallery.items[1]));
+
+        let html = gallery.render(&filter);
+        assert!(html.contains("1 of 2 image(s)"));
+        assert!(html.contains("/files/base%201.jpg"));
+        assert!(!html.contains("/files/base2.jpg"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_handle_request_serves_files_inside_the_directory() -> io::Result<()> {
+        let dir = tempdir()?;
+        write_output(dir.path(), "base 1", "")?;
+
+        let page = handle_request(dir.path(), "GET /?class=Gold%20Storage HTTP/1.1");
+        assert_eq!(page.status, 200);
+        let image = handle_request(dir.path(), "GET /files/base%201.jpg HTTP/1.1");
+        assert_eq!((image.status, image.content_type), (200, "image/jpeg"));
+        assert_eq!(image.body, b"jpeg");
+        assert_eq!(
+            handle_request(dir.path(), "GET /files/..%2Fsecret HTTP/1.1").status,
+            404
+        );
+        assert_eq!(
+            handle_request(dir.path(), "GET /files/.. HTTP/1.1").status,
+            404
+        );
+        assert_eq!(
+            handle_request(dir.path(), "GET /files/C:secret.txt HTTP/1.1").status,
+            404
+        );
+        assert_eq!(handle_request(dir.path(), "POST / HTTP/1.1").status, 405);
+
+        let mut written = Vec::new();
+        image.write_to(&mut written)?;
+        assert!(written.starts_with(b"HTTP/1.1 200 OK\r\n"));
+        assert!(written.ends_with(b"\r\n\r\njpeg"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_served_file_stays_inside_the_directory() -> io::Result<()> {
+        let outside = tempdir()?;
+        fs::write(outside.path().join("secret.txt"), b"secret")?;
+        let dir = tempdir()?;
+        fs::write(dir.path().join("base.jpg"), b"jpeg")?;
+
+        assert!(served_file(dir.path(), "base.jpg").is_some());
+        assert!(served_file(dir.path(), "C:secret.txt").is_none());
+        let absolute = outside.path().join("secret.txt");
+        assert!(served_file(dir.path(), &absolute.to_string_lossy()).is_none());
+        #[cfg(windows)]
+        assert!(served_file(dir.path(), "C:base.jpg").is_none());
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&absolute, dir.path().join("link.txt"))?;
+            assert!(served_file(dir.path(), "link.txt").is_none());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,431 @@
+//! Static HTML report generation for batch runs
+
+use crate::class::locale::ClassNames;
+use crate::class::registry::ClassRegistry;
+use crate::detection::BoundingBox;
+use crate::session::warning::Warning;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Detection results of a single image included in a report
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportEntry {
+    pub source_path: PathBuf,
+    pub annotated_path: Option<PathBuf>,
+    pub image_dimensions: (u32, u32),
+    pub boxes: Vec<BoundingBox>,
+    pub error: Option<String>,
+    pub timed_out: bool,
+    pub warnings: Vec<Warning>,
+}
+
+impl ReportEntry {
+    /// Creates an entry for a successfully processed image
+    #[must_use]
+    pub fn success(
+        source_path: impl Into<PathBuf>,
+        annotated_path: Option<PathBuf>,
+        image_dimensions: (u32, u32),
+        boxes: Vec<BoundingBox>,
+    ) -> Self {
+        Self {
+            source_path: source_path.into(),
+            annotated_path,
+            image_dimensions,
+            boxes,
+            error: None,
+            timed_out: false,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Creates an entry for an image that failed to process
+    #[must_use]
+    pub fn failure(source_path: impl Into<PathBuf>, error: impl Into<String>) -> Self {
+        Self {
+            source_path: source_path.into(),
+            annotated_path: None,
+            image_dimensions: (0, 0),
+            boxes: Vec::new(),
+            error: Some(error.into()),
+            timed_out: false,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Creates an entry for an image skipped after exceeding its time budget
+    #[must_use]
+    pub fn timeout(source_path: impl Into<PathBuf>, budget: Duration) -> Self {
+        Self {
+            timed_out: true,
+            ..Self::failure(source_path, format!("Skipped: timed out after {budget:?}"))
+        }
+    }
+
+    /// Attaches the warnings raised while processing the image
+    #[must_use]
+    pub fn with_warnings(mut self, warnings: Vec<Warning>) -> Self {
+        self.warnings = warnings;
+        self
+    }
+}
+
+/// Static HTML page summarizing the detections of a batch
+#[derive(Debug, Clone, Default)]
+pub struct HtmlReport {
+    title: String,
+    entries: Vec<ReportEntry>,
+    class_names: ClassNames,
+    classes: ClassRegistry,
+}
+
+impl HtmlReport {
+    /// Creates an empty report with the given page title
+    #[must_use]
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            entries: Vec::new(),
+            class_names: ClassNames::default(),
+            classes: ClassRegistry::default(),
+        }
+    }
+
+    /// Uses the names and colors of `classes`, and its first translation if any
+    #[must_use]
+    pub fn with_classes(mut self, classes: ClassRegistry) -> Self {
+        if let Some(class_names) = classes.translations().first() {
+            self.class_names = class_names.clone();
+        }
+        self.classes = classes;
+        self
+    }
+
+    /// Uses localized class names, and their locale as the page language
+    #[must_use]
+    pub fn with_class_names(mut self, class_names: ClassNames) -> Self {
+        self.class_names = class_names;
+        self
+    }
+
+    /// Adds an image entry to the report
+    pub fn add_entry(&mut self, entry: ReportEntry) {
+        self.entries.push(entry);
+    }
+
+    /// Returns the entries of the report
+    #[inline]
+    #[must_use]
+    pub fn entries(&self) -> &[ReportEntry] {
+        &self.entries
+    }
+
+    /// Returns the number of detections per class id across all entries
+    #[must_use]
+    pub fn class_counts(&self) -> BTreeMap<usize, usize> {
+        let mut counts = BTreeMap::new();
+        for bbox in self.entries.iter().flat_map(|entry| &entry.boxes) {
+            *counts.entry(bbox.class_id).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Returns the mean confidence over all detections, if any
+    #[must_use]
+    pub fn mean_confidence(&self) -> Option<f32> {
+        let (sum, count) = self
+            .entries
+            .iter()
+            .flat_map(|entry| &entry.boxes)
+            .fold((0.0f32, 0usize), |(sum, count), bbox| {
+                (sum + bbox.confidence, count + 1)
+            });
+        (count > 0).then(|| sum / count as f32)
+    }
+
+    /// Renders the report as a standalone HTML page.
+    ///
+    /// Annotated images located under `base_dir` are linked relatively so the report
+    /// can be moved together with its output directory.
+    #[must_use]
+    pub fn render(&self, base_dir: &Path) -> String {
+        let mut html = String::with_capacity(4096 + self.entries.len() * 1024);
+        let title = escape_html(&self.title);
+
+        let _ = writeln!(html, "<!DOCTYPE html>");
+        let _ = writeln!(
+            html,
+            "<html lang=\"{}\">\n<head>\n<meta charset=\"utf-8\">",
+            escape_html(self.class_names.locale())
+        );
+        let _ = writeln!(html, "<title>{title}</title>");
+        let _ = writeln!(html, "<style>{REPORT_STYLE}</style>\n</head>\n<body>");
+        let _ = writeln!(html, "<h1>{title}</h1>");
+
+        self.render_summary(&mut html);
+        self.render_class_chart(&mut html);
+
+        for entry in &self.entries {
+            render_entry(&mut html, entry, base_dir, &self.class_names, &self.classes);
+        }
+
+        let _ = writeln!(html, "</body>\n</html>");
+        html
+    }
+
+    /// Writes the rendered report to `output_path`
+    pub fn write(&self, output_path: &Path) -> io::Result<()> {
+        let base_dir = output_path.parent().unwrap_or_else(|| Path::new(""));
+        fs::write(output_path, self.render(base_dir))
+    }
+
+    /// Renders the aggregate statistics table
+    fn render_summary(&self, html: &mut String) {
+        let failed = self.entries.iter().filter(|e| e.error.is_some()).count();
+        let timed_out = self.entries.iter().filter(|e| e.timed_out).count();
+        let detections: usize = self.entries.iter().map(|e| e.boxes.len()).sum();
+        let warnings: usize = self.entries.iter().map(|e| e.warnings.len()).sum();
+        let mean_confidence = self
+            .mean_confidence()
+            .map_or_else(|| "-".to_string(), |c| format!("{c:.3}"));
+
+        let _ = writeln!(html, "<h2>Summary</h2>\n<table class=\"summary\">");
+        let _ = writeln!(
+            html,
+            "<tr><th>Images</th><td>{}</td></tr>",
+            self.entries.len()
+        );
+        let _ = writeln!(html, "<tr><th>Failed</th><td>{failed}</td></tr>");
+        let _ = writeln!(html, "<tr><th>Timed out</th><td>{timed_out}</td></tr>");
+        let _ = writeln!(html, "<tr><th>Detections</th><td>{detections}</td></tr>");
+        let _ = writeln!(html, "<tr><th>Warnings</th><td>{warnings}</td></tr>");
+        let _ = writeln!(
+            html,
+            "<tr><th>Mean confidence</th><td>{mean_confidence}</td></tr>"
+        );
+        let _ = writeln!(html, "</table>");
+    }
+
+    /// Renders a bar chart of detections per class
+    fn render_class_chart(&self, html: &mut String) {
+        let counts = self.class_counts();
+        let max_count = counts.values().copied().max().unwrap_or(0).max(1);
+
+        let _ = writeln!(html, "<h2>Detections per class</h2>\n<div class=\"chart\">");
+        for (&class_id, &count) in &counts {
+            let (r, g, b, _) = self.classes.color(class_id);
+            let width = count as f32 / max_count as f32 * 100.0;
+            let _ = writeln!(
+                html,
+                "<div class=\"bar-row\"><span class=\"bar-label\">{}</span>\
+                 <span class=\"bar\" style=\"width:{width:.1}%;background:rgb({r},{g},{b})\"></span>\
+                 <span class=\"bar-value\">{count}</span></div>",
+                escape_html(&self.class_names.name_or(class_id, &self.classes)),
+            );
+        }
+        let _ = writeln!(html, "</div>");
+    }
+}
+
+/// Renders a single image section with its thumbnail and detection table
+fn render_entry(
+    html: &mut String,
+    entry: &ReportEntry,
+    base_dir: &Path,
+    class_names: &ClassNames,
+    classes: &ClassRegistry,
+) {
+    let source = escape_html(&entry.source_path.display().to_string());
+    let _ = writeln!(html, "<section class=\"entry\">\n<h3>{source}</h3>");
+
+    if let Some(error) = &entry.error {
+        let _ = writeln!(
+            html,
+            "<p class=\"error\">{}</p>\n</section>",
+            escape_html(error)
+        );
+        return;
+    }
+
+    if let Some(annotated_path) = &entry.annotated_path {
+        let link = annotated_path
+            .strip_prefix(base_dir)
+            .unwrap_or(annotated_path)
+            .display()
+            .to_string()
+            .replace('\\', "/");
+        let link = escape_html(&link);
+        let _ = writeln!(
+            html,
+            "<a href=\"{link}\"><img class=\"thumb\" src=\"{link}\" loading=\"lazy\" alt=\"{source}\"></a>"
+        );
+    }
+
+    let (width, height) = entry.image_dimensions;
+    let _ = writeln!(
+        html,
+        "<p>{width}x{height} &middot; {} detections</p>",
+        entry.boxes.len()
+    );
+
+    if !entry.warnings.is_empty() {
+        let _ = writeln!(html, "<ul class=\"warnings\">");
+        for warning in &entry.warnings {
+            let _ = writeln!(html, "<li>{}</li>", escape_html(&warning.to_string()));
+        }
+        let _ = writeln!(html, "</ul>");
+    }
+
+    if entry.boxes.is_empty() {
+        let _ = writeln!(html, "</section>");
+        return;
+    }
+
+    let _ = writeln!(
+        html,
+        "<table class=\"detections\">\n<tr><th>#</th><th>Class</th><th>Confidence</th>\
+         <th>x1</th><th>y1</th><th>x2</th><th>y2</th></tr>"
+    );
+    for (i, bbox) in entry.boxes.iter().enumerate() {
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td>{}</td><td>{:.3}</td><td>{:.1}</td><td>{:.1}</td><td>{:.1}</td><td>{:.1}</td></tr>",
+            i + 1,
+            escape_html(&class_names.name_or(bbox.class_id, classes)),
+            bbox.confidence,
+            bbox.x1,
+            bbox.y1,
+            bbox.x2,
+            bbox.y2,
+        );
+    }
+    let _ = writeln!(html, "</table>\n</section>");
+}
+
+/// Escapes the characters that are significant in HTML text and attributes
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+const REPORT_STYLE: &str = "body{font-family:sans-serif;margin:2em;color:#222}\
+table{border-collapse:collapse;margin-bottom:1em}\
+th,td{border:1px solid #ccc;padding:4px 8px;text-align:left}\
+.chart{max-width:600px}\
+.bar-row{display:flex;align-items:center;margin:4px 0}\
+.bar-label{width:140px}\
+.bar{display:inline-block;height:16px;margin-right:8px}\
+.entry{border-top:1px solid #ddd;padding-top:1em}\
+.thumb{max-width:320px;display:block;margin-bottom:0.5em}\
+.error{color:#b00020}\
+.warnings{color:#8a5a00}";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn sample_report() -> HtmlReport {
+        let mut report = HtmlReport::new("Batch <1>");
+        report.add_entry(
+            ReportEntry::success(
+                "images/village.png",
+                Some(PathBuf::from("output/village.jpg")),
+                (640, 640),
+                vec![
+                    BoundingBox::new(10.0, 20.0, 50.0, 80.0, 1, 0.9),
+                    BoundingBox::new(30.0, 40.0, 70.0, 90.0, 0, 0.7),
+                    BoundingBox::new(60.0, 40.0, 90.0, 90.0, 1, 0.5),
+                ],
+            )
+            .with_warnings(vec![Warning::BoxesClamped { count: 1 }]),
+        );
+        report.add_entry(ReportEntry::failure("images/broken.png", "decode error"));
+        report.add_entry(ReportEntry::timeout(
+            "images/huge.png",
+            Duration::from_secs(2),
+        ));
+        report
+    }
+
+    #[test]
+    fn test_class_counts() {
+        let counts = sample_report().class_counts();
+        assert_eq!(counts.get(&0), Some(&1));
+        assert_eq!(counts.get(&1), Some(&2));
+    }
+
+    #[test]
+    fn test_mean_confidence() {
+        let mean = sample_report().mean_confidence().unwrap();
+        assert!((mean - 0.7).abs() < 0.0001);
+        assert!(HtmlReport::new("empty").mean_confidence().is_none());
+    }
+
+    #[test]
+    fn test_render_contains_entries() {
+        let html = sample_report().render(Path::new("output"));
+        assert!(html.contains("<title>Batch &lt;1&gt;</title>"));
+        assert!(html.contains("src=\"village.jpg\""));
+        assert!(html.contains("Gold Storage"));
+        assert!(html.contains("Elixir Storage"));
+        assert!(html.contains("decode error"));
+        assert!(html.contains("<tr><th>Detections</th><td>3</td></tr>"));
+        assert!(html.contains("<tr><th>Timed out</th><td>1</td></tr>"));
+        assert!(html.contains("timed out after 2s"));
+        assert!(html.contains("<tr><th>Warnings</th><td>1</td></tr>"));
+        assert!(html.contains("<li>1 box(es) clamped to the image borders</li>"));
+    }
+
+    #[test]
+    fn test_render_localized() {
+        let report = sample_report().with_class_names(ClassNames::builtin("fr").unwrap());
+        let html = report.render(Path::new("output"));
+        assert!(html.contains("<html lang=\"fr\">"));
+        assert!(html.contains("Réserve d&#39;or"));
+        assert!(!html.contains("Gold Storage"));
+    }
+
+    #[test]
+    fn test_render_uses_registry() {
+        let classes = ClassRegistry::from_json(r#"["Town Hall", "Cannon"]"#)
+            .unwrap()
+            .with_color(1, (1, 2, 3, 255));
+        let html = sample_report()
+            .with_classes(classes)
+            .render(Path::new("output"));
+        assert!(html.contains("Cannon"));
+        assert!(html.contains("Town Hall"));
+        assert!(!html.contains("Gold Storage"));
+        assert!(html.contains("background:rgb(1,2,3)"));
+    }
+
+    #[test]
+    fn test_write_report() -> io::Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("report.html");
+        sample_report().write(&path)?;
+        let content = fs::read_to_string(path)?;
+        assert!(content.starts_with("<!DOCTYPE html>"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(escape_html("a<b>&\"c'"), "a&lt;b&gt;&amp;&quot;c&#39;");
+    }
+}
@@ -0,0 +1,408 @@
+//! Per-image manifest of a run and diffs between the manifests of two runs
+
+use crate::class::registry::ClassRegistry;
+use crate::detection::BoundingBox;
+#[cfg(feature = "encryption")]
+use crate::report::encryption::OutputKey;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+/// Relative slowdown above which an image is reported as a timing regression
+pub const DEFAULT_REGRESSION_RATIO: f64 = 0.2;
+
+/// Outcome of a single image in a run
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ManifestEntry {
+    pub detections: usize,
+    pub detections_per_class: BTreeMap<String, usize>,
+    pub duration_ms: f64,
+    pub error: Option<String>,
+    /// Whether the image failed by exceeding its per-image time budget
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub timed_out: bool,
+    /// Content fingerprint of the image file, checked before skipping it on resume
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
+}
+
+/// Errors that can occur while resuming a run from its manifest
+#[derive(Debug, thiserror::Error)]
+pub enum ResumeError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Run fingerprint mismatch: the checkpoint was written by run {expected}, not {actual}")]
+    FingerprintMismatch { expected: String, actual: String },
+}
+
+/// Outcome of every image of a run, keyed by image path
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunManifest {
+    /// Fingerprint of the model and settings of the run, checked before resuming it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_fingerprint: Option<String>,
+    pub images: BTreeMap<String, ManifestEntry>,
+}
+
+impl RunManifest {
+    /// Records the detections of a successfully processed image, counted under the names of `classes`
+    pub fn record_success(
+        &mut self,
+        image: &str,
+        boxes: &[BoundingBox],
+        duration: Duration,
+        classes: &ClassRegistry,
+    ) {
+        let mut detections_per_class = BTreeMap::new();
+        for bbox in boxes {
+            *detections_per_class
+                .entry(classes.name(bbox.class_id))
+                .or_insert(0) += 1;
+        }
+        self.images.insert(
+            image.to_string(),
+            ManifestEntry {
+                detections: boxes.len(),
+                detections_per_class,
+                duration_ms: duration.as_secs_f64() * 1000.0,
+                error: None,
+                timed_out: false,
+                fingerprint: None,
+            },
+        );
+    }
+
+    /// Records the content fingerprint of an image already in the manifest
+    pub fn record_fingerprint(&mut self, image: &str, fingerprint: impl Into<String>) {
+        if let Some(entry) = self.images.get_mut(image) {
+            entry.fingerprint = Some(fingerprint.into());
+        }
+    }
+
+    /// Returns true if the image was processed successfully and its content is unchanged since
+    #[must_use]
+    pub fn is_complete(&self, image: &str, fingerprint: &str) -> bool {
+        self.images.get(image).is_some_and(|entry| {
+            entry.error.is_none() && entry.fingerprint.as_deref() == Some(fingerprint)
+        })
+    }
+
+    /// Records an image that failed to process
+    pub fn record_failure(&mut self, image: &str, error: impl Into<String>, duration: Duration) {
+        self.images.insert(
+            image.to_string(),
+            ManifestEntry {
+                duration_ms: duration.as_secs_f64() * 1000.0,
+                error: Some(error.into()),
+                ..ManifestEntry::default()
+            },
+        );
+    }
+
+    /// Records an image that failed by exceeding its per-image time budget
+    pub fn record_timeout(&mut self, image: &str, error: impl Into<String>, duration: Duration) {
+        self.record_failure(image, error, duration);
+        if let Some(entry) = self.images.get_mut(image) {
+            entry.timed_out = true;
+        }
+    }
+
+    /// Reads a manifest written by `write_json`
+    pub fn load(path: &Path) -> io::Result<Self> {
+        serde_json::from_str(&fs::read_to_string(path)?).map_err(io::Error::other)
+    }
+
+    /// Loads the checkpoint of an interrupted run, refusing it if it was written by a run with
+    /// another model or settings, whose results cannot be mixed with the ones of this run
+    pub fn resume(path: &Path, run_fingerprint: &str) -> Result<Self, ResumeError> {
+        Self::load(path)?.check_fingerprint(run_fingerprint)
+    }
+
+    /// Reads a manifest written by `write_encrypted_checkpoint`
+    #[cfg(feature = "encryption")]
+    pub fn load_encrypted(path: &Path, key: &OutputKey) -> io::Result<Self> {
+        let json = key.read_opened(path).map_err(io::Error::other)?;
+        serde_json::from_slice(&json).map_err(io::Error::other)
+    }
+
+    /// Loads the encrypted checkpoint of an interrupted run, like `resume`
+    #[cfg(feature = "encryption")]
+    pub fn resume_encrypted(
+        path: &Path,
+        run_fingerprint: &str,
+        key: &OutputKey,
+    ) -> Result<Self, ResumeError> {
+        Self::load_encrypted(path, key)?.check_fingerprint(run_fingerprint)
+    }
+
+    /// Refuses the manifest if it was written by a run with another fingerprint
+    fn check_fingerprint(self, run_fingerprint: &str) -> Result<Self, ResumeError> {
+        match &self.run_fingerprint {
+            Some(expected) if expected != run_fingerprint => {
+                Err(ResumeError::FingerprintMismatch {
+                    expected: expected.clone(),
+                    actual: run_fingerprint.to_string(),
+                })
+            }
+            _ => Ok(self),
+        }
+    }
+
+    /// Writes the manifest as pretty-printed JSON to `output_path`
+    pub fn write_json(&self, output_path: &Path) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(output_path, json)
+    }
+
+    /// Writes the manifest like `write_json`, through a temporary file renamed over
+    /// `output_path`, so that a crash while writing never leaves a truncated checkpoint
+    pub fn write_checkpoint(&self, output_path: &Path) -> io::Result<()> {
+        let mut partial = output_path.as_os_str().to_owned();
+        partial.push(".part");
+        self.write_json(Path::new(&partial))?;
+        fs::rename(&partial, output_path)
+    }
+
+    /// Writes the manifest like `write_checkpoint`, encrypted with `key`
+    #[cfg(feature = "encryption")]
+    pub fn write_encrypted_checkpoint(
+        &self,
+        output_path: &Path,
+        key: &OutputKey,
+    ) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        key.write_sealed(output_path, json.as_bytes())
+    }
+
+    /// Compares this (baseline) manifest with the manifest of a newer run.
+    ///
+    /// Images slower by more than `regression_ratio` (e.g. 0.2 for 20%) are timing regressions.
+    #[must_use]
+    pub fn diff(&self, newer: &Self, regression_ratio: f64) -> ManifestDiff {
+        let mut diff = ManifestDiff::default();
+
+        for (image, before) in &self.images {
+            let Some(after) = newer.images.get(image) else {
+                diff.removed.push(image.clone());
+                continue;
+            };
+
+            if before.error.is_none() && after.error.is_some() {
+                diff.newly_failed.push(image.clone());
+            } else if before.detections != after.detections {
+                diff.count_changes.push(CountChange {
+                    image: image.clone(),
+                    before: before.detections,
+                    after: after.detections,
+                });
+            }
+
+            if after.duration_ms > before.duration_ms * (1.0 + regression_ratio) {
+                diff.timing_regressions.push(TimingRegression {
+                    image: image.clone(),
+                    before_ms: before.duration_ms,
+                    after_ms: after.duration_ms,
+                });
+            }
+        }
+
+        diff.added = newer
+            .images
+            .keys()
+            .filter(|image| !self.images.contains_key(*image))
+            .cloned()
+            .collect();
+        diff
+    }
+}
+
+/// Detection count change of an image between two runs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CountChange {
+    pub image: String,
+    pub before: usize,
+    pub after: usize,
+}
+
+/// Processing time increase of an image between two runs
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TimingRegression {
+    pub image: String,
+    pub before_ms: f64,
+    pub after_ms: f64,
+}
+
+/// Differences between a baseline run and a newer run
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ManifestDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub newly_failed: Vec<String>,
+    pub count_changes: Vec<CountChange>,
+    pub timing_regressions: Vec<TimingRegression>,
+}
+
+impl ManifestDiff {
+    /// Returns true if both runs covered the same images with the same outcomes and no regression
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.newly_failed.is_empty()
+            && self.count_changes.is_empty()
+            && self.timing_regressions.is_empty()
+    }
+
+    /// Renders the concise human-readable diff
+    #[must_use]
+    pub fn render_text(&self) -> String {
+        let mut text = String::new();
+        let _ = writeln!(
+            text,
+            "{} added, {} removed, {} newly failed, {} count change(s), {} timing regression(s)",
+            self.added.len(),
+            self.removed.len(),
+            self.newly_failed.len(),
+            self.count_changes.len(),
+            self.timing_regressions.len()
+        );
+        for image in &self.added {
+            let _ = writeln!(text, "  + {image}");
+        }
+        for image in &self.removed {
+            let _ = writeln!(text, "  - {image}");
+        }
+        for image in &self.newly_failed {
+            let _ = writeln!(text, "  ! {image}: failed");
+        }
+        for change in &self.count_changes {
+            let delta = change.after as i64 - change.before as i64;
+            let _ = writeln!(
+                text,
+                "  ~ {}: {} -> {} detection(s) ({delta:+})",
+                change.image, change.before, change.after
+            );
+        }
+        for regression in &self.timing_regressions {
+            let _ = writeln!(
+                text,
+                "  ~ {}: {:.0}ms -> {:.0}ms",
+                regression.image, regression.before_ms, regression.after_ms
+            );
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn boxes(count: usize) -> Vec<BoundingBox> {
+        vec![BoundingBox::new(0.0, 0.0, 1.0, 1.0, 1, 0.9); count]
+    }
+
+    fn classes() -> ClassRegistry {
+        ClassRegistry::default()
+    }
+
+    fn baseline() -> RunManifest {
+        let mut manifest = RunManifest::default();
+        manifest.record_success("a.png", &boxes(2), Duration::from_millis(100), &classes());
+        manifest.record_success("b.png", &boxes(1), Duration::from_millis(100), &classes());
+        manifest.record_success("c.png", &boxes(0), Duration::from_millis(100), &classes());
+        manifest
+    }
+
+    #[test]
+    fn test_identical_runs_are_clean() {
+        assert!(
+            baseline()
+                .diff(&baseline(), DEFAULT_REGRESSION_RATIO)
+                .is_clean()
+        );
+    }
+
+    #[test]
+    fn test_diff() {
+        let mut newer = RunManifest::default();
+        newer.record_success("a.png", &boxes(3), Duration::from_millis(110), &classes());
+        newer.record_failure("b.png", "decode error", Duration::from_millis(5));
+        newer.record_success("d.png", &boxes(1), Duration::from_millis(100), &classes());
+
+        let mut slower = baseline();
+        slower.record_success("c.png", &boxes(0), Duration::from_millis(150), &classes());
+
+        let diff = baseline().diff(&newer, DEFAULT_REGRESSION_RATIO);
+        assert_eq!(diff.added, ["d.png"]);
+        assert_eq!(diff.removed, ["c.png"]);
+        assert_eq!(diff.newly_failed, ["b.png"]);
+        assert_eq!(
+            diff.count_changes,
+            [CountChange {
+                image: "a.png".to_string(),
+                before: 2,
+                after: 3
+            }]
+        );
+        assert!(diff.timing_regressions.is_empty());
+        assert!(
+            diff.render_text()
+                .contains("a.png: 2 -> 3 detection(s) (+1)")
+        );
+
+        let diff = baseline().diff(&slower, DEFAULT_REGRESSION_RATIO);
+        assert_eq!(diff.timing_regressions.len(), 1);
+        assert_eq!(diff.timing_regressions[0].image, "c.png");
+    }
+
+    #[test]
+    fn test_resume_from_checkpoint() -> Result<(), ResumeError> {
+        let dir = tempdir()?;
+        let path = dir.path().join("manifest.json");
+        let mut manifest = baseline();
+        manifest.run_fingerprint = Some("run-a".to_string());
+        manifest.record_fingerprint("a.png", "0011");
+        manifest.record_failure("b.png", "decode error", Duration::from_millis(5));
+        manifest.record_fingerprint("b.png", "0022");
+        manifest.write_checkpoint(&path)?;
+        assert!(!dir.path().join("manifest.json.part").exists());
+
+        let resumed = RunManifest::resume(&path, "run-a")?;
+        assert!(resumed.is_complete("a.png", "0011"));
+        assert!(!resumed.is_complete("a.png", "ffff"));
+        assert!(!resumed.is_complete("b.png", "0022"));
+        assert!(!resumed.is_complete("c.png", ""));
+        assert!(!resumed.is_complete("d.png", "0011"));
+
+        assert!(matches!(
+            RunManifest::resume(&path, "run-b"),
+            Err(ResumeError::FingerprintMismatch { expected, .. }) if expected == "run-a"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_manifest_round_trip() -> io::Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("manifest.json");
+        let mut manifest = baseline();
+        manifest.record_timeout("slow.png", "timed out", Duration::from_secs(5));
+        manifest.write_json(&path)?;
+        let loaded = RunManifest::load(&path)?;
+        assert_eq!(loaded, manifest);
+        assert!(loaded.images["slow.png"].timed_out);
+        assert!(!loaded.images["a.png"].timed_out);
+        assert_eq!(
+            loaded.images["a.png"].detections_per_class["Gold Storage"],
+            2
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,105 @@
+//! Fine-grained timing of the stages of the detection pipeline for one image, with optional
+//! `tracing` spans around each stage
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Time spent in each step of the pipeline for the last image processed by a session.
+///
+/// Steps skipped for an image, e.g. loading an in-memory image or saving when only detecting,
+/// stay at zero. Tiled images add up the time of every tile.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PipelineMetrics {
+    /// Reading and decoding the image file
+    pub load: Duration,
+    /// Letterboxing to the model input size
+    pub resize: Duration,
+    /// Conversion to the normalized input tensor
+    pub normalize: Duration,
+    /// Model run and output parsing
+    pub inference: Duration,
+    /// NMS, detection limits, level estimation and middleware
+    pub nms: Duration,
+    /// Drawing the boxes and watermark on the image
+    pub draw: Duration,
+    /// Writing the output files, results store and sinks
+    pub save: Duration,
+}
+
+impl PipelineMetrics {
+    /// Returns the time spent in all the steps together
+    #[must_use]
+    pub fn total(&self) -> Duration {
+        self.steps().iter().map(|(_, duration)| *duration).sum()
+    }
+
+    /// Returns the name and duration of each step, in pipeline order
+    #[must_use]
+    pub const fn steps(&self) -> [(&'static str, Duration); 7] {
+        [
+            ("load", self.load),
+            ("resize", self.resize),
+            ("normalize", self.normalize),
+            ("inference", self.inference),
+            ("nms", self.nms),
+            ("draw", self.draw),
+            ("save", self.save),
+        ]
+    }
+}
+
+impl fmt::Display for PipelineMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, (step, duration)) in self.steps().iter().enumerate() {
+            if index > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{step} {:.1}ms", duration.as_secs_f64() * 1000.0)?;
+        }
+        Ok(())
+    }
+}
+
+/// Measures one step of the pipeline, inside a `pipeline_step` span with the `tracing` feature
+pub(crate) struct StepTimer {
+    start: Instant,
+    #[cfg(feature = "tracing")]
+    _span: tracing::span::EnteredSpan,
+}
+
+impl StepTimer {
+    /// Starts timing `step` of the named image
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    pub(crate) fn start(step: &'static str, image_name: &str) -> Self {
+        Self {
+            start: Instant::now(),
+            #[cfg(feature = "tracing")]
+            _span: tracing::info_span!("pipeline_step", step, image = image_name).entered(),
+        }
+    }
+
+    /// Stops timing, closing the span, and returns the duration of the step
+    pub(crate) fn stop(self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics_total_and_display() {
+        let metrics = PipelineMetrics {
+            load: Duration::from_millis(4),
+            inference: Duration::from_millis(20),
+            save: Duration::from_micros(1500),
+            ..PipelineMetrics::default()
+        };
+        assert_eq!(metrics.total(), Duration::from_micros(25_500));
+        assert_eq!(
+            metrics.to_string(),
+            "load 4.0ms, resize 0.0ms, normalize 0.0ms, inference 20.0ms, nms 0.0ms, draw 0.0ms, save 1.5ms"
+        );
+    }
+}
@@ -0,0 +1,10 @@
+pub mod detection;
+#[cfg(feature = "encryption")]
+pub mod encryption;
+pub mod gallery;
+pub mod html;
+pub mod manifest;
+pub mod metrics;
+pub mod parity;
+pub mod summary;
+pub mod usage;
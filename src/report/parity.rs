@@ -0,0 +1,266 @@
+//! Numerical parity of the pipeline with reference outputs exported from Ultralytics
+
+use crate::detection::BoundingBox;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Minimum IoU for a reference box to be paired with a box of the same class
+const MATCH_IOU: f32 = 0.5;
+
+/// Corners of a reference box, in original image pixels
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct ReferenceCorners {
+    pub x1: f32,
+    pub y1: f32,
+    pub x2: f32,
+    pub y2: f32,
+}
+
+/// Box of an Ultralytics `Results.to_json()` / `Results.summary()` export
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ReferenceBox {
+    pub class: usize,
+    pub confidence: f32,
+    #[serde(rename = "box")]
+    pub corners: ReferenceCorners,
+}
+
+impl ReferenceBox {
+    /// Converts the reference to a bounding box
+    pub fn to_bbox(&self) -> BoundingBox {
+        let ReferenceCorners { x1, y1, x2, y2 } = self.corners;
+        BoundingBox::new(x1, y1, x2, y2, self.class, self.confidence)
+    }
+}
+
+/// Reference boxes by image, read from a JSON object mapping image names to their boxes
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(transparent)]
+pub struct ParityReference {
+    pub images: BTreeMap<String, Vec<ReferenceBox>>,
+}
+
+impl ParityReference {
+    /// Reads a reference file
+    pub fn load(path: &Path) -> io::Result<Self> {
+        serde_json::from_str(&fs::read_to_string(path)?).map_err(io::Error::other)
+    }
+
+    /// Returns the reference boxes of an image, looked up by path and then by file name
+    #[must_use]
+    pub fn boxes(&self, image_path: &str) -> Option<&[ReferenceBox]> {
+        self.images
+            .get(image_path)
+            .or_else(|| {
+                let file_name = Path::new(image_path).file_name()?.to_str()?;
+                self.images.get(file_name)
+            })
+            .map(Vec::as_slice)
+    }
+}
+
+/// Largest deviations allowed for the outputs to be considered at parity
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParityTolerance {
+    pub coordinate: f32, // Pixels, on any corner coordinate
+    pub confidence: f32, // Absolute confidence difference
+}
+
+impl Default for ParityTolerance {
+    fn default() -> Self {
+        Self {
+            coordinate: 1.0,
+            confidence: 0.01,
+        }
+    }
+}
+
+/// Deviation of the boxes of one image from the reference
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ImageParity {
+    pub image: String,
+    pub matched: usize,
+    /// Reference boxes without a counterpart
+    pub missing: usize,
+    /// Boxes without a reference counterpart
+    pub extra: usize,
+    pub max_coordinate_deviation: f32,
+    pub max_confidence_deviation: f32,
+}
+
+impl ImageParity {
+    /// Pairs each reference box with the unpaired box of the same class it overlaps most
+    #[must_use]
+    pub fn compare(image: &str, boxes: &[BoundingBox], reference: &[ReferenceBox]) -> Self {
+        let mut parity = Self {
+            image: image.to_string(),
+            ..Self::default()
+        };
+        let mut paired = vec![false; boxes.len()];
+        for expected in reference.iter().map(ReferenceBox::to_bbox) {
+            let best = boxes
+                .iter()
+                .enumerate()
+                .filter(|&(i, bbox)| !paired[i] && bbox.class_id == expected.class_id)
+                .map(|(i, bbox)| (i, bbox.iou(&expected)))
+                .filter(|&(_, iou)| iou >= MATCH_IOU)
+                .max_by(|a, b| a.1.total_cmp(&b.1));
+            let Some((i, _)) = best else {
+                parity.missing += 1;
+                continue;
+            };
+            paired[i] = true;
+            parity.matched += 1;
+
+            let actual = &boxes[i];
+            let coordinate = [
+                actual.x1 - expected.x1,
+                actual.y1 - expected.y1,
+                actual.x2 - expected.x2,
+                actual.y2 - expected.y2,
+            ]
+            .iter()
+            .fold(0.0f32, |max, delta| max.max(delta.abs()));
+            parity.max_coordinate_deviation = parity.max_coordinate_deviation.max(coordinate);
+            parity.max_confidence_deviation = parity
+                .max_confidence_deviation
+                .max((actual.confidence - expected.confidence).abs());
+        }
+        parity.extra = paired.iter().filter(|&&p| !p).count();
+        parity
+    }
+
+    /// Returns true if every box has a counterpart within the tolerance
+    #[must_use]
+    pub fn is_within(&self, tolerance: &ParityTolerance) -> bool {
+        self.missing == 0
+            && self.extra == 0
+            && self.max_coordinate_deviation <= tolerance.coordinate
+            && self.max_confidence_deviation <= tolerance.confidence
+    }
+}
+
+/// Parity of every compared image of a run
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ParityReport {
+    pub images: Vec<ImageParity>,
+    /// Processed images absent from the reference
+    pub unreferenced: Vec<String>,
+}
+
+impl ParityReport {
+    /// Compares the boxes of an image with its reference, if there is one
+    pub fn record(&mut self, image: &str, boxes: &[BoundingBox], reference: &ParityReference) {
+        match reference.boxes(image) {
+            Some(expected) => self
+                .images
+                .push(ImageParity::compare(image, boxes, expected)),
+            None => self.unreferenced.push(image.to_string()),
+        }
+    }
+
+    /// Returns true if every compared image is within the tolerance
+    #[must_use]
+    pub fn is_within(&self, tolerance: &ParityTolerance) -> bool {
+        self.images.iter().all(|image| image.is_within(tolerance))
+    }
+
+    /// Renders a line per compared image, flagging the ones outside the tolerance
+    #[must_use]
+    pub fn render_text(&self, tolerance: &ParityTolerance) -> String {
+        let mut text = String::new();
+        for image in &self.images {
+            let _ = writeln!(
+                text,
+                "{} {}: {} matched, {} missing, {} extra, max deviation {:.3}px / {:.4} confidence",
+                if image.is_within(tolerance) {
+                    "ok  "
+                } else {
+                    "FAIL"
+                },
+                image.image,
+                image.matched,
+                image.missing,
+                image.extra,
+                image.max_coordinate_deviation,
+                image.max_confidence_deviation
+            );
+        }
+        for image in &self.unreferenced {
+            let _ = writeln!(text, "skip {image}: no reference output");
+        }
+        let failed = self
+            .images
+            .iter()
+            .filter(|image| !image.is_within(tolerance))
+            .count();
+        let _ = writeln!(
+            text,
+            "Parity: {} of {} image(s) within {}px / {} confidence",
+            self.images.len() - failed,
+            self.images.len(),
+            tolerance.coordinate,
+            tolerance.confidence
+        );
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reference() -> ParityReference {
+        serde_json::from_str(
+            r#"{
+                "village.png": [
+                    {"name": "Gold Storage", "class": 1, "confidence": 0.91,
+                     "box": {"x1": 10.0, "y1": 20.0, "x2": 50.0, "y2": 80.0}},
+                    {"name": "Elixir Storage", "class": 0, "confidence": 0.55,
+                     "box": {"x1": 100.0, "y1": 100.0, "x2": 140.0, "y2": 150.0}}
+                ]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_compare_with_reference() {
+        let reference = reference();
+        let tolerance = ParityTolerance::default();
+        let mut report = ParityReport::default();
+
+        let boxes = [
+            BoundingBox::new(100.2, 99.9, 140.0, 150.4, 0, 0.552),
+            BoundingBox::new(10.5, 20.0, 50.0, 80.0, 1, 0.905),
+        ];
+        report.record("shots/village.png", &boxes, &reference);
+        let parity = &report.images[0];
+        assert_eq!((parity.matched, parity.missing, parity.extra), (2, 0, 0));
+        assert!((parity.max_coordinate_deviation - 0.5).abs() < 1e-4);
+        assert!((parity.max_confidence_deviation - 0.005).abs() < 1e-4);
+        assert!(report.is_within(&tolerance));
+
+        // A box of another class does not stand in for the reference one
+        report.record(
+            "village.png",
+            &[
+                boxes[1],
+                BoundingBox::new(100.0, 100.0, 140.0, 150.0, 2, 0.55),
+            ],
+            &reference,
+        );
+        assert_eq!((report.images[1].missing, report.images[1].extra), (1, 1));
+        assert!(!report.is_within(&tolerance));
+
+        report.record("other.png", &[], &reference);
+        assert_eq!(report.unreferenced, ["other.png"]);
+        let text = report.render_text(&tolerance);
+        assert!(text.contains("FAIL village.png: 1 matched, 1 missing, 1 extra"));
+        assert!(text.contains("Parity: 1 of 2 image(s)"));
+    }
+}
@@ -0,0 +1,233 @@
+//! Aggregate summary of a run, printed by the CLI or exported as JSON
+
+use crate::class::group::ClassGroups;
+use crate::class::registry::ClassRegistry;
+use crate::detection::BoundingBox;
+#[cfg(feature = "encryption")]
+use crate::report::encryption::{OutputKey, encrypted_path};
+use crate::session::warning::Warning;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+/// Counts and timing of a processing run
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RunSummary {
+    pub images_processed: usize,
+    pub images_failed: usize,
+    /// Failed images that ran out of their per-image time budget
+    pub images_timed_out: usize,
+    pub total_detections: usize,
+    pub detections_per_class: BTreeMap<String, usize>,
+    pub detections_per_group: BTreeMap<String, usize>,
+    pub total_time_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency: Option<LatencyStats>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub warnings: BTreeMap<String, usize>,
+}
+
+/// End-to-end latency, from frame capture to detection emission, of captured frames
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct LatencyStats {
+    pub frames: usize,
+    pub mean_ms: f64,
+    pub max_ms: f64,
+}
+
+impl LatencyStats {
+    /// Adds the latency of a frame
+    pub fn record(&mut self, latency: Duration) {
+        let latency_ms = latency.as_secs_f64() * 1000.0;
+        self.frames += 1;
+        self.mean_ms += (latency_ms - self.mean_ms) / self.frames as f64;
+        self.max_ms = self.max_ms.max(latency_ms);
+    }
+}
+
+impl RunSummary {
+    /// Records the detections of a successfully processed image, counted under the names of `classes`
+    pub fn record_success(&mut self, boxes: &[BoundingBox], classes: &ClassRegistry) {
+        self.images_processed += 1;
+        self.total_detections += boxes.len();
+        for bbox in boxes {
+            *self
+                .detections_per_class
+                .entry(classes.name(bbox.class_id))
+                .or_insert(0) += 1;
+        }
+        for (group, count) in ClassGroups::default().counts(boxes) {
+            *self
+                .detections_per_group
+                .entry(group.as_str().to_string())
+                .or_insert(0) += count;
+        }
+    }
+
+    /// Records an image that failed to process
+    pub fn record_failure(&mut self) {
+        self.images_failed += 1;
+    }
+
+    /// Records an image that failed by exceeding its per-image time budget
+    pub fn record_timeout(&mut self) {
+        self.images_failed += 1;
+        self.images_timed_out += 1;
+    }
+
+    /// Records a warning raised while processing an image, counted by kind
+    pub fn record_warning(&mut self, warning: &Warning) {
+        *self
+            .warnings
+            .entry(warning.as_str().to_string())
+            .or_insert(0) += 1;
+    }
+
+    /// Records the end-to-end latency of a captured frame, see `ImageRecord::latency`
+    pub fn record_latency(&mut self, latency: Duration) {
+        self.latency.get_or_insert_default().record(latency);
+    }
+
+    /// Sets the wall-clock duration of the whole run
+    pub fn set_total_time(&mut self, elapsed: Duration) {
+        self.total_time_ms = elapsed.as_secs_f64() * 1000.0;
+    }
+
+    /// Renders the concise human-readable summary
+    #[must_use]
+    pub fn render_text(&self) -> String {
+        let mut text = String::new();
+        let _ = write!(text, "Processed {} image(s)", self.images_processed);
+        if self.images_failed > 0 {
+            let _ = write!(text, ", {} failed", self.images_failed);
+        }
+        if self.images_timed_out > 0 {
+            let _ = write!(text, " ({} timed out)", self.images_timed_out);
+        }
+        let _ = writeln!(text, " in {:.2}s", self.total_time_ms / 1000.0);
+
+        let _ = writeln!(text, "Detections: {}", self.total_detections);
+        for (class, count) in &self.detections_per_class {
+            let _ = writeln!(text, "  {class}: {count}");
+        }
+        if !self.detections_per_group.is_empty() {
+            let groups: Vec<String> = self
+                .detections_per_group
+                .iter()
+                .map(|(group, count)| format!("{group} {count}"))
+                .collect();
+            let _ = writeln!(text, "Groups: {}", groups.join(", "));
+        }
+        if !self.warnings.is_empty() {
+            let warnings: Vec<String> = self
+                .warnings
+                .iter()
+                .map(|(kind, count)| format!("{kind} {count}"))
+                .collect();
+            let _ = writeln!(text, "Warnings: {}", warnings.join(", "));
+        }
+        if let Some(latency) = &self.latency {
+            let _ = writeln!(
+                text,
+                "Latency: {:.0}ms mean, {:.0}ms max over {} frame(s)",
+                latency.mean_ms, latency.max_ms, latency.frames
+            );
+        }
+        text
+    }
+
+    /// Writes the summary as pretty-printed JSON to `output_path`
+    pub fn write_json(&self, output_path: &Path) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(output_path, json)
+    }
+
+    /// Writes the summary like `write_json`, encrypted with `key` to `output_path` with `.enc`
+    /// appended
+    #[cfg(feature = "encryption")]
+    pub fn write_encrypted_json(&self, output_path: &Path, key: &OutputKey) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        key.write_sealed(&encrypted_path(output_path), json.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn sample_summary() -> RunSummary {
+        let mut summary = RunSummary::default();
+        summary.record_success(
+            &[
+                BoundingBox::new(0.0, 0.0, 1.0, 1.0, 0, 0.9),
+                BoundingBox::new(0.0, 0.0, 1.0, 1.0, 1, 0.8),
+                BoundingBox::new(0.0, 0.0, 1.0, 1.0, 1, 0.7),
+            ],
+            &ClassRegistry::default(),
+        );
+        summary.record_failure();
+        summary.set_total_time(Duration::from_millis(1500));
+        summary
+    }
+
+    #[test]
+    fn test_render_text() {
+        let text = sample_summary().render_text();
+        assert!(text.starts_with("Processed 1 image(s), 1 failed in 1.50s"));
+        assert!(text.contains("Detections: 3"));
+        assert!(text.contains("  Gold Storage: 2"));
+        assert!(text.contains("  Elixir Storage: 1"));
+        assert!(text.contains("Groups: buildings 3, resources 3"));
+        assert!(!text.contains("Warnings"));
+        assert!(!text.contains("timed out"));
+
+        let mut summary = sample_summary();
+        summary.record_timeout();
+        assert!(
+            summary
+                .render_text()
+                .starts_with("Processed 1 image(s), 2 failed (1 timed out) in")
+        );
+
+        let mut summary = sample_summary();
+        summary.record_warning(&Warning::BoxesClamped { count: 2 });
+        summary.record_warning(&Warning::BoxesClamped { count: 1 });
+        assert!(summary.render_text().contains("Warnings: boxes_clamped 2"));
+    }
+
+    #[test]
+    fn test_latency_stats() {
+        let mut summary = sample_summary();
+        assert!(!summary.render_text().contains("Latency"));
+
+        summary.record_latency(Duration::from_millis(40));
+        summary.record_latency(Duration::from_millis(80));
+        let latency = summary.latency.unwrap();
+        assert_eq!(latency.frames, 2);
+        assert!((latency.mean_ms - 60.0).abs() < 1e-9);
+        assert!((latency.max_ms - 80.0).abs() < 1e-9);
+        assert!(
+            summary
+                .render_text()
+                .contains("Latency: 60ms mean, 80ms max over 2 frame(s)")
+        );
+    }
+
+    #[test]
+    fn test_write_json() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("summary.json");
+        sample_summary().write_json(&path).unwrap();
+
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(json["images_processed"], 1);
+        assert_eq!(json["detections_per_class"]["Gold Storage"], 2);
+        assert_eq!(json["detections_per_group"]["resources"], 3);
+    }
+}
@@ -0,0 +1,113 @@
+//! Opt-in usage statistics, accumulated across runs in a local JSON file.
+//!
+//! Nothing is collected unless a statistics file is requested, and nothing leaves the machine.
+
+use crate::report::summary::RunSummary;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Usage counters of every run recorded in a statistics file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UsageStats {
+    pub runs: u64,
+    pub images_processed: u64,
+    pub images_failed: u64,
+    pub total_time_ms: f64,
+    pub average_latency_ms: f64,
+    pub runs_per_model: BTreeMap<String, u64>,
+}
+
+impl UsageStats {
+    /// Reads the statistics file, starting from empty counters if it does not exist yet
+    pub fn load(path: &Path) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json).map_err(io::Error::other),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Adds a run of the model identified by `model_hash`
+    pub fn record_run(&mut self, summary: &RunSummary, model_hash: &str) {
+        self.runs += 1;
+        self.images_processed += summary.images_processed as u64;
+        self.images_failed += summary.images_failed as u64;
+        self.total_time_ms += summary.total_time_ms;
+        *self
+            .runs_per_model
+            .entry(model_hash.to_string())
+            .or_insert(0) += 1;
+
+        let images = self.images_processed + self.images_failed;
+        self.average_latency_ms = if images == 0 {
+            0.0
+        } else {
+            self.total_time_ms / images as f64
+        };
+    }
+
+    /// Writes the statistics as pretty-printed JSON to `path`
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(path, json)
+    }
+
+    /// Loads the statistics file, records a run and writes it back
+    pub fn update(path: &Path, summary: &RunSummary, model_hash: &str) -> io::Result<Self> {
+        let mut stats = Self::load(path)?;
+        stats.record_run(summary, model_hash);
+        stats.save(path)?;
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::class::registry::ClassRegistry;
+    use crate::detection::BoundingBox;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    fn summary(images: usize, elapsed_ms: u64) -> RunSummary {
+        let mut summary = RunSummary::default();
+        for _ in 0..images {
+            summary.record_success(
+                &[BoundingBox::new(0.0, 0.0, 1.0, 1.0, 0, 0.9)],
+                &ClassRegistry::default(),
+            );
+        }
+        summary.set_total_time(Duration::from_millis(elapsed_ms));
+        summary
+    }
+
+    #[test]
+    fn test_update_accumulates_runs() -> io::Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("usage.json");
+
+        UsageStats::update(&path, &summary(2, 200), "aaaa")?;
+        let stats = UsageStats::update(&path, &summary(3, 600), "bbbb")?;
+
+        assert_eq!(stats.runs, 2);
+        assert_eq!(stats.images_processed, 5);
+        assert!((stats.average_latency_ms - 160.0).abs() < 1e-9);
+        assert_eq!(stats.runs_per_model.get("aaaa"), Some(&1));
+        assert_eq!(UsageStats::load(&path)?, stats);
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_file_starts_empty() -> io::Result<()> {
+        let dir = tempdir()?;
+        assert_eq!(
+            UsageStats::load(&dir.path().join("usage.json"))?,
+            UsageStats::default()
+        );
+        Ok(())
+    }
+}
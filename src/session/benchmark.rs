@@ -0,0 +1,83 @@
+//! Latency statistics of repeated runs of the pipeline on one image, for performance tuning
+
+use crate::report::detection::StageTimings;
+use std::time::Duration;
+
+/// Mean and percentiles of the durations of a pipeline stage
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StageLatency {
+    pub mean: Duration,
+    pub p50: Duration,
+    pub p95: Duration,
+}
+
+impl StageLatency {
+    /// Computes the statistics of the samples, nearest-rank percentiles; zero when empty
+    #[must_use]
+    pub fn from_samples(samples: &[Duration]) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+        let percentile = |p: usize| sorted[(sorted.len() * p).div_ceil(100).max(1) - 1];
+        Self {
+            mean: sorted.iter().sum::<Duration>() / sorted.len() as u32,
+            p50: percentile(50),
+            p95: percentile(95),
+        }
+    }
+}
+
+/// Latency of each stage over the iterations of `YoloSession::benchmark`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LatencyStats {
+    pub iterations: usize,
+    pub preprocess: StageLatency,
+    pub inference: StageLatency,
+    pub postprocess: StageLatency,
+    pub total: StageLatency,
+}
+
+impl LatencyStats {
+    /// Computes the statistics of the stage timings of every iteration
+    #[must_use]
+    pub fn from_timings(timings: &[StageTimings]) -> Self {
+        let stage = |duration: fn(&StageTimings) -> Duration| {
+            let samples: Vec<Duration> = timings.iter().map(duration).collect();
+            StageLatency::from_samples(&samples)
+        };
+        Self {
+            iterations: timings.len(),
+            preprocess: stage(|t| t.preprocess),
+            inference: stage(|t| t.inference),
+            postprocess: stage(|t| t.postprocess),
+            total: stage(StageTimings::total),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stage_latency_percentiles() {
+        let timings: Vec<StageTimings> = (1..=20)
+            .map(|ms| StageTimings {
+                preprocess: Duration::from_millis(1),
+                inference: Duration::from_millis(ms),
+                postprocess: Duration::ZERO,
+            })
+            .collect();
+        let stats = LatencyStats::from_timings(&timings);
+        assert_eq!(stats.iterations, 20);
+        assert_eq!(stats.inference.p50, Duration::from_millis(10));
+        assert_eq!(stats.inference.p95, Duration::from_millis(19));
+        assert_eq!(stats.inference.mean, Duration::from_micros(10_500));
+        assert_eq!(stats.total.p95, Duration::from_millis(20));
+        assert_eq!(stats.preprocess.mean, Duration::from_millis(1));
+
+        assert_eq!(LatencyStats::from_timings(&[]), LatencyStats::default());
+    }
+}
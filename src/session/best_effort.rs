@@ -0,0 +1,134 @@
+//! Best-effort detection within a latency budget for interactive use, trading accuracy for
+//! speed through cumulative downgrades of the pipeline
+
+use crate::detection::BoundingBox;
+use std::time::Duration;
+
+/// Cheaper setting applied to the pipeline to meet a latency budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Downgrade {
+    /// Run large images whole instead of slicing them into tiles
+    SkipTiling,
+    /// Resize with a bilinear filter instead of the configured one
+    FastResize,
+    /// Use the smallest registered model input size
+    SmallestInput,
+}
+
+impl Downgrade {
+    /// Returns the name of the downgrade
+    #[inline]
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::SkipTiling => "skip-tiling",
+            Self::FastResize => "fast-resize",
+            Self::SmallestInput => "smallest-input",
+        }
+    }
+
+    /// Returns all downgrades, in the order they are applied
+    #[inline]
+    #[must_use]
+    pub const fn values() -> [Self; 3] {
+        [Self::SkipTiling, Self::FastResize, Self::SmallestInput]
+    }
+}
+
+/// Controller picking the downgrades of each best-effort detection from the latencies observed
+/// at each level, a level applying the first `level` downgrades of `Downgrade::values`.
+///
+/// The least degraded level not known to exceed the budget is used. A level finishing within
+/// half the budget forgets the latency of the level above, so that it is tried again once the
+/// machine is less loaded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BestEffort {
+    budget: Duration,
+    latencies: [Option<Duration>; 4],
+}
+
+impl BestEffort {
+    /// Creates a controller for a latency budget, starting without downgrades
+    #[must_use]
+    pub const fn new(budget: Duration) -> Self {
+        Self {
+            budget,
+            latencies: [None; 4],
+        }
+    }
+
+    /// Returns the latency budget
+    #[inline]
+    #[must_use]
+    pub const fn budget(&self) -> Duration {
+        self.budget
+    }
+
+    /// Returns the downgrades to apply to the next detection
+    #[must_use]
+    pub fn plan(&self) -> &'static [Downgrade] {
+        const DOWNGRADES: [Downgrade; 3] = Downgrade::values();
+        let level = self
+            .latencies
+            .iter()
+            .position(|latency| latency.is_none_or(|latency| latency <= self.budget))
+            .unwrap_or(DOWNGRADES.len());
+        &DOWNGRADES[..level]
+    }
+
+    /// Records the latency of a detection run with the `downgrades` of `plan`
+    pub fn record(&mut self, downgrades: &[Downgrade], elapsed: Duration) {
+        let level = downgrades.len().min(self.latencies.len() - 1);
+        self.latencies[level] = Some(elapsed);
+        if level > 0 && elapsed < self.budget / 2 {
+            self.latencies[level - 1] = None;
+        }
+    }
+}
+
+/// Detections of a best-effort run with the downgrades that were applied to it
+#[derive(Debug, Clone, PartialEq)]
+pub struct BestEffortResult {
+    pub boxes: Vec<BoundingBox>,
+    /// Downgrades that changed the pipeline, a subset of the planned ones
+    pub downgrades: Vec<Downgrade>,
+    pub elapsed: Duration,
+}
+
+impl BestEffortResult {
+    /// Returns true if the detection finished within `budget`
+    #[inline]
+    #[must_use]
+    pub fn within(&self, budget: Duration) -> bool {
+        self.elapsed <= budget
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_follows_observed_latencies() {
+        let ms = Duration::from_millis;
+        let mut best_effort = BestEffort::new(ms(100));
+        assert!(best_effort.plan().is_empty());
+
+        best_effort.record(&[], ms(250));
+        assert_eq!(best_effort.plan(), [Downgrade::SkipTiling]);
+        best_effort.record(&[Downgrade::SkipTiling], ms(120));
+        assert_eq!(best_effort.plan().len(), 2);
+        best_effort.record(best_effort.plan(), ms(90));
+        assert_eq!(best_effort.plan().len(), 2);
+
+        // Well within the budget: the less degraded level is tried again
+        best_effort.record(best_effort.plan(), ms(30));
+        assert_eq!(best_effort.plan(), [Downgrade::SkipTiling]);
+
+        let mut overloaded = BestEffort::new(ms(10));
+        for _ in 0..5 {
+            overloaded.record(overloaded.plan(), ms(50));
+        }
+        assert_eq!(overloaded.plan(), Downgrade::values());
+    }
+}
@@ -0,0 +1,164 @@
+//! Execution provider selection (CPU, CUDA, TensorRT, CoreML, DirectML, OpenVINO)
+
+use ort::ep::{self, ExecutionProvider as _, ExecutionProviderDispatch};
+use ort::session::builder::SessionBuilder;
+use std::fmt;
+use std::str::FromStr;
+
+/// Hardware backend running the model
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ExecutionProvider {
+    #[default]
+    Cpu,
+    Cuda,
+    TensorRt,
+    CoreMl,
+    DirectMl,
+    OpenVino,
+}
+
+impl ExecutionProvider {
+    /// Returns the string representation of the `ExecutionProvider` variant.
+    #[inline]
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Cpu => "cpu",
+            Self::Cuda => "cuda",
+            Self::TensorRt => "tensorrt",
+            Self::CoreMl => "coreml",
+            Self::DirectMl => "directml",
+            Self::OpenVino => "openvino",
+        }
+    }
+
+    /// Returns a static slice of all `ExecutionProvider` variants.
+    #[must_use]
+    pub const fn values() -> &'static [Self] {
+        &[
+            Self::Cpu,
+            Self::Cuda,
+            Self::TensorRt,
+            Self::CoreMl,
+            Self::DirectMl,
+            Self::OpenVino,
+        ]
+    }
+
+    /// Returns true if the loaded ONNX Runtime library was built with this provider
+    #[must_use]
+    pub fn is_available(&self) -> bool {
+        let available = match self {
+            Self::Cpu => return true,
+            Self::Cuda => ep::CUDA::default().is_available(),
+            Self::TensorRt => ep::TensorRT::default().is_available(),
+            Self::CoreMl => ep::CoreML::default().is_available(),
+            Self::DirectMl => ep::DirectML::default().is_available(),
+            Self::OpenVino => ep::OpenVINO::default().is_available(),
+        };
+        available.unwrap_or(false)
+    }
+
+    /// Builds the ONNX Runtime registration of the provider on device `device_id`
+    fn dispatch(self, device_id: i32) -> ExecutionProviderDispatch {
+        match self {
+            Self::Cpu => ep::CPU::default().build(),
+            Self::Cuda => ep::CUDA::default().with_device_id(device_id).build(),
+            Self::TensorRt => ep::TensorRT::default().with_device_id(device_id).build(),
+            Self::CoreMl => ep::CoreML::default().build(),
+            Self::DirectMl => ep::DirectML::default().with_device_id(device_id).build(),
+            Self::OpenVino => ep::OpenVINO::default().build(),
+        }
+    }
+}
+
+impl fmt::Display for ExecutionProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ExecutionProvider {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::values()
+            .iter()
+            .copied()
+            .find(|provider| provider.as_str().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| format!("Unknown execution provider: {s}"))
+    }
+}
+
+/// Execution providers of a session, tried in order before falling back to the CPU
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ExecutionConfig {
+    pub providers: Vec<ExecutionProvider>,
+    pub device_id: i32,
+    pub require_provider: bool,
+}
+
+impl ExecutionConfig {
+    /// Creates a configuration preferring `provider`, falling back to the CPU
+    #[must_use]
+    pub fn with_provider(provider: ExecutionProvider) -> Self {
+        Self {
+            providers: vec![provider],
+            ..Self::default()
+        }
+    }
+
+    /// Creates a session builder registering the configured providers.
+    ///
+    /// Providers that fail to register are skipped, unless `require_provider` is set, in which
+    /// case the first failure is returned.
+    pub fn session_builder(&self) -> ort::Result<SessionBuilder> {
+        let builder = SessionBuilder::new()?;
+        if self.providers.is_empty() {
+            return Ok(builder);
+        }
+
+        let dispatches: Vec<ExecutionProviderDispatch> = self
+            .providers
+            .iter()
+            .map(|provider| {
+                let dispatch = provider.dispatch(self.device_id);
+                if self.require_provider {
+                    dispatch.error_on_failure()
+                } else {
+                    dispatch
+                }
+            })
+            .collect();
+        builder.with_execution_providers(dispatches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provider_names_round_trip() {
+        for provider in ExecutionProvider::values() {
+            assert_eq!(
+                provider.as_str().parse::<ExecutionProvider>(),
+                Ok(*provider)
+            );
+        }
+        assert_eq!("CUDA".parse(), Ok(ExecutionProvider::Cuda));
+        assert!("tpu".parse::<ExecutionProvider>().is_err());
+    }
+
+    #[test]
+    fn test_default_config_is_cpu() {
+        let config = ExecutionConfig::default();
+        assert!(config.providers.is_empty());
+        assert!(!config.require_provider);
+        assert!(ExecutionProvider::Cpu.is_available());
+        assert_eq!(
+            ExecutionConfig::with_provider(ExecutionProvider::CoreMl).providers,
+            vec![ExecutionProvider::CoreMl]
+        );
+    }
+}
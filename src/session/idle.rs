@@ -0,0 +1,134 @@
+//! Idle unloading of a session for long-running processes, releasing the ONNX Runtime session
+//! and its memory after a period without requests and reloading it on the next one
+
+use crate::session::SessionError;
+use crate::session::yolo_session::YoloSession;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Session of `IdleUnload` loading a YOLO session
+pub type IdleYoloSession = IdleUnload<YoloSession, SessionError>;
+
+/// Function (re)loading the managed value
+type Loader<T, E> = Box<dyn FnMut() -> Result<T, E> + Send>;
+
+/// Value loaded on first use and dropped once unused for `idle_timeout`.
+///
+/// Nothing runs in the background: the owner calls `unload_if_idle` periodically, e.g. from
+/// the loop of a daemon. `keepalive` marks the value as used without touching it, for health
+/// checks that should keep the session warm.
+pub struct IdleUnload<T, E> {
+    loader: Loader<T, E>,
+    value: Option<T>,
+    idle_timeout: Duration,
+    last_used: Instant,
+    loads: usize,
+}
+
+impl<T, E> IdleUnload<T, E> {
+    /// Creates an unloaded value, loaded by `loader` on first use
+    pub fn new(
+        idle_timeout: Duration,
+        loader: impl FnMut() -> Result<T, E> + Send + 'static,
+    ) -> Self {
+        Self {
+            loader: Box::new(loader),
+            value: None,
+            idle_timeout,
+            last_used: Instant::now(),
+            loads: 0,
+        }
+    }
+
+    /// Returns the value, loading it if it was never loaded or was unloaded
+    pub fn get(&mut self) -> Result<&mut T, E> {
+        self.last_used = Instant::now();
+        if self.value.is_none() {
+            self.value = Some((self.loader)()?);
+            self.loads += 1;
+        }
+        Ok(self.value.as_mut().expect("value loaded above"))
+    }
+
+    /// Marks the value as used, postponing its unloading
+    pub fn keepalive(&mut self) {
+        self.last_used = Instant::now();
+    }
+
+    /// Drops the value if it was not used for the idle timeout, returning true if it was dropped
+    pub fn unload_if_idle(&mut self) -> bool {
+        self.unload_if_idle_at(Instant::now())
+    }
+
+    /// Drops the value if it was not used for the idle timeout at `now`
+    pub fn unload_if_idle_at(&mut self, now: Instant) -> bool {
+        if self.value.is_some() && now.duration_since(self.last_used) >= self.idle_timeout {
+            self.value = None;
+            return true;
+        }
+        false
+    }
+
+    /// Returns the time left before the value is unloaded, `None` if it is not loaded
+    #[must_use]
+    pub fn time_to_unload(&self) -> Option<Duration> {
+        self.value
+            .as_ref()
+            .map(|_| self.idle_timeout.saturating_sub(self.last_used.elapsed()))
+    }
+
+    /// Returns true if the value is currently loaded
+    #[inline]
+    #[must_use]
+    pub const fn is_loaded(&self) -> bool {
+        self.value.is_some()
+    }
+
+    /// Returns how many times the value was loaded
+    #[inline]
+    #[must_use]
+    pub const fn loads(&self) -> usize {
+        self.loads
+    }
+}
+
+impl<T, E> fmt::Debug for IdleUnload<T, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IdleUnload")
+            .field("loaded", &self.is_loaded())
+            .field("idle_timeout", &self.idle_timeout)
+            .field("loads", &self.loads)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unloads_when_idle_and_reloads_lazily() {
+        let mut idle: IdleUnload<String, String> =
+            IdleUnload::new(Duration::from_secs(60), || Ok("session".to_string()));
+        assert!(!idle.is_loaded());
+        assert_eq!(idle.get().unwrap(), "session");
+        assert_eq!(idle.get().unwrap(), "session");
+        assert_eq!(idle.loads(), 1);
+
+        let now = Instant::now();
+        assert!(!idle.unload_if_idle_at(now));
+        assert!(idle.unload_if_idle_at(now + Duration::from_secs(61)));
+        assert!(!idle.is_loaded());
+        assert_eq!(idle.time_to_unload(), None);
+
+        idle.get().unwrap();
+        assert_eq!(idle.loads(), 2);
+        idle.keepalive();
+        assert!(!idle.unload_if_idle_at(Instant::now() + Duration::from_secs(30)));
+
+        let mut failing: IdleUnload<String, String> =
+            IdleUnload::new(Duration::ZERO, || Err("no model".to_string()));
+        assert_eq!(failing.get(), Err("no model".to_string()));
+        assert!(!failing.is_loaded());
+    }
+}
@@ -0,0 +1,244 @@
+//! Validation of the input tensor against what the model expects
+
+use crate::session::SessionError;
+use crate::session::warning::Warning;
+use ndarray::Array4;
+
+/// Value range expected by the model for its input tensor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InputRange {
+    /// Pixels scaled to [0, 1] (Ultralytics exports)
+    #[default]
+    UnitInterval,
+    /// Pixels normalized with the `ImageNet` mean and standard deviation
+    ImageNet,
+    /// Raw pixel values in [0, 255]
+    Raw,
+}
+
+impl InputRange {
+    /// Returns the inclusive bounds of the range, with a small tolerance for `ImageNet`
+    #[inline]
+    #[must_use]
+    pub const fn bounds(&self) -> (f32, f32) {
+        match self {
+            Self::UnitInterval => (0.0, 1.0),
+            Self::ImageNet => (-2.2, 2.7),
+            Self::Raw => (0.0, 255.0),
+        }
+    }
+
+    /// Returns the string representation of the `InputRange` variant.
+    #[inline]
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::UnitInterval => "[0, 1]",
+            Self::ImageNet => "ImageNet-normalized",
+            Self::Raw => "[0, 255]",
+        }
+    }
+}
+
+/// Element type and shape of a model input as declared in the ONNX graph
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputSpec {
+    pub name: String,
+    /// Element type name as reported by ONNX Runtime (e.g. `f32`)
+    pub dtype: String,
+    /// Dimensions of the input, `-1` for dynamic ones
+    pub shape: Vec<i64>,
+}
+
+impl InputSpec {
+    /// Checks that the model accepts the `f32` tensors produced by the pipeline
+    pub fn validate_dtype(&self) -> Result<(), SessionError> {
+        if self.dtype == "f32" {
+            return Ok(());
+        }
+        Err(SessionError::InvalidInput(format!(
+            "model input '{}' expects {} tensors but the pipeline produces f32",
+            self.name, self.dtype
+        )))
+    }
+
+    /// Checks that a NCHW tensor of `shape` fits the declared input shape
+    pub fn validate_layout(&self, shape: &[usize]) -> Result<(), SessionError> {
+        if self.shape.len() != shape.len() {
+            return Err(SessionError::InvalidInput(format!(
+                "model input '{}' has rank {} ({:?}) but the pipeline produces a rank {} NCHW tensor",
+                self.name,
+                self.shape.len(),
+                self.shape,
+                shape.len()
+            )));
+        }
+
+        if self.shape.len() == 4 && self.shape[3] == 3 && self.shape[1] != 3 {
+            return Err(SessionError::InvalidInput(format!(
+                "model input '{}' has shape {:?} which looks NHWC, but the pipeline produces NCHW",
+                self.name, self.shape
+            )));
+        }
+
+        let mismatch = self
+            .shape
+            .iter()
+            .zip(shape)
+            .any(|(&expected, &actual)| expected >= 0 && expected as usize != actual);
+        if mismatch {
+            return Err(SessionError::InvalidInput(format!(
+                "model input '{}' expects shape {:?} but got {shape:?}; check the configured input size",
+                self.name, self.shape
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks that every tensor value lies within the expected range.
+///
+/// Values outside the range usually mean the wrong normalization was applied, which makes the
+/// model silently return near-zero confidences.
+pub fn validate_tensor_range(tensor: &Array4<f32>, range: InputRange) -> Result<(), SessionError> {
+    if tensor.iter().any(|v| !v.is_finite()) {
+        return Err(SessionError::InvalidInput(
+            "input tensor contains NaN or infinite values".to_string(),
+        ));
+    }
+
+    let (low, high) = range.bounds();
+    let (min, max) = value_span(tensor);
+
+    if min < low || max > high {
+        return Err(SessionError::InvalidInput(format!(
+            "input tensor values span [{min:.3}, {max:.3}] but the model expects {}",
+            range.as_str()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Returns a warning if the tensor values look normalized differently from `range`.
+///
+/// Unlike `validate_tensor_range`, this also flags tensors within bounds, such as pixels scaled
+/// to [0, 1] twice, and never fails the image.
+#[must_use]
+pub fn check_normalization(tensor: &Array4<f32>, range: InputRange) -> Option<Warning> {
+    let (low, high) = range.bounds();
+    let (min, max) = value_span(tensor);
+    if min > max {
+        return None;
+    }
+
+    let suspicious = min < low
+        || max > high
+        || match range {
+            InputRange::UnitInterval => max > 0.0 && max <= 1.5 / 255.0,
+            InputRange::Raw => max > 0.0 && max <= 1.0,
+            InputRange::ImageNet => min >= 0.0 && max <= 1.0,
+        };
+    suspicious.then_some(Warning::SuspiciousNormalization {
+        min,
+        max,
+        expected: range,
+    })
+}
+
+/// Returns the smallest and largest finite values of the tensor
+fn value_span(tensor: &Array4<f32>) -> (f32, f32) {
+    tensor
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), &v| {
+            (min.min(v), max.max(v))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(dtype: &str, shape: Vec<i64>) -> InputSpec {
+        InputSpec {
+            name: "images".to_string(),
+            dtype: dtype.to_string(),
+            shape,
+        }
+    }
+
+    #[test]
+    fn test_validate_dtype() {
+        assert!(spec("f32", vec![1, 3, 640, 640]).validate_dtype().is_ok());
+        assert!(spec("f16", vec![1, 3, 640, 640]).validate_dtype().is_err());
+    }
+
+    #[test]
+    fn test_validate_layout() {
+        let nchw = [1, 3, 640, 640];
+        assert!(
+            spec("f32", vec![1, 3, 640, 640])
+                .validate_layout(&nchw)
+                .is_ok()
+        );
+        assert!(
+            spec("f32", vec![-1, 3, -1, -1])
+                .validate_layout(&nchw)
+                .is_ok()
+        );
+        assert!(
+            spec("f32", vec![1, 3, 320, 320])
+                .validate_layout(&nchw)
+                .is_err()
+        );
+        assert!(
+            spec("f32", vec![1, 640, 640, 3])
+                .validate_layout(&nchw)
+                .is_err()
+        );
+        assert!(
+            spec("f32", vec![3, 640, 640])
+                .validate_layout(&nchw)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_validate_tensor_range() {
+        let unit = Array4::from_elem((1, 3, 2, 2), 0.5f32);
+        assert!(validate_tensor_range(&unit, InputRange::UnitInterval).is_ok());
+
+        let raw = Array4::from_elem((1, 3, 2, 2), 200.0f32);
+        assert!(validate_tensor_range(&raw, InputRange::UnitInterval).is_err());
+        assert!(validate_tensor_range(&raw, InputRange::Raw).is_ok());
+
+        let mut imagenet = Array4::from_elem((1, 3, 2, 2), -1.5f32);
+        assert!(validate_tensor_range(&imagenet, InputRange::UnitInterval).is_err());
+        assert!(validate_tensor_range(&imagenet, InputRange::ImageNet).is_ok());
+
+        imagenet[[0, 0, 0, 0]] = f32::NAN;
+        assert!(validate_tensor_range(&imagenet, InputRange::ImageNet).is_err());
+    }
+
+    #[test]
+    fn test_check_normalization() {
+        let unit = Array4::from_elem((1, 3, 2, 2), 0.5f32);
+        assert_eq!(check_normalization(&unit, InputRange::UnitInterval), None);
+        assert!(check_normalization(&unit, InputRange::Raw).is_some());
+        assert!(check_normalization(&unit, InputRange::ImageNet).is_some());
+
+        // Scaled by 1/255 twice: within [0, 1], but far too dark
+        let twice = Array4::from_elem((1, 3, 2, 2), 0.5f32 / 255.0);
+        assert!(matches!(
+            check_normalization(&twice, InputRange::UnitInterval),
+            Some(Warning::SuspiciousNormalization {
+                expected: InputRange::UnitInterval,
+                ..
+            })
+        ));
+
+        let black = Array4::zeros((1, 3, 2, 2));
+        assert_eq!(check_normalization(&black, InputRange::UnitInterval), None);
+    }
+}
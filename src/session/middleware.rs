@@ -0,0 +1,247 @@
+//! Middleware intercepting the stages of the detection pipeline
+
+use crate::detection::BoundingBox;
+use crate::session::SessionError;
+use ndarray::ArrayViewD;
+use std::fmt;
+
+/// Pipeline stage at which middleware is invoked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stage {
+    PreParse,
+    PostParse,
+    PostNms,
+    PreOutput,
+}
+
+impl Stage {
+    /// Returns the string representation of the `Stage` variant.
+    #[inline]
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::PreParse => "pre-parse",
+            Self::PostParse => "post-parse",
+            Self::PostNms => "post-nms",
+            Self::PreOutput => "pre-output",
+        }
+    }
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Decision returned by a middleware
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Flow {
+    /// Hands the data to the next middleware and pipeline stage
+    Continue,
+    /// Stops processing the image, failing it with `SessionError::Vetoed`
+    Veto(String),
+}
+
+/// Image being processed, shared with every stage
+#[derive(Debug, Clone, Copy)]
+pub struct StageContext<'a> {
+    /// Name of the image, empty when `YoloSession::run_inference` is called directly
+    pub image_name: &'a str,
+    /// Width and height of the letterboxed model input
+    pub input_size: (u32, u32),
+}
+
+/// A component intercepting the detection pipeline for logging, mutation or vetoing.
+///
+/// Every stage defaults to `Flow::Continue`, so implementations only override the stages
+/// they care about. Boxes are expressed in the letterboxed input space.
+pub trait DetectionMiddleware {
+    /// Inspects the raw model output before it is parsed into boxes
+    fn pre_parse(&mut self, _context: &StageContext<'_>, _output: &ArrayViewD<'_, f32>) -> Flow {
+        Flow::Continue
+    }
+
+    /// Intercepts the parsed boxes, before NMS
+    fn post_parse(&mut self, _context: &StageContext<'_>, _boxes: &mut Vec<BoundingBox>) -> Flow {
+        Flow::Continue
+    }
+
+    /// Intercepts the boxes surviving NMS, before level classification
+    fn post_nms(&mut self, _context: &StageContext<'_>, _boxes: &mut Vec<BoundingBox>) -> Flow {
+        Flow::Continue
+    }
+
+    /// Intercepts the final boxes, before they are drawn, saved and sent to sinks
+    fn pre_output(&mut self, _context: &StageContext<'_>, _boxes: &mut Vec<BoundingBox>) -> Flow {
+        Flow::Continue
+    }
+}
+
+impl<M: DetectionMiddleware + ?Sized> DetectionMiddleware for Box<M> {
+    fn pre_parse(&mut self, context: &StageContext<'_>, output: &ArrayViewD<'_, f32>) -> Flow {
+        (**self).pre_parse(context, output)
+    }
+
+    fn post_parse(&mut self, context: &StageContext<'_>, boxes: &mut Vec<BoundingBox>) -> Flow {
+        (**self).post_parse(context, boxes)
+    }
+
+    fn post_nms(&mut self, context: &StageContext<'_>, boxes: &mut Vec<BoundingBox>) -> Flow {
+        (**self).post_nms(context, boxes)
+    }
+
+    fn pre_output(&mut self, context: &StageContext<'_>, boxes: &mut Vec<BoundingBox>) -> Flow {
+        (**self).pre_output(context, boxes)
+    }
+}
+
+/// Ordered middleware chain; the first veto stops the chain
+#[derive(Default)]
+pub struct MiddlewareChain {
+    middlewares: Vec<Box<dyn DetectionMiddleware>>,
+}
+
+impl MiddlewareChain {
+    /// Appends a middleware, run after the ones already registered
+    pub fn push(&mut self, middleware: Box<dyn DetectionMiddleware>) {
+        self.middlewares.push(middleware);
+    }
+
+    /// Returns the number of registered middlewares
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.middlewares.len()
+    }
+
+    /// Returns true if no middleware is registered
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.middlewares.is_empty()
+    }
+
+    /// Runs the pre-parse stage of every middleware
+    pub fn pre_parse(
+        &mut self,
+        context: &StageContext<'_>,
+        output: &ArrayViewD<'_, f32>,
+    ) -> Result<(), SessionError> {
+        self.run(Stage::PreParse, |m| m.pre_parse(context, output))
+    }
+
+    /// Runs the box stage `stage` of every middleware; `Stage::PreParse` has no boxes and is a no-op
+    pub fn intercept(
+        &mut self,
+        stage: Stage,
+        context: &StageContext<'_>,
+        boxes: &mut Vec<BoundingBox>,
+    ) -> Result<(), SessionError> {
+        self.run(stage, |m| match stage {
+            Stage::PreParse => Flow::Continue,
+            Stage::PostParse => m.post_parse(context, boxes),
+            Stage::PostNms => m.post_nms(context, boxes),
+            Stage::PreOutput => m.pre_output(context, boxes),
+        })
+    }
+
+    fn run(
+        &mut self,
+        stage: Stage,
+        mut call: impl FnMut(&mut dyn DetectionMiddleware) -> Flow,
+    ) -> Result<(), SessionError> {
+        for middleware in &mut self.middlewares {
+            if let Flow::Veto(reason) = call(middleware.as_mut()) {
+                return Err(SessionError::Vetoed(format!("{stage}: {reason}")));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTEXT: StageContext<'static> = StageContext {
+        image_name: "village.png",
+        input_size: (640, 640),
+    };
+
+    /// Drops boxes below a confidence floor after NMS
+    struct MinConfidence(f32);
+
+    impl DetectionMiddleware for MinConfidence {
+        fn post_nms(&mut self, _context: &StageContext<'_>, boxes: &mut Vec<BoundingBox>) -> Flow {
+            boxes.retain(|bbox| bbox.confidence >= self.0);
+            Flow::Continue
+        }
+    }
+
+    /// Vetoes images without any detection before output
+    struct RequireDetections;
+
+    impl DetectionMiddleware for RequireDetections {
+        fn pre_output(&mut self, context: &StageContext<'_>, boxes: &mut Vec<BoundingBox>) -> Flow {
+            if boxes.is_empty() {
+                Flow::Veto(format!("no detections in {}", context.image_name))
+            } else {
+                Flow::Continue
+            }
+        }
+    }
+
+    fn sample_boxes() -> Vec<BoundingBox> {
+        vec![
+            BoundingBox::new(0.0, 0.0, 10.0, 10.0, 0, 0.9),
+            BoundingBox::new(20.0, 20.0, 30.0, 30.0, 1, 0.3),
+        ]
+    }
+
+    #[test]
+    fn test_middleware_mutates_boxes() {
+        let mut chain = MiddlewareChain::default();
+        chain.push(Box::new(MinConfidence(0.5)));
+
+        let mut boxes = sample_boxes();
+        chain
+            .intercept(Stage::PostParse, &CONTEXT, &mut boxes)
+            .unwrap();
+        assert_eq!(boxes.len(), 2);
+
+        chain
+            .intercept(Stage::PostNms, &CONTEXT, &mut boxes)
+            .unwrap();
+        assert_eq!(boxes, vec![sample_boxes()[0]]);
+    }
+
+    #[test]
+    fn test_veto_stops_chain() {
+        let mut chain = MiddlewareChain::default();
+        chain.push(Box::new(MinConfidence(0.95)));
+        chain.push(Box::new(RequireDetections));
+        chain.push(Box::new(MinConfidence(2.0)));
+
+        let mut boxes = sample_boxes();
+        chain
+            .intercept(Stage::PostNms, &CONTEXT, &mut boxes)
+            .unwrap();
+        let error = chain
+            .intercept(Stage::PreOutput, &CONTEXT, &mut boxes)
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Vetoed by middleware at pre-output: no detections in village.png"
+        );
+    }
+
+    #[test]
+    fn test_pre_parse_defaults_to_continue() {
+        let mut chain = MiddlewareChain::default();
+        chain.push(Box::new(RequireDetections));
+        let output = ndarray::ArrayD::<f32>::zeros(vec![1, 6, 4]);
+        assert!(chain.pre_parse(&CONTEXT, &output.view()).is_ok());
+        assert_eq!(chain.len(), 1);
+    }
+}
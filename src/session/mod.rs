@@ -1,7 +1,27 @@
+use crate::model::inference::InferenceError;
+use crate::sink::SinkError;
+use crate::store::StoreError;
+use std::time::Duration;
 use thiserror::Error;
 
+pub mod benchmark;
+pub mod best_effort;
+pub mod execution;
+pub mod idle;
+pub mod input_validation;
+pub mod middleware;
+#[cfg(feature = "http")]
+pub mod model_download;
+pub mod model_file;
+pub mod model_info;
 pub mod ort_inference_session;
-mod session_config;
+pub mod panic_guard;
+pub mod progress;
+#[cfg(feature = "rhai")]
+pub mod script;
+pub mod self_test;
+pub mod session_config;
+pub mod warning;
 pub mod yolo_session;
 
 /// Session-specific errors
@@ -13,6 +33,35 @@ pub enum SessionError {
     #[error("Inference failed: {0}")]
     Inference(String),
 
+    #[error("Invalid model output: {0}")]
+    Output(#[from] InferenceError),
+
+    #[error("Invalid model input: {0}")]
+    InvalidInput(String),
+
+    #[error("Vetoed by middleware at {0}")]
+    Vetoed(String),
+
+    #[error("Model self-test failed: {0}")]
+    SelfTest(String),
+
+    #[error("Image processing timed out after {0:?}")]
+    Timeout(Duration),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
+
+    #[error("Results store error: {0}")]
+    Store(#[from] StoreError),
+
+    #[error("Detection sink error: {0}")]
+    Sink(#[from] SinkError),
+
+    #[cfg(feature = "panic-guard")]
+    #[error("Internal error: {0}")]
+    Internal(String),
+
+    #[cfg(feature = "http")]
+    #[error("Model download failed: {0}")]
+    Download(#[from] model_download::DownloadError),
 }
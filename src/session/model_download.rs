@@ -0,0 +1,202 @@
+//! ONNX models downloaded over HTTP, verified against their SHA-256 checksum and cached on disk
+
+use crate::image::image_util::content_fingerprint;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Errors that can occur while fetching a model
+#[derive(Debug, thiserror::Error)]
+pub enum DownloadError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("HTTP request failed: {0}")]
+    Http(String),
+    #[error("Invalid SHA-256 checksum: {0}")]
+    InvalidChecksum(String),
+    #[error("Checksum mismatch: expected {expected}, got {actual}")]
+    ChecksumMismatch { expected: String, actual: String },
+}
+
+/// Returns the per-user directory models are cached in by default.
+///
+/// This is `$XDG_CACHE_HOME/clashvision/models`, falling back to `~/.cache` and, on Windows,
+/// to `%LOCALAPPDATA%`.
+#[must_use]
+pub fn default_cache_dir() -> PathBuf {
+    std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("LOCALAPPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+        .unwrap_or_else(std::env::temp_dir)
+        .join("clashvision")
+        .join("models")
+}
+
+/// Returns the hex encoded SHA-256 digest of `bytes`
+#[must_use]
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Returns the path of the model at `url` in `cache_dir`, downloading it on a cache miss.
+///
+/// Without an `expected_sha256`, the checksum is read from `<url>.sha256`, in `sha256sum`
+/// format. A cached model is reused as long as it still matches the checksum recorded when it
+/// was downloaded, and `expected_sha256` if given; a corrupt or outdated copy is replaced.
+pub fn fetch_model(
+    url: &str,
+    expected_sha256: Option<&str>,
+    cache_dir: &Path,
+) -> Result<PathBuf, DownloadError> {
+    let expected = expected_sha256.map(parse_checksum).transpose()?;
+    let path = cache_dir.join(format!("{}.onnx", content_fingerprint(url.as_bytes())));
+    let checksum_path = path.with_extension("onnx.sha256");
+
+    if let (Ok(model), Ok(recorded)) = (fs::read(&path), fs::read_to_string(&checksum_path)) {
+        let actual = sha256_hex(&model);
+        if actual == recorded.trim() && expected.as_ref().is_none_or(|e| *e == actual) {
+            return Ok(path);
+        }
+    }
+
+    let agent = ureq::Agent::new_with_defaults();
+    let get = |url: &str| -> Result<Vec<u8>, DownloadError> {
+        agent
+            .get(url)
+            .call()
+            .and_then(|mut response| {
+                response
+                    .body_mut()
+                    .with_config()
+                    .limit(u64::MAX)
+                    .read_to_vec()
+            })
+            .map_err(|e| DownloadError::Http(format!("{url}: {e}")))
+    };
+    let expected = match expected {
+        Some(expected) => expected,
+        None => parse_checksum(&String::from_utf8_lossy(&get(&format!("{url}.sha256"))?))?,
+    };
+
+    let model = get(url)?;
+    let actual = sha256_hex(&model);
+    if actual != expected {
+        return Err(DownloadError::ChecksumMismatch { expected, actual });
+    }
+
+    // Written under a temporary name first so that an interrupted download is never reused
+    fs::create_dir_all(cache_dir)?;
+    let partial = path.with_extension("onnx.part");
+    fs::write(&partial, &model)?;
+    fs::rename(&partial, &path)?;
+    fs::write(&checksum_path, &actual)?;
+    Ok(path)
+}
+
+/// Extracts the digest from a checksum, either bare or as a `sha256sum` line
+fn parse_checksum(checksum: &str) -> Result<String, DownloadError> {
+    let digest = checksum.split_whitespace().next().unwrap_or_default();
+    if digest.len() == 64 && digest.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(digest.to_ascii_lowercase())
+    } else {
+        Err(DownloadError::InvalidChecksum(checksum.trim().to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use tempfile::tempdir;
+
+    /// Serves `requests` GET requests with the body of their path, returning the paths asked for
+    fn serve(
+        routes: Vec<(&'static str, Vec<u8>)>,
+        requests: usize,
+    ) -> (String, std::thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let mut paths = Vec::new();
+            for _ in 0..requests {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut header = String::new();
+                while reader.read_line(&mut header).unwrap() > 2 {
+                    header.clear();
+                }
+
+                let path = request_line.split(' ').nth(1).unwrap().to_string();
+                let body = routes
+                    .iter()
+                    .find(|(route, _)| *route == path)
+                    .map(|(_, body)| body.clone())
+                    .unwrap_or_default();
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                )
+                .unwrap();
+                stream.write_all(&body).unwrap();
+                paths.push(path);
+            }
+            paths
+        });
+        (base, server)
+    }
+
+    #[test]
+    fn test_download_verifies_and_caches() {
+        let model = b"onnx model bytes".to_vec();
+        let checksum = format!("{}  best.onnx\n", sha256_hex(&model));
+        let (base, server) = serve(
+            vec![
+                ("/best.onnx", model.clone()),
+                ("/best.onnx.sha256", checksum.into_bytes()),
+            ],
+            2,
+        );
+        let url = format!("{base}/best.onnx");
+        let dir = tempdir().unwrap();
+
+        let path = fetch_model(&url, None, dir.path()).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), model);
+        assert_eq!(
+            server.join().unwrap(),
+            vec!["/best.onnx.sha256", "/best.onnx"]
+        );
+
+        // The server is gone, so this only succeeds from the cache
+        let pinned = sha256_hex(&model).to_uppercase();
+        assert_eq!(fetch_model(&url, Some(&pinned), dir.path()).unwrap(), path);
+    }
+
+    #[test]
+    fn test_checksum_mismatch_is_not_cached() {
+        let (base, server) = serve(vec![("/best.onnx", b"tampered".to_vec())], 1);
+        let dir = tempdir().unwrap();
+        let expected = sha256_hex(b"onnx model bytes");
+
+        let result = fetch_model(&format!("{base}/best.onnx"), Some(&expected), dir.path());
+        assert!(matches!(
+            result,
+            Err(DownloadError::ChecksumMismatch { expected: e, .. }) if e == expected
+        ));
+        server.join().unwrap();
+        assert!(fs::read_dir(dir.path()).is_ok_and(|mut entries| entries.next().is_none()));
+
+        assert!(matches!(
+            fetch_model("http://127.0.0.1:1/best.onnx", Some("abc"), dir.path()),
+            Err(DownloadError::InvalidChecksum(_))
+        ));
+    }
+}
@@ -0,0 +1,74 @@
+//! Model file access through a memory map, with a buffered fallback
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::ops::Deref;
+use std::path::Path;
+
+/// Contents of an ONNX model file, memory-mapped where the platform supports it
+pub enum ModelFile {
+    #[cfg(any(unix, windows))]
+    Mapped(memmap2::Mmap),
+    Buffered(Vec<u8>),
+}
+
+impl ModelFile {
+    /// Maps `model_path` into memory, reading it into a buffer if mapping is unavailable or fails.
+    ///
+    /// The file must not be modified while the `ModelFile` is alive.
+    pub fn open(model_path: &Path) -> io::Result<Self> {
+        let mut file = File::open(model_path)?;
+
+        #[cfg(any(unix, windows))]
+        // SAFETY: the mapping is read-only and only lives while the model is being loaded
+        if let Ok(mmap) = unsafe { memmap2::Mmap::map(&file) } {
+            return Ok(Self::Mapped(mmap));
+        }
+
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        Ok(Self::Buffered(bytes))
+    }
+
+    /// Returns true if the file is memory-mapped rather than buffered
+    #[inline]
+    #[must_use]
+    pub const fn is_mapped(&self) -> bool {
+        !matches!(self, Self::Buffered(_))
+    }
+}
+
+impl Deref for ModelFile {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            #[cfg(any(unix, windows))]
+            Self::Mapped(mmap) => mmap,
+            Self::Buffered(bytes) => bytes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_open_maps_file_contents() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("model.onnx");
+        std::fs::write(&path, b"onnx model bytes").unwrap();
+
+        let model = ModelFile::open(&path).unwrap();
+        assert_eq!(&*model, b"onnx model bytes");
+        assert_eq!(model.is_mapped(), cfg!(any(unix, windows)));
+    }
+
+    #[test]
+    fn test_open_missing_file() {
+        let dir = tempdir().unwrap();
+        assert!(ModelFile::open(&dir.path().join("missing.onnx")).is_err());
+    }
+}
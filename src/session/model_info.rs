@@ -0,0 +1,225 @@
+//! Introspection of the ONNX graph of a model: tensor names, input shape and class count, so
+//! that sessions adapt to the model instead of assuming an Ultralytics 640x640 export
+
+use crate::model::yolo_type::YoloType;
+use crate::session::SessionError;
+use crate::session::ort_inference_session::OrtInferenceSession;
+use crate::session::session_config::SessionConfig;
+
+/// Input name assumed for models declaring no input, as named by Ultralytics exports
+const DEFAULT_INPUT_NAME: &str = "images";
+
+/// Output name assumed for models declaring no output, as named by Ultralytics exports
+const DEFAULT_OUTPUT_NAME: &str = "output0";
+
+/// Inputs, outputs and classes of a model as declared in its ONNX graph and metadata
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModelInfo {
+    pub input_names: Vec<String>,
+    pub output_names: Vec<String>,
+    /// Dimensions of the image input, `-1` for dynamic ones
+    pub input_shape: Vec<i64>,
+    /// Dimensions of the detection output, `-1` for dynamic ones
+    pub output_shape: Vec<i64>,
+    /// Class names of the Ultralytics `names` metadata, empty when absent
+    pub class_names: Vec<String>,
+    /// Number of classes, from the metadata or else the detection head shape
+    pub num_classes: Option<usize>,
+    /// Training size of the Ultralytics `imgsz` metadata, as (width, height)
+    pub trained_size: Option<(u32, u32)>,
+}
+
+impl ModelInfo {
+    /// Inspects the graph and metadata of a session running a `model_type` head
+    #[must_use]
+    pub fn from_session(session: &OrtInferenceSession, model_type: &YoloType) -> Self {
+        let input_names = session.input_names();
+        let output_names = session.output_names();
+        let input_shape = input_names
+            .first()
+            .and_then(|name| session.input_spec(name))
+            .map(|spec| spec.shape)
+            .unwrap_or_default();
+        let output_shape = output_names
+            .first()
+            .and_then(|name| session.output_shape(name))
+            .unwrap_or_default();
+        let class_names = session
+            .metadata("names")
+            .and_then(|names| parse_names(&names))
+            .unwrap_or_default();
+        let trained_size = session
+            .metadata("imgsz")
+            .and_then(|imgsz| parse_imgsz(&imgsz));
+
+        let num_classes = if class_names.is_empty() {
+            head_classes(&output_shape, model_type)
+        } else {
+            Some(class_names.len())
+        };
+        Self {
+            input_names,
+            output_names,
+            input_shape,
+            output_shape,
+            class_names,
+            num_classes,
+            trained_size,
+        }
+    }
+
+    /// Returns the name of the image input, the first one of the model
+    #[must_use]
+    pub fn input_name(&self) -> &str {
+        self.input_names
+            .first()
+            .map_or(DEFAULT_INPUT_NAME, String::as_str)
+    }
+
+    /// Returns the name of the detection output, the first one of the model
+    #[must_use]
+    pub fn output_name(&self) -> &str {
+        self.output_names
+            .first()
+            .map_or(DEFAULT_OUTPUT_NAME, String::as_str)
+    }
+
+    /// Returns the input size expected by the model as (width, height): the static spatial
+    /// dimensions of its NCHW input, or else its training size
+    #[must_use]
+    pub fn input_size(&self) -> Option<(u32, u32)> {
+        let dimension = |index: usize| {
+            self.input_shape
+                .get(index)
+                .and_then(|&dim| u32::try_from(dim).ok())
+                .filter(|&dim| dim > 0)
+        };
+        match (dimension(3), dimension(2)) {
+            (Some(width), Some(height)) if self.input_shape.len() == 4 => Some((width, height)),
+            _ => self.trained_size,
+        }
+    }
+
+    /// Checks that the class ids of the class filter and per-class thresholds exist in the model
+    pub fn validate(&self, config: &SessionConfig) -> Result<(), SessionError> {
+        let Some(num_classes) = self.num_classes else {
+            return Ok(());
+        };
+        let filtered = config.class_filter.iter().flatten();
+        let thresholded = config.per_class_confidence.keys();
+        match filtered.chain(thresholded).find(|&&id| id >= num_classes) {
+            Some(class_id) => Err(SessionError::InvalidInput(format!(
+                "class id {class_id} is configured but the model has {num_classes} classes"
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Returns the class count implied by the static shape of a detection head
+fn head_classes(output_shape: &[i64], model_type: &YoloType) -> Option<usize> {
+    let &[_, rows, columns] = output_shape else {
+        return None;
+    };
+    let (rows, columns) = (usize::try_from(rows).ok()?, usize::try_from(columns).ok()?);
+    match model_type {
+        // (1, 4 + classes, detections), or transposed for some v11 exports
+        YoloType::YoloV8 | YoloType::YoloV11 => rows.min(columns).checked_sub(4),
+        YoloType::YoloV5 => columns.checked_sub(5),
+        YoloType::YoloV10 | YoloType::Custom(_) => None,
+    }
+    .filter(|&classes| classes > 0)
+}
+
+/// Parses the Ultralytics `names` metadata, a Python dict such as `{0: 'cannon', 1: 'mortar'}`
+fn parse_names(names: &str) -> Option<Vec<String>> {
+    let mut rest = names.trim().strip_prefix('{')?.strip_suffix('}')?;
+    let mut parsed = Vec::new();
+    loop {
+        rest = rest.trim_start_matches([',', ' ']);
+        if rest.is_empty() {
+            return Some(parsed);
+        }
+        let (id, value) = rest.split_once(':')?;
+        if id.trim().parse::<usize>().ok()? != parsed.len() {
+            return None;
+        }
+        let value = value.trim_start();
+        let quote = value.chars().next().filter(|c| matches!(c, '\'' | '"'))?;
+        let end = value[1..].find(quote)? + 1;
+        parsed.push(value[1..end].to_string());
+        rest = &value[end + 1..];
+    }
+}
+
+/// Parses the Ultralytics `imgsz` metadata, `[height, width]`, into (width, height)
+fn parse_imgsz(imgsz: &str) -> Option<(u32, u32)> {
+    let dims: Vec<u32> = imgsz
+        .trim()
+        .strip_prefix('[')?
+        .strip_suffix(']')?
+        .split(',')
+        .map(|dim| dim.trim().parse().ok())
+        .collect::<Option<_>>()?;
+    match dims[..] {
+        [height, width] if height > 0 && width > 0 => Some((width, height)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_parse_ultralytics_metadata() {
+        assert_eq!(
+            parse_names("{0: 'Elixir Storage', 1: \"Gold's Mine\", 2: 'Town Hall'}"),
+            Some(vec![
+                "Elixir Storage".to_string(),
+                "Gold's Mine".to_string(),
+                "Town Hall".to_string()
+            ])
+        );
+        assert_eq!(parse_names("{}"), Some(Vec::new()));
+        assert_eq!(parse_names("{1: 'cannon'}"), None);
+        assert_eq!(parse_names("cannon"), None);
+
+        assert_eq!(parse_imgsz("[480, 640]"), Some((640, 480)));
+        assert_eq!(parse_imgsz("640"), None);
+    }
+
+    #[test]
+    fn test_input_size_and_classes() {
+        let mut info = ModelInfo {
+            input_shape: vec![1, 3, 480, 640],
+            output_shape: vec![1, 11, 6300],
+            ..ModelInfo::default()
+        };
+        assert_eq!(info.input_size(), Some((640, 480)));
+        assert_eq!(info.input_name(), "images");
+        assert_eq!(head_classes(&info.output_shape, &YoloType::YoloV8), Some(7));
+        assert_eq!(head_classes(&[1, 6300, 11], &YoloType::YoloV11), Some(7));
+        assert_eq!(head_classes(&[1, 25200, 12], &YoloType::YoloV5), Some(7));
+        assert_eq!(head_classes(&[1, -1, 11], &YoloType::YoloV8), None);
+        assert_eq!(head_classes(&[1, 300, 6], &YoloType::YoloV10), None);
+
+        info.input_shape = vec![1, 3, -1, -1];
+        assert_eq!(info.input_size(), None);
+        info.trained_size = Some((1024, 1024));
+        assert_eq!(info.input_size(), Some((1024, 1024)));
+
+        info.num_classes = Some(7);
+        let mut config = SessionConfig {
+            class_filter: Some(HashSet::from([1, 6])),
+            ..SessionConfig::default()
+        };
+        assert!(info.validate(&config).is_ok());
+        config.per_class_confidence.insert(7, 0.5);
+        assert!(matches!(
+            info.validate(&config),
+            Err(SessionError::InvalidInput(_))
+        ));
+    }
+}
@@ -1,28 +1,253 @@
+use crate::session::execution::ExecutionConfig;
+use crate::session::input_validation::InputSpec;
 use ndarray::{ArrayBase, Dim, OwnedRepr};
-use ort::session::builder::SessionBuilder;
+use ort::io_binding::IoBinding;
+use ort::session::builder::PrepackedWeights;
 use ort::session::{Session, SessionInputValue, SessionInputs, SessionOutputs};
+use ort::tensor::TensorElementType;
 use ort::value::{Tensor, Value};
 use std::borrow::Cow;
 use std::path::Path;
+use std::sync::Arc;
+
+/// Model loaded once and shared by several sessions.
+///
+/// The model bytes are held once behind an `Arc`, and every session created from it registers
+/// its pre-packed weights in a shared container, so a pool of sessions keeps a single copy of
+/// the packed convolution and matrix weights instead of one per session.
+#[derive(Clone)]
+pub struct SharedModel {
+    bytes: Arc<[u8]>,
+    prepacked_weights: PrepackedWeights,
+}
+
+impl SharedModel {
+    /// Shares in-memory model bytes
+    #[must_use]
+    pub fn from_bytes(model_bytes: impl Into<Arc<[u8]>>) -> Self {
+        Self {
+            bytes: model_bytes.into(),
+            prepacked_weights: PrepackedWeights::new(),
+        }
+    }
+
+    /// Reads a model file once, to be shared by every session
+    pub fn from_file(model_path: &Path) -> std::io::Result<Self> {
+        Ok(Self::from_bytes(std::fs::read(model_path)?))
+    }
+
+    /// Returns the shared model bytes
+    #[inline]
+    #[must_use]
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Creates a new session sharing the model's weights, on the configured execution providers
+    pub fn session(&self, execution: &ExecutionConfig) -> ort::Result<OrtInferenceSession> {
+        OrtInferenceSession::from_shared(self, execution)
+    }
+}
 
 /// ONNX Runtime inference session wrapper.
 #[must_use]
 #[non_exhaustive]
 pub struct OrtInferenceSession {
     session: Session,
+    bound: Option<BoundBuffers>,
+}
+
+/// Input and output buffers allocated once and reused across `IoBinding` runs
+struct BoundBuffers {
+    binding: IoBinding,
+    input_name: String,
+    input_shape: Vec<usize>,
+    input: Tensor<f32>,
 }
 
 impl OrtInferenceSession {
     /// Creates a new ONNX Runtime inference session from the specified model path.
     pub fn new(model_path: &Path) -> ort::Result<Self> {
-        let session: Session = SessionBuilder::new()?.commit_from_file(model_path)?;
-        Ok(Self { session })
+        Self::with_execution(model_path, &ExecutionConfig::default())
+    }
+
+    /// Creates a new ONNX Runtime inference session from the specified model path, on the
+    /// configured execution providers.
+    pub fn with_execution(model_path: &Path, execution: &ExecutionConfig) -> ort::Result<Self> {
+        let session: Session = execution.session_builder()?.commit_from_file(model_path)?;
+        Ok(Self {
+            session,
+            bound: None,
+        })
     }
 
     /// Creates a new ONNX Runtime inference session from model bytes.
     pub fn from_bytes(model_bytes: &[u8]) -> ort::Result<Self> {
-        let session: Session = SessionBuilder::new()?.commit_from_memory(model_bytes)?;
-        Ok(Self { session })
+        Self::from_bytes_with_execution(model_bytes, &ExecutionConfig::default())
+    }
+
+    /// Creates a new ONNX Runtime inference session from model bytes, on the configured
+    /// execution providers.
+    pub fn from_bytes_with_execution(
+        model_bytes: &[u8],
+        execution: &ExecutionConfig,
+    ) -> ort::Result<Self> {
+        let session: Session = execution
+            .session_builder()?
+            .commit_from_memory(model_bytes)?;
+        Ok(Self {
+            session,
+            bound: None,
+        })
+    }
+
+    /// Creates a new ONNX Runtime inference session sharing the weights of a `SharedModel`.
+    pub fn from_shared(model: &SharedModel, execution: &ExecutionConfig) -> ort::Result<Self> {
+        let session: Session = execution
+            .session_builder()?
+            .with_prepacked_weights(&model.prepacked_weights)?
+            .commit_from_memory(&model.bytes)?;
+        Ok(Self {
+            session,
+            bound: None,
+        })
+    }
+
+    /// Returns the names of the model inputs.
+    #[must_use]
+    pub fn input_names(&self) -> Vec<String> {
+        self.session
+            .inputs()
+            .iter()
+            .map(|input| input.name().to_string())
+            .collect()
+    }
+
+    /// Returns the names of the model outputs.
+    #[must_use]
+    pub fn output_names(&self) -> Vec<String> {
+        self.session
+            .outputs()
+            .iter()
+            .map(|output| output.name().to_string())
+            .collect()
+    }
+
+    /// Returns the declared shape of the named model output, `-1` for dynamic dimensions.
+    #[must_use]
+    pub fn output_shape(&self, output_name: &str) -> Option<Vec<i64>> {
+        let output = self
+            .session
+            .outputs()
+            .iter()
+            .find(|output| output.name() == output_name)?;
+        output
+            .dtype()
+            .tensor_shape()
+            .map(|shape| shape.iter().copied().collect())
+    }
+
+    /// Returns the value of a custom metadata key of the model, e.g. `names` for Ultralytics
+    /// exports.
+    #[must_use]
+    pub fn metadata(&self, key: &str) -> Option<String> {
+        self.session.metadata().ok()?.custom(key)
+    }
+
+    /// Returns the declared element type and shape of the named model input.
+    #[must_use]
+    pub fn input_spec(&self, input_name: &str) -> Option<InputSpec> {
+        let input = self
+            .session
+            .inputs()
+            .iter()
+            .find(|input| input.name() == input_name)?;
+        let dtype = input.dtype();
+
+        Some(InputSpec {
+            name: input_name.to_string(),
+            dtype: dtype
+                .tensor_type()
+                .map_or_else(|| "non-tensor".to_string(), |ty| ty.to_string()),
+            shape: dtype
+                .tensor_shape()
+                .map(|shape| shape.iter().copied().collect())
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Pre-allocates the input tensor and the model outputs, reused by every `run_inference_bound` call.
+    ///
+    /// Outputs with a fully static f32 shape are bound to pre-allocated tensors; the others are bound
+    /// to the session allocator's device, so ONNX Runtime can reuse its buffers across runs.
+    pub fn enable_io_binding(
+        &mut self,
+        input_name: &str,
+        input_shape: [usize; 4],
+    ) -> ort::Result<()> {
+        let mut binding = self.session.create_binding()?;
+        let input = zeroed_tensor(input_shape.to_vec())?;
+
+        for output in self.session.outputs() {
+            let dtype = output.dtype();
+            let static_shape: Option<Vec<usize>> = dtype
+                .tensor_shape()
+                .and_then(|shape| shape.iter().map(|&d| usize::try_from(d).ok()).collect());
+
+            match static_shape {
+                Some(shape) if dtype.tensor_type() == Some(TensorElementType::Float32) => {
+                    binding.bind_output(output.name(), zeroed_tensor(shape)?)?;
+                }
+                _ => binding
+                    .bind_output_to_device(output.name(), self.session.allocator().memory_info())?,
+            }
+        }
+
+        self.bound = Some(BoundBuffers {
+            binding,
+            input_name: input_name.to_string(),
+            input_shape: input_shape.to_vec(),
+            input,
+        });
+        Ok(())
+    }
+
+    /// Releases the buffers allocated by `enable_io_binding`.
+    pub fn disable_io_binding(&mut self) {
+        self.bound = None;
+    }
+
+    /// Returns true if `IoBinding` buffers are allocated.
+    #[inline]
+    #[must_use]
+    pub const fn is_io_binding_enabled(&self) -> bool {
+        self.bound.is_some()
+    }
+
+    /// Runs inference through the pre-allocated `IoBinding` buffers.
+    ///
+    /// Falls back to `run_inference_with_input` on `input_name` when binding is disabled or the
+    /// tensor shape differs from the bound one.
+    pub fn run_inference_bound(
+        &mut self,
+        input_name: &str,
+        input_image: &ArrayBase<OwnedRepr<f32>, Dim<[usize; 4]>>,
+    ) -> ort::Result<SessionOutputs<'_>> {
+        let Self { session, bound } = self;
+        match bound {
+            Some(bound) if bound.input_shape == input_image.shape() => {
+                let contiguous = input_image.as_standard_layout();
+                let (_, data) = bound.input.extract_tensor_mut();
+                data.copy_from_slice(contiguous.as_slice().unwrap());
+
+                // Binding copies the input data, so it is refreshed before every run
+                bound
+                    .binding
+                    .bind_input(bound.input_name.as_str(), &bound.input)?;
+                session.run_binding(&bound.binding)
+            }
+            _ => run_with_input(session, input_name, input_image),
+        }
     }
 
     /// Runs inference on the provided input image tensor.
@@ -30,18 +255,42 @@ impl OrtInferenceSession {
         &mut self,
         input_image: &ArrayBase<OwnedRepr<f32>, Dim<[usize; 4]>>,
     ) -> ort::Result<SessionOutputs<'_>> {
-        let shape: Vec<usize> = input_image.shape().to_vec();
-        // Use as_standard_layout to get contiguous data, then avoid extra copy if already contiguous
-        let contiguous = input_image.as_standard_layout();
-        let raw_data: Box<[f32]> = contiguous.as_slice().unwrap().to_vec().into_boxed_slice();
-        let input_tensor: Tensor<f32> = Tensor::from_array((shape, raw_data))?;
+        self.run_inference_with_input("images", input_image)
+    }
+
+    /// Runs inference feeding the input image tensor to the named model input.
+    pub fn run_inference_with_input(
+        &mut self,
+        input_name: &str,
+        input_image: &ArrayBase<OwnedRepr<f32>, Dim<[usize; 4]>>,
+    ) -> ort::Result<SessionOutputs<'_>> {
+        run_with_input(&mut self.session, input_name, input_image)
+    }
+}
 
-        let input_value: SessionInputValue = SessionInputValue::Owned(Value::from(input_tensor));
-        let inputs: Vec<(Cow<str>, SessionInputValue)> =
-            vec![(Cow::Borrowed("images"), input_value)];
+/// Runs the session feeding the input image tensor to the named model input.
+fn run_with_input<'s>(
+    session: &'s mut Session,
+    input_name: &str,
+    input_image: &ArrayBase<OwnedRepr<f32>, Dim<[usize; 4]>>,
+) -> ort::Result<SessionOutputs<'s>> {
+    let shape: Vec<usize> = input_image.shape().to_vec();
+    // Use as_standard_layout to get contiguous data, then avoid extra copy if already contiguous
+    let contiguous = input_image.as_standard_layout();
+    let raw_data: Box<[f32]> = contiguous.as_slice().unwrap().to_vec().into_boxed_slice();
+    let input_tensor: Tensor<f32> = Tensor::from_array((shape, raw_data))?;
 
-        let outputs: SessionOutputs = self.session.run(SessionInputs::from(inputs))?;
+    let input_value: SessionInputValue = SessionInputValue::Owned(Value::from(input_tensor));
+    let inputs: Vec<(Cow<str>, SessionInputValue)> =
+        vec![(Cow::Owned(input_name.to_string()), input_value)];
 
-        Ok(outputs)
-    }
+    let outputs: SessionOutputs = session.run(SessionInputs::from(inputs))?;
+
+    Ok(outputs)
+}
+
+/// Allocates a zero-filled f32 tensor of the given shape.
+fn zeroed_tensor(shape: Vec<usize>) -> ort::Result<Tensor<f32>> {
+    let len = shape.iter().product();
+    Tensor::from_array((shape, vec![0.0f32; len].into_boxed_slice()))
 }
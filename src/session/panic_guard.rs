@@ -0,0 +1,67 @@
+//! Boundary converting panics of the pipeline's dependencies into errors.
+//!
+//! With the `panic-guard` feature, decoding a frame, detecting objects in it and drawing and
+//! saving its outputs each run under `catch_unwind`. A panic in `image`, `raqote` or ONNX
+//! Runtime bindings on a pathological input then fails that image with
+//! `SessionError::Internal` instead of unwinding through the caller, so a server embedding the
+//! session keeps serving other requests. The panic hook still runs and prints the panic.
+//! Without the feature, panics propagate as usual.
+
+use crate::session::SessionError;
+
+/// Runs a pipeline step on `context` (e.g. an image name), converting a panic into
+/// `SessionError::Internal`
+#[cfg(feature = "panic-guard")]
+pub fn guard<T>(
+    context: &str,
+    step: impl FnOnce() -> Result<T, SessionError>,
+) -> Result<T, SessionError> {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(step)).unwrap_or_else(|payload| {
+        Err(SessionError::Internal(format!(
+            "{context}: panicked: {}",
+            panic_message(payload.as_ref())
+        )))
+    })
+}
+
+/// Runs a pipeline step, letting panics propagate
+#[cfg(not(feature = "panic-guard"))]
+#[inline]
+pub fn guard<T>(
+    _context: &str,
+    step: impl FnOnce() -> Result<T, SessionError>,
+) -> Result<T, SessionError> {
+    step()
+}
+
+/// Returns the message of a panic payload, when it is a string
+#[must_use]
+pub fn panic_message(payload: &(dyn std::any::Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("non-string panic payload")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_panic_message() {
+        let payload = std::panic::catch_unwind(|| panic!("bad {}", "input")).unwrap_err();
+        assert_eq!(panic_message(payload.as_ref()), "bad input");
+        assert_eq!(guard("a.png", || Ok(1)).unwrap(), 1);
+    }
+
+    #[cfg(feature = "panic-guard")]
+    #[test]
+    fn test_guard_converts_panics() {
+        let result: Result<(), SessionError> = guard("a.png", || panic!("corrupt chunk"));
+        assert!(matches!(
+            result,
+            Err(SessionError::Internal(message)) if message == "a.png: panicked: corrupt chunk"
+        ));
+    }
+}
@@ -0,0 +1,121 @@
+//! Progress events reported while processing a batch of images, e.g. to render a progress bar
+
+use crate::session::SessionError;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Step of a batch run, `index` counting from 0 and `total` being the number of images
+#[derive(Debug)]
+pub enum ProgressEvent<'a> {
+    /// The image is about to be loaded
+    Started {
+        index: usize,
+        total: usize,
+        path: &'a Path,
+    },
+    /// The image was processed and its outputs saved
+    Finished {
+        index: usize,
+        total: usize,
+        path: &'a Path,
+        detections: usize,
+        elapsed: Duration,
+        eta: Option<Duration>,
+    },
+    /// The image failed, the run going on with the next one
+    Failed {
+        index: usize,
+        total: usize,
+        path: &'a Path,
+        error: &'a SessionError,
+        eta: Option<Duration>,
+    },
+}
+
+impl ProgressEvent<'_> {
+    /// Returns the path of the image the event is about
+    #[must_use]
+    pub const fn path(&self) -> &Path {
+        match self {
+            Self::Started { path, .. }
+            | Self::Finished { path, .. }
+            | Self::Failed { path, .. } => path,
+        }
+    }
+
+    /// Returns the number of images done once this event happened
+    #[must_use]
+    pub const fn completed(&self) -> usize {
+        match self {
+            Self::Started { index, .. } => *index,
+            Self::Finished { index, .. } | Self::Failed { index, .. } => *index + 1,
+        }
+    }
+}
+
+/// Estimates the remaining time of a run from the mean time per completed image
+#[derive(Debug, Clone, Copy)]
+pub struct ProgressTracker {
+    total: usize,
+    completed: usize,
+    started: Instant,
+}
+
+impl ProgressTracker {
+    /// Starts tracking a run over `total` images
+    #[must_use]
+    pub fn new(total: usize) -> Self {
+        Self {
+            total,
+            completed: 0,
+            started: Instant::now(),
+        }
+    }
+
+    /// Records a completed image and returns the estimated remaining time
+    pub fn complete(&mut self) -> Option<Duration> {
+        self.completed = (self.completed + 1).min(self.total);
+        self.eta_after(self.started.elapsed())
+    }
+
+    /// Returns the remaining time estimated after `elapsed`, `None` before any image completed
+    #[must_use]
+    pub fn eta_after(&self, elapsed: Duration) -> Option<Duration> {
+        let completed = u32::try_from(self.completed).ok().filter(|&n| n > 0)?;
+        let remaining = u32::try_from(self.total - self.completed).ok()?;
+        Some(elapsed / completed * remaining)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracker_eta() {
+        let mut tracker = ProgressTracker::new(4);
+        assert_eq!(tracker.eta_after(Duration::from_secs(1)), None);
+        tracker.complete();
+        assert_eq!(
+            tracker.eta_after(Duration::from_secs(2)),
+            Some(Duration::from_secs(6))
+        );
+        for _ in 0..5 {
+            tracker.complete();
+        }
+        assert_eq!(
+            tracker.eta_after(Duration::from_secs(8)),
+            Some(Duration::ZERO)
+        );
+
+        let error = SessionError::Timeout(Duration::from_secs(1));
+        let event = ProgressEvent::Failed {
+            index: 2,
+            total: 4,
+            path: Path::new("a.png"),
+            error: &error,
+            eta: None,
+        };
+        assert_eq!((event.completed(), event.path()), (3, Path::new("a.png")));
+    }
+}
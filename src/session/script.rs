@@ -0,0 +1,291 @@
+//! Rhai scripts filtering, annotating and reporting on the detections of every image
+
+use crate::class::registry::ClassRegistry;
+use crate::detection::{BoundingBox, LevelEstimate};
+use crate::session::middleware::{DetectionMiddleware, Flow, StageContext};
+use rhai::{AST, Array, Dynamic, Engine, EvalAltResult, FLOAT, INT, Map, Scope};
+use std::cell::RefCell;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+/// Script function called with the name and the final boxes of every image
+pub const ENTRY_POINT: &str = "on_detections";
+
+/// Errors that can occur while loading a script
+#[derive(Debug, thiserror::Error)]
+pub enum ScriptError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Script compilation failed: {0}")]
+    Compile(#[from] rhai::ParseError),
+    #[error("Script does not define `fn {ENTRY_POINT}(image, boxes)`")]
+    MissingEntryPoint,
+}
+
+/// Value emitted by a script with `emit(value)` while processing an image
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptOutput {
+    pub image_name: String,
+    pub value: serde_json::Value,
+}
+
+/// Middleware running a Rhai script on the final boxes of every image.
+///
+/// The script defines `fn on_detections(image, boxes)`, where `boxes` is an array of maps with
+/// `x1`, `y1`, `x2`, `y2`, `class_id`, `class_name`, `confidence` and, for boxes with an
+/// estimated level, `level` and `level_confidence`. Coordinates are in the letterboxed input
+/// space. Returning an array replaces the boxes, returning nothing keeps them, `emit(value)`
+/// reports a custom output and `throw "reason"` fails the image.
+pub struct ScriptMiddleware {
+    engine: Engine,
+    ast: AST,
+    classes: ClassRegistry,
+    emitted: Rc<RefCell<Vec<Dynamic>>>,
+    sender: Option<Sender<ScriptOutput>>,
+}
+
+impl ScriptMiddleware {
+    /// Compiles a script, which must define the `on_detections` entry point
+    pub fn compile(source: &str) -> Result<Self, ScriptError> {
+        let emitted = Rc::new(RefCell::new(Vec::new()));
+        let mut engine = Engine::new();
+        let sink = Rc::clone(&emitted);
+        engine.register_fn("emit", move |value: Dynamic| sink.borrow_mut().push(value));
+
+        let ast = engine.compile(source)?;
+        if !ast
+            .iter_functions()
+            .any(|f| f.name == ENTRY_POINT && f.params.len() == 2)
+        {
+            return Err(ScriptError::MissingEntryPoint);
+        }
+
+        Ok(Self {
+            engine,
+            ast,
+            classes: ClassRegistry::default(),
+            emitted,
+            sender: None,
+        })
+    }
+
+    /// Loads and compiles a script file
+    pub fn load(path: &Path) -> Result<Self, ScriptError> {
+        Self::compile(&fs::read_to_string(path)?)
+    }
+
+    /// Names the classes of the boxes handed to the script after `classes`
+    #[must_use]
+    pub fn with_classes(mut self, classes: ClassRegistry) -> Self {
+        self.classes = classes;
+        self
+    }
+
+    /// Returns the middleware together with the receiving end of the values it emits
+    #[must_use]
+    pub fn with_outputs(mut self) -> (Self, Receiver<ScriptOutput>) {
+        let (sender, receiver) = mpsc::channel();
+        self.sender = Some(sender);
+        (self, receiver)
+    }
+
+    /// Runs the script on the boxes of an image, returning the reason it failed
+    fn run(&self, image_name: &str, boxes: &mut Vec<BoundingBox>) -> Result<(), String> {
+        self.emitted.borrow_mut().clear();
+        let input: Array = boxes
+            .iter()
+            .map(|bbox| box_to_map(bbox, &self.classes).into())
+            .collect();
+
+        let result = self
+            .engine
+            .call_fn::<Dynamic>(
+                &mut Scope::new(),
+                &self.ast,
+                ENTRY_POINT,
+                (image_name.to_string(), input),
+            )
+            .map_err(|e| match *e {
+                EvalAltResult::ErrorRuntime(reason, _) => reason.to_string(),
+                e => e.to_string(),
+            })?;
+
+        if !result.is_unit() {
+            let returned = result
+                .try_cast::<Array>()
+                .ok_or_else(|| format!("{ENTRY_POINT} must return an array of boxes"))?;
+            *boxes = returned
+                .into_iter()
+                .map(map_to_box)
+                .collect::<Result<_, _>>()?;
+        }
+
+        if let Some(sender) = &self.sender {
+            for value in self.emitted.borrow_mut().drain(..) {
+                let value = rhai::serde::from_dynamic(&value)
+                    .unwrap_or_else(|_| serde_json::Value::String(value.to_string()));
+                // A dropped receiver only means nobody listens to the outputs anymore
+                let _ = sender.send(ScriptOutput {
+                    image_name: image_name.to_string(),
+                    value,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl DetectionMiddleware for ScriptMiddleware {
+    fn pre_output(&mut self, context: &StageContext<'_>, boxes: &mut Vec<BoundingBox>) -> Flow {
+        match self.run(context.image_name, boxes) {
+            Ok(()) => Flow::Continue,
+            Err(reason) => Flow::Veto(format!("script: {reason}")),
+        }
+    }
+}
+
+/// Converts a box to the map handed to scripts
+fn box_to_map(bbox: &BoundingBox, classes: &ClassRegistry) -> Map {
+    let mut map = Map::new();
+    map.insert("x1".into(), Dynamic::from_float(bbox.x1.into()));
+    map.insert("y1".into(), Dynamic::from_float(bbox.y1.into()));
+    map.insert("x2".into(), Dynamic::from_float(bbox.x2.into()));
+    map.insert("y2".into(), Dynamic::from_float(bbox.y2.into()));
+    map.insert("class_id".into(), Dynamic::from_int(bbox.class_id as INT));
+    map.insert("class_name".into(), classes.name(bbox.class_id).into());
+    map.insert(
+        "confidence".into(),
+        Dynamic::from_float(bbox.confidence.into()),
+    );
+    if let Some(level) = bbox.level {
+        map.insert("level".into(), Dynamic::from_int(level.level.into()));
+        map.insert(
+            "level_confidence".into(),
+            Dynamic::from_float(level.confidence.into()),
+        );
+    }
+    map
+}
+
+/// Converts a map returned by a script back to a box
+fn map_to_box(value: Dynamic) -> Result<BoundingBox, String> {
+    let map = value
+        .try_cast::<Map>()
+        .ok_or_else(|| format!("{ENTRY_POINT} must return an array of box maps"))?;
+    let number = |key: &str| {
+        map.get(key)
+            .and_then(|v| {
+                v.as_float()
+                    .ok()
+                    .or_else(|| v.as_int().ok().map(|i| i as FLOAT))
+            })
+            .map(|v| v as f32)
+            .ok_or_else(|| format!("box without a numeric `{key}`"))
+    };
+    let class_id = map
+        .get("class_id")
+        .and_then(|v| v.as_int().ok())
+        .and_then(|id| usize::try_from(id).ok())
+        .ok_or_else(|| "box without a valid `class_id`".to_string())?;
+
+    let mut bbox = BoundingBox::new(
+        number("x1")?,
+        number("y1")?,
+        number("x2")?,
+        number("y2")?,
+        class_id,
+        number("confidence")?,
+    );
+    if let Some(level) = map.get("level").and_then(|v| v.as_int().ok()) {
+        bbox.level = Some(LevelEstimate {
+            level: u32::try_from(level).map_err(|_| format!("invalid level {level}"))?,
+            confidence: number("level_confidence").unwrap_or(1.0),
+        });
+    }
+    Ok(bbox)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTEXT: StageContext<'static> = StageContext {
+        image_name: "village.png",
+        input_size: (640, 640),
+    };
+
+    fn sample_boxes() -> Vec<BoundingBox> {
+        vec![
+            BoundingBox::new(0.0, 0.0, 10.0, 10.0, 0, 0.9),
+            BoundingBox::new(20.0, 20.0, 30.0, 30.0, 1, 0.3),
+        ]
+    }
+
+    #[test]
+    fn test_script_filters_and_emits() {
+        let script = ScriptMiddleware::compile(
+            r#"
+            fn on_detections(image, boxes) {
+                let kept = boxes.filter(|b| b.confidence >= 0.5);
+                emit(#{ image: image, gold: boxes.filter(|b| b.class_name == "Gold Storage").len() });
+                kept
+            }
+            "#,
+        )
+        .unwrap();
+        let (mut script, outputs) = script.with_outputs();
+
+        let mut boxes = sample_boxes();
+        assert_eq!(script.pre_output(&CONTEXT, &mut boxes), Flow::Continue);
+        assert_eq!(boxes, vec![sample_boxes()[0]]);
+
+        let output = outputs.try_recv().unwrap();
+        assert_eq!(output.image_name, "village.png");
+        assert_eq!(output.value["gold"], 1);
+        assert_eq!(output.value["image"], "village.png");
+    }
+
+    #[test]
+    fn test_unit_result_keeps_boxes_and_throw_vetoes() {
+        let mut script = ScriptMiddleware::compile(
+            r#"
+            fn on_detections(image, boxes) {
+                if boxes.len() > 1 { throw "too many storages"; }
+            }
+            "#,
+        )
+        .unwrap();
+
+        let mut boxes = vec![sample_boxes()[0]];
+        assert_eq!(script.pre_output(&CONTEXT, &mut boxes), Flow::Continue);
+        assert_eq!(boxes.len(), 1);
+
+        let mut boxes = sample_boxes();
+        assert_eq!(
+            script.pre_output(&CONTEXT, &mut boxes),
+            Flow::Veto("script: too many storages".to_string())
+        );
+    }
+
+    #[test]
+    fn test_invalid_scripts() {
+        assert!(matches!(
+            ScriptMiddleware::compile("fn other(boxes) { boxes }"),
+            Err(ScriptError::MissingEntryPoint)
+        ));
+        assert!(matches!(
+            ScriptMiddleware::compile("fn on_detections(image, boxes) {"),
+            Err(ScriptError::Compile(_))
+        ));
+
+        let mut script =
+            ScriptMiddleware::compile("fn on_detections(image, boxes) { [#{ x1: 1 }] }").unwrap();
+        let mut boxes = sample_boxes();
+        assert!(matches!(
+            script.pre_output(&CONTEXT, &mut boxes),
+            Flow::Veto(reason) if reason.contains("x1") || reason.contains("class_id")
+        ));
+    }
+}
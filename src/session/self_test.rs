@@ -0,0 +1,74 @@
+//! Synthetic self-test input and output sanity checks used to tell whether a model is ready
+
+use crate::detection::BoundingBox;
+use image::{DynamicImage, Rgb, RgbImage};
+
+/// Side of the squares of the synthetic self-test pattern, in pixels
+const PATTERN_CELL: u32 = 32;
+
+/// Builds the synthetic self-test image: a colored checkerboard over a horizontal gradient
+#[must_use]
+pub fn synthetic_image(width: u32, height: u32) -> DynamicImage {
+    let image = RgbImage::from_fn(width, height, |x, y| {
+        let shade = (x * 255 / width.max(1)) as u8;
+        if (x / PATTERN_CELL + y / PATTERN_CELL).is_multiple_of(2) {
+            Rgb([shade, 96, 255 - shade])
+        } else {
+            Rgb([212, 175, 55])
+        }
+    });
+    DynamicImage::ImageRgb8(image)
+}
+
+/// Checks that parsed detections are plausible for an input of `input_size`: finite, with
+/// confidences in `[0, 1]`, non-inverted corners and centers inside the input
+pub fn check_output_sanity(boxes: &[BoundingBox], input_size: (u32, u32)) -> Result<(), String> {
+    let (width, height) = (input_size.0 as f32, input_size.1 as f32);
+    for bbox in boxes {
+        let values = [bbox.x1, bbox.y1, bbox.x2, bbox.y2, bbox.confidence];
+        if values.iter().any(|v| !v.is_finite()) {
+            return Err(format!("non-finite detection {bbox:?}"));
+        }
+        if !(0.0..=1.0).contains(&bbox.confidence) {
+            return Err(format!("confidence {} outside [0, 1]", bbox.confidence));
+        }
+        if bbox.x2 < bbox.x1 || bbox.y2 < bbox.y1 {
+            return Err(format!("inverted detection {bbox:?}"));
+        }
+        let (cx, cy) = bbox.center();
+        if !(0.0..=width).contains(&cx) || !(0.0..=height).contains(&cy) {
+            return Err(format!("detection center ({cx}, {cy}) outside the input"));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_synthetic_image() {
+        let image = synthetic_image(64, 48).to_rgb8();
+        assert_eq!(image.dimensions(), (64, 48));
+        assert_eq!(image.get_pixel(0, 0).0, [0, 96, 255]);
+        assert_eq!(image.get_pixel(40, 0).0, [212, 175, 55]);
+    }
+
+    #[test]
+    fn test_check_output_sanity() {
+        let valid = BoundingBox::new(10.0, 10.0, 50.0, 60.0, 0, 0.8);
+        assert!(check_output_sanity(&[], (640, 640)).is_ok());
+        assert!(check_output_sanity(&[valid], (640, 640)).is_ok());
+
+        let invalid = [
+            BoundingBox::new(f32::NAN, 10.0, 50.0, 60.0, 0, 0.8),
+            BoundingBox::new(10.0, 10.0, 50.0, 60.0, 0, 1.5),
+            BoundingBox::new(50.0, 10.0, 10.0, 60.0, 0, 0.8),
+            BoundingBox::new(700.0, 10.0, 720.0, 60.0, 0, 0.8),
+        ];
+        for bbox in invalid {
+            assert!(check_output_sanity(&[valid, bbox], (640, 640)).is_err());
+        }
+    }
+}
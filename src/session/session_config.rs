@@ -1,4 +1,19 @@
+use crate::analysis::fullness::FullnessConfig;
+use crate::class::registry::ClassRegistry;
+use crate::detection::BoundingBox;
+use crate::detection::nms::{GroupNms, NmsStrategy};
+use crate::detection::output::OutputFormat;
 use crate::detection::visualization::DrawConfig;
+use crate::detection::watermark::Watermark;
+use crate::image::tiling::TileConfig;
+use crate::session::execution::ExecutionConfig;
+use crate::session::input_validation::InputRange;
+use image::imageops::FilterType;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+/// Input size of Ultralytics exports, replaced by the size declared by the model when left as is
+pub const DEFAULT_INPUT_SIZE: (u32, u32) = (640, 640);
 
 /// Configuration for YOLO session settings.
 /// Includes parameters for input size, NMS settings, confidence thresholds, and drawing configurations.
@@ -6,38 +21,126 @@ use crate::detection::visualization::DrawConfig;
 pub struct SessionConfig {
     pub input_size: (u32, u32),
     pub use_nms: bool,
+    pub nms_strategy: NmsStrategy,
     pub nms_threshold: f32,
     pub confidence_threshold: f32,
     pub use_per_class_nms: bool,
+    pub group_nms: Option<GroupNms>,
     pub draw_config: DrawConfig,
+    pub padding_color: Option<[u8; 3]>,
+    pub validate_input: bool,
+    pub input_range: InputRange,
+    pub use_io_binding: bool,
+    pub image_timeout: Option<Duration>,
+    pub batch_size: usize,
+    pub execution: ExecutionConfig,
+    pub classes: ClassRegistry,
+    pub watermark: Option<Watermark>,
+    pub output_format: OutputFormat,
+    pub tiling: Option<TileConfig>,
+    pub class_filter: Option<HashSet<usize>>,
+    pub per_class_confidence: HashMap<usize, f32>,
+    pub max_detections: Option<usize>,
+    pub top_k_per_class: Option<usize>,
+    pub resize_filter: FilterType,
+    pub fullness: Option<FullnessConfig>,
 }
 
 impl Default for SessionConfig {
     fn default() -> Self {
         Self {
-            input_size: (640, 640),             // Width, Height
-            use_nms: true,                      // Whether to apply Non-Maximum Suppression
-            nms_threshold: 0.45,                // IoU threshold for NMS
-            confidence_threshold: 0.25,         // Minimum confidence for detections
-            use_per_class_nms: false,           // Whether to apply NMS per class
+            input_size: DEFAULT_INPUT_SIZE,        // Width, Height
+            use_nms: true,                         // Whether to apply Non-Maximum Suppression
+            nms_strategy: NmsStrategy::Hard, // Drop overlapping boxes, group NMS only applies here
+            nms_threshold: 0.45,             // IoU threshold for NMS
+            confidence_threshold: 0.25,      // Minimum confidence for detections
+            use_per_class_nms: false,        // Whether to apply NMS per class
+            group_nms: None,                 // Groups whose classes suppress each other
             draw_config: DrawConfig::default(), // Default drawing configuration
+            padding_color: None,             // Letterbox color, defaults to the model's
+            validate_input: true,            // Check the input tensor against the model
+            input_range: InputRange::default(), // Pixels scaled to [0, 1]
+            use_io_binding: false,           // Reuse pre-allocated input/output buffers
+            image_timeout: None,             // Per-image wall-clock budget
+            batch_size: 8,                   // Images per batched inference
+            execution: ExecutionConfig::default(), // CPU execution
+            classes: ClassRegistry::default(), // Built-in Clash of Clans classes
+            watermark: None,                 // Provenance footer on annotated images
+            output_format: OutputFormat::Json, // Detection files written next to annotated images
+            tiling: None,                    // Sliced inference of images larger than a tile
+            class_filter: None,              // Class ids kept, all when unset
+            per_class_confidence: HashMap::new(), // Thresholds overriding confidence_threshold
+            max_detections: None,            // Boxes kept after NMS, the most confident first
+            top_k_per_class: None,           // Boxes kept after NMS for each class
+            resize_filter: FilterType::Lanczos3, // Letterbox resampling, sharpest but slowest
+            fullness: None,                  // Storage fill levels estimated after NMS
         }
     }
 }
 
+impl SessionConfig {
+    /// Returns the minimum confidence of the detections of a class
+    #[must_use]
+    pub fn confidence_threshold_of(&self, class_id: usize) -> f32 {
+        self.per_class_confidence
+            .get(&class_id)
+            .copied()
+            .unwrap_or(self.confidence_threshold)
+    }
+
+    /// Returns the threshold the output parsers apply, low enough for every per-class threshold
+    #[must_use]
+    pub fn parse_threshold(&self) -> f32 {
+        self.per_class_confidence
+            .values()
+            .copied()
+            .fold(self.confidence_threshold, f32::min)
+    }
+
+    /// Returns true if a parsed detection passes the class filter and the threshold of its class
+    #[must_use]
+    pub fn accepts(&self, bbox: &BoundingBox) -> bool {
+        self.class_filter
+            .as_ref()
+            .is_none_or(|classes| classes.contains(&bbox.class_id))
+            && (self.per_class_confidence.is_empty()
+                || bbox.confidence >= self.confidence_threshold_of(bbox.class_id))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::class::palette::Palette;
+    use crate::detection::label::LabelPosition;
+    use crate::detection::nms::SoftNmsMethod;
+    use crate::session::execution::ExecutionProvider;
 
     #[test]
     fn test_session_config_default() {
         let config = SessionConfig::default();
         assert_eq!(config.input_size, (640, 640));
         assert!(config.use_nms);
+        assert_eq!(config.nms_strategy, NmsStrategy::Hard);
         assert_eq!(config.nms_threshold, 0.45);
         assert_eq!(config.confidence_threshold, 0.25);
         assert!(!config.use_per_class_nms);
         assert_eq!(config.draw_config, DrawConfig::default());
+        assert!(config.padding_color.is_none());
+        assert!(config.validate_input);
+        assert_eq!(config.input_range, InputRange::UnitInterval);
+        assert!(!config.use_io_binding);
+        assert!(config.image_timeout.is_none());
+        assert_eq!(config.classes, ClassRegistry::default());
+        assert!(config.watermark.is_none());
+        assert_eq!(config.output_format, OutputFormat::Json);
+        assert!(config.tiling.is_none());
+        assert!(config.class_filter.is_none());
+        assert!(config.per_class_confidence.is_empty());
+        assert_eq!(config.max_detections, None);
+        assert_eq!(config.top_k_per_class, None);
+        assert_eq!(config.resize_filter, FilterType::Lanczos3);
+        assert!(config.fullness.is_none());
     }
 
     #[test]
@@ -45,20 +148,68 @@ mod tests {
         let config = SessionConfig {
             input_size: (800, 600),
             use_nms: false,
+            nms_strategy: NmsStrategy::Soft {
+                sigma: 0.5,
+                method: SoftNmsMethod::Gaussian,
+            },
             nms_threshold: 0.5,
             confidence_threshold: 0.3,
             use_per_class_nms: true,
+            group_nms: Some(GroupNms::default()),
             draw_config: DrawConfig {
                 line_width: 0.0,
                 alpha_blend: false,
+                show_labels: false,
                 show_confidence: false,
                 font_size: 0.0,
+                label_position: LabelPosition::Inside,
+                label_background: false,
+                text_color: (0, 0, 0, 255),
+                palette: Palette::Protanopia,
+                adaptive_density: false,
+                density_threshold: 0,
+                groups: None,
             },
+            padding_color: Some([0, 0, 0]),
+            validate_input: false,
+            input_range: InputRange::Raw,
+            use_io_binding: true,
+            image_timeout: Some(Duration::from_secs(5)),
+            batch_size: 4,
+            execution: ExecutionConfig::with_provider(ExecutionProvider::Cuda),
+            classes: ClassRegistry::new(["Town Hall"]),
+            watermark: Some(Watermark::new("yolov8 test")),
+            output_format: OutputFormat::Csv,
+            tiling: Some(TileConfig::default()),
+            class_filter: Some(HashSet::from([1])),
+            per_class_confidence: HashMap::from([(0, 0.6)]),
+            max_detections: Some(300),
+            top_k_per_class: Some(50),
+            resize_filter: FilterType::Triangle,
+            fullness: Some(FullnessConfig::default()),
         };
         assert_eq!(config.input_size, (800, 600));
         assert!(!config.use_nms);
         assert_eq!(config.nms_threshold, 0.5);
         assert_eq!(config.confidence_threshold, 0.3);
         assert!(config.use_per_class_nms);
+        assert_eq!(config.padding_color, Some([0, 0, 0]));
+    }
+
+    #[test]
+    fn test_class_filter_and_per_class_confidence() {
+        let mut config = SessionConfig {
+            per_class_confidence: HashMap::from([(0, 0.6), (2, 0.1)]),
+            ..SessionConfig::default()
+        };
+        assert_eq!(config.parse_threshold(), 0.1);
+        assert!(!config.accepts(&BoundingBox::new(0.0, 0.0, 1.0, 1.0, 0, 0.5)));
+        assert!(config.accepts(&BoundingBox::new(0.0, 0.0, 1.0, 1.0, 1, 0.3)));
+        assert!(!config.accepts(&BoundingBox::new(0.0, 0.0, 1.0, 1.0, 1, 0.2)));
+        assert!(config.accepts(&BoundingBox::new(0.0, 0.0, 1.0, 1.0, 2, 0.15)));
+
+        config.class_filter = Some(HashSet::from([1]));
+        assert!(!config.accepts(&BoundingBox::new(0.0, 0.0, 1.0, 1.0, 2, 0.9)));
+        assert!(config.accepts(&BoundingBox::new(0.0, 0.0, 1.0, 1.0, 1, 0.9)));
     }
 }
@@ -0,0 +1,101 @@
+//! Soft problems noticed while processing an image, reported without failing it
+
+use crate::session::input_validation::InputRange;
+use std::fmt;
+
+/// Problem that does not prevent an image from being processed but may affect its results
+#[derive(Debug, Clone, PartialEq)]
+pub enum Warning {
+    /// Input tensor values are within the expected range but look normalized differently
+    SuspiciousNormalization {
+        min: f32,
+        max: f32,
+        expected: InputRange,
+    },
+    /// Boxes reaching past the image were clamped to its borders
+    BoxesClamped { count: usize },
+    /// Detections of a class id the class registry has no name for
+    UnknownClass { class_id: usize, count: usize },
+    /// File that could not be decoded, skipped by a directory run
+    UnreadableImage { error: String },
+}
+
+impl Warning {
+    /// Returns the kind of the warning, stable across releases for filtering and counting
+    #[inline]
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::SuspiciousNormalization { .. } => "suspicious_normalization",
+            Self::BoxesClamped { .. } => "boxes_clamped",
+            Self::UnknownClass { .. } => "unknown_class",
+            Self::UnreadableImage { .. } => "unreadable_image",
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SuspiciousNormalization { min, max, expected } => write!(
+                f,
+                "input tensor values span [{min:.3}, {max:.3}], which is unusual for {} input; \
+                 check the normalization config",
+                expected.as_str()
+            ),
+            Self::BoxesClamped { count } => {
+                write!(f, "{count} box(es) clamped to the image borders")
+            }
+            Self::UnknownClass { class_id, count } => {
+                write!(
+                    f,
+                    "{count} detection(s) of class id {class_id}, missing from the label map"
+                )
+            }
+            Self::UnreadableImage { error } => write!(f, "skipped unreadable image: {error}"),
+        }
+    }
+}
+
+/// Warning raised while processing a named image
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageWarning {
+    pub image_name: String,
+    pub warning: Warning,
+}
+
+impl fmt::Display for ImageWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: warning: {}", self.image_name, self.warning)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warning_kinds_and_messages() {
+        let warning = ImageWarning {
+            image_name: "village.png".to_string(),
+            warning: Warning::UnknownClass {
+                class_id: 7,
+                count: 2,
+            },
+        };
+        assert_eq!(warning.warning.as_str(), "unknown_class");
+        assert_eq!(
+            warning.to_string(),
+            "village.png: warning: 2 detection(s) of class id 7, missing from the label map"
+        );
+        assert!(
+            Warning::SuspiciousNormalization {
+                min: 0.0,
+                max: 0.004,
+                expected: InputRange::UnitInterval,
+            }
+            .to_string()
+            .contains("[0.000, 0.004]")
+        );
+    }
+}
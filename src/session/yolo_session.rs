@@ -1,26 +1,86 @@
 use crate::detection::BoundingBox;
-use crate::detection::nms::{nms, nms_per_class};
+use crate::detection::nms::{
+    NmsStrategy, limit_detections, nms, nms_per_class, nms_per_group, soft_nms, soft_nms_per_class,
+};
 use crate::detection::output::OutputFormat;
+use crate::detection::tile_overlay::draw_tile_overlay;
 use crate::detection::visualization::DrawConfig;
-use crate::image::image_util::load_image_u8_default;
+use crate::image::image_config::ImageConfig;
+use crate::image::image_size::ImageSize;
+use crate::image::image_util::content_fingerprint;
 use crate::image::image_util::normalize_image_f32;
+use crate::image::image_util::preprocess_image_u8;
+use crate::image::letterbox::LetterboxTransform;
 use crate::image::loaded_image::LoadedImageU8;
+use crate::image::pyramid::PyramidCache;
+use crate::image::tiling::TileConfig;
 use crate::model::inference::{YoloInference, create_inference};
+use crate::model::level_classifier::LevelClassifier;
 use crate::model::yolo_type::YoloType;
+use crate::report::detection::{DetectionReport, StageTimings};
+#[cfg(feature = "encryption")]
+use crate::report::encryption::OutputKey;
+use crate::report::html::{HtmlReport, ReportEntry};
+use crate::report::metrics::{PipelineMetrics, StepTimer};
 use crate::session::SessionError;
-use crate::session::ort_inference_session::OrtInferenceSession;
-use crate::session::session_config::SessionConfig;
+use crate::session::benchmark::LatencyStats;
+use crate::session::best_effort::{BestEffort, BestEffortResult, Downgrade};
+use crate::session::input_validation::{check_normalization, validate_tensor_range};
+use crate::session::middleware::{DetectionMiddleware, MiddlewareChain, Stage, StageContext};
+use crate::session::model_file::ModelFile;
+use crate::session::model_info::ModelInfo;
+use crate::session::ort_inference_session::{OrtInferenceSession, SharedModel};
+use crate::session::panic_guard::guard;
+use crate::session::progress::{ProgressEvent, ProgressTracker};
+use crate::session::self_test::{check_output_sanity, synthetic_image};
+use crate::session::session_config::{DEFAULT_INPUT_SIZE, SessionConfig};
+use crate::session::warning::{ImageWarning, Warning};
+use crate::sink::DetectionSink;
+#[cfg(feature = "encryption")]
+use crate::sink::file::write_encrypted_outputs;
+use crate::sink::file::write_outputs;
+use crate::source::file::DirectoryOptions;
+use crate::source::{Frame, ImageSource};
+use crate::store::ImageRecord;
+#[cfg(feature = "sqlite")]
+use crate::store::sqlite::SqliteStore;
+use image::imageops::FilterType;
 use image::{DynamicImage, RgbImage};
-use ndarray::Array4;
+use ndarray::{Array4, Axis, Slice};
 use ort::session::SessionOutputs;
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Index, letterboxed image, input tensor and preprocessing time of a frame awaiting batched
+/// inference
+type PreparedFrame = (usize, RgbImage, Array4<f32>, Duration);
+
+/// Path of a file processed by `process_directory` with its outcome
+pub type FileResult = (PathBuf, Result<DetectionReport, SessionError>);
 
 /// YOLO session struct for managing model inference and image processing
 #[must_use]
 pub struct YoloSession {
     session: OrtInferenceSession,
+    standby_sessions: BTreeMap<(u32, u32), OrtInferenceSession>,
+    input_size_override: Option<(u32, u32)>,
+    metrics: PipelineMetrics,
+    model_info: ModelInfo,
     config: SessionConfig,
+    model_type: YoloType,
     inference: Box<dyn YoloInference>,
+    level_classifier: Option<LevelClassifier>,
+    pyramid: Option<PyramidCache>,
+    sinks: Vec<Box<dyn DetectionSink>>,
+    middleware: MiddlewareChain,
+    warnings: Vec<ImageWarning>,
+    unflushed: bool,
+    #[cfg(feature = "sqlite")]
+    results_store: Option<SqliteStore>,
+    #[cfg(feature = "encryption")]
+    output_key: Option<OutputKey>,
 }
 
 impl YoloSession {
@@ -35,15 +95,9 @@ impl YoloSession {
         model_type: &YoloType,
         config: SessionConfig,
     ) -> Result<Self, SessionError> {
-        let session = OrtInferenceSession::new(Path::new(model_path))
+        let session = OrtInferenceSession::with_execution(Path::new(model_path), &config.execution)
             .map_err(|e| SessionError::Io(std::io::Error::other(e)))?;
-        let inference = create_inference(&model_type);
-
-        Ok(Self {
-            session,
-            config,
-            inference,
-        })
+        Self::from_session(session, model_type, config)
     }
 
     /// Creates a new YOLO session with default configuration from model bytes
@@ -57,28 +111,488 @@ impl YoloSession {
         model_type: &YoloType,
         config: SessionConfig,
     ) -> Result<Self, SessionError> {
-        let session = OrtInferenceSession::from_bytes(model_bytes)
+        let session =
+            OrtInferenceSession::from_bytes_with_execution(model_bytes, &config.execution)
+                .map_err(|e| SessionError::Io(std::io::Error::other(e)))?;
+        Self::from_session(session, model_type, config)
+    }
+
+    /// Creates a new YOLO session with default configuration from a memory-mapped model file
+    pub fn from_mmap(model_path: &str, model_type: YoloType) -> Result<Self, SessionError> {
+        Self::from_mmap_with_config(model_path, &model_type, SessionConfig::default())
+    }
+
+    /// Creates a new YOLO session with custom configuration from a memory-mapped model file,
+    /// falling back to a buffered read where mapping is unavailable
+    pub fn from_mmap_with_config(
+        model_path: &str,
+        model_type: &YoloType,
+        config: SessionConfig,
+    ) -> Result<Self, SessionError> {
+        let model = ModelFile::open(Path::new(model_path))?;
+        Self::from_bytes_with_config(&model, model_type, config)
+    }
+
+    /// Creates a new YOLO session with default configuration from a model at `url`.
+    ///
+    /// The model is verified against the checksum published at `<url>.sha256` and cached in
+    /// `cache_dir`, so later sessions load it without downloading it again.
+    #[cfg(feature = "http")]
+    pub fn from_url(
+        url: &str,
+        cache_dir: &Path,
+        model_type: YoloType,
+    ) -> Result<Self, SessionError> {
+        Self::from_url_with_config(url, None, cache_dir, &model_type, SessionConfig::default())
+    }
+
+    /// Creates a new YOLO session with custom configuration from a model at `url`, verified
+    /// against `sha256` if given and against `<url>.sha256` otherwise
+    #[cfg(feature = "http")]
+    pub fn from_url_with_config(
+        url: &str,
+        sha256: Option<&str>,
+        cache_dir: &Path,
+        model_type: &YoloType,
+        config: SessionConfig,
+    ) -> Result<Self, SessionError> {
+        let path = crate::session::model_download::fetch_model(url, sha256, cache_dir)?;
+        let model = ModelFile::open(&path)?;
+        Self::from_bytes_with_config(&model, model_type, config)
+    }
+
+    /// Creates a new YOLO session sharing the weights of a model with the other sessions created from it
+    pub fn from_shared_model(
+        model: &SharedModel,
+        model_type: &YoloType,
+        config: SessionConfig,
+    ) -> Result<Self, SessionError> {
+        let session = model
+            .session(&config.execution)
             .map_err(|e| SessionError::Io(std::io::Error::other(e)))?;
-        let inference = create_inference(&model_type);
+        Self::from_session(session, model_type, config)
+    }
+
+    /// Wraps a freshly created inference session
+    fn from_session(
+        mut session: OrtInferenceSession,
+        model_type: &YoloType,
+        mut config: SessionConfig,
+    ) -> Result<Self, SessionError> {
+        let inference = create_inference(model_type)?;
+        let model_info = ModelInfo::from_session(&session, model_type);
+        if config.input_size == DEFAULT_INPUT_SIZE
+            && let Some(input_size) = model_info.input_size()
+        {
+            config.input_size = input_size;
+        }
+        model_info.validate(&config)?;
+        prepare_session(
+            &mut session,
+            &config,
+            model_info.input_name(),
+            config.input_size,
+        )?;
 
         Ok(Self {
             session,
+            standby_sessions: BTreeMap::new(),
+            input_size_override: None,
+            metrics: PipelineMetrics::default(),
+            model_info,
             config,
+            model_type: model_type.clone(),
             inference,
+            level_classifier: None,
+            pyramid: None,
+            sinks: Vec::new(),
+            middleware: MiddlewareChain::default(),
+            warnings: Vec::new(),
+            unflushed: false,
+            #[cfg(feature = "sqlite")]
+            results_store: None,
+            #[cfg(feature = "encryption")]
+            output_key: None,
         })
     }
 
+    /// Attaches a secondary level classifier run on every detection crop
+    pub fn with_level_classifier(mut self, classifier: LevelClassifier) -> Self {
+        self.level_classifier = Some(classifier);
+        self
+    }
+
+    /// Caches the letterboxed resizes of up to `capacity` images, so passes that revisit
+    /// the same pixels at another input size, filter or tile reuse earlier resizes
+    pub fn with_pyramid_cache(mut self, capacity: usize) -> Self {
+        self.pyramid = Some(PyramidCache::new(capacity));
+        self
+    }
+
+    /// Detaches and returns the level classifier, releasing it with the session if dropped
+    pub fn take_level_classifier(&mut self) -> Option<LevelClassifier> {
+        self.level_classifier.take()
+    }
+
+    /// Returns the tensor names, input shape and class count read from the model
+    #[inline]
+    #[must_use]
+    pub const fn model_info(&self) -> &ModelInfo {
+        &self.model_info
+    }
+
+    /// Returns the model type of the session
+    #[inline]
+    #[must_use]
+    pub const fn model_type(&self) -> &YoloType {
+        &self.model_type
+    }
+
+    /// Returns the letterbox padding color, from the configuration or the model type default
+    #[inline]
+    #[must_use]
+    pub fn padding_color(&self) -> [u8; 3] {
+        self.config
+            .padding_color
+            .unwrap_or_else(|| self.model_type.default_padding_color())
+    }
+
+    /// Returns a fingerprint of the settings affecting detection results, to tell runs apart
+    #[must_use]
+    pub fn run_fingerprint(&self) -> String {
+        let mut settings = format!(
+            "model={:?};input={:?};padding={:?};nms={}/{}/{};group_nms={:?};confidence={}",
+            self.model_type,
+            self.input_sizes(),
+            self.padding_color(),
+            self.config.use_nms,
+            self.config.use_per_class_nms,
+            self.config.nms_threshold,
+            self.config
+                .group_nms
+                .as_ref()
+                .map(|group_nms| &group_nms.thresholds),
+            self.config.confidence_threshold,
+        );
+        // Only Soft-NMS is spelled out, keeping the fingerprints of hard NMS runs unchanged
+        if let NmsStrategy::Soft { sigma, method } = self.config.nms_strategy {
+            settings.push_str(&format!(";soft_nms={sigma}/{method:?}"));
+        }
+        if let Some(max_detections) = self.config.max_detections {
+            settings.push_str(&format!(";max_det={max_detections}"));
+        }
+        if let Some(top_k) = self.config.top_k_per_class {
+            settings.push_str(&format!(";top_k={top_k}"));
+        }
+        if self.config.resize_filter != FilterType::Lanczos3 {
+            settings.push_str(&format!(";resize={:?}", self.config.resize_filter));
+        }
+        if let Some(fullness) = &self.config.fullness {
+            settings.push_str(&format!(";fullness={fullness:?}"));
+        }
+        if let Some(classes) = &self.config.class_filter {
+            let mut classes: Vec<usize> = classes.iter().copied().collect();
+            classes.sort_unstable();
+            settings.push_str(&format!(";classes={classes:?}"));
+        }
+        if !self.config.per_class_confidence.is_empty() {
+            let mut thresholds: Vec<(usize, f32)> = self
+                .config
+                .per_class_confidence
+                .iter()
+                .map(|(&class_id, &threshold)| (class_id, threshold))
+                .collect();
+            thresholds.sort_unstable_by_key(|&(class_id, _)| class_id);
+            settings.push_str(&format!(";class_confidence={thresholds:?}"));
+        }
+        content_fingerprint(settings.as_bytes())
+    }
+
+    /// Loads a warm standby model for `input_size`, used automatically for images closer to that size
+    pub fn add_input_size(
+        &mut self,
+        input_size: (u32, u32),
+        model_path: &str,
+    ) -> Result<(), SessionError> {
+        let session =
+            OrtInferenceSession::with_execution(Path::new(model_path), &self.config.execution)
+                .map_err(|e| SessionError::Io(std::io::Error::other(e)))?;
+        self.insert_standby_session(input_size, session)
+    }
+
+    /// Loads a warm standby model for `input_size` from model bytes
+    pub fn add_input_size_from_bytes(
+        &mut self,
+        input_size: (u32, u32),
+        model_bytes: &[u8],
+    ) -> Result<(), SessionError> {
+        let session =
+            OrtInferenceSession::from_bytes_with_execution(model_bytes, &self.config.execution)
+                .map_err(|e| SessionError::Io(std::io::Error::other(e)))?;
+        self.insert_standby_session(input_size, session)
+    }
+
+    /// Loads a warm standby model for `input_size` sharing the weights of `model`
+    pub fn add_input_size_from_shared(
+        &mut self,
+        input_size: (u32, u32),
+        model: &SharedModel,
+    ) -> Result<(), SessionError> {
+        let session = model
+            .session(&self.config.execution)
+            .map_err(|e| SessionError::Io(std::io::Error::other(e)))?;
+        self.insert_standby_session(input_size, session)
+    }
+
+    /// Validates and registers a standby session, replacing any previous one for the same size
+    fn insert_standby_session(
+        &mut self,
+        input_size: (u32, u32),
+        mut session: OrtInferenceSession,
+    ) -> Result<(), SessionError> {
+        if input_size == self.config.input_size {
+            return Err(SessionError::InvalidInput(format!(
+                "input size {input_size:?} is already served by the primary model"
+            )));
+        }
+        prepare_session(
+            &mut session,
+            &self.config,
+            self.model_info.input_name(),
+            input_size,
+        )?;
+        self.standby_sessions.insert(input_size, session);
+        Ok(())
+    }
+
+    /// Returns every input size served by the session, sorted by area
+    #[must_use]
+    pub fn input_sizes(&self) -> Vec<(u32, u32)> {
+        let mut sizes: Vec<(u32, u32)> = std::iter::once(self.config.input_size)
+            .chain(self.standby_sessions.keys().copied())
+            .collect();
+        sizes.sort_by_key(|&(w, h)| u64::from(w) * u64::from(h));
+        sizes
+    }
+
+    /// Picks the smallest input size covering the longest side of the image, or the largest one
+    #[must_use]
+    pub fn select_input_size(&self, image_dimensions: (u32, u32)) -> (u32, u32) {
+        if let Some(input_size) = self.input_size_override {
+            return input_size;
+        }
+        pick_input_size(&self.input_sizes(), image_dimensions).unwrap_or(self.config.input_size)
+    }
+
+    /// Runs a blank tensor through every model so the first real requests skip lazy allocations
+    pub fn warm_up(&mut self) -> Result<(), SessionError> {
+        self.warmup(1)
+    }
+
+    /// Runs a blank tensor `n_runs` times through every model, letting ONNX Runtime finish its
+    /// graph optimizations and the execution provider settle before latencies are measured
+    pub fn warmup(&mut self, n_runs: usize) -> Result<(), SessionError> {
+        for (w, h) in self.input_sizes() {
+            for _ in 0..n_runs {
+                self.run_inference(Array4::zeros((1, 3, h as usize, w as usize)))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the pipeline `iterations` times on `image` without writing any file, returning the
+    /// mean, median and 95th percentile of the preprocessing, inference and postprocessing times.
+    /// Warnings raised while benchmarking are discarded.
+    pub fn benchmark(
+        &mut self,
+        image: &DynamicImage,
+        iterations: usize,
+    ) -> Result<LatencyStats, SessionError> {
+        let frame = Frame::new("benchmark", image.clone());
+        let warnings = self.warnings.len();
+        let timings = (0..iterations)
+            .map(|_| {
+                self.detect_frame(&frame, Instant::now())
+                    .map(|report| report.timings)
+            })
+            .collect::<Result<Vec<StageTimings>, SessionError>>();
+        self.warnings.truncate(warnings);
+        Ok(LatencyStats::from_timings(&timings?))
+    }
+
+    /// Runs a synthetic image through every model and checks the detections are sane.
+    ///
+    /// Returns the number of detections on the self-test image, or `SessionError::SelfTest`
+    /// when the model and execution provider produce implausible output.
+    pub fn self_test(&mut self) -> Result<usize, SessionError> {
+        let mut detections = 0;
+        for (w, h) in self.input_sizes() {
+            let (_, loaded_image) = self.preprocess_image(&synthetic_image(w, h))?;
+            let normalized_image = normalize_image_f32(&loaded_image, None, None);
+            let boxes = self.infer(normalized_image.image_array, "self-test")?;
+            check_output_sanity(&boxes, (w, h)).map_err(SessionError::SelfTest)?;
+            detections += boxes.len();
+        }
+        Ok(detections)
+    }
+
+    /// Adds a sink receiving the results of every processed image
+    pub fn with_sink(mut self, sink: impl DetectionSink + 'static) -> Self {
+        self.sinks.push(Box::new(sink));
+        self
+    }
+
+    /// Adds a boxed sink receiving the results of every processed image
+    pub fn add_sink(&mut self, sink: Box<dyn DetectionSink>) {
+        self.sinks.push(sink);
+    }
+
+    /// Adds a middleware intercepting the pipeline stages, run after those already added
+    pub fn with_middleware(mut self, middleware: impl DetectionMiddleware + 'static) -> Self {
+        self.middleware.push(Box::new(middleware));
+        self
+    }
+
+    /// Adds a boxed middleware intercepting the pipeline stages
+    pub fn add_middleware(&mut self, middleware: Box<dyn DetectionMiddleware>) {
+        self.middleware.push(middleware);
+    }
+
+    /// Returns the step timings of the last image run through the single-image pipeline,
+    /// e.g. by `process_image` or `process_frame`
+    #[inline]
+    #[must_use]
+    pub const fn last_metrics(&self) -> &PipelineMetrics {
+        &self.metrics
+    }
+
+    /// Returns the warnings raised since they were last taken
+    #[inline]
+    #[must_use]
+    pub fn warnings(&self) -> &[ImageWarning] {
+        &self.warnings
+    }
+
+    /// Returns and clears the warnings raised since they were last taken
+    pub fn take_warnings(&mut self) -> Vec<ImageWarning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Records a warning about the named image
+    fn warn(&mut self, image_name: &str, warning: Warning) {
+        self.warnings.push(ImageWarning {
+            image_name: image_name.to_string(),
+            warning,
+        });
+    }
+
+    /// Returns true if sinks received output since they were last flushed.
+    ///
+    /// Dropping the session in that state logs a warning when the `tracing` feature is enabled.
+    #[inline]
+    #[must_use]
+    pub const fn has_unflushed_output(&self) -> bool {
+        self.unflushed
+    }
+
+    /// Flushes every attached sink
+    pub fn flush_sinks(&mut self) -> Result<(), SessionError> {
+        for sink in &mut self.sinks {
+            sink.flush()?;
+        }
+        self.unflushed = false;
+        Ok(())
+    }
+
+    /// Closes every sink and releases the ONNX Runtime sessions deterministically.
+    ///
+    /// All sinks are closed even if one fails; the first error is returned.
+    pub fn shutdown(mut self) -> Result<(), SessionError> {
+        let mut result = Ok(());
+        for mut sink in self.sinks.drain(..) {
+            if let Err(e) = sink.close()
+                && result.is_ok()
+            {
+                result = Err(e.into());
+            }
+        }
+        self.unflushed = false;
+
+        self.level_classifier = None;
+        self.standby_sessions.clear();
+        self.session.disable_io_binding();
+        result
+    }
+
+    /// Attaches a results store receiving every processed image's detections
+    #[cfg(feature = "sqlite")]
+    pub fn with_results_store(mut self, store: SqliteStore) -> Self {
+        self.results_store = Some(store);
+        self
+    }
+
+    /// Encrypts the detection files saved by the session with `key`, as `<name>.<ext>.enc`
+    #[cfg(feature = "encryption")]
+    pub fn with_output_key(mut self, key: OutputKey) -> Self {
+        self.output_key = Some(key);
+        self
+    }
+
+    /// Returns the attached results store, if any
+    #[cfg(feature = "sqlite")]
+    #[must_use]
+    pub const fn results_store(&self) -> Option<&SqliteStore> {
+        self.results_store.as_ref()
+    }
+
     /// Runs inference on the preprocessed input tensor
     pub fn run_inference(
         &mut self,
         input_tensor: Array4<f32>,
     ) -> Result<Vec<BoundingBox>, SessionError> {
-        let outputs: SessionOutputs = self
-            .session
-            .run_inference(&input_tensor)
+        self.infer(input_tensor, "")
+    }
+
+    /// Runs inference and the pre-parse and post-parse middleware for the named image
+    fn infer(
+        &mut self,
+        input_tensor: Array4<f32>,
+        image_name: &str,
+    ) -> Result<Vec<BoundingBox>, SessionError> {
+        let mut boxes = self.infer_batch(input_tensor, &[image_name])?;
+        boxes
+            .pop()
+            .ok_or_else(|| SessionError::Inference("Model returned an empty batch".to_string()))
+    }
+
+    /// Runs inference on a batch of stacked images, one per name, and the pre-parse and
+    /// post-parse middleware of each image
+    fn infer_batch(
+        &mut self,
+        input_tensor: Array4<f32>,
+        image_names: &[&str],
+    ) -> Result<Vec<Vec<BoundingBox>>, SessionError> {
+        if self.config.validate_input {
+            validate_tensor_range(&input_tensor, self.config.input_range)?;
+        }
+        if let Some(warning) = check_normalization(&input_tensor, self.config.input_range) {
+            for image_name in image_names {
+                self.warn(image_name, warning.clone());
+            }
+        }
+
+        // Route the tensor to the model serving its spatial size
+        let tensor_size = (input_tensor.dim().3 as u32, input_tensor.dim().2 as u32);
+        let session = self
+            .standby_sessions
+            .get_mut(&tensor_size)
+            .unwrap_or(&mut self.session);
+
+        let outputs: SessionOutputs = session
+            .run_inference_bound(self.model_info.input_name(), &input_tensor)
             .map_err(|e| SessionError::Inference(e.to_string()))?;
 
-        let (shape, data) = outputs["output0"]
+        let (shape, data) = outputs[self.model_info.output_name()]
             .try_extract_tensor::<f32>()
             .map_err(|e| SessionError::Inference(format!("Failed to extract tensor: {e}")))?;
 
@@ -93,12 +607,45 @@ impl YoloSession {
         let output = ndarray::ArrayViewD::from_shape(shape_usize, &data)
             .map_err(|e| SessionError::Inference(format!("Failed to build ndarray view: {e}")))?;
 
-        // Parse output using appropriate inference implementation
-        let boxes = self
-            .inference
-            .parse_output(output, self.config.confidence_threshold);
+        if output.ndim() == 0 || output.shape()[0] != image_names.len() {
+            return Err(SessionError::Inference(format!(
+                "Output shape {:?} does not match a batch of {}",
+                output.shape(),
+                image_names.len()
+            )));
+        }
+
+        let mut batch_boxes = Vec::with_capacity(image_names.len());
+        for (index, image_name) in image_names.iter().enumerate() {
+            // Keep a batch dimension of 1, as the parsers expect
+            let image_output = output.slice_axis(Axis(0), Slice::from(index..=index));
+            let context = StageContext {
+                image_name,
+                input_size: tensor_size,
+            };
+            self.middleware.pre_parse(&context, &image_output)?;
+
+            // Parse output using appropriate inference implementation
+            let mut boxes = self
+                .inference
+                .parse_output(image_output, self.config.parse_threshold())?;
+            if self.config.class_filter.is_some() || !self.config.per_class_confidence.is_empty() {
+                boxes.retain(|bbox| self.config.accepts(bbox));
+            }
+
+            self.middleware
+                .intercept(Stage::PostParse, &context, &mut boxes)?;
+            batch_boxes.push(boxes);
+        }
+        Ok(batch_boxes)
+    }
 
-        Ok(boxes)
+    /// Returns true if the model accepts batches of several images, i.e. its batch dimension is dynamic
+    #[must_use]
+    pub fn supports_batching(&self) -> bool {
+        self.session
+            .input_spec(self.model_info.input_name())
+            .is_some_and(|spec| spec.shape.first().is_some_and(|&dim| dim < 0))
     }
 
     /// Loads and preprocesses an image
@@ -106,147 +653,1054 @@ impl YoloSession {
         &self,
         image_path: &str,
     ) -> Result<(RgbImage, LoadedImageU8), SessionError> {
-        let loaded_image = load_image_u8_default(image_path, self.config.input_size)
-            .map_err(|e| SessionError::ImageProcessing(format!("Failed to load image:{e}")))?;
+        let frame = load_frame(image_path)?;
+        self.preprocess_image(&frame.image)
+    }
 
-        // Convert NCHW to interleaved HWC using direct buffer access
-        let src = loaded_image.image_array.as_slice().ok_or_else(|| {
-            SessionError::ImageProcessing("Image array not contiguous".to_string())
-        })?;
-        let h = loaded_image.size.height as usize;
-        let w = loaded_image.size.width as usize;
-        let hw = h * w;
-        let mut interleaved_data = vec![0u8; hw * 3];
-
-        let ch_r = &src[0..hw];
-        let ch_g = &src[hw..2 * hw];
-        let ch_b = &src[2 * hw..3 * hw];
-
-        for i in 0..hw {
-            let dst = i * 3;
-            interleaved_data[dst] = ch_r[i];
-            interleaved_data[dst + 1] = ch_g[i];
-            interleaved_data[dst + 2] = ch_b[i];
-        }
-
-        let img = RgbImage::from_raw(
-            loaded_image.size.width,
-            loaded_image.size.height,
-            interleaved_data,
-        )
-        .ok_or_else(|| {
-            SessionError::ImageProcessing("Failed to create image from raw data".to_string())
-        })?;
+    /// Preprocesses an already decoded image, returning the letterboxed RGB image and model input
+    pub fn preprocess_image(
+        &self,
+        image: &DynamicImage,
+    ) -> Result<(RgbImage, LoadedImageU8), SessionError> {
+        let loaded_image = preprocess_image_u8(image, &self.input_image_config(image));
+        Ok((interleave(&loaded_image)?, loaded_image))
+    }
 
-        Ok((img, loaded_image))
+    /// Preprocesses an image through the pyramid cache when one is attached
+    fn preprocess_cached(
+        &mut self,
+        image: &DynamicImage,
+    ) -> Result<(RgbImage, LoadedImageU8), SessionError> {
+        let config = self.input_image_config(image);
+        let loaded_image = match self.pyramid.as_mut() {
+            Some(pyramid) => pyramid.letterbox(image, &config).as_ref().clone(),
+            None => preprocess_image_u8(image, &config),
+        };
+        Ok((interleave(&loaded_image)?, loaded_image))
     }
 
-    /// Saves detection outputs
+    /// Returns the letterbox settings of the model input selected for an image
+    fn input_image_config(&self, image: &DynamicImage) -> ImageConfig {
+        let (width, height) = self.select_input_size((image.width(), image.height()));
+        ImageConfig {
+            target_size: ImageSize::new(width, height),
+            padding_color: self.padding_color(),
+            filter_type: self.config.resize_filter,
+            ..Default::default()
+        }
+    }
+
+    /// Saves the annotated image and the detections file of a report
     pub fn save_outputs(
         &self,
         image: &RgbImage,
-        boxes: &[BoundingBox],
-        image_path: &str,
+        report: &DetectionReport,
         output_dir: Option<&str>,
         format: Option<OutputFormat>,
     ) -> Result<(), SessionError> {
-        let output_dir_str = output_dir.unwrap_or("output");
-        let output_dir = Path::new(output_dir_str);
-        let format = format.unwrap_or_default();
+        let output_dir = Path::new(output_dir.unwrap_or("output"));
 
-        if !output_dir.exists() {
-            std::fs::create_dir_all(output_dir)?;
+        if Path::new(&report.image_path).file_stem().is_none() {
+            return Err(SessionError::ImageProcessing(
+                "Invalid image path".to_string(),
+            ));
         }
 
-        let file_name = Path::new(image_path)
-            .file_stem()
-            .ok_or_else(|| SessionError::ImageProcessing("Invalid image path".to_string()))?;
-
-        let image_output_path = output_dir.join(format!("{}.jpg", file_name.to_string_lossy()));
-        let output_path = output_dir.join(format!(
-            "{}.{}",
-            file_name.to_string_lossy(),
-            format.extension()
-        ));
-
-        // Save image
-        image
-            .save(&image_output_path)
-            .map_err(|e| SessionError::Io(std::io::Error::other(e)))?;
-
-        // Save YOLO format detections
-        OutputFormat::output_detections(boxes, image.dimensions(), &output_path, Some(format))?;
+        #[cfg(feature = "encryption")]
+        if let Some(key) = &self.output_key {
+            write_encrypted_outputs(
+                image,
+                report,
+                output_dir,
+                format.unwrap_or_default(),
+                &self.config.classes,
+                key,
+            )?;
+            return Ok(());
+        }
+        write_outputs(
+            image,
+            report,
+            output_dir,
+            format.unwrap_or_default(),
+            &self.config.classes,
+        )?;
 
         Ok(())
     }
 
     /// Processes an image: loads, preprocesses, runs inference, applies NMS, draws boxes, and saves outputs
-    pub fn process_image(&mut self, image_path: &str) -> Result<(), SessionError> {
+    pub fn process_image(&mut self, image_path: &str) -> Result<DetectionReport, SessionError> {
         self.process_image_with_output_dir(image_path, None)
     }
 
+    /// Processes an image with custom output directory and returns its final detections
+    pub fn process_image_with_detections(
+        &mut self,
+        image_path: &str,
+        output_dir: Option<&str>,
+    ) -> Result<Vec<BoundingBox>, SessionError> {
+        self.detect_and_save(image_path, output_dir)
+            .map(|(_, report)| report.boxes)
+    }
+
+    /// Processes an image with custom output directory and returns its final detections
+    /// together with the warnings raised while processing it
+    pub fn process_image_with_warnings(
+        &mut self,
+        image_path: &str,
+        output_dir: Option<&str>,
+    ) -> Result<(Vec<BoundingBox>, Vec<Warning>), SessionError> {
+        let first = self.warnings.len();
+        let boxes = self.process_image_with_detections(image_path, output_dir)?;
+        let warnings = self.warnings.drain(first..).map(|w| w.warning).collect();
+        Ok((boxes, warnings))
+    }
+
     /// Processes an image with custom output directory
     pub fn process_image_with_output_dir(
         &mut self,
         image_path: &str,
         output_dir: Option<&str>,
-    ) -> Result<(), SessionError> {
-        let (original_image, loaded_image) = self.load_and_preprocess_image(image_path)?;
+    ) -> Result<DetectionReport, SessionError> {
+        self.detect_and_save(image_path, output_dir)
+            .map(|(_, report)| report)
+    }
 
-        let normalized_image = normalize_image_f32(&loaded_image, None, None);
-        let mut inferred_boxes = self.run_inference(normalized_image.image_array)?;
+    /// Detects objects in an in-memory image without reading or writing any file
+    pub fn process_dynamic_image(
+        &mut self,
+        image: &DynamicImage,
+    ) -> Result<Vec<BoundingBox>, SessionError> {
+        let frame = Frame::new("memory", image.clone());
+        self.detect_frame(&frame, Instant::now())
+            .map(|report| report.boxes)
+    }
 
-        // Apply NMS if enabled
-        if self.config.use_nms {
-            inferred_boxes = if self.config.use_per_class_nms {
-                nms_per_class(&inferred_boxes, self.config.nms_threshold)
+    /// Detects objects in an in-memory image within the latency budget of `best_effort`.
+    ///
+    /// The downgrades planned from the latencies of the previous calls are applied for this call
+    /// only, and the ones that changed the pipeline are reported. The first call runs the full
+    /// pipeline, so the budget is met from the calls following a slow one.
+    pub fn detect_best_effort(
+        &mut self,
+        image: &DynamicImage,
+        best_effort: &mut BestEffort,
+    ) -> Result<BestEffortResult, SessionError> {
+        let start = Instant::now();
+        let frame = Frame::new("best-effort", image.clone());
+        let planned = best_effort.plan();
+        let (tiling, resize_filter) = (self.config.tiling, self.config.resize_filter);
+
+        let mut downgrades = Vec::new();
+        for &downgrade in planned {
+            let applied = match downgrade {
+                Downgrade::SkipTiling => {
+                    let applied = self.tiling_of(&frame).is_some();
+                    self.config.tiling = None;
+                    applied
+                }
+                Downgrade::FastResize => {
+                    let applied =
+                        !matches!(resize_filter, FilterType::Nearest | FilterType::Triangle);
+                    self.config.resize_filter = FilterType::Triangle;
+                    applied
+                }
+                Downgrade::SmallestInput => {
+                    let smallest = self.input_sizes()[0];
+                    let applied =
+                        smallest != self.select_input_size((image.width(), image.height()));
+                    self.input_size_override = Some(smallest);
+                    applied
+                }
+            };
+            if applied {
+                downgrades.push(downgrade);
+            }
+        }
+
+        let report = self.detect_frame(&frame, start);
+        self.config.tiling = tiling;
+        self.config.resize_filter = resize_filter;
+        self.input_size_override = None;
+
+        let boxes = report?.boxes;
+        let elapsed = start.elapsed();
+        best_effort.record(planned, elapsed);
+        Ok(BestEffortResult {
+            boxes,
+            downgrades,
+            elapsed,
+        })
+    }
+
+    /// Detects objects in a frame without writing any file, e.g. a frame of a video
+    pub fn process_frame(&mut self, frame: &Frame) -> Result<Vec<BoundingBox>, SessionError> {
+        self.detect_frame(frame, Instant::now())
+            .map(|report| report.boxes)
+    }
+
+    /// Detects objects in a raw interleaved RGB8 buffer of `width` x `height` pixels
+    pub fn process_rgb_bytes(
+        &mut self,
+        rgb_bytes: &[u8],
+        width: u32,
+        height: u32,
+    ) -> Result<Vec<BoundingBox>, SessionError> {
+        let image = RgbImage::from_raw(width, height, rgb_bytes.to_vec()).ok_or_else(|| {
+            SessionError::InvalidInput(format!(
+                "expected {} RGB bytes for {width}x{height}, got {}",
+                width as usize * height as usize * 3,
+                rgb_bytes.len()
+            ))
+        })?;
+        self.process_dynamic_image(&DynamicImage::ImageRgb8(image))
+    }
+
+    /// Processes every frame of an image source, returning one result per frame.
+    ///
+    /// Attached sinks are flushed once the source is exhausted.
+    pub fn process_source<S: ImageSource + ?Sized>(
+        &mut self,
+        source: &mut S,
+        output_dir: Option<&str>,
+    ) -> Result<Vec<Result<(), SessionError>>, SessionError> {
+        let mut results = Vec::new();
+        while let Some(frame) = source.next() {
+            let start = Instant::now();
+            let result = frame
+                .map_err(|e| SessionError::ImageProcessing(format!("Failed to load image:{e}")))
+                .and_then(|frame| {
+                    self.detect_frame_and_save(&frame, output_dir, start)
+                        .map(|_| ())
+                });
+            results.push(result);
+        }
+        self.flush_sinks()?;
+        Ok(results)
+    }
+
+    /// Runs the full pipeline on every image of `dir` found with `options`, in path order.
+    ///
+    /// Files that cannot be decoded are skipped with an `UnreadableImage` warning instead of
+    /// stopping the run; the result of every file is returned, and sinks are flushed at the end.
+    pub fn process_directory(
+        &mut self,
+        dir: &Path,
+        options: &DirectoryOptions,
+        output_dir: Option<&str>,
+    ) -> Result<Vec<FileResult>, SessionError> {
+        self.process_directory_with_progress(dir, options, output_dir, |_| {})
+    }
+
+    /// Processes the images of `dir` like `process_directory`, reporting to `on_progress` when
+    /// each image starts and finishes or fails
+    pub fn process_directory_with_progress(
+        &mut self,
+        dir: &Path,
+        options: &DirectoryOptions,
+        output_dir: Option<&str>,
+        mut on_progress: impl FnMut(ProgressEvent<'_>),
+    ) -> Result<Vec<FileResult>, SessionError> {
+        let paths = options.find(dir).map_err(|e| {
+            SessionError::ImageProcessing(format!("Failed to scan {}: {e}", dir.display()))
+        })?;
+
+        let total = paths.len();
+        let mut tracker = ProgressTracker::new(total);
+        let mut results = Vec::with_capacity(total);
+        for (index, path) in paths.into_iter().enumerate() {
+            on_progress(ProgressEvent::Started {
+                index,
+                total,
+                path: &path,
+            });
+            let start = Instant::now();
+            let image_path = path.to_string_lossy().into_owned();
+            let timer = StepTimer::start("load", &image_path);
+            let frame = match self.config.image_timeout {
+                Some(budget) => load_frame_with_timeout(&image_path, budget),
+                None => load_frame(&image_path),
+            };
+            let load = timer.stop();
+            let result = match frame {
+                Ok(frame) => {
+                    let result = self.detect_frame_and_save(&frame, output_dir, start);
+                    self.metrics.load = load;
+                    result.map(|(_, report)| report)
+                }
+                Err(e) => {
+                    self.warn(
+                        &image_path,
+                        Warning::UnreadableImage {
+                            error: e.to_string(),
+                        },
+                    );
+                    Err(e)
+                }
+            };
+            let eta = tracker.complete();
+            on_progress(match &result {
+                Ok(report) => ProgressEvent::Finished {
+                    index,
+                    total,
+                    path: &path,
+                    detections: report.boxes.len(),
+                    elapsed: start.elapsed(),
+                    eta,
+                },
+                Err(error) => ProgressEvent::Failed {
+                    index,
+                    total,
+                    path: &path,
+                    error,
+                    eta,
+                },
+            });
+            results.push((path, result));
+        }
+        self.flush_sinks()?;
+        Ok(results)
+    }
+
+    /// Runs the full pipeline on an image file and returns the annotated image dimensions and its report
+    fn detect_and_save(
+        &mut self,
+        image_path: &str,
+        output_dir: Option<&str>,
+    ) -> Result<((u32, u32), DetectionReport), SessionError> {
+        let start = Instant::now();
+        let timer = StepTimer::start("load", image_path);
+        let frame = match self.config.image_timeout {
+            Some(budget) => load_frame_with_timeout(image_path, budget)?,
+            None => load_frame(image_path)?,
+        };
+        let load = timer.stop();
+        let result = self.detect_frame_and_save(&frame, output_dir, start);
+        self.metrics.load = load;
+        result
+    }
+
+    /// Runs the full pipeline on a frame and returns the annotated image dimensions and its report.
+    ///
+    /// `start` marks when work on the image began; the per-image timeout is checked against it
+    /// between pipeline stages, before any output is written.
+    fn detect_frame_and_save(
+        &mut self,
+        frame: &Frame,
+        output_dir: Option<&str>,
+        start: Instant,
+    ) -> Result<((u32, u32), DetectionReport), SessionError> {
+        let report = self.detect_frame(frame, start)?;
+        self.save_detection(frame, report, output_dir, start)
+    }
+
+    /// Draws and writes the outputs of a detected frame and feeds the results store and sinks
+    fn save_detection(
+        &mut self,
+        frame: &Frame,
+        report: DetectionReport,
+        output_dir: Option<&str>,
+        start: Instant,
+    ) -> Result<((u32, u32), DetectionReport), SessionError> {
+        guard(&frame.name, || {
+            let started_at = SystemTime::now() - start.elapsed();
+            let image_size = (frame.image.width(), frame.image.height());
+
+            // Draw boxes with custom configuration on the original image
+            let timer = StepTimer::start("draw", &frame.name);
+            let mut result_image = DrawConfig::draw_with_classes(
+                &frame.image,
+                &report.boxes,
+                image_size,
+                Some(self.config.draw_config.clone()),
+                &self.config.classes,
+            );
+            if let Some(tiling) = self.tiling_of(frame)
+                && tiling.debug_overlay
+            {
+                draw_tile_overlay(&mut result_image, &tiling.tiles(image_size), &report.boxes);
+            }
+            if let Some(watermark) = &self.config.watermark {
+                result_image = watermark.stamp(&result_image, started_at, &self.run_fingerprint());
+            }
+            self.metrics.draw = timer.stop();
+
+            let timer = StepTimer::start("save", &frame.name);
+            self.save_outputs(
+                &result_image,
+                &report,
+                output_dir,
+                Some(self.config.output_format),
+            )?;
+
+            #[cfg(feature = "sqlite")]
+            let has_store = self.results_store.is_some();
+            #[cfg(not(feature = "sqlite"))]
+            let has_store = false;
+
+            if has_store || !self.sinks.is_empty() {
+                let fingerprint = match &frame.path {
+                    Some(path) => std::fs::read(path)
+                        .ok()
+                        .map(|bytes| content_fingerprint(&bytes)),
+                    None => Some(content_fingerprint(frame.image.as_bytes())),
+                };
+                let record = ImageRecord {
+                    image_path: frame.name.clone(),
+                    fingerprint,
+                    processed_at: started_at,
+                    duration: start.elapsed(),
+                    image_dimensions: result_image.dimensions(),
+                    boxes: report.boxes.clone(),
+                    captured_at: frame.captured_at,
+                };
+
+                #[cfg(feature = "sqlite")]
+                if let Some(store) = self.results_store.as_mut() {
+                    store.insert(&record)?;
+                }
+
+                for sink in &mut self.sinks {
+                    sink.write(&record, &result_image)?;
+                }
+                self.unflushed |= !self.sinks.is_empty();
+            }
+            self.metrics.save = timer.stop();
+
+            Ok((result_image.dimensions(), report))
+        })
+    }
+
+    /// Runs preprocessing, inference, NMS, level estimation and middleware on a frame,
+    /// returning its report with the final boxes in original image coordinates
+    fn detect_frame(
+        &mut self,
+        frame: &Frame,
+        start: Instant,
+    ) -> Result<DetectionReport, SessionError> {
+        guard(&frame.name, || {
+            self.metrics = PipelineMetrics::default();
+            let timer = StepTimer::start("resize", &frame.name);
+            let (original_image, loaded_image) = self.preprocess_cached(&frame.image)?;
+            self.metrics.resize = timer.stop();
+            if let Some(tiling) = self.tiling_of(frame) {
+                return self.detect_tiled(frame, original_image, &loaded_image, tiling, start);
+            }
+            let timer = StepTimer::start("normalize", &frame.name);
+            let normalized_image = normalize_image_f32(&loaded_image, None, None);
+            self.metrics.normalize = timer.stop();
+            self.check_timeout(start)?;
+
+            let timer = StepTimer::start("inference", &frame.name);
+            let inferred_boxes = self.infer(normalized_image.image_array, &frame.name)?;
+            self.metrics.inference = timer.stop();
+            self.check_timeout(start)?;
+
+            let timings = StageTimings {
+                preprocess: self.metrics.resize + self.metrics.normalize,
+                inference: self.metrics.inference,
+                ..StageTimings::default()
+            };
+            self.finish_detection(frame, original_image, inferred_boxes, start, timings)
+        })
+    }
+
+    /// Returns the tiling of a frame, if tiling is enabled and the frame spans several tiles
+    fn tiling_of(&self, frame: &Frame) -> Option<TileConfig> {
+        self.config
+            .tiling
+            .filter(|tiling| tiling.applies_to((frame.image.width(), frame.image.height())))
+    }
+
+    /// Detects objects in the overlapping tiles of a large frame, and in the whole frame if
+    /// configured. Tile boxes are mapped into the input space of the whole frame so that
+    /// `finish_detection` merges the duplicates of tile overlaps with a global NMS.
+    fn detect_tiled(
+        &mut self,
+        frame: &Frame,
+        original_image: RgbImage,
+        loaded_image: &LoadedImageU8,
+        tiling: TileConfig,
+        start: Instant,
+    ) -> Result<DetectionReport, SessionError> {
+        let image_size = (frame.image.width(), frame.image.height());
+        let frame_transform = LetterboxTransform::new(
+            ImageSize::new(image_size.0, image_size.1),
+            ImageSize::new(original_image.width(), original_image.height()),
+        );
+        let mut boxes = Vec::new();
+
+        if tiling.full_image {
+            let timer = StepTimer::start("normalize", &frame.name);
+            let tensor = normalize_image_f32(loaded_image, None, None).image_array;
+            self.metrics.normalize += timer.stop();
+            let timer = StepTimer::start("inference", &frame.name);
+            boxes = self.infer(tensor, &frame.name)?;
+            self.metrics.inference += timer.stop();
+            self.check_timeout(start)?;
+        }
+
+        for (index, tile) in tiling.tiles(image_size).into_iter().enumerate() {
+            let timer = StepTimer::start("resize", &frame.name);
+            let crop = frame
+                .image
+                .crop_imm(tile.x, tile.y, tile.width, tile.height);
+            let (tile_image, tile_loaded) = self.preprocess_cached(&crop)?;
+            self.metrics.resize += timer.stop();
+            let timer = StepTimer::start("normalize", &frame.name);
+            let tensor = normalize_image_f32(&tile_loaded, None, None).image_array;
+            self.metrics.normalize += timer.stop();
+
+            let timer = StepTimer::start("inference", &frame.name);
+            let tile_boxes = self.infer(tensor, &frame.name)?;
+            self.metrics.inference += timer.stop();
+            self.check_timeout(start)?;
+
+            let tile_transform = LetterboxTransform::new(
+                ImageSize::new(tile.width, tile.height),
+                ImageSize::new(tile_image.width(), tile_image.height()),
+            );
+            boxes.extend(tile_boxes.iter().map(|bbox| {
+                let bbox = tile.to_image_space(&bbox.to_original_space(&tile_transform));
+                let (x1, y1) = frame_transform.to_letterbox(bbox.x1, bbox.y1);
+                let (x2, y2) = frame_transform.to_letterbox(bbox.x2, bbox.y2);
+                BoundingBox {
+                    x1,
+                    y1,
+                    x2,
+                    y2,
+                    tile: Some(index),
+                    ..bbox
+                }
+            }));
+        }
+
+        let timings = StageTimings {
+            preprocess: self.metrics.resize + self.metrics.normalize,
+            inference: self.metrics.inference,
+            ..StageTimings::default()
+        };
+        self.finish_detection(frame, original_image, boxes, start, timings)
+    }
+
+    /// Detects objects in several frames, one result per frame.
+    ///
+    /// Frames sharing an input size are stacked into one inference when the model supports
+    /// batching; if a batched run fails, its frames are retried one at a time.
+    fn detect_frames(
+        &mut self,
+        frames: &[Frame],
+        start: Instant,
+    ) -> Vec<Result<DetectionReport, SessionError>> {
+        let mut results: Vec<Option<Result<DetectionReport, SessionError>>> =
+            frames.iter().map(|_| None).collect();
+
+        // Group the preprocessed frames by input size
+        let mut groups: BTreeMap<(u32, u32), Vec<PreparedFrame>> = BTreeMap::new();
+        for (index, frame) in frames.iter().enumerate() {
+            // Tiled frames run through one inference per tile instead
+            if self.tiling_of(frame).is_some() {
+                results[index] = Some(self.detect_frame(frame, start));
+                continue;
+            }
+            let stage_start = Instant::now();
+            match guard(&frame.name, || self.preprocess_cached(&frame.image)) {
+                Ok((original_image, loaded_image)) => {
+                    let tensor = normalize_image_f32(&loaded_image, None, None).image_array;
+                    groups
+                        .entry(original_image.dimensions())
+                        .or_default()
+                        .push((index, original_image, tensor, stage_start.elapsed()));
+                }
+                Err(e) => results[index] = Some(Err(e)),
+            }
+        }
+
+        let batching = self.supports_batching();
+        for group in groups.into_values() {
+            let stage_start = Instant::now();
+            let batched = if batching && group.len() > 1 {
+                let views: Vec<_> = group
+                    .iter()
+                    .map(|(_, _, tensor, _)| tensor.view())
+                    .collect();
+                let names: Vec<&str> = group
+                    .iter()
+                    .map(|(index, _, _, _)| frames[*index].name.as_str())
+                    .collect();
+                ndarray::concatenate(Axis(0), &views)
+                    .ok()
+                    .and_then(|tensor| guard("batch", || self.infer_batch(tensor, &names)).ok())
             } else {
-                nms(&inferred_boxes, self.config.nms_threshold)
+                None
             };
+
+            match batched {
+                Some(batch_boxes) => {
+                    let inference = stage_start.elapsed() / group.len() as u32;
+                    for ((index, original_image, _, preprocess), boxes) in
+                        group.into_iter().zip(batch_boxes)
+                    {
+                        let timings = StageTimings {
+                            preprocess,
+                            inference,
+                            ..StageTimings::default()
+                        };
+                        results[index] = Some(guard(&frames[index].name, || {
+                            self.finish_detection(
+                                &frames[index],
+                                original_image,
+                                boxes,
+                                start,
+                                timings,
+                            )
+                        }));
+                    }
+                }
+                None => {
+                    for (index, original_image, tensor, preprocess) in group {
+                        let stage_start = Instant::now();
+                        let name = &frames[index].name;
+                        let result = guard(name, || self.infer(tensor, name)).and_then(|boxes| {
+                            let timings = StageTimings {
+                                preprocess,
+                                inference: stage_start.elapsed(),
+                                ..StageTimings::default()
+                            };
+                            guard(name, || {
+                                self.finish_detection(
+                                    &frames[index],
+                                    original_image,
+                                    boxes,
+                                    start,
+                                    timings,
+                                )
+                            })
+                        });
+                        results[index] = Some(result);
+                    }
+                }
+            }
+        }
+
+        results
+            .into_iter()
+            .map(|result| result.expect("every frame has a result"))
+            .collect()
+    }
+
+    /// Applies NMS, the post-NMS middleware, level and fullness estimation and the pre-output
+    /// middleware to the parsed boxes of a frame, then maps them back to original image coordinates.
+    ///
+    /// `timings` holds the preprocessing and inference times, completed with postprocessing.
+    fn finish_detection(
+        &mut self,
+        frame: &Frame,
+        original_image: RgbImage,
+        mut inferred_boxes: Vec<BoundingBox>,
+        start: Instant,
+        mut timings: StageTimings,
+    ) -> Result<DetectionReport, SessionError> {
+        let stage_start = Instant::now();
+        let timer = StepTimer::start("nms", &frame.name);
+        let input_size = original_image.dimensions();
+
+        // Apply NMS if enabled
+        if self.config.use_nms {
+            inferred_boxes = suppress_overlaps(&self.config, &inferred_boxes);
+        }
+        if self.config.max_detections.is_some() || self.config.top_k_per_class.is_some() {
+            inferred_boxes = limit_detections(
+                &inferred_boxes,
+                self.config.max_detections,
+                self.config.top_k_per_class,
+            );
         }
 
-        // Draw boxes with custom configuration
-        let result_image = DrawConfig::draw_boxes(
-            &DynamicImage::ImageRgb8(original_image),
-            &inferred_boxes,
-            self.config.input_size,
+        let context = StageContext {
+            image_name: &frame.name,
+            input_size,
+        };
+        self.middleware
+            .intercept(Stage::PostNms, &context, &mut inferred_boxes)?;
+
+        // Estimate building levels on the surviving detections
+        if let Some(classifier) = self.level_classifier.as_mut() {
+            classifier.annotate(&original_image, &mut inferred_boxes)?;
+        }
+        if let Some(fullness) = &self.config.fullness
+            && self.config.classes.has_storage_classes()
+        {
+            fullness.annotate(&original_image, &mut inferred_boxes, &self.config.classes);
+        }
+
+        self.middleware
+            .intercept(Stage::PreOutput, &context, &mut inferred_boxes)?;
+        self.metrics.nms = timer.stop();
+        self.check_timeout(start)?;
+
+        let mut unknown_classes: BTreeMap<usize, usize> = BTreeMap::new();
+        for bbox in &inferred_boxes {
+            if self.config.classes.get(bbox.class_id).is_none() {
+                *unknown_classes.entry(bbox.class_id).or_insert(0) += 1;
+            }
+        }
+        for (class_id, count) in unknown_classes {
+            self.warn(&frame.name, Warning::UnknownClass { class_id, count });
+        }
+
+        let (width, height) = (frame.image.width(), frame.image.height());
+        let transform = LetterboxTransform::new(
+            ImageSize::new(width, height),
+            ImageSize::new(input_size.0, input_size.1),
         );
 
-        self.save_outputs(
-            &result_image,
-            &inferred_boxes,
-            image_path,
-            output_dir,
-            Some(OutputFormat::Json),
-        )?;
+        // Less than a pixel outside the image is rounding, not a clamped box
+        let (width, height) = (width as f32 + 1.0, height as f32 + 1.0);
+        let clamped = inferred_boxes
+            .iter()
+            .filter(|bbox| {
+                let (x1, y1) = transform.to_original(bbox.x1, bbox.y1);
+                let (x2, y2) = transform.to_original(bbox.x2, bbox.y2);
+                x1 < -1.0 || y1 < -1.0 || x2 > width || y2 > height
+            })
+            .count();
+        if clamped > 0 {
+            self.warn(&frame.name, Warning::BoxesClamped { count: clamped });
+        }
 
-        Ok(())
+        let boxes = inferred_boxes
+            .iter()
+            .map(|bbox| bbox.to_original_space(&transform))
+            .collect();
+        timings.postprocess = stage_start.elapsed();
+        Ok(DetectionReport::new(
+            frame.name.clone(),
+            (frame.image.width(), frame.image.height()),
+            input_size,
+            boxes,
+            timings,
+        ))
     }
 
-    /// Processes multiple images in batch
+    /// Fails with `SessionError::Timeout` once the per-image budget is exhausted
+    fn check_timeout(&self, start: Instant) -> Result<(), SessionError> {
+        match self.config.image_timeout {
+            Some(budget) if start.elapsed() > budget => Err(SessionError::Timeout(budget)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Processes multiple images in batch.
+    ///
+    /// Up to `SessionConfig::batch_size` images sharing an input size run through a single
+    /// inference when the model has a dynamic batch dimension.
     pub fn process_images_batch<P: AsRef<Path>>(
         &mut self,
         image_paths: &[P],
         output_dir: Option<&str>,
     ) -> Result<Vec<Result<(), SessionError>>, SessionError> {
-        let results = image_paths
-            .iter()
-            .map(|path| {
-                let path_str = path
+        self.process_images_batch_with_progress(image_paths, output_dir, |_| {})
+    }
+
+    /// Processes images like `process_images_batch`, reporting to `on_progress` when each image
+    /// starts and finishes or fails. Images of a chunk all start before the chunk runs through
+    /// inference.
+    pub fn process_images_batch_with_progress<P: AsRef<Path>>(
+        &mut self,
+        image_paths: &[P],
+        output_dir: Option<&str>,
+        mut on_progress: impl FnMut(ProgressEvent<'_>),
+    ) -> Result<Vec<Result<(), SessionError>>, SessionError> {
+        let total = image_paths.len();
+        let mut tracker = ProgressTracker::new(total);
+        let mut results = Vec::with_capacity(total);
+        for chunk in image_paths.chunks(self.config.batch_size.max(1)) {
+            let start = Instant::now();
+            let mut frames = Vec::with_capacity(chunk.len());
+            let mut slots = Vec::with_capacity(chunk.len());
+            for (offset, path) in chunk.iter().enumerate() {
+                on_progress(ProgressEvent::Started {
+                    index: results.len() + offset,
+                    total,
+                    path: path.as_ref(),
+                });
+                let frame = path
                     .as_ref()
                     .to_str()
-                    .ok_or_else(|| SessionError::ImageProcessing("Invalid path".to_string()))?;
-                self.process_image_with_output_dir(path_str, output_dir)
-            })
+                    .ok_or_else(|| SessionError::ImageProcessing("Invalid path".to_string()))
+                    .and_then(|path| match self.config.image_timeout {
+                        Some(budget) => load_frame_with_timeout(path, budget),
+                        None => load_frame(path),
+                    });
+                slots.push(frame.map(|frame| frames.push(frame)));
+            }
+
+            let mut detections = self.detect_frames(&frames, start).into_iter();
+            let mut frames = frames.iter();
+            for (slot, path) in slots.into_iter().zip(chunk) {
+                let result = slot.and_then(|()| {
+                    let frame = frames.next().expect("one frame per loaded image");
+                    let report = detections.next().expect("one result per frame")?;
+                    self.save_detection(frame, report, output_dir, start)
+                        .map(|(_, report)| report.boxes.len())
+                });
+                let (index, path, eta) = (results.len(), path.as_ref(), tracker.complete());
+                on_progress(match &result {
+                    Ok(detections) => ProgressEvent::Finished {
+                        index,
+                        total,
+                        path,
+                        detections: *detections,
+                        elapsed: start.elapsed(),
+                        eta,
+                    },
+                    Err(error) => ProgressEvent::Failed {
+                        index,
+                        total,
+                        path,
+                        error,
+                        eta,
+                    },
+                });
+                results.push(result.map(|_| ()));
+            }
+        }
+
+        self.flush_sinks()?;
+        Ok(results)
+    }
+
+    /// Detects objects in several in-memory images, one result per image.
+    ///
+    /// Images sharing an input size run through a single inference when the model has a
+    /// dynamic batch dimension; no file is read or written.
+    pub fn process_dynamic_images(
+        &mut self,
+        images: &[DynamicImage],
+    ) -> Vec<Result<Vec<BoundingBox>, SessionError>> {
+        let frames: Vec<Frame> = images
+            .iter()
+            .enumerate()
+            .map(|(index, image)| Frame::new(format!("memory-{index}"), image.clone()))
             .collect();
+        self.detect_frames(&frames, Instant::now())
+            .into_iter()
+            .map(|result| result.map(|report| report.boxes))
+            .collect()
+    }
+
+    /// Processes multiple images in batch and writes a `report.html` summary into the output directory
+    pub fn process_images_batch_with_report<P: AsRef<Path>>(
+        &mut self,
+        image_paths: &[P],
+        output_dir: Option<&str>,
+    ) -> Result<Vec<Result<(), SessionError>>, SessionError> {
+        let output_dir_path = Path::new(output_dir.unwrap_or("output"));
+        let mut report =
+            HtmlReport::new("ClashVision batch report").with_classes(self.config.classes.clone());
+        let mut results = Vec::with_capacity(image_paths.len());
+
+        for path in image_paths {
+            let path = path.as_ref();
+            let Some(path_str) = path.to_str() else {
+                report.add_entry(ReportEntry::failure(path, "Invalid path"));
+                results.push(Err(SessionError::ImageProcessing(
+                    "Invalid path".to_string(),
+                )));
+                continue;
+            };
+
+            let first_warning = self.warnings.len();
+            let result = self.detect_and_save(path_str, output_dir);
+            let warnings: Vec<Warning> = self.warnings[first_warning..]
+                .iter()
+                .map(|w| w.warning.clone())
+                .collect();
+
+            match result {
+                Err(SessionError::Timeout(budget)) => {
+                    report.add_entry(ReportEntry::timeout(path, budget));
+                    results.push(Err(SessionError::Timeout(budget)));
+                }
+                Ok((dimensions, detection)) => {
+                    let annotated_path = path.file_stem().map(|stem| {
+                        output_dir_path.join(format!("{}.jpg", stem.to_string_lossy()))
+                    });
+                    report.add_entry(
+                        ReportEntry::success(path, annotated_path, dimensions, detection.boxes)
+                            .with_warnings(warnings),
+                    );
+                    results.push(Ok(()));
+                }
+                Err(e) => {
+                    report.add_entry(ReportEntry::failure(path, e.to_string()));
+                    results.push(Err(e));
+                }
+            }
+        }
+
+        std::fs::create_dir_all(output_dir_path)?;
+        report.write(&output_dir_path.join("report.html"))?;
 
         Ok(results)
     }
 }
 
+impl Drop for YoloSession {
+    fn drop(&mut self) {
+        #[cfg(feature = "tracing")]
+        if self.has_unflushed_output() {
+            tracing::warn!(
+                "YoloSession dropped with unflushed sink output; call flush_sinks() or shutdown() first"
+            );
+        }
+    }
+}
+
+/// Applies the configured NMS strategy to parsed boxes.
+///
+/// Soft-NMS drops boxes decayed under the lowest configured threshold, then the threshold of
+/// each class is applied to the decayed scores, so that per-class thresholds under the global
+/// one keep their boxes.
+fn suppress_overlaps(config: &SessionConfig, boxes: &[BoundingBox]) -> Vec<BoundingBox> {
+    let mut kept = match config.nms_strategy {
+        NmsStrategy::Soft { sigma, method } if config.use_per_class_nms => {
+            soft_nms_per_class(boxes, sigma, config.parse_threshold(), method)
+        }
+        NmsStrategy::Soft { sigma, method } => {
+            soft_nms(boxes, sigma, config.parse_threshold(), method)
+        }
+        NmsStrategy::Hard => {
+            return if let Some(group_nms) = &config.group_nms {
+                nms_per_group(boxes, group_nms, config.nms_threshold)
+            } else if config.use_per_class_nms {
+                nms_per_class(boxes, config.nms_threshold)
+            } else {
+                nms(boxes, config.nms_threshold)
+            };
+        }
+    };
+    if !config.per_class_confidence.is_empty() {
+        kept.retain(|bbox| config.accepts(bbox));
+    }
+    kept
+}
+
+/// Picks the first size (sorted by area) covering the longest image side, or the largest size
+fn pick_input_size(sizes: &[(u32, u32)], image_dimensions: (u32, u32)) -> Option<(u32, u32)> {
+    let longest_side = image_dimensions.0.max(image_dimensions.1);
+    sizes
+        .iter()
+        .copied()
+        .find(|&(w, h)| w.max(h) >= longest_side)
+        .or_else(|| sizes.last().copied())
+}
+
+/// Validates the model input and allocates `IoBinding` buffers for `input_size` when configured
+fn prepare_session(
+    session: &mut OrtInferenceSession,
+    config: &SessionConfig,
+    input_name: &str,
+    input_size: (u32, u32),
+) -> Result<(), SessionError> {
+    if config.validate_input {
+        validate_model_input(session, input_name, input_size)?;
+    }
+    if config.use_io_binding {
+        session
+            .enable_io_binding(
+                input_name,
+                [1, 3, input_size.1 as usize, input_size.0 as usize],
+            )
+            .map_err(|e| SessionError::Inference(format!("Failed to set up IoBinding: {e}")))?;
+    }
+    Ok(())
+}
+
+/// Checks that the model's image input accepts the NCHW f32 tensors of the configured size
+fn validate_model_input(
+    session: &OrtInferenceSession,
+    input_name: &str,
+    input_size: (u32, u32),
+) -> Result<(), SessionError> {
+    let spec = session.input_spec(input_name).ok_or_else(|| {
+        SessionError::InvalidInput(format!(
+            "model has no input named '{input_name}' (inputs: {:?})",
+            session.input_names()
+        ))
+    })?;
+
+    spec.validate_dtype()?;
+    spec.validate_layout(&[1, 3, input_size.1 as usize, input_size.0 as usize])
+}
+
+/// Decodes the image on a worker thread, giving up after `budget`.
+///
+/// A decode that times out keeps running in the background until it finishes, but its result
+/// is discarded so the batch can move on.
+fn load_frame_with_timeout(image_path: &str, budget: Duration) -> Result<Frame, SessionError> {
+    let (sender, receiver) = mpsc::channel();
+    let path = image_path.to_string();
+    std::thread::spawn(move || {
+        let _ = sender.send(load_frame(&path));
+    });
+
+    match receiver.recv_timeout(budget) {
+        Ok(frame) => frame,
+        Err(mpsc::RecvTimeoutError::Timeout) => Err(SessionError::Timeout(budget)),
+        Err(mpsc::RecvTimeoutError::Disconnected) => Err(SessionError::ImageProcessing(
+            "Image decoding thread panicked".to_string(),
+        )),
+    }
+}
+
+/// Converts a letterboxed NCHW model input to an interleaved RGB image
+fn interleave(loaded_image: &LoadedImageU8) -> Result<RgbImage, SessionError> {
+    // Convert NCHW to interleaved HWC using direct buffer access
+    let src = loaded_image
+        .image_array
+        .as_slice()
+        .ok_or_else(|| SessionError::ImageProcessing("Image array not contiguous".to_string()))?;
+    let h = loaded_image.size.height as usize;
+    let w = loaded_image.size.width as usize;
+    let hw = h * w;
+    let mut interleaved_data = vec![0u8; hw * 3];
+
+    let ch_r = &src[0..hw];
+    let ch_g = &src[hw..2 * hw];
+    let ch_b = &src[2 * hw..3 * hw];
+
+    for i in 0..hw {
+        let dst = i * 3;
+        interleaved_data[dst] = ch_r[i];
+        interleaved_data[dst + 1] = ch_g[i];
+        interleaved_data[dst + 2] = ch_b[i];
+    }
+
+    RgbImage::from_raw(
+        loaded_image.size.width,
+        loaded_image.size.height,
+        interleaved_data,
+    )
+    .ok_or_else(|| {
+        SessionError::ImageProcessing("Failed to create image from raw data".to_string())
+    })
+}
+
+/// Decodes the image at `image_path` into a frame
+fn load_frame(image_path: &str) -> Result<Frame, SessionError> {
+    guard(image_path, || {
+        Frame::from_path(image_path)
+            .map_err(|e| SessionError::ImageProcessing(format!("Failed to load image:{e}")))
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::detection::nms::SoftNmsMethod;
 
     #[test]
     fn test_session_config_default() {
@@ -256,4 +1710,54 @@ mod tests {
         assert_eq!(config.nms_threshold, 0.45);
         assert_eq!(config.confidence_threshold, 0.25);
     }
+
+    #[test]
+    fn test_load_frame_with_timeout() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.png");
+        RgbImage::new(4, 4).save(&path).unwrap();
+        let path = path.to_str().unwrap();
+
+        let frame = load_frame_with_timeout(path, Duration::from_secs(30)).unwrap();
+        assert_eq!(frame.image.width(), 4);
+        assert!(load_frame_with_timeout("missing.png", Duration::from_secs(30)).is_err());
+    }
+
+    #[test]
+    fn test_soft_nms_keeps_per_class_thresholds_under_global() {
+        let mut config = SessionConfig {
+            confidence_threshold: 0.5,
+            nms_strategy: NmsStrategy::Soft {
+                sigma: 0.5,
+                method: SoftNmsMethod::Gaussian,
+            },
+            ..SessionConfig::default()
+        };
+        config.per_class_confidence.insert(1, 0.2);
+        let boxes = [
+            BoundingBox::new(0.0, 0.0, 10.0, 10.0, 0, 0.9),
+            BoundingBox::new(50.0, 50.0, 60.0, 60.0, 1, 0.3),
+            BoundingBox::new(100.0, 0.0, 110.0, 10.0, 0, 0.3),
+        ];
+
+        let kept = suppress_overlaps(&config, &boxes);
+        assert_eq!(kept.len(), 2);
+        assert!(kept.iter().any(|bbox| bbox.class_id == 1));
+        assert!(kept.iter().all(|bbox| bbox.confidence >= 0.2));
+        assert!(
+            !kept
+                .iter()
+                .any(|bbox| bbox.class_id == 0 && bbox.confidence < 0.5)
+        );
+    }
+
+    #[test]
+    fn test_pick_input_size() {
+        let sizes = [(640, 640), (1280, 1280)];
+        assert_eq!(pick_input_size(&sizes, (500, 300)), Some((640, 640)));
+        assert_eq!(pick_input_size(&sizes, (640, 480)), Some((640, 640)));
+        assert_eq!(pick_input_size(&sizes, (1000, 700)), Some((1280, 1280)));
+        assert_eq!(pick_input_size(&sizes, (4000, 3000)), Some((1280, 1280)));
+        assert_eq!(pick_input_size(&[], (640, 640)), None);
+    }
 }
@@ -0,0 +1,283 @@
+//! Alert rules on the detections of an image, posted to a Discord or Slack compatible webhook
+
+use super::{DetectionSink, SinkError};
+use crate::class::registry::ClassRegistry;
+use crate::detection::BoundingBox;
+use crate::store::ImageRecord;
+use image::{ImageFormat, RgbImage};
+use std::fmt;
+use std::io::Cursor;
+use std::path::Path;
+use std::str::FromStr;
+use ureq::Agent;
+
+/// Boundary separating the parts of multipart webhook requests
+const MULTIPART_BOUNDARY: &str = "clashvision-alert-boundary";
+
+/// Rule firing when an image has at least `min_count` detections of a class above a confidence,
+/// written `<class> >= <count> [@ <confidence>]`, e.g. `Gold Storage>=4@0.6`
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlertRule {
+    pub class: String,
+    pub min_count: usize,
+    pub min_confidence: f32,
+}
+
+impl AlertRule {
+    /// Returns the number of matching detections if the rule fires on `boxes`
+    #[must_use]
+    pub fn evaluate(&self, boxes: &[BoundingBox], classes: &ClassRegistry) -> Option<usize> {
+        let count = boxes
+            .iter()
+            .filter(|bbox| {
+                bbox.confidence > self.min_confidence
+                    && classes
+                        .name(bbox.class_id)
+                        .eq_ignore_ascii_case(&self.class)
+            })
+            .count();
+        (count > 0 && count >= self.min_count).then_some(count)
+    }
+}
+
+impl FromStr for AlertRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid alert rule '{s}', expected e.g. 'Gold Storage>=4@0.6'");
+        let (class, threshold) = s.split_once(">=").ok_or_else(invalid)?;
+        let (count, confidence) = threshold.split_once('@').unwrap_or((threshold, "0"));
+        let class = class.trim();
+        if class.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Self {
+            class: class.to_string(),
+            min_count: count.trim().parse().map_err(|_| invalid())?,
+            min_confidence: confidence
+                .trim()
+                .parse()
+                .ok()
+                .filter(|c| (0.0..=1.0).contains(c))
+                .ok_or_else(invalid)?,
+        })
+    }
+}
+
+impl fmt::Display for AlertRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} >= {}", self.class, self.min_count)?;
+        if self.min_confidence > 0.0 {
+            write!(f, " @ {}", self.min_confidence)?;
+        }
+        Ok(())
+    }
+}
+
+/// Sink posting an alert to a webhook for every image on which a rule fires.
+///
+/// The message is sent as `content` (Discord) and `text` (Slack). The annotated image is
+/// attached as multipart form data, except for Slack webhooks which only accept JSON.
+#[derive(Debug, Clone)]
+pub struct WebhookSink {
+    agent: Agent,
+    url: String,
+    rules: Vec<AlertRule>,
+    classes: ClassRegistry,
+    attach_image: bool,
+}
+
+impl WebhookSink {
+    /// Creates a sink posting to `url` when any of `rules` fires, naming classes after `classes`
+    #[must_use]
+    pub fn new(url: impl Into<String>, rules: Vec<AlertRule>, classes: ClassRegistry) -> Self {
+        let url = url.into();
+        Self {
+            agent: Agent::new_with_defaults(),
+            attach_image: !url.contains("hooks.slack.com"),
+            url,
+            rules,
+            classes,
+        }
+    }
+
+    /// Sets whether the annotated image is attached to the alerts
+    #[must_use]
+    pub const fn with_image(mut self, attach_image: bool) -> Self {
+        self.attach_image = attach_image;
+        self
+    }
+
+    /// Returns the alert message for the detections of an image, if any rule fires
+    #[must_use]
+    pub fn message(&self, image_path: &str, boxes: &[BoundingBox]) -> Option<String> {
+        let fired: Vec<String> = self
+            .rules
+            .iter()
+            .filter_map(|rule| {
+                rule.evaluate(boxes, &self.classes)
+                    .map(|count| format!("{rule} (found {count})"))
+            })
+            .collect();
+        if fired.is_empty() {
+            return None;
+        }
+        let name = Path::new(image_path)
+            .file_name()
+            .map_or_else(|| image_path.into(), |name| name.to_string_lossy());
+        Some(format!("Alert on {name}: {}", fired.join(", ")))
+    }
+
+    /// Builds the multipart body of an alert with the annotated image as a JPEG attachment
+    fn multipart_body(payload: &str, image: &RgbImage) -> Result<Vec<u8>, SinkError> {
+        let mut jpeg = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut jpeg), ImageFormat::Jpeg)
+            .map_err(|e| SinkError::Http(e.to_string()))?;
+
+        let mut body = format!(
+            "--{MULTIPART_BOUNDARY}\r\n\
+             Content-Disposition: form-data; name=\"payload_json\"\r\n\
+             Content-Type: application/json\r\n\r\n\
+             {payload}\r\n\
+             --{MULTIPART_BOUNDARY}\r\n\
+             Content-Disposition: form-data; name=\"files[0]\"; filename=\"detections.jpg\"\r\n\
+             Content-Type: image/jpeg\r\n\r\n"
+        )
+        .into_bytes();
+        body.extend_from_slice(&jpeg);
+        body.extend_from_slice(format!("\r\n--{MULTIPART_BOUNDARY}--\r\n").as_bytes());
+        Ok(body)
+    }
+}
+
+impl DetectionSink for WebhookSink {
+    fn write(&mut self, record: &ImageRecord, annotated_image: &RgbImage) -> Result<(), SinkError> {
+        let Some(message) = self.message(&record.image_path, &record.boxes) else {
+            return Ok(());
+        };
+        let payload = serde_json::json!({ "content": message, "text": message }).to_string();
+
+        let request = self.agent.post(&self.url);
+        let response = if self.attach_image {
+            request
+                .header(
+                    "Content-Type",
+                    format!("multipart/form-data; boundary={MULTIPART_BOUNDARY}"),
+                )
+                .send(&Self::multipart_body(&payload, annotated_image)?)
+        } else {
+            request
+                .header("Content-Type", "application/json")
+                .send(&payload)
+        };
+        response.map_err(|e| SinkError::Http(e.to_string()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::time::{Duration, SystemTime};
+
+    fn boxes() -> Vec<BoundingBox> {
+        vec![
+            BoundingBox::new(0.0, 0.0, 4.0, 4.0, 1, 0.9),
+            BoundingBox::new(8.0, 0.0, 12.0, 4.0, 1, 0.7),
+            BoundingBox::new(8.0, 8.0, 12.0, 12.0, 1, 0.5),
+        ]
+    }
+
+    #[test]
+    fn test_alert_rule_parse_and_evaluate() {
+        let rule: AlertRule = "Gold Storage >= 2 @ 0.6".parse().unwrap();
+        assert_eq!(
+            rule,
+            AlertRule {
+                class: "Gold Storage".to_string(),
+                min_count: 2,
+                min_confidence: 0.6
+            }
+        );
+        let classes = ClassRegistry::default();
+        assert_eq!(rule.evaluate(&boxes(), &classes), Some(2));
+        assert_eq!(
+            "gold storage>=3"
+                .parse::<AlertRule>()
+                .unwrap()
+                .evaluate(&boxes(), &classes),
+            Some(3)
+        );
+        assert_eq!(
+            "Gold Storage>=3@0.6"
+                .parse::<AlertRule>()
+                .unwrap()
+                .evaluate(&boxes(), &classes),
+            None
+        );
+        assert!("Gold Storage".parse::<AlertRule>().is_err());
+        assert!(">=2".parse::<AlertRule>().is_err());
+        assert!("Gold Storage>=2@1.5".parse::<AlertRule>().is_err());
+    }
+
+    #[test]
+    fn test_webhook_sink_posts_fired_alerts_with_image() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/webhook", listener.local_addr().unwrap());
+
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut content_type = String::new();
+            let mut content_length = 0;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if header.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = header.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    } else if name.eq_ignore_ascii_case("content-type") {
+                        content_type = value.trim().to_string();
+                    }
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+
+            let mut stream = stream;
+            stream
+                .write_all(b"HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n")
+                .unwrap();
+            (content_type, String::from_utf8_lossy(&body).into_owned())
+        });
+
+        let rules = vec!["Gold Storage>=2@0.6".parse().unwrap()];
+        let mut sink = WebhookSink::new(url, rules, ClassRegistry::default());
+        let mut record = ImageRecord {
+            image_path: "scout/base.png".to_string(),
+            fingerprint: None,
+            processed_at: SystemTime::now(),
+            duration: Duration::ZERO,
+            image_dimensions: (640, 640),
+            boxes: boxes()[..1].to_vec(),
+            captured_at: None,
+        };
+        // Below the threshold: nothing is posted
+        sink.write(&record, &RgbImage::new(4, 4)).unwrap();
+        record.boxes = boxes();
+        sink.write(&record, &RgbImage::new(4, 4)).unwrap();
+
+        let (content_type, body) = server.join().unwrap();
+        assert!(content_type.starts_with("multipart/form-data"));
+        assert!(
+            body.contains(r#""content":"Alert on base.png: Gold Storage >= 2 @ 0.6 (found 2)""#)
+        );
+        assert!(body.contains("Content-Type: image/jpeg"));
+    }
+}
@@ -0,0 +1,70 @@
+//! Sink forwarding detections to another thread
+
+use super::{DetectionSink, SinkError};
+use crate::store::ImageRecord;
+use image::RgbImage;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+/// Sink sending every image record over an `mpsc` channel
+#[derive(Debug, Clone)]
+pub struct ChannelSink {
+    sender: Sender<ImageRecord>,
+}
+
+impl ChannelSink {
+    /// Creates a sink sending to an existing channel
+    #[must_use]
+    pub const fn new(sender: Sender<ImageRecord>) -> Self {
+        Self { sender }
+    }
+
+    /// Creates a sink together with the receiving end of its channel
+    #[must_use]
+    pub fn channel() -> (Self, Receiver<ImageRecord>) {
+        let (sender, receiver) = mpsc::channel();
+        (Self::new(sender), receiver)
+    }
+}
+
+impl DetectionSink for ChannelSink {
+    fn write(&mut self, record: &ImageRecord, _: &RgbImage) -> Result<(), SinkError> {
+        self.sender
+            .send(record.clone())
+            .map_err(|_| SinkError::Disconnected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    fn record() -> ImageRecord {
+        ImageRecord {
+            image_path: "a.png".to_string(),
+            fingerprint: None,
+            processed_at: SystemTime::now(),
+            duration: Duration::ZERO,
+            image_dimensions: (640, 640),
+            boxes: Vec::new(),
+            captured_at: None,
+        }
+    }
+
+    #[test]
+    fn test_channel_sink_forwards_records() {
+        let (mut sink, receiver) = ChannelSink::channel();
+        sink.write(&record(), &RgbImage::new(1, 1)).unwrap();
+        assert_eq!(receiver.recv().unwrap().image_path, "a.png");
+    }
+
+    #[test]
+    fn test_channel_sink_disconnected() {
+        let (mut sink, receiver) = ChannelSink::channel();
+        drop(receiver);
+        assert!(matches!(
+            sink.write(&record(), &RgbImage::new(1, 1)),
+            Err(SinkError::Disconnected)
+        ));
+    }
+}
@@ -0,0 +1,179 @@
+//! Sink saving annotated images and detection files to a directory
+
+use super::{DetectionSink, SinkError};
+use crate::class::registry::ClassRegistry;
+use crate::detection::output::OutputFormat;
+use crate::report::detection::DetectionReport;
+#[cfg(feature = "encryption")]
+use crate::report::encryption::{OutputKey, encrypted_path};
+use crate::store::ImageRecord;
+use image::RgbImage;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Sink writing `<name>.jpg` and the detections file of every image into a directory
+#[derive(Debug, Clone)]
+pub struct FileWriterSink {
+    output_dir: PathBuf,
+    format: OutputFormat,
+    classes: ClassRegistry,
+    #[cfg(feature = "encryption")]
+    key: Option<OutputKey>,
+}
+
+impl FileWriterSink {
+    /// Creates a sink writing into `output_dir` using the given detections format
+    #[must_use]
+    pub fn new(output_dir: impl Into<PathBuf>, format: OutputFormat) -> Self {
+        Self {
+            output_dir: output_dir.into(),
+            format,
+            classes: ClassRegistry::default(),
+            #[cfg(feature = "encryption")]
+            key: None,
+        }
+    }
+
+    /// Names the categories of the detection files after `classes`
+    #[must_use]
+    pub fn with_classes(mut self, classes: ClassRegistry) -> Self {
+        self.classes = classes;
+        self
+    }
+
+    /// Encrypts the detection files with `key`, written as e.g. `village.json.enc`
+    #[cfg(feature = "encryption")]
+    #[must_use]
+    pub fn with_key(mut self, key: OutputKey) -> Self {
+        self.key = Some(key);
+        self
+    }
+
+    /// Returns the directory the sink writes into
+    #[inline]
+    #[must_use]
+    pub fn output_dir(&self) -> &Path {
+        &self.output_dir
+    }
+}
+
+impl DetectionSink for FileWriterSink {
+    fn write(&mut self, record: &ImageRecord, annotated_image: &RgbImage) -> Result<(), SinkError> {
+        #[cfg(feature = "encryption")]
+        if let Some(key) = &self.key {
+            write_encrypted_outputs(
+                annotated_image,
+                &DetectionReport::from(record),
+                &self.output_dir,
+                self.format,
+                &self.classes,
+                key,
+            )?;
+            return Ok(());
+        }
+        write_outputs(
+            annotated_image,
+            &DetectionReport::from(record),
+            &self.output_dir,
+            self.format,
+            &self.classes,
+        )?;
+        Ok(())
+    }
+}
+
+/// Saves the annotated image and the detections file named after the image of `report`
+/// into `output_dir`
+pub fn write_outputs(
+    image: &RgbImage,
+    report: &DetectionReport,
+    output_dir: &Path,
+    format: OutputFormat,
+    classes: &ClassRegistry,
+) -> io::Result<()> {
+    let output_path = save_annotated_image(image, report, output_dir, format)?;
+    OutputFormat::output_detections(
+        &report.boxes,
+        image.dimensions(),
+        &output_path,
+        Some(format),
+        classes,
+    )
+}
+
+/// Saves the outputs like `write_outputs`, encrypting the detections file with `key` into
+/// e.g. `village.json.enc` without writing its plaintext. The annotated image is not encrypted.
+#[cfg(feature = "encryption")]
+pub fn write_encrypted_outputs(
+    image: &RgbImage,
+    report: &DetectionReport,
+    output_dir: &Path,
+    format: OutputFormat,
+    classes: &ClassRegistry,
+    key: &OutputKey,
+) -> io::Result<()> {
+    let output_path = save_annotated_image(image, report, output_dir, format)?;
+    let contents = OutputFormat::render_detections(
+        &report.boxes,
+        image.dimensions(),
+        &output_path,
+        Some(format),
+        classes,
+    );
+    key.write_sealed(&encrypted_path(&output_path), contents.as_bytes())
+}
+
+/// Saves the annotated image as `<name>.jpg`, returning the path of the detections file
+fn save_annotated_image(
+    image: &RgbImage,
+    report: &DetectionReport,
+    output_dir: &Path,
+    format: OutputFormat,
+) -> io::Result<PathBuf> {
+    let file_name = Path::new(&report.image_path)
+        .file_stem()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Invalid image path"))?;
+
+    if !output_dir.exists() {
+        std::fs::create_dir_all(output_dir)?;
+    }
+
+    let image_output_path = output_dir.join(format!("{}.jpg", file_name.to_string_lossy()));
+    let output_path = output_dir.join(format!(
+        "{}.{}",
+        file_name.to_string_lossy(),
+        format.extension()
+    ));
+
+    // Save image
+    image.save(&image_output_path).map_err(io::Error::other)?;
+    Ok(output_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detection::BoundingBox;
+    use std::time::{Duration, SystemTime};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_file_writer_sink() {
+        let dir = tempdir().unwrap();
+        let mut sink = FileWriterSink::new(dir.path().join("out"), OutputFormat::Yolo);
+        let record = ImageRecord {
+            image_path: "images/village.png".to_string(),
+            fingerprint: None,
+            processed_at: SystemTime::now(),
+            duration: Duration::ZERO,
+            image_dimensions: (8, 8),
+            boxes: vec![BoundingBox::new(0.0, 0.0, 4.0, 4.0, 1, 0.9)],
+            captured_at: None,
+        };
+
+        sink.write(&record, &RgbImage::new(8, 8)).unwrap();
+        assert!(sink.output_dir().join("village.jpg").exists());
+        let labels = std::fs::read_to_string(sink.output_dir().join("village.txt")).unwrap();
+        assert!(labels.starts_with("1 0.250000 0.250000"));
+    }
+}
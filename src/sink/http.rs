@@ -0,0 +1,105 @@
+//! Sink posting detections to an HTTP endpoint
+
+use super::{DetectionSink, SinkError};
+use crate::store::ImageRecord;
+use image::RgbImage;
+use ureq::Agent;
+
+/// Sink sending a COCO JSON document per image as an HTTP POST request
+#[derive(Debug, Clone)]
+pub struct HttpSink {
+    agent: Agent,
+    url: String,
+}
+
+impl HttpSink {
+    /// Creates a sink posting to `url`
+    #[must_use]
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            agent: Agent::new_with_defaults(),
+            url: url.into(),
+        }
+    }
+
+    /// Returns the endpoint the sink posts to
+    #[inline]
+    #[must_use]
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+impl DetectionSink for HttpSink {
+    fn write(&mut self, record: &ImageRecord, _: &RgbImage) -> Result<(), SinkError> {
+        let body = record.to_json().to_string();
+
+        self.agent
+            .post(&self.url)
+            .header("Content-Type", "application/json")
+            .send(&body)
+            .map_err(|e| SinkError::Http(e.to_string()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detection::BoundingBox;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn test_http_sink_posts_json() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/detections", listener.local_addr().unwrap());
+
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+
+            let mut content_length = 0;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if header.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = header.split_once(':')
+                    && name.eq_ignore_ascii_case("content-length")
+                {
+                    content_length = value.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+
+            let mut stream = stream;
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                .unwrap();
+            (request_line, String::from_utf8(body).unwrap())
+        });
+
+        let mut sink = HttpSink::new(url);
+        let record = ImageRecord {
+            image_path: "a.png".to_string(),
+            fingerprint: None,
+            processed_at: SystemTime::now(),
+            duration: Duration::ZERO,
+            image_dimensions: (640, 640),
+            boxes: vec![BoundingBox::new(0.0, 0.0, 4.0, 4.0, 1, 0.9)],
+            captured_at: None,
+        };
+        sink.write(&record, &RgbImage::new(1, 1)).unwrap();
+
+        let (request_line, body) = server.join().unwrap();
+        assert!(request_line.starts_with("POST /detections"));
+        let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(json["images"][0]["file_name"], "a.png");
+    }
+}
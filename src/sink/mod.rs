@@ -0,0 +1,68 @@
+//! Detection sinks receiving the results of the detection pipeline
+
+#[cfg(feature = "http")]
+pub mod alert;
+pub mod channel;
+pub mod file;
+#[cfg(feature = "http")]
+pub mod http;
+#[cfg(feature = "notify")]
+pub mod notification;
+#[cfg(feature = "arrow")]
+pub mod parquet;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+pub mod stdout;
+
+use crate::store::{ImageRecord, StoreError};
+use image::RgbImage;
+
+/// Errors that can occur while publishing detections to a sink
+#[derive(Debug, thiserror::Error)]
+pub enum SinkError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Results store error: {0}")]
+    Store(#[from] StoreError),
+    #[error("Channel receiver disconnected")]
+    Disconnected,
+    #[cfg(feature = "http")]
+    #[error("HTTP error: {0}")]
+    Http(String),
+    #[cfg(feature = "notify")]
+    #[error("Notification error: {0}")]
+    Notification(String),
+}
+
+/// A consumer of detection results.
+///
+/// A session fans every processed image out to all of its sinks, so saving,
+/// publishing and storing results can be combined freely.
+pub trait DetectionSink {
+    /// Consumes the results of a processed image and its annotated rendering
+    fn write(&mut self, record: &ImageRecord, annotated_image: &RgbImage) -> Result<(), SinkError>;
+
+    /// Flushes any buffered output, called once a source is exhausted
+    fn flush(&mut self) -> Result<(), SinkError> {
+        Ok(())
+    }
+
+    /// Flushes and releases the sink's resources, called once on shutdown
+    fn close(&mut self) -> Result<(), SinkError> {
+        self.flush()
+    }
+}
+
+impl<S: DetectionSink + ?Sized> DetectionSink for Box<S> {
+    fn write(&mut self, record: &ImageRecord, annotated_image: &RgbImage) -> Result<(), SinkError> {
+        (**self).write(record, annotated_image)
+    }
+
+    fn flush(&mut self) -> Result<(), SinkError> {
+        (**self).flush()
+    }
+
+    fn close(&mut self) -> Result<(), SinkError> {
+        (**self).close()
+    }
+}
@@ -0,0 +1,143 @@
+//! Sink raising a desktop notification summarizing the detections once processing completes
+
+use super::{DetectionSink, SinkError};
+use crate::class::registry::ClassRegistry;
+use crate::store::ImageRecord;
+use image::RgbImage;
+use std::collections::BTreeMap;
+
+/// Function showing a notification with a summary line and a body
+type Notifier = Box<dyn FnMut(&str, &str) -> Result<(), String>>;
+
+/// Sink counting the detections of every image and raising a single notification when flushed,
+/// e.g. "3 Gold Storages, 2 Elixir Storages detected"
+pub struct NotificationSink {
+    classes: ClassRegistry,
+    images: usize,
+    counts: BTreeMap<usize, usize>,
+    notify: Notifier,
+}
+
+impl NotificationSink {
+    /// Creates a sink raising desktop notifications, naming the classes after `classes`
+    #[must_use]
+    pub fn new(classes: ClassRegistry) -> Self {
+        Self::with_notifier(classes, |summary, body| {
+            notify_rust::Notification::new()
+                .appname("clashvision")
+                .summary(summary)
+                .body(body)
+                .show()
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    /// Creates a sink passing the summary and body of its notifications to `notify`
+    #[must_use]
+    pub fn with_notifier(
+        classes: ClassRegistry,
+        notify: impl FnMut(&str, &str) -> Result<(), String> + 'static,
+    ) -> Self {
+        Self {
+            classes,
+            images: 0,
+            counts: BTreeMap::new(),
+            notify: Box::new(notify),
+        }
+    }
+
+    /// Returns the summary of the detections counted so far
+    #[must_use]
+    pub fn summary(&self) -> String {
+        if self.counts.is_empty() {
+            return "No buildings detected".to_string();
+        }
+        let parts: Vec<String> = self
+            .counts
+            .iter()
+            .map(|(&class_id, &count)| {
+                let name = self.classes.name(class_id);
+                if count == 1 {
+                    format!("1 {name}")
+                } else {
+                    format!("{count} {name}s")
+                }
+            })
+            .collect();
+        format!("{} detected", parts.join(", "))
+    }
+}
+
+impl std::fmt::Debug for NotificationSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NotificationSink")
+            .field("images", &self.images)
+            .field("counts", &self.counts)
+            .finish_non_exhaustive()
+    }
+}
+
+impl DetectionSink for NotificationSink {
+    fn write(&mut self, record: &ImageRecord, _: &RgbImage) -> Result<(), SinkError> {
+        self.images += 1;
+        for bbox in &record.boxes {
+            *self.counts.entry(bbox.class_id).or_insert(0) += 1;
+        }
+        Ok(())
+    }
+
+    /// Raises the notification if any image was processed since the last flush
+    fn flush(&mut self) -> Result<(), SinkError> {
+        if self.images == 0 {
+            return Ok(());
+        }
+        let summary = self.summary();
+        let body = format!("{} image(s) processed", self.images);
+        self.images = 0;
+        self.counts.clear();
+        (self.notify)(&summary, &body).map_err(SinkError::Notification)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detection::BoundingBox;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn test_notification_summarizes_detections() {
+        let shown = Rc::new(RefCell::new(Vec::new()));
+        let log = Rc::clone(&shown);
+        let mut sink = NotificationSink::with_notifier(ClassRegistry::default(), move |s, b| {
+            log.borrow_mut().push(format!("{s} / {b}"));
+            Ok(())
+        });
+        let record = ImageRecord {
+            image_path: "a.png".to_string(),
+            fingerprint: None,
+            processed_at: SystemTime::now(),
+            duration: Duration::ZERO,
+            image_dimensions: (640, 640),
+            boxes: vec![
+                BoundingBox::new(0.0, 0.0, 4.0, 4.0, 1, 0.9),
+                BoundingBox::new(8.0, 0.0, 12.0, 4.0, 1, 0.9),
+                BoundingBox::new(8.0, 8.0, 12.0, 12.0, 0, 0.9),
+            ],
+            captured_at: None,
+        };
+
+        sink.flush().unwrap();
+        sink.write(&record, &RgbImage::new(1, 1)).unwrap();
+        sink.write(&record, &RgbImage::new(1, 1)).unwrap();
+        sink.flush().unwrap();
+        assert_eq!(
+            *shown.borrow(),
+            ["2 Elixir Storages, 4 Gold Storages detected / 2 image(s) processed"]
+        );
+        assert_eq!(sink.summary(), "No buildings detected");
+    }
+}
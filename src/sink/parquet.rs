@@ -0,0 +1,250 @@
+//! Sink collecting the detections of a run into a Parquet file
+
+use super::{DetectionSink, SinkError};
+use crate::class::registry::ClassRegistry;
+#[cfg(feature = "encryption")]
+use crate::report::encryption::{OutputKey, encrypted_path};
+use crate::store::parquet::{detection_writer, records_to_batch};
+use crate::store::{ImageRecord, StoreError};
+use image::RgbImage;
+use parquet::arrow::ArrowWriter;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Sink writing the records of every image, one row per detection, to a Parquet file.
+///
+/// Each flush appends the records received since the previous one as a row group; the file
+/// footer is written on close, so the file is only readable once the sink is closed.
+#[derive(Debug)]
+pub struct ParquetSink {
+    path: PathBuf,
+    classes: ClassRegistry,
+    pending: Vec<ImageRecord>,
+    writer: Option<ArrowWriter<Output>>,
+    closed: bool,
+    #[cfg(feature = "encryption")]
+    key: Option<OutputKey>,
+}
+
+/// Destination of the Parquet bytes
+#[derive(Debug)]
+enum Output {
+    File(File),
+    /// In-memory file encrypted as a whole on close
+    #[cfg(feature = "encryption")]
+    Sealed(Vec<u8>),
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::File(file) => file.write(buf),
+            #[cfg(feature = "encryption")]
+            Self::Sealed(buffer) => buffer.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::File(file) => file.flush(),
+            #[cfg(feature = "encryption")]
+            Self::Sealed(_) => Ok(()),
+        }
+    }
+}
+
+impl ParquetSink {
+    /// Creates a sink writing to `path`, naming classes after the built-in classes
+    #[must_use]
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            classes: ClassRegistry::default(),
+            pending: Vec::new(),
+            writer: None,
+            closed: false,
+            #[cfg(feature = "encryption")]
+            key: None,
+        }
+    }
+
+    /// Fills the `class_name` column from `classes`
+    #[must_use]
+    pub fn with_classes(mut self, classes: ClassRegistry) -> Self {
+        self.classes = classes;
+        self
+    }
+
+    /// Encrypts the Parquet file with `key`, written to its path with `.enc` appended
+    #[cfg(feature = "encryption")]
+    #[must_use]
+    pub fn with_key(mut self, key: OutputKey) -> Self {
+        self.key = Some(key);
+        self
+    }
+
+    /// Returns the path of the Parquet file
+    #[inline]
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Starts the Parquet file, in memory if it is to be encrypted
+    fn open(&self) -> Result<ArrowWriter<Output>, SinkError> {
+        #[cfg(feature = "encryption")]
+        if self.key.is_some() {
+            return Ok(detection_writer(Output::Sealed(Vec::new()))?);
+        }
+        Ok(detection_writer(Output::File(File::create(&self.path)?))?)
+    }
+}
+
+impl DetectionSink for ParquetSink {
+    fn write(&mut self, record: &ImageRecord, _: &RgbImage) -> Result<(), SinkError> {
+        self.pending.push(record.clone());
+        Ok(())
+    }
+
+    /// Appends the records received since the last flush as a row group
+    fn flush(&mut self) -> Result<(), SinkError> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let batch = records_to_batch(&self.pending, &self.classes)?;
+        let writer = match &mut self.writer {
+            Some(writer) => writer,
+            None => self.writer.insert(self.open()?),
+        };
+        writer.write(&batch).map_err(StoreError::from)?;
+        writer.flush().map_err(StoreError::from)?;
+        self.pending.clear();
+        Ok(())
+    }
+
+    /// Flushes the pending records and writes the file footer, sealing the file if encrypted
+    fn close(&mut self) -> Result<(), SinkError> {
+        if self.closed {
+            return Ok(());
+        }
+        self.flush()?;
+        let writer = match self.writer.take() {
+            Some(writer) => writer,
+            None => self.open()?,
+        };
+        self.closed = true;
+        match writer.into_inner().map_err(StoreError::from)? {
+            Output::File(mut file) => file.flush()?,
+            #[cfg(feature = "encryption")]
+            Output::Sealed(parquet) => {
+                if let Some(key) = &self.key {
+                    key.write_sealed(&encrypted_path(&self.path), &parquet)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detection::BoundingBox;
+    use arrow_array::{StringArray, UInt32Array};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use std::fs::File;
+    use std::time::{Duration, SystemTime};
+    use tempfile::tempdir;
+
+    fn record(image_path: &str, boxes: Vec<BoundingBox>) -> ImageRecord {
+        ImageRecord {
+            image_path: image_path.to_string(),
+            fingerprint: None,
+            processed_at: SystemTime::now(),
+            duration: Duration::from_millis(10),
+            image_dimensions: (640, 480),
+            boxes,
+            captured_at: None,
+        }
+    }
+
+    #[test]
+    fn test_parquet_sink_collects_the_run() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let classes = ClassRegistry::from_json(r#"["Town Hall", "Cannon"]"#)?;
+        let mut sink = ParquetSink::new(dir.path().join("run.parquet")).with_classes(classes);
+        let image = RgbImage::new(1, 1);
+        sink.write(
+            &record(
+                "village.png",
+                vec![
+                    BoundingBox::new(0.0, 0.0, 10.0, 10.0, 0, 0.9),
+                    BoundingBox::new(5.0, 5.0, 15.0, 15.0, 1, 0.8),
+                ],
+            ),
+            &image,
+        )?;
+        sink.write(&record("empty.png", Vec::new()), &image)?;
+        sink.flush()?;
+        sink.flush()?;
+        sink.write(
+            &record(
+                "war.png",
+                vec![BoundingBox::new(1.0, 1.0, 2.0, 2.0, 1, 0.7)],
+            ),
+            &image,
+        )?;
+        sink.close()?;
+        sink.close()?;
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(sink.path())?)?;
+        assert_eq!(reader.metadata().num_row_groups(), 2);
+        let batches = reader.build()?.collect::<Result<Vec<_>, _>>()?;
+        let strings = |column: usize| -> Vec<String> {
+            batches
+                .iter()
+                .flat_map(|batch| {
+                    let values = batch.column(column).as_any().downcast_ref::<StringArray>();
+                    let values = values.expect("string column");
+                    values
+                        .iter()
+                        .flatten()
+                        .map(str::to_string)
+                        .collect::<Vec<_>>()
+                })
+                .collect()
+        };
+        assert_eq!(strings(0), ["village.png", "village.png", "war.png"]);
+        assert_eq!(strings(6), ["Town Hall", "Cannon", "Cannon"]);
+        let class_ids: Vec<u32> = batches
+            .iter()
+            .flat_map(|batch| {
+                let values = batch.column(5).as_any().downcast_ref::<UInt32Array>();
+                values.expect("class id column").values().to_vec()
+            })
+            .collect();
+        assert_eq!(class_ids, [0, 1, 1]);
+        Ok(())
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn test_encrypted_parquet_is_sealed_on_close() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let key = OutputKey::from_hex(&"2a".repeat(32))?;
+        let mut sink = ParquetSink::new(dir.path().join("run.parquet")).with_key(key.clone());
+        let boxes = vec![BoundingBox::new(0.0, 0.0, 10.0, 10.0, 1, 0.9)];
+        sink.write(&record("village.png", boxes.clone()), &RgbImage::new(1, 1))?;
+        sink.flush()?;
+        sink.write(&record("war.png", boxes), &RgbImage::new(1, 1))?;
+        sink.close()?;
+
+        assert!(!sink.path().exists());
+        let opened = dir.path().join("opened.parquet");
+        std::fs::write(&opened, key.read_opened(&encrypted_path(sink.path()))?)?;
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(opened)?)?;
+        assert_eq!(reader.metadata().num_row_groups(), 2);
+        Ok(())
+    }
+}
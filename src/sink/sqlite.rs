@@ -0,0 +1,13 @@
+//! Results store used as a detection sink
+
+use super::{DetectionSink, SinkError};
+use crate::store::ImageRecord;
+use crate::store::sqlite::SqliteStore;
+use image::RgbImage;
+
+impl DetectionSink for SqliteStore {
+    fn write(&mut self, record: &ImageRecord, _: &RgbImage) -> Result<(), SinkError> {
+        self.insert(record)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+//! Sink printing detections as JSON lines
+
+use super::{DetectionSink, SinkError};
+use crate::store::ImageRecord;
+use image::RgbImage;
+use std::io::{self, Write};
+
+/// Sink writing one compact COCO JSON document per image and line, to stdout by default
+#[derive(Debug)]
+pub struct StdoutSink<W: Write = io::Stdout> {
+    writer: W,
+}
+
+impl StdoutSink {
+    /// Creates a sink writing to the process standard output
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            writer: io::stdout(),
+        }
+    }
+}
+
+impl Default for StdoutSink {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<W: Write> StdoutSink<W> {
+    /// Creates a sink writing to an arbitrary writer
+    #[must_use]
+    pub const fn with_writer(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Consumes the sink and returns its writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> DetectionSink for StdoutSink<W> {
+    fn write(&mut self, record: &ImageRecord, _: &RgbImage) -> Result<(), SinkError> {
+        writeln!(self.writer, "{}", record.to_json())?;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), SinkError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detection::BoundingBox;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn test_stdout_sink_writes_json_lines() {
+        let mut sink = StdoutSink::with_writer(Vec::new());
+        let mut record = ImageRecord {
+            image_path: "a.png".to_string(),
+            fingerprint: None,
+            processed_at: SystemTime::now(),
+            duration: Duration::ZERO,
+            image_dimensions: (640, 640),
+            boxes: vec![BoundingBox::new(0.0, 0.0, 4.0, 4.0, 1, 0.9)],
+            captured_at: None,
+        };
+        sink.write(&record, &RgbImage::new(1, 1)).unwrap();
+        record.image_path = "b.png".to_string();
+        sink.write(&record, &RgbImage::new(1, 1)).unwrap();
+
+        let output = String::from_utf8(sink.into_inner()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let json: serde_json::Value = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(json["images"][0]["file_name"], "b.png");
+        assert_eq!(json["detections"][0]["category_id"], 1);
+    }
+}
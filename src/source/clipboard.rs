@@ -0,0 +1,66 @@
+//! Image read from the system clipboard, e.g. a screenshot that was just snipped
+
+use super::{Frame, ImageSource, SourceError};
+use image::{DynamicImage, RgbaImage};
+
+/// Name of the frames read from the clipboard
+pub const CLIPBOARD_FRAME_NAME: &str = "clipboard";
+
+/// Reads the image currently in the system clipboard
+pub fn read_image() -> Result<DynamicImage, SourceError> {
+    let mut clipboard =
+        arboard::Clipboard::new().map_err(|e| SourceError::Clipboard(e.to_string()))?;
+    let data = clipboard
+        .get_image()
+        .map_err(|e| SourceError::Clipboard(e.to_string()))?;
+    rgba_to_image(data.width, data.height, data.bytes.into_owned())
+}
+
+/// Converts clipboard RGBA pixels to an image
+fn rgba_to_image(width: usize, height: usize, bytes: Vec<u8>) -> Result<DynamicImage, SourceError> {
+    let invalid = || SourceError::Clipboard(format!("invalid {width}x{height} RGBA image"));
+    let width = u32::try_from(width).map_err(|_| invalid())?;
+    let height = u32::try_from(height).map_err(|_| invalid())?;
+    RgbaImage::from_raw(width, height, bytes)
+        .map(DynamicImage::ImageRgba8)
+        .ok_or_else(invalid)
+}
+
+/// Source yielding the clipboard image once
+#[derive(Debug, Clone, Default)]
+pub struct ClipboardSource {
+    read: bool,
+}
+
+impl ClipboardSource {
+    /// Creates a source over the current clipboard content
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { read: false }
+    }
+}
+
+impl ImageSource for ClipboardSource {
+    fn next(&mut self) -> Option<Result<Frame, SourceError>> {
+        if self.read {
+            return None;
+        }
+        self.read = true;
+        Some(read_image().map(|image| Frame::new(CLIPBOARD_FRAME_NAME, image)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rgba_to_image() {
+        let image = rgba_to_image(2, 1, vec![255, 0, 0, 255, 0, 0, 255, 255]).unwrap();
+        assert_eq!(image.to_rgb8().get_pixel(1, 0).0, [0, 0, 255]);
+        assert!(matches!(
+            rgba_to_image(2, 2, vec![0; 4]),
+            Err(SourceError::Clipboard(_))
+        ));
+    }
+}
@@ -0,0 +1,253 @@
+//! File-based image sources
+
+use super::{Frame, ImageSource, SourceError};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File extensions recognized as images when scanning a directory
+pub const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "bmp", "webp", "tif", "tiff"];
+
+/// Source yielding the images of an explicit list of files, in order
+#[derive(Debug, Clone, Default)]
+pub struct FileListSource {
+    paths: VecDeque<PathBuf>,
+}
+
+impl FileListSource {
+    /// Creates a source over the given file paths
+    #[must_use]
+    pub fn new<P: AsRef<Path>>(paths: &[P]) -> Self {
+        Self {
+            paths: paths.iter().map(|p| p.as_ref().to_path_buf()).collect(),
+        }
+    }
+
+    /// Returns the number of files not yet read
+    #[inline]
+    #[must_use]
+    pub fn remaining(&self) -> usize {
+        self.paths.len()
+    }
+}
+
+impl ImageSource for FileListSource {
+    fn next(&mut self) -> Option<Result<Frame, SourceError>> {
+        self.paths.pop_front().map(Frame::from_path)
+    }
+}
+
+/// Source yielding every image file of a directory, sorted by file name
+#[derive(Debug, Clone)]
+pub struct DirectorySource {
+    files: FileListSource,
+}
+
+impl DirectorySource {
+    /// Scans `dir` (non-recursively) for files with a known image extension
+    pub fn new(dir: impl AsRef<Path>) -> Result<Self, SourceError> {
+        Ok(Self {
+            files: FileListSource::new(&image_files(dir)?),
+        })
+    }
+
+    /// Returns the number of files not yet read
+    #[inline]
+    #[must_use]
+    pub fn remaining(&self) -> usize {
+        self.files.remaining()
+    }
+}
+
+impl ImageSource for DirectorySource {
+    fn next(&mut self) -> Option<Result<Frame, SourceError>> {
+        self.files.next()
+    }
+}
+
+/// Options of the discovery of the images of a directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectoryOptions {
+    pub recursive: bool,         // Descend into subdirectories
+    pub extensions: Vec<String>, // Accepted extensions, case-insensitive
+    pub pattern: Option<String>, // Glob on file names (`*` and `?`), e.g. `base_*.png`
+}
+
+impl Default for DirectoryOptions {
+    fn default() -> Self {
+        Self {
+            recursive: false,
+            extensions: IMAGE_EXTENSIONS.iter().map(ToString::to_string).collect(),
+            pattern: None,
+        }
+    }
+}
+
+impl DirectoryOptions {
+    /// Returns the files of `dir` matching the options, sorted by path
+    pub fn find(&self, dir: impl AsRef<Path>) -> Result<Vec<PathBuf>, SourceError> {
+        let mut paths = find_images(dir, self.recursive, &self.extensions)?;
+        if let Some(pattern) = &self.pattern {
+            paths.retain(|path| {
+                path.file_name()
+                    .is_some_and(|name| matches_glob(pattern, &name.to_string_lossy()))
+            });
+        }
+        Ok(paths)
+    }
+}
+
+/// Returns the files of `dir` (non-recursively) with a known image extension, sorted by name
+pub fn image_files(dir: impl AsRef<Path>) -> Result<Vec<PathBuf>, SourceError> {
+    find_images(dir, false, IMAGE_EXTENSIONS)
+}
+
+/// Returns the files of `dir` with one of `extensions` (case-insensitive), descending into
+/// subdirectories if `recursive`, sorted by path. Symbolic links to directories are not followed.
+pub fn find_images<S: AsRef<str>>(
+    dir: impl AsRef<Path>,
+    recursive: bool,
+    extensions: &[S],
+) -> Result<Vec<PathBuf>, SourceError> {
+    let mut paths = Vec::new();
+    let mut pending = vec![dir.as_ref().to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if recursive && entry.file_type()?.is_dir() {
+                pending.push(path);
+            } else if path.is_file() && has_extension(&path, extensions) {
+                paths.push(path);
+            }
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Returns true if the path has one of `extensions` (case-insensitive)
+fn has_extension<S: AsRef<str>>(path: &Path, extensions: &[S]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            extensions
+                .iter()
+                .any(|known| known.as_ref().eq_ignore_ascii_case(ext))
+        })
+}
+
+/// Matches a name against a glob where `*` stands for any run of characters and `?` for one
+fn matches_glob(pattern: &str, name: &str) -> bool {
+    let (pattern, name): (Vec<char>, Vec<char>) =
+        (pattern.chars().collect(), name.chars().collect());
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                // Let the last star swallow one more character
+                Some((star, matched)) => {
+                    backtrack = Some((star, matched + 1));
+                    p = star + 1;
+                    n = matched + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbImage;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_file_list_source_order() {
+        let dir = tempdir().unwrap();
+        let a = dir.path().join("a.png");
+        let b = dir.path().join("b.png");
+        RgbImage::new(2, 2).save(&a).unwrap();
+        RgbImage::new(3, 3).save(&b).unwrap();
+
+        let mut source = FileListSource::new(&[&b, &a]);
+        assert_eq!(source.remaining(), 2);
+        assert_eq!(source.next().unwrap().unwrap().image.width(), 3);
+        assert_eq!(source.next().unwrap().unwrap().path, Some(a));
+        assert!(source.next().is_none());
+    }
+
+    #[test]
+    fn test_file_list_source_reports_missing_files() {
+        let mut source = FileListSource::new(&["missing.png"]);
+        assert!(source.next().unwrap().is_err());
+        assert!(source.next().is_none());
+    }
+
+    #[test]
+    fn test_directory_source_filters_and_sorts() {
+        let dir = tempdir().unwrap();
+        RgbImage::new(2, 2).save(dir.path().join("b.png")).unwrap();
+        RgbImage::new(2, 2).save(dir.path().join("a.JPG")).unwrap();
+        fs::write(dir.path().join("notes.txt"), "not an image").unwrap();
+
+        let mut source = DirectorySource::new(dir.path()).unwrap();
+        assert_eq!(source.remaining(), 2);
+        let first = source.next().unwrap().unwrap();
+        assert!(first.name.ends_with("a.JPG"));
+    }
+
+    #[test]
+    fn test_find_images_recursive_with_pattern() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("war/day2")).unwrap();
+        for name in [
+            "base_1.png",
+            "war/base_2.PNG",
+            "war/day2/base_3.jpg",
+            "war/raid.png",
+        ] {
+            fs::write(dir.path().join(name), b"").unwrap();
+        }
+
+        assert_eq!(find_images(dir.path(), false, &["png"]).unwrap().len(), 1);
+        assert_eq!(find_images(dir.path(), true, &["png"]).unwrap().len(), 3);
+
+        let options = DirectoryOptions {
+            recursive: true,
+            pattern: Some("base_*".to_string()),
+            ..DirectoryOptions::default()
+        };
+        let found = options.find(dir.path()).unwrap();
+        assert_eq!(
+            found,
+            [
+                dir.path().join("base_1.png"),
+                dir.path().join("war/base_2.PNG"),
+                dir.path().join("war/day2/base_3.jpg"),
+            ]
+        );
+
+        assert!(matches_glob("*_?.p*g", "base_1.png"));
+        assert!(!matches_glob("base_?.png", "base_12.png"));
+        assert!(matches_glob("*", ""));
+    }
+
+    #[test]
+    fn test_has_extension() {
+        assert!(has_extension(Path::new("village.PNG"), IMAGE_EXTENSIONS));
+        assert!(!has_extension(Path::new("village.txt"), IMAGE_EXTENSIONS));
+        assert!(!has_extension(Path::new("village"), IMAGE_EXTENSIONS));
+    }
+}
@@ -0,0 +1,108 @@
+//! Image sources feeding frames into the detection pipeline
+
+#[cfg(feature = "clipboard")]
+pub mod clipboard;
+pub mod file;
+pub mod priority;
+pub mod shard;
+
+use crate::image::image_util::ImageLoadError;
+use image::DynamicImage;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Errors that can occur while reading frames from a source
+#[derive(Debug, thiserror::Error)]
+pub enum SourceError {
+    #[error("Image load error: {0}")]
+    Image(#[from] ImageLoadError),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[cfg(feature = "clipboard")]
+    #[error("Clipboard error: {0}")]
+    Clipboard(String),
+}
+
+/// A decoded image produced by an `ImageSource`
+#[derive(Debug, Clone)]
+pub struct Frame {
+    /// Name used to derive output file names
+    pub name: String,
+    /// Path the frame was read from, if it came from a file
+    pub path: Option<PathBuf>,
+    /// When the frame was captured, for streaming sources
+    pub captured_at: Option<SystemTime>,
+    pub image: DynamicImage,
+}
+
+impl Frame {
+    /// Creates a frame from an in-memory image
+    #[must_use]
+    pub fn new(name: impl Into<String>, image: DynamicImage) -> Self {
+        Self {
+            name: name.into(),
+            path: None,
+            captured_at: None,
+            image,
+        }
+    }
+
+    /// Sets the capture timestamp, carried to the emitted `ImageRecord` for latency accounting
+    #[must_use]
+    pub const fn with_captured_at(mut self, captured_at: SystemTime) -> Self {
+        self.captured_at = Some(captured_at);
+        self
+    }
+
+    /// Decodes the image file at `path` into a frame
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, SourceError> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Err(ImageLoadError::InvalidPath(path.display().to_string()).into());
+        }
+
+        let image = image::open(path).map_err(ImageLoadError::from)?;
+        Ok(Self {
+            name: path.display().to_string(),
+            path: Some(path.to_path_buf()),
+            captured_at: None,
+            image,
+        })
+    }
+}
+
+/// A producer of frames for the detection pipeline.
+///
+/// Implement this trait to add a new capture backend; `YoloSession::process_source`
+/// works with any implementation.
+pub trait ImageSource {
+    /// Returns the next frame, or `None` once the source is exhausted
+    fn next(&mut self) -> Option<Result<Frame, SourceError>>;
+}
+
+impl<S: ImageSource + ?Sized> ImageSource for Box<S> {
+    fn next(&mut self) -> Option<Result<Frame, SourceError>> {
+        (**self).next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_from_missing_path() {
+        let result = Frame::from_path("does/not/exist.png");
+        assert!(matches!(
+            result,
+            Err(SourceError::Image(ImageLoadError::InvalidPath(_)))
+        ));
+    }
+
+    #[test]
+    fn test_frame_new_has_no_path() {
+        let frame = Frame::new("frame_0", DynamicImage::new_rgb8(4, 4));
+        assert_eq!(frame.name, "frame_0");
+        assert!(frame.path.is_none());
+    }
+}
@@ -0,0 +1,248 @@
+//! Two-level frame queue serving realtime frames ahead of background batch work
+
+use super::{Frame, ImageSource, SourceError};
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
+
+/// Consecutive realtime frames after which a pending background frame is served
+pub const DEFAULT_MAX_REALTIME_STREAK: usize = 8;
+
+/// Queue a frame is submitted to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    /// Live frames, served first
+    Realtime,
+    /// Batch work, served when no realtime frame is waiting
+    Background,
+}
+
+#[derive(Default)]
+struct QueueState {
+    realtime: VecDeque<Frame>,
+    background: VecDeque<Frame>,
+    realtime_streak: usize,
+    producers: usize,
+    closed: bool,
+}
+
+impl QueueState {
+    /// Pops the next frame, letting a background frame through after `max_streak` realtime ones
+    fn pop(&mut self, max_streak: usize) -> Option<Frame> {
+        let starving = self.realtime_streak >= max_streak && !self.background.is_empty();
+        if !starving && let Some(frame) = self.realtime.pop_front() {
+            self.realtime_streak += 1;
+            return Some(frame);
+        }
+
+        self.realtime_streak = 0;
+        self.background
+            .pop_front()
+            .or_else(|| self.realtime.pop_front())
+    }
+}
+
+struct Shared {
+    state: Mutex<QueueState>,
+    ready: Condvar,
+}
+
+/// Handle submitting frames to a `PrioritySource`, cloneable across producer threads.
+///
+/// The queue closes when its last handle is dropped.
+pub struct PriorityQueue {
+    shared: Arc<Shared>,
+}
+
+impl PriorityQueue {
+    /// Enqueues a frame; returns it back if the queue is closed
+    pub fn submit(&self, frame: Frame, priority: Priority) -> Result<(), Frame> {
+        let mut state = self
+            .shared
+            .state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if state.closed {
+            return Err(frame);
+        }
+        match priority {
+            Priority::Realtime => state.realtime.push_back(frame),
+            Priority::Background => state.background.push_back(frame),
+        }
+        self.shared.ready.notify_one();
+        Ok(())
+    }
+
+    /// Closes the queue; the source ends once the frames already queued are served
+    pub fn close(&self) {
+        let mut state = self
+            .shared
+            .state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        state.closed = true;
+        self.shared.ready.notify_all();
+    }
+
+    /// Returns the number of queued realtime and background frames
+    #[must_use]
+    pub fn pending(&self) -> (usize, usize) {
+        let state = self
+            .shared
+            .state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        (state.realtime.len(), state.background.len())
+    }
+}
+
+impl Clone for PriorityQueue {
+    fn clone(&self) -> Self {
+        let mut state = self
+            .shared
+            .state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        state.producers += 1;
+        Self {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl Drop for PriorityQueue {
+    fn drop(&mut self) {
+        let mut state = self
+            .shared
+            .state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        state.producers -= 1;
+        if state.producers == 0 {
+            state.closed = true;
+            self.shared.ready.notify_all();
+        }
+    }
+}
+
+/// Source serving realtime frames ahead of background frames.
+///
+/// `YoloSession::process_source` pulls one frame per inference, so a realtime frame submitted
+/// while the session is busy preempts the queued background frames at the next inference.
+/// After `max_realtime_streak` consecutive realtime frames, one waiting background frame is
+/// served so batch work is never starved. `next` blocks until a frame is available and
+/// returns `None` once the queue is closed and drained.
+pub struct PrioritySource {
+    shared: Arc<Shared>,
+    max_realtime_streak: usize,
+}
+
+impl PrioritySource {
+    /// Creates a source and the queue feeding it
+    #[must_use]
+    pub fn new(max_realtime_streak: usize) -> (PriorityQueue, Self) {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(QueueState {
+                producers: 1,
+                ..QueueState::default()
+            }),
+            ready: Condvar::new(),
+        });
+        let queue = PriorityQueue {
+            shared: Arc::clone(&shared),
+        };
+        let source = Self {
+            shared,
+            max_realtime_streak: max_realtime_streak.max(1),
+        };
+        (queue, source)
+    }
+}
+
+impl ImageSource for PrioritySource {
+    fn next(&mut self) -> Option<Result<Frame, SourceError>> {
+        let mut state = self
+            .shared
+            .state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        loop {
+            if let Some(frame) = state.pop(self.max_realtime_streak) {
+                return Some(Ok(frame));
+            }
+            if state.closed {
+                return None;
+            }
+            state = self
+                .shared
+                .ready
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::DynamicImage;
+    use std::thread;
+
+    fn frame(name: &str) -> Frame {
+        Frame::new(name, DynamicImage::new_rgb8(1, 1))
+    }
+
+    fn drain(source: &mut PrioritySource) -> Vec<String> {
+        std::iter::from_fn(|| source.next())
+            .map(|frame| frame.unwrap().name)
+            .collect()
+    }
+
+    #[test]
+    fn test_realtime_preempts_background() {
+        let (queue, mut source) = PrioritySource::new(DEFAULT_MAX_REALTIME_STREAK);
+        queue.submit(frame("b0"), Priority::Background).unwrap();
+        queue.submit(frame("b1"), Priority::Background).unwrap();
+        assert_eq!(source.next().unwrap().unwrap().name, "b0");
+
+        queue.submit(frame("r0"), Priority::Realtime).unwrap();
+        queue.close();
+        assert_eq!(drain(&mut source), ["r0", "b1"]);
+    }
+
+    #[test]
+    fn test_starvation_protection() {
+        let (queue, mut source) = PrioritySource::new(2);
+        for name in ["b0", "b1"] {
+            queue.submit(frame(name), Priority::Background).unwrap();
+        }
+        for name in ["r0", "r1", "r2", "r3", "r4"] {
+            queue.submit(frame(name), Priority::Realtime).unwrap();
+        }
+        queue.close();
+        assert_eq!(
+            drain(&mut source),
+            ["r0", "r1", "b0", "r2", "r3", "b1", "r4"]
+        );
+    }
+
+    #[test]
+    fn test_closed_queue_rejects_frames() {
+        let (queue, mut source) = PrioritySource::new(1);
+        queue.close();
+        assert!(queue.submit(frame("late"), Priority::Realtime).is_err());
+        assert!(source.next().is_none());
+        assert_eq!(queue.pending(), (0, 0));
+    }
+
+    #[test]
+    fn test_next_waits_until_last_handle_is_dropped() {
+        let (queue, mut source) = PrioritySource::new(1);
+        let second = queue.clone();
+        drop(second);
+        let producer = thread::spawn(move || {
+            queue.submit(frame("r0"), Priority::Realtime).unwrap();
+        });
+        assert_eq!(drain(&mut source), ["r0"]);
+        producer.join().unwrap();
+    }
+}
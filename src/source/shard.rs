@@ -0,0 +1,149 @@
+//! Deterministic partitioning of an input set across independent processes or machines
+
+use crate::image::content_hash::HashBackend;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// One of `count` disjoint parts of an input set, numbered from 1.
+///
+/// An input belongs to a shard by the FNV-1a hash of its path, so every process sharding the
+/// same input list agrees on the split without coordinating, whatever the order of the list.
+/// The hash is fixed rather than the content hash backend of the build, so that builds with
+/// different hash features still split inputs alike.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shard {
+    index: usize,
+    count: usize,
+}
+
+impl Shard {
+    /// Creates shard `index` of `count`, or `None` unless `1 <= index <= count`
+    #[must_use]
+    pub const fn new(index: usize, count: usize) -> Option<Self> {
+        if index == 0 || index > count {
+            return None;
+        }
+        Some(Self { index, count })
+    }
+
+    /// Returns the number of the shard, from 1
+    #[inline]
+    #[must_use]
+    pub const fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the number of shards the input set is split into
+    #[inline]
+    #[must_use]
+    pub const fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns true if the input at `path` belongs to this shard
+    #[must_use]
+    pub fn contains(&self, path: &Path) -> bool {
+        let hash = HashBackend::Fnv1a.hash_u64(path.to_string_lossy().as_bytes());
+        hash % self.count as u64 == (self.index - 1) as u64
+    }
+
+    /// Returns the inputs belonging to this shard, in their original order
+    #[must_use]
+    pub fn select<'a, P: AsRef<Path>>(&self, paths: &'a [P]) -> Vec<&'a P> {
+        paths
+            .iter()
+            .filter(|path| self.contains(path.as_ref()))
+            .collect()
+    }
+
+    /// Returns `path` with the shard inserted before its extension, so that the outputs of
+    /// every shard can share a directory: `manifest.json` becomes `manifest.shard-2-of-4.json`
+    #[must_use]
+    pub fn output_path(&self, path: &Path) -> PathBuf {
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let mut name = format!("{stem}.shard-{}-of-{}", self.index, self.count);
+        if let Some(extension) = path.extension() {
+            name.push('.');
+            name.push_str(&extension.to_string_lossy());
+        }
+        path.with_file_name(name)
+    }
+}
+
+impl fmt::Display for Shard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.index, self.count)
+    }
+}
+
+impl FromStr for Shard {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid shard {s}, expected i/n with 1 <= i <= n");
+        let (index, count) = s.trim().split_once('/').ok_or_else(invalid)?;
+        let index = index.trim().parse().map_err(|_| invalid())?;
+        let count = count.trim().parse().map_err(|_| invalid())?;
+        Self::new(index, count).ok_or_else(invalid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shards_partition_inputs() {
+        let paths: Vec<PathBuf> = (0..200)
+            .map(|i| PathBuf::from(format!("scouts/base_{i}.png")))
+            .collect();
+        let shards: Vec<Shard> = (1..=3).map(|i| Shard::new(i, 3).unwrap()).collect();
+
+        let mut selected: Vec<&PathBuf> = shards.iter().flat_map(|s| s.select(&paths)).collect();
+        assert!(shards.iter().all(|s| s.select(&paths).len() > 40));
+        selected.sort();
+        selected.dedup();
+        assert_eq!(selected.len(), paths.len());
+
+        // Membership does not depend on the rest of the input list
+        let reversed: Vec<PathBuf> = paths.iter().rev().cloned().collect();
+        let mut from_reversed = shards[1].select(&reversed);
+        from_reversed.reverse();
+        assert_eq!(from_reversed, shards[1].select(&paths));
+    }
+
+    #[test]
+    fn test_shard_assignment_is_pinned() {
+        for (path, expected) in [
+            ("scouts/base_0.png", 1),
+            ("scouts/base_1.png", 4),
+            ("village.png", 3),
+            ("raids/war 12.jpg", 4),
+        ] {
+            let owners: Vec<usize> = (1..=4)
+                .filter(|&i| Shard::new(i, 4).unwrap().contains(Path::new(path)))
+                .collect();
+            assert_eq!(owners, [expected], "{path}");
+        }
+    }
+
+    #[test]
+    fn test_parse_and_output_path() {
+        let shard: Shard = "2/4".parse().unwrap();
+        assert_eq!((shard.index(), shard.count()), (2, 4));
+        assert_eq!(shard.to_string(), "2/4");
+        assert!("0/4".parse::<Shard>().is_err());
+        assert!("5/4".parse::<Shard>().is_err());
+        assert!("2".parse::<Shard>().is_err());
+
+        assert_eq!(
+            shard.output_path(Path::new("runs/manifest.json")),
+            PathBuf::from("runs/manifest.shard-2-of-4.json")
+        );
+        assert_eq!(
+            shard.output_path(Path::new("summary")),
+            PathBuf::from("summary.shard-2-of-4")
+        );
+    }
+}
@@ -0,0 +1,130 @@
+#[cfg(feature = "arrow")]
+pub mod parquet;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+use crate::class::registry::ClassRegistry;
+use crate::detection::BoundingBox;
+use crate::detection::output::OutputFormat;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Errors that can occur while persisting or querying results
+#[derive(Debug, thiserror::Error)]
+pub enum StoreError {
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[cfg(feature = "sqlite")]
+    #[error("Only read-only queries are allowed: {0}")]
+    ReadOnly(String),
+    #[cfg(feature = "arrow")]
+    #[error("Arrow error: {0}")]
+    Arrow(#[from] arrow_schema::ArrowError),
+    #[cfg(feature = "arrow")]
+    #[error("Parquet error: {0}")]
+    Parquet(#[from] ::parquet::errors::ParquetError),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid timestamp: {0}")]
+    InvalidTimestamp(String),
+}
+
+/// Results of a single processed image, as persisted by a results store
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageRecord {
+    pub image_path: String,
+    pub fingerprint: Option<String>,
+    pub processed_at: SystemTime,
+    pub duration: Duration,
+    pub image_dimensions: (u32, u32),
+    pub boxes: Vec<BoundingBox>,
+    /// When the frame was captured, for streaming sources
+    pub captured_at: Option<SystemTime>,
+}
+
+impl ImageRecord {
+    /// Returns the end-to-end latency from frame capture to the emission of this record
+    #[must_use]
+    pub fn latency(&self) -> Option<Duration> {
+        let emitted_at = self.processed_at + self.duration;
+        self.captured_at
+            .map(|captured_at| emitted_at.duration_since(captured_at).unwrap_or_default())
+    }
+
+    /// Returns the COCO JSON document of the record, with `latency_ms` for captured frames
+    #[must_use]
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut json = OutputFormat::to_coco_json(
+            &self.boxes,
+            self.image_dimensions,
+            &self.image_path,
+            &ClassRegistry::default(),
+        );
+        if let Some(latency) = self.latency() {
+            json["latency_ms"] = serde_json::json!(latency.as_secs_f64() * 1000.0);
+        }
+        json
+    }
+}
+
+/// A detection read back from a results store together with its image metadata
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredDetection {
+    pub image_id: i64,
+    pub image_path: String,
+    pub processed_at: SystemTime,
+    pub bbox: BoundingBox,
+}
+
+/// Converts a timestamp to milliseconds since the Unix epoch
+pub fn to_unix_millis(time: SystemTime) -> Result<i64, StoreError> {
+    let since_epoch = time
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| StoreError::InvalidTimestamp(e.to_string()))?;
+    i64::try_from(since_epoch.as_millis()).map_err(|e| StoreError::InvalidTimestamp(e.to_string()))
+}
+
+/// Converts milliseconds since the Unix epoch to a timestamp
+pub fn from_unix_millis(millis: i64) -> Result<SystemTime, StoreError> {
+    u64::try_from(millis)
+        .map(|millis| UNIX_EPOCH + Duration::from_millis(millis))
+        .map_err(|e| StoreError::InvalidTimestamp(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unix_millis_round_trip() {
+        let time = UNIX_EPOCH + Duration::from_millis(1_759_583_099_123);
+        let millis = to_unix_millis(time).unwrap();
+        assert_eq!(millis, 1_759_583_099_123);
+        assert_eq!(from_unix_millis(millis).unwrap(), time);
+    }
+
+    #[test]
+    fn test_latency_from_capture_to_emission() {
+        let captured_at = UNIX_EPOCH + Duration::from_secs(100);
+        let mut record = ImageRecord {
+            image_path: "frame_0".to_string(),
+            fingerprint: None,
+            processed_at: captured_at + Duration::from_millis(30),
+            duration: Duration::from_millis(20),
+            image_dimensions: (640, 640),
+            boxes: Vec::new(),
+            captured_at: Some(captured_at),
+        };
+        assert_eq!(record.latency(), Some(Duration::from_millis(50)));
+        assert_eq!(record.to_json()["latency_ms"], 50.0);
+
+        record.captured_at = None;
+        assert_eq!(record.latency(), None);
+        assert!(record.to_json().get("latency_ms").is_none());
+    }
+
+    #[test]
+    fn test_negative_millis_rejected() {
+        assert!(from_unix_millis(-1).is_err());
+    }
+}
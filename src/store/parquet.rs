@@ -0,0 +1,212 @@
+//! Parquet export of stored detections
+
+use super::{ImageRecord, StoreError, to_unix_millis};
+use crate::class::registry::ClassRegistry;
+use arrow_array::{
+    ArrayRef, Float32Array, Float64Array, RecordBatch, StringArray, TimestampMillisecondArray,
+    UInt32Array,
+};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use parquet::arrow::ArrowWriter;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Returns the Arrow schema of the exported detections table (one row per detection)
+#[must_use]
+pub fn detection_schema() -> Schema {
+    Schema::new(vec![
+        Field::new("image", DataType::Utf8, false),
+        Field::new("fingerprint", DataType::Utf8, true),
+        Field::new(
+            "processed_at",
+            DataType::Timestamp(TimeUnit::Millisecond, None),
+            false,
+        ),
+        Field::new(
+            "captured_at",
+            DataType::Timestamp(TimeUnit::Millisecond, None),
+            true,
+        ),
+        Field::new("duration_ms", DataType::Float64, false),
+        Field::new("class_id", DataType::UInt32, false),
+        Field::new("class_name", DataType::Utf8, false),
+        Field::new("x1", DataType::Float32, false),
+        Field::new("y1", DataType::Float32, false),
+        Field::new("x2", DataType::Float32, false),
+        Field::new("y2", DataType::Float32, false),
+        Field::new("confidence", DataType::Float32, false),
+    ])
+}
+
+/// Flattens image records into a single Arrow record batch with one row per detection, naming
+/// the classes after `classes`
+pub fn records_to_batch(
+    records: &[ImageRecord],
+    classes: &ClassRegistry,
+) -> Result<RecordBatch, StoreError> {
+    let rows: usize = records.iter().map(|record| record.boxes.len()).sum();
+
+    let mut images = Vec::with_capacity(rows);
+    let mut fingerprints = Vec::with_capacity(rows);
+    let mut processed_at = Vec::with_capacity(rows);
+    let mut captured_at = Vec::with_capacity(rows);
+    let mut durations = Vec::with_capacity(rows);
+    let mut class_ids = Vec::with_capacity(rows);
+    let mut class_names = Vec::with_capacity(rows);
+    let (mut x1, mut y1, mut x2, mut y2) = (
+        Vec::with_capacity(rows),
+        Vec::with_capacity(rows),
+        Vec::with_capacity(rows),
+        Vec::with_capacity(rows),
+    );
+    let mut confidences = Vec::with_capacity(rows);
+
+    for record in records {
+        let timestamp = to_unix_millis(record.processed_at)?;
+        let captured = record.captured_at.map(to_unix_millis).transpose()?;
+        let duration_ms = record.duration.as_secs_f64() * 1000.0;
+
+        for bbox in &record.boxes {
+            images.push(record.image_path.as_str());
+            fingerprints.push(record.fingerprint.as_deref());
+            processed_at.push(timestamp);
+            captured_at.push(captured);
+            durations.push(duration_ms);
+            class_ids.push(u32::try_from(bbox.class_id).unwrap_or(u32::MAX));
+            class_names.push(classes.name(bbox.class_id));
+            x1.push(bbox.x1);
+            y1.push(bbox.y1);
+            x2.push(bbox.x2);
+            y2.push(bbox.y2);
+            confidences.push(bbox.confidence);
+        }
+    }
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from(images)),
+        Arc::new(StringArray::from(fingerprints)),
+        Arc::new(TimestampMillisecondArray::from(processed_at)),
+        Arc::new(TimestampMillisecondArray::from(captured_at)),
+        Arc::new(Float64Array::from(durations)),
+        Arc::new(UInt32Array::from(class_ids)),
+        Arc::new(StringArray::from(class_names)),
+        Arc::new(Float32Array::from(x1)),
+        Arc::new(Float32Array::from(y1)),
+        Arc::new(Float32Array::from(x2)),
+        Arc::new(Float32Array::from(y2)),
+        Arc::new(Float32Array::from(confidences)),
+    ];
+
+    Ok(RecordBatch::try_new(Arc::new(detection_schema()), columns)?)
+}
+
+/// Writes the detections of all records to a Parquet file
+pub fn write_parquet(
+    records: &[ImageRecord],
+    classes: &ClassRegistry,
+    output_path: &Path,
+) -> Result<(), StoreError> {
+    write_parquet_to(records, classes, File::create(output_path)?)
+}
+
+/// Writes the detections of all records as Parquet to `writer`, e.g. an in-memory buffer
+pub fn write_parquet_to(
+    records: &[ImageRecord],
+    classes: &ClassRegistry,
+    writer: impl Write + Send,
+) -> Result<(), StoreError> {
+    let batch = records_to_batch(records, classes)?;
+
+    let mut writer = detection_writer(writer)?;
+    writer.write(&batch)?;
+    writer.close()?;
+
+    Ok(())
+}
+
+/// Creates a Parquet writer of the detections table, each `flush` of which ends a row group
+pub fn detection_writer<W: Write + Send>(writer: W) -> Result<ArrowWriter<W>, StoreError> {
+    Ok(ArrowWriter::try_new(
+        writer,
+        Arc::new(detection_schema()),
+        None,
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detection::BoundingBox;
+    use arrow_array::Array;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use std::time::{Duration, UNIX_EPOCH};
+    use tempfile::tempdir;
+
+    fn records() -> Vec<ImageRecord> {
+        vec![
+            ImageRecord {
+                image_path: "a.png".to_string(),
+                fingerprint: Some("abc".to_string()),
+                processed_at: UNIX_EPOCH + Duration::from_secs(100),
+                duration: Duration::from_millis(12),
+                image_dimensions: (640, 640),
+                boxes: vec![
+                    BoundingBox::new(0.0, 0.0, 10.0, 10.0, 0, 0.9),
+                    BoundingBox::new(20.0, 20.0, 30.0, 30.0, 1, 0.8),
+                ],
+                captured_at: Some(UNIX_EPOCH + Duration::from_secs(99)),
+            },
+            ImageRecord {
+                image_path: "b.png".to_string(),
+                fingerprint: None,
+                processed_at: UNIX_EPOCH + Duration::from_secs(200),
+                duration: Duration::from_millis(15),
+                image_dimensions: (640, 640),
+                boxes: vec![BoundingBox::new(5.0, 5.0, 15.0, 15.0, 1, 0.7)],
+                captured_at: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_records_to_batch() {
+        let batch = records_to_batch(&records(), &ClassRegistry::default()).unwrap();
+        assert_eq!(batch.num_rows(), 3);
+        assert_eq!(batch.num_columns(), 12);
+
+        let images = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(images.value(2), "b.png");
+        assert_eq!(batch.column(1).null_count(), 1);
+
+        let captured_at = batch
+            .column(3)
+            .as_any()
+            .downcast_ref::<TimestampMillisecondArray>()
+            .unwrap();
+        assert_eq!(captured_at.value(0), 99_000);
+        assert!(captured_at.is_null(2));
+        let class_names = batch
+            .column(6)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(class_names.value(0), "Elixir Storage");
+        assert_eq!(class_names.value(2), "Gold Storage");
+    }
+
+    #[test]
+    fn test_write_parquet() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("detections.parquet");
+        write_parquet(&records(), &ClassRegistry::default(), &path).unwrap();
+
+        let reader = SerializedFileReader::new(File::open(&path).unwrap()).unwrap();
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 3);
+    }
+}
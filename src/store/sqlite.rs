@@ -0,0 +1,497 @@
+//! SQLite-backed results store
+
+use super::{ImageRecord, StoreError, StoredDetection, from_unix_millis, to_unix_millis};
+use crate::detection::BoundingBox;
+use rusqlite::{Connection, Row, params};
+use std::path::Path;
+use std::time::SystemTime;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS images (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    image_path TEXT NOT NULL,
+    fingerprint TEXT,
+    processed_at INTEGER NOT NULL,
+    duration_ms REAL NOT NULL,
+    width INTEGER NOT NULL,
+    height INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS detections (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    image_id INTEGER NOT NULL REFERENCES images(id) ON DELETE CASCADE,
+    class_id INTEGER NOT NULL,
+    confidence REAL NOT NULL,
+    x1 REAL NOT NULL,
+    y1 REAL NOT NULL,
+    x2 REAL NOT NULL,
+    y2 REAL NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_images_processed_at ON images(processed_at);
+CREATE INDEX IF NOT EXISTS idx_images_fingerprint ON images(fingerprint);
+CREATE INDEX IF NOT EXISTS idx_detections_class_id ON detections(class_id);
+CREATE INDEX IF NOT EXISTS idx_detections_image_id ON detections(image_id);
+CREATE VIEW IF NOT EXISTS image_class_counts AS
+SELECT i.id AS image_id, i.image_path, i.processed_at, d.class_id, COUNT(*) AS detections
+FROM detections d
+JOIN images i ON i.id = d.image_id
+GROUP BY i.id, d.class_id;
+CREATE VIEW IF NOT EXISTS daily_class_confidence AS
+SELECT date(i.processed_at / 1000, 'unixepoch') AS day, d.class_id,
+       COUNT(*) AS detections, AVG(d.confidence) AS mean_confidence
+FROM detections d
+JOIN images i ON i.id = d.image_id
+GROUP BY day, d.class_id;
+";
+
+const DETECTION_QUERY: &str = "
+SELECT d.image_id, i.image_path, i.processed_at, d.x1, d.y1, d.x2, d.y2, d.class_id, d.confidence
+FROM detections d
+JOIN images i ON i.id = d.image_id";
+
+/// Number of detections of a stored image, from the `image_class_counts` view
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageDetectionCount {
+    pub image_id: i64,
+    pub image_path: String,
+    pub detections: usize,
+}
+
+/// Detections of a class on a UTC day, from the `daily_class_confidence` view
+#[derive(Debug, Clone, PartialEq)]
+pub struct DailyClassConfidence {
+    pub day: String, // YYYY-MM-DD
+    pub class_id: usize,
+    pub detections: usize,
+    pub mean_confidence: f64,
+}
+
+/// Value of a column returned by an ad-hoc query
+#[derive(Debug, Clone, PartialEq)]
+pub enum SqlValue {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Blob(Vec<u8>),
+}
+
+impl SqlValue {
+    /// Returns the value as an integer, if it is one
+    #[must_use]
+    pub const fn as_i64(&self) -> Option<i64> {
+        match self {
+            Self::Integer(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a float, converting integers
+    #[must_use]
+    pub const fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Real(value) => Some(*value),
+            Self::Integer(value) => Some(*value as f64),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as text, if it is some
+    #[must_use]
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::Text(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl From<rusqlite::types::ValueRef<'_>> for SqlValue {
+    fn from(value: rusqlite::types::ValueRef<'_>) -> Self {
+        use rusqlite::types::ValueRef;
+        match value {
+            ValueRef::Null => Self::Null,
+            ValueRef::Integer(value) => Self::Integer(value),
+            ValueRef::Real(value) => Self::Real(value),
+            ValueRef::Text(text) => Self::Text(String::from_utf8_lossy(text).into_owned()),
+            ValueRef::Blob(blob) => Self::Blob(blob.to_vec()),
+        }
+    }
+}
+
+/// Columns and rows returned by an ad-hoc query
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SqlRows {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<SqlValue>>,
+}
+
+impl SqlRows {
+    /// Returns the values of a column by name
+    #[must_use]
+    pub fn column(&self, name: &str) -> Option<Vec<&SqlValue>> {
+        let index = self.columns.iter().position(|column| column == name)?;
+        Some(self.rows.iter().map(|row| &row[index]).collect())
+    }
+}
+
+/// Queryable history of processed images and their detections
+pub struct SqliteStore {
+    connection: Connection,
+}
+
+impl SqliteStore {
+    /// Opens (or creates) a store at the given database path
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, StoreError> {
+        Self::from_connection(Connection::open(path)?)
+    }
+
+    /// Opens a transient store living only in memory
+    pub fn open_in_memory() -> Result<Self, StoreError> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    /// Initializes the schema on an existing connection
+    fn from_connection(connection: Connection) -> Result<Self, StoreError> {
+        connection.execute_batch("PRAGMA foreign_keys = ON;")?;
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self { connection })
+    }
+
+    /// Inserts an image record and all its detections, returning the image id
+    pub fn insert(&mut self, record: &ImageRecord) -> Result<i64, StoreError> {
+        let processed_at = to_unix_millis(record.processed_at)?;
+        let tx = self.connection.transaction()?;
+
+        tx.execute(
+            "INSERT INTO images (image_path, fingerprint, processed_at, duration_ms, width, height)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                record.image_path,
+                record.fingerprint,
+                processed_at,
+                record.duration.as_secs_f64() * 1000.0,
+                record.image_dimensions.0,
+                record.image_dimensions.1,
+            ],
+        )?;
+        let image_id = tx.last_insert_rowid();
+
+        {
+            let mut statement = tx.prepare(
+                "INSERT INTO detections (image_id, class_id, confidence, x1, y1, x2, y2)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            )?;
+            for bbox in &record.boxes {
+                statement.execute(params![
+                    image_id,
+                    bbox.class_id as i64,
+                    bbox.confidence,
+                    bbox.x1,
+                    bbox.y1,
+                    bbox.x2,
+                    bbox.y2,
+                ])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(image_id)
+    }
+
+    /// Returns the number of stored images
+    pub fn image_count(&self) -> Result<usize, StoreError> {
+        let count: i64 = self
+            .connection
+            .query_row("SELECT COUNT(*) FROM images", [], |row| row.get(0))?;
+        Ok(count.max(0) as usize)
+    }
+
+    /// Returns whether an image with the given fingerprint was already stored
+    pub fn contains_fingerprint(&self, fingerprint: &str) -> Result<bool, StoreError> {
+        let exists: bool = self.connection.query_row(
+            "SELECT EXISTS(SELECT 1 FROM images WHERE fingerprint = ?1)",
+            params![fingerprint],
+            |row| row.get(0),
+        )?;
+        Ok(exists)
+    }
+
+    /// Returns all detections of the given class, most recent first
+    pub fn detections_by_class(&self, class_id: usize) -> Result<Vec<StoredDetection>, StoreError> {
+        self.query_detections(
+            &format!("{DETECTION_QUERY} WHERE d.class_id = ?1 ORDER BY i.processed_at DESC, d.id"),
+            params![class_id as i64],
+        )
+    }
+
+    /// Returns all detections of images processed within `[from, to)`, most recent first
+    pub fn detections_in_time_range(
+        &self,
+        from: SystemTime,
+        to: SystemTime,
+    ) -> Result<Vec<StoredDetection>, StoreError> {
+        self.query_detections(
+            &format!(
+                "{DETECTION_QUERY} WHERE i.processed_at >= ?1 AND i.processed_at < ?2 \
+                 ORDER BY i.processed_at DESC, d.id"
+            ),
+            params![to_unix_millis(from)?, to_unix_millis(to)?],
+        )
+    }
+
+    /// Returns detections of the given class for images processed within `[from, to)`
+    pub fn detections_by_class_in_time_range(
+        &self,
+        class_id: usize,
+        from: SystemTime,
+        to: SystemTime,
+    ) -> Result<Vec<StoredDetection>, StoreError> {
+        self.query_detections(
+            &format!(
+                "{DETECTION_QUERY} WHERE d.class_id = ?1 AND i.processed_at >= ?2 \
+                 AND i.processed_at < ?3 ORDER BY i.processed_at DESC, d.id"
+            ),
+            params![class_id as i64, to_unix_millis(from)?, to_unix_millis(to)?],
+        )
+    }
+
+    /// Returns the images with the most detections, of `class_id` only if given
+    pub fn top_images_by_detections(
+        &self,
+        class_id: Option<usize>,
+        limit: usize,
+    ) -> Result<Vec<ImageDetectionCount>, StoreError> {
+        let mut statement = self.connection.prepare(
+            "SELECT image_id, image_path, SUM(detections) AS total FROM image_class_counts \
+             WHERE ?1 IS NULL OR class_id = ?1 \
+             GROUP BY image_id ORDER BY total DESC, image_id LIMIT ?2",
+        )?;
+        let rows =
+            statement.query_map(params![class_id.map(|id| id as i64), limit as i64], |row| {
+                let detections: i64 = row.get(2)?;
+                Ok(ImageDetectionCount {
+                    image_id: row.get(0)?,
+                    image_path: row.get(1)?,
+                    detections: detections.max(0) as usize,
+                })
+            })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Returns the number and mean confidence of the detections of each class per UTC day,
+    /// oldest day first
+    pub fn daily_confidence_by_class(&self) -> Result<Vec<DailyClassConfidence>, StoreError> {
+        let mut statement = self.connection.prepare(
+            "SELECT day, class_id, detections, mean_confidence FROM daily_class_confidence \
+             ORDER BY day, class_id",
+        )?;
+        let rows = statement.query_map([], |row| {
+            let class_id: i64 = row.get(1)?;
+            let detections: i64 = row.get(2)?;
+            Ok(DailyClassConfidence {
+                day: row.get(0)?,
+                class_id: class_id.max(0) as usize,
+                detections: detections.max(0) as usize,
+                mean_confidence: row.get(3)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Runs an ad-hoc read-only query, e.g. on the `image_class_counts` and
+    /// `daily_class_confidence` views. Statements modifying the store are rejected.
+    pub fn query(&self, sql: &str, params: impl rusqlite::Params) -> Result<SqlRows, StoreError> {
+        let mut statement = self.connection.prepare(sql)?;
+        if !statement.readonly() {
+            return Err(StoreError::ReadOnly(sql.to_string()));
+        }
+        let columns: Vec<String> = statement
+            .column_names()
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        let mut rows = statement.query(params)?;
+
+        let mut result = SqlRows {
+            columns,
+            rows: Vec::new(),
+        };
+        while let Some(row) = rows.next()? {
+            let values = (0..result.columns.len())
+                .map(|index| row.get_ref(index).map(SqlValue::from))
+                .collect::<Result<_, _>>()?;
+            result.rows.push(values);
+        }
+        Ok(result)
+    }
+
+    /// Runs a detection query and maps its rows
+    fn query_detections(
+        &self,
+        sql: &str,
+        params: impl rusqlite::Params,
+    ) -> Result<Vec<StoredDetection>, StoreError> {
+        let mut statement = self.connection.prepare(sql)?;
+        let rows = statement.query_map(params, read_detection_row)?;
+
+        let mut detections = Vec::new();
+        for row in rows {
+            let (image_id, image_path, processed_at, bbox) = row?;
+            detections.push(StoredDetection {
+                image_id,
+                image_path,
+                processed_at: from_unix_millis(processed_at)?,
+                bbox,
+            });
+        }
+        Ok(detections)
+    }
+}
+
+/// Reads a row produced by `DETECTION_QUERY`
+fn read_detection_row(row: &Row<'_>) -> rusqlite::Result<(i64, String, i64, BoundingBox)> {
+    let class_id: i64 = row.get(7)?;
+    let bbox = BoundingBox::new(
+        row.get(3)?,
+        row.get(4)?,
+        row.get(5)?,
+        row.get(6)?,
+        class_id.max(0) as usize,
+        row.get(8)?,
+    );
+    Ok((row.get(0)?, row.get(1)?, row.get(2)?, bbox))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn record(path: &str, seconds: u64, boxes: Vec<BoundingBox>) -> ImageRecord {
+        ImageRecord {
+            image_path: path.to_string(),
+            fingerprint: Some(format!("fp-{path}")),
+            processed_at: UNIX_EPOCH + Duration::from_secs(seconds),
+            duration: Duration::from_millis(42),
+            image_dimensions: (640, 640),
+            boxes,
+            captured_at: None,
+        }
+    }
+
+    fn populated_store() -> SqliteStore {
+        let mut store = SqliteStore::open_in_memory().unwrap();
+        store
+            .insert(&record(
+                "a.png",
+                100,
+                vec![
+                    BoundingBox::new(0.0, 0.0, 10.0, 10.0, 0, 0.9),
+                    BoundingBox::new(20.0, 20.0, 30.0, 30.0, 1, 0.8),
+                ],
+            ))
+            .unwrap();
+        store
+            .insert(&record(
+                "b.png",
+                200,
+                vec![BoundingBox::new(5.0, 5.0, 15.0, 15.0, 1, 0.7)],
+            ))
+            .unwrap();
+        store
+    }
+
+    #[test]
+    fn test_insert_and_count() {
+        let store = populated_store();
+        assert_eq!(store.image_count().unwrap(), 2);
+        assert!(store.contains_fingerprint("fp-a.png").unwrap());
+        assert!(!store.contains_fingerprint("fp-c.png").unwrap());
+    }
+
+    #[test]
+    fn test_detections_by_class() {
+        let store = populated_store();
+        let gold = store.detections_by_class(1).unwrap();
+        assert_eq!(gold.len(), 2);
+        assert_eq!(gold[0].image_path, "b.png");
+        assert_eq!(gold[1].image_path, "a.png");
+        assert_eq!(
+            gold[1].bbox,
+            BoundingBox::new(20.0, 20.0, 30.0, 30.0, 1, 0.8)
+        );
+    }
+
+    #[test]
+    fn test_views_and_ad_hoc_queries() {
+        let mut store = populated_store();
+        store
+            .insert(&record(
+                "c.png",
+                86_400 + 10,
+                vec![
+                    BoundingBox::new(0.0, 0.0, 10.0, 10.0, 1, 0.6),
+                    BoundingBox::new(20.0, 0.0, 30.0, 10.0, 1, 0.4),
+                    BoundingBox::new(40.0, 0.0, 50.0, 10.0, 1, 0.5),
+                ],
+            ))
+            .unwrap();
+
+        let top = store.top_images_by_detections(None, 2).unwrap();
+        let paths: Vec<&str> = top.iter().map(|t| t.image_path.as_str()).collect();
+        assert_eq!(paths, ["c.png", "a.png"]);
+        assert_eq!(top[0].detections, 3);
+        let elixir = store.top_images_by_detections(Some(0), 10).unwrap();
+        assert_eq!(elixir.len(), 1);
+        assert_eq!(
+            (elixir[0].image_path.as_str(), elixir[0].detections),
+            ("a.png", 1)
+        );
+
+        let daily = store.daily_confidence_by_class().unwrap();
+        assert_eq!(daily.len(), 3);
+        assert_eq!(
+            (daily[1].day.as_str(), daily[1].class_id),
+            ("1970-01-01", 1)
+        );
+        assert_eq!(daily[1].detections, 2);
+        assert!((daily[1].mean_confidence - 0.75).abs() < 1e-6);
+        assert_eq!(
+            (daily[2].day.as_str(), daily[2].detections),
+            ("1970-01-02", 3)
+        );
+
+        let rows = store
+            .query(
+                "SELECT image_path, COUNT(*) AS n FROM image_class_counts \
+                 WHERE detections >= ?1 GROUP BY image_path ORDER BY image_path",
+                params![1],
+            )
+            .unwrap();
+        assert_eq!(rows.columns, ["image_path", "n"]);
+        assert_eq!(rows.rows[0][0], SqlValue::Text("a.png".to_string()));
+        assert_eq!(rows.column("n").unwrap()[0].as_i64(), Some(2));
+        assert!(matches!(
+            store.query("DELETE FROM images", []),
+            Err(StoreError::ReadOnly(_))
+        ));
+        assert_eq!(store.image_count().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_detections_in_time_range() {
+        let store = populated_store();
+        let from = UNIX_EPOCH + Duration::from_secs(50);
+        let to = UNIX_EPOCH + Duration::from_secs(150);
+
+        let detections = store.detections_in_time_range(from, to).unwrap();
+        assert_eq!(detections.len(), 2);
+        assert!(detections.iter().all(|d| d.image_path == "a.png"));
+
+        let gold = store
+            .detections_by_class_in_time_range(1, from, to)
+            .unwrap();
+        assert_eq!(gold.len(), 1);
+        assert_eq!(gold[0].processed_at, UNIX_EPOCH + Duration::from_secs(100));
+    }
+}
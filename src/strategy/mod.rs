@@ -0,0 +1,242 @@
+//! Deployment suggestions derived from detected buildings, kept out of the core vision runtime.
+
+use crate::class::clash_class::ClashClass;
+use crate::detection::BoundingBox;
+use serde::Serialize;
+
+/// Detected buildings of a base screenshot
+#[derive(Debug, Clone, PartialEq)]
+pub struct BaseLayout {
+    pub width: u32,
+    pub height: u32,
+    pub buildings: Vec<BoundingBox>,
+}
+
+impl BaseLayout {
+    /// Creates a layout from the detections of an image of `width` x `height` pixels
+    #[must_use]
+    pub const fn new(width: u32, height: u32, buildings: Vec<BoundingBox>) -> Self {
+        Self {
+            width,
+            height,
+            buildings,
+        }
+    }
+
+    /// Returns the detected resource storages
+    pub fn storages(&self) -> impl Iterator<Item = &BoundingBox> {
+        self.buildings
+            .iter()
+            .filter(|bbox| ClashClass::try_from(bbox.class_id).is_ok())
+    }
+
+    /// Returns the length of the image diagonal in pixels
+    #[inline]
+    #[must_use]
+    pub fn diagonal(&self) -> f32 {
+        (self.width as f32).hypot(self.height as f32)
+    }
+}
+
+/// Side of the screen troops are deployed from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Edge {
+    Top,
+    Right,
+    Bottom,
+    Left,
+}
+
+impl Edge {
+    /// Returns the string representation of the `Edge` variant.
+    #[inline]
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Top => "top",
+            Self::Right => "right",
+            Self::Bottom => "bottom",
+            Self::Left => "left",
+        }
+    }
+}
+
+/// Suggested deployment zone with the reason it was chosen
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DeploymentSuggestion {
+    pub edge: Edge,
+    pub point: (f32, f32),
+    pub score: f32,
+    pub rationale: String,
+}
+
+/// Configuration of the deployment suggestions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StrategyConfig {
+    pub cluster_radius: f32,
+    pub max_suggestions: usize,
+}
+
+impl Default for StrategyConfig {
+    fn default() -> Self {
+        Self {
+            cluster_radius: 0.15, // Storage distance joining a cluster, as a fraction of the diagonal
+            max_suggestions: 3,   // Number of ranked suggestions returned
+        }
+    }
+}
+
+/// Group of nearby storages
+#[derive(Debug, Clone, Copy)]
+struct Cluster {
+    center: (f32, f32),
+    weight: f32,
+    storages: usize,
+}
+
+/// Suggests deployment zones on the edges nearest to the densest storage clusters, best first
+#[must_use]
+pub fn suggest_deployments(
+    layout: &BaseLayout,
+    config: &StrategyConfig,
+) -> Vec<DeploymentSuggestion> {
+    let diagonal = layout.diagonal();
+    if diagonal == 0.0 {
+        return Vec::new();
+    }
+
+    let mut suggestions: Vec<DeploymentSuggestion> = cluster_storages(layout, config, diagonal)
+        .into_iter()
+        .map(|cluster| {
+            let (edge, distance, point) = nearest_edge(layout, cluster.center);
+            DeploymentSuggestion {
+                edge,
+                point,
+                score: cluster.weight / (1.0 + distance / diagonal),
+                rationale: format!(
+                    "{} storage(s) around ({:.0}, {:.0}), {distance:.0}px from the {} edge",
+                    cluster.storages,
+                    cluster.center.0,
+                    cluster.center.1,
+                    edge.as_str()
+                ),
+            }
+        })
+        .collect();
+
+    suggestions.sort_by(|a, b| b.score.total_cmp(&a.score));
+    suggestions.truncate(config.max_suggestions);
+    suggestions
+}
+
+/// Groups storages whose centers are chained within the cluster radius
+fn cluster_storages(layout: &BaseLayout, config: &StrategyConfig, diagonal: f32) -> Vec<Cluster> {
+    let storages: Vec<&BoundingBox> = layout.storages().collect();
+    let radius = config.cluster_radius * diagonal;
+    let mut cluster_of: Vec<Option<usize>> = vec![None; storages.len()];
+    let mut clusters = Vec::new();
+
+    for seed in 0..storages.len() {
+        if cluster_of[seed].is_some() {
+            continue;
+        }
+        let id = clusters.len();
+        cluster_of[seed] = Some(id);
+        let mut members = vec![seed];
+        let mut next = 0;
+        while next < members.len() {
+            let (cx, cy) = storages[members[next]].center();
+            for (other, bbox) in storages.iter().enumerate() {
+                let (ox, oy) = bbox.center();
+                if cluster_of[other].is_none() && (ox - cx).hypot(oy - cy) <= radius {
+                    cluster_of[other] = Some(id);
+                    members.push(other);
+                }
+            }
+            next += 1;
+        }
+
+        let weight: f32 = members.iter().map(|&i| storages[i].confidence).sum();
+        let (sum_x, sum_y) = members.iter().fold((0.0, 0.0), |(x, y), &i| {
+            let (cx, cy) = storages[i].center();
+            (x + cx, y + cy)
+        });
+        let count = members.len() as f32;
+        clusters.push(Cluster {
+            center: (sum_x / count, sum_y / count),
+            weight,
+            storages: members.len(),
+        });
+    }
+    clusters
+}
+
+/// Returns the edge closest to `center`, its distance and the deployment point on it
+fn nearest_edge(layout: &BaseLayout, (x, y): (f32, f32)) -> (Edge, f32, (f32, f32)) {
+    let (width, height) = (layout.width as f32, layout.height as f32);
+    [
+        (Edge::Top, y, (x, 0.0)),
+        (Edge::Right, width - x, (width, y)),
+        (Edge::Bottom, height - y, (x, height)),
+        (Edge::Left, x, (0.0, y)),
+    ]
+    .into_iter()
+    .min_by(|a, b| a.1.total_cmp(&b.1))
+    .expect("four edges")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn storage(cx: f32, cy: f32) -> BoundingBox {
+        BoundingBox::from_center(cx, cy, 20.0, 20.0, 1, 1.0)
+    }
+
+    #[test]
+    fn test_densest_cluster_ranked_first() {
+        let layout = BaseLayout::new(
+            1000,
+            800,
+            vec![
+                storage(100.0, 400.0),
+                storage(130.0, 420.0),
+                storage(110.0, 450.0),
+                storage(950.0, 300.0),
+            ],
+        );
+        let suggestions = suggest_deployments(&layout, &StrategyConfig::default());
+
+        assert_eq!(suggestions.len(), 2);
+        assert_eq!(suggestions[0].edge, Edge::Left);
+        assert_eq!(suggestions[0].point.0, 0.0);
+        assert!(suggestions[0].rationale.starts_with("3 storage(s)"));
+        assert_eq!(suggestions[1].edge, Edge::Right);
+        assert!(suggestions[0].score > suggestions[1].score);
+    }
+
+    #[test]
+    fn test_ignores_unknown_classes_and_truncates() {
+        let mut buildings: Vec<BoundingBox> = (0..5)
+            .map(|i| storage(100.0 + i as f32 * 300.0, 500.0))
+            .collect();
+        buildings.push(BoundingBox::from_center(50.0, 50.0, 10.0, 10.0, 9, 1.0));
+        let layout = BaseLayout::new(1600, 1000, buildings);
+        let config = StrategyConfig {
+            cluster_radius: 0.05,
+            max_suggestions: 2,
+        };
+
+        assert_eq!(layout.storages().count(), 5);
+        assert_eq!(suggest_deployments(&layout, &config).len(), 2);
+    }
+
+    #[test]
+    fn test_empty_layout() {
+        let layout = BaseLayout::new(0, 0, vec![storage(1.0, 1.0)]);
+        assert!(suggest_deployments(&layout, &StrategyConfig::default()).is_empty());
+        let layout = BaseLayout::new(640, 640, Vec::new());
+        assert!(suggest_deployments(&layout, &StrategyConfig::default()).is_empty());
+    }
+}
@@ -0,0 +1,306 @@
+//! SORT-style tracking assigning stable ids to detections across consecutive frames.
+//!
+//! Each track predicts its next box with a constant-velocity Kalman filter over the box center,
+//! area and aspect ratio; predictions are then matched greedily to the detections of the same
+//! class by decreasing `IoU`.
+
+use crate::detection::BoundingBox;
+
+/// Configuration of the tracker
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrackerConfig {
+    pub iou_threshold: f32,
+    pub max_age: u32,
+    pub min_hits: u32,
+}
+
+impl Default for TrackerConfig {
+    fn default() -> Self {
+        Self {
+            iou_threshold: 0.3, // Minimum IoU between a prediction and a detection to match
+            max_age: 1,         // Frames a track survives without a matching detection
+            min_hits: 3,        // Matches before a track is reported
+        }
+    }
+}
+
+/// Detection with the id of the track it belongs to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrackedBox {
+    pub track_id: u64,
+    /// Filtered box, with the class and confidence of the matched detection
+    pub bbox: BoundingBox,
+    /// Number of frames the track was matched in
+    pub hits: u32,
+}
+
+/// Constant-velocity Kalman filter over one coordinate and its velocity
+#[derive(Debug, Clone, Copy)]
+struct Kalman1D {
+    value: f32,
+    velocity: f32,
+    covariance: [[f32; 2]; 2],
+    process_noise: [f32; 2],
+    measurement_noise: f32,
+}
+
+impl Kalman1D {
+    fn new(
+        value: f32,
+        variance: [f32; 2],
+        process_noise: [f32; 2],
+        measurement_noise: f32,
+    ) -> Self {
+        Self {
+            value,
+            velocity: 0.0,
+            covariance: [[variance[0], 0.0], [0.0, variance[1]]],
+            process_noise,
+            measurement_noise,
+        }
+    }
+
+    /// Advances the state by one frame
+    fn predict(&mut self) {
+        let [[p00, p01], [p10, p11]] = self.covariance;
+        self.value += self.velocity;
+        self.covariance = [
+            [p00 + p01 + p10 + p11 + self.process_noise[0], p01 + p11],
+            [p10 + p11, p11 + self.process_noise[1]],
+        ];
+    }
+
+    /// Corrects the state with a measured value
+    fn update(&mut self, measured: f32) {
+        let [[p00, p01], [p10, p11]] = self.covariance;
+        let innovation_variance = p00 + self.measurement_noise;
+        let gain = [p00 / innovation_variance, p10 / innovation_variance];
+        let innovation = measured - self.value;
+
+        self.value += gain[0] * innovation;
+        self.velocity += gain[1] * innovation;
+        self.covariance = [
+            [(1.0 - gain[0]) * p00, (1.0 - gain[0]) * p01],
+            [p10 - gain[1] * p00, p11 - gain[1] * p01],
+        ];
+    }
+}
+
+/// Track of one object, filtering its center, area and aspect ratio
+#[derive(Debug, Clone)]
+struct Track {
+    id: u64,
+    center_x: Kalman1D,
+    center_y: Kalman1D,
+    area: Kalman1D,
+    ratio: f32,
+    last: BoundingBox,
+    hits: u32,
+    hit_streak: u32,
+    misses: u32,
+}
+
+impl Track {
+    /// Starts a track on a detection, with the noise levels of the reference SORT implementation
+    fn new(id: u64, bbox: BoundingBox) -> Self {
+        let (width, height) = bbox.dimensions();
+        let (x, y) = bbox.center();
+        let position = |value| Kalman1D::new(value, [10.0, 10_000.0], [1.0, 0.01], 1.0);
+        Self {
+            id,
+            center_x: position(x),
+            center_y: position(y),
+            area: Kalman1D::new(width * height, [10.0, 10_000.0], [1.0, 0.0001], 10.0),
+            ratio: width / height.max(f32::EPSILON),
+            last: bbox,
+            hits: 1,
+            hit_streak: 1,
+            misses: 0,
+        }
+    }
+
+    /// Returns the filtered box
+    fn bbox(&self) -> BoundingBox {
+        let area = self.area.value.max(0.0);
+        let width = (area * self.ratio).sqrt();
+        let height = if width > 0.0 { area / width } else { 0.0 };
+        BoundingBox::from_center(
+            self.center_x.value,
+            self.center_y.value,
+            width,
+            height,
+            self.last.class_id,
+            self.last.confidence,
+        )
+    }
+
+    fn predict(&mut self) {
+        // A shrinking box must not predict a negative area
+        if self.area.value + self.area.velocity <= 0.0 {
+            self.area.velocity = 0.0;
+        }
+        self.center_x.predict();
+        self.center_y.predict();
+        self.area.predict();
+        if self.misses > 0 {
+            self.hit_streak = 0;
+        }
+        self.misses += 1;
+    }
+
+    fn update(&mut self, bbox: BoundingBox) {
+        let (width, height) = bbox.dimensions();
+        let (x, y) = bbox.center();
+        self.center_x.update(x);
+        self.center_y.update(y);
+        self.area.update(width * height);
+        self.ratio = width / height.max(f32::EPSILON);
+        self.last = bbox;
+        self.hits += 1;
+        self.hit_streak += 1;
+        self.misses = 0;
+    }
+}
+
+/// Multi-object tracker following detections across the frames of a video
+#[derive(Debug, Clone, Default)]
+pub struct Tracker {
+    config: TrackerConfig,
+    tracks: Vec<Track>,
+    next_id: u64,
+    frame_count: u32,
+}
+
+impl Tracker {
+    /// Creates a tracker with a custom configuration
+    #[must_use]
+    pub fn new(config: TrackerConfig) -> Self {
+        Self {
+            config,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the number of live tracks, reported or not
+    #[inline]
+    #[must_use]
+    pub fn track_count(&self) -> usize {
+        self.tracks.len()
+    }
+
+    /// Feeds the detections of the next frame, returning the confirmed tracks matched in it
+    pub fn update(&mut self, detections: &[BoundingBox]) -> Vec<TrackedBox> {
+        self.frame_count += 1;
+        for track in &mut self.tracks {
+            track.predict();
+        }
+
+        // Greedy matching of the same-class prediction and detection pairs, best IoU first
+        let predictions: Vec<BoundingBox> = self.tracks.iter().map(Track::bbox).collect();
+        let mut candidates: Vec<(f32, usize, usize)> = Vec::new();
+        for (t, prediction) in predictions.iter().enumerate() {
+            for (d, detection) in detections.iter().enumerate() {
+                if prediction.class_id != detection.class_id {
+                    continue;
+                }
+                let iou = prediction.iou(detection);
+                if iou >= self.config.iou_threshold {
+                    candidates.push((iou, t, d));
+                }
+            }
+        }
+        candidates.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+
+        let mut track_matched = vec![false; self.tracks.len()];
+        let mut detection_matched = vec![false; detections.len()];
+        for (_, t, d) in candidates {
+            if !track_matched[t] && !detection_matched[d] {
+                track_matched[t] = true;
+                detection_matched[d] = true;
+                self.tracks[t].update(detections[d]);
+            }
+        }
+
+        for (detection, _) in detections
+            .iter()
+            .zip(&detection_matched)
+            .filter(|(_, matched)| !**matched)
+        {
+            self.tracks.push(Track::new(self.next_id, *detection));
+            self.next_id += 1;
+        }
+
+        let max_age = self.config.max_age;
+        self.tracks.retain(|track| track.misses <= max_age);
+
+        // Young tracks are reported as soon as the video starts, as in SORT
+        let warming_up = self.frame_count <= self.config.min_hits;
+        self.tracks
+            .iter()
+            .filter(|track| {
+                track.misses == 0 && (track.hit_streak >= self.config.min_hits || warming_up)
+            })
+            .map(|track| TrackedBox {
+                track_id: track.id,
+                bbox: track.bbox(),
+                hits: track.hits,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn storage(x: f32, class_id: usize) -> BoundingBox {
+        BoundingBox::new(x, 100.0, x + 40.0, 140.0, class_id, 0.9)
+    }
+
+    #[test]
+    fn test_ids_follow_moving_boxes() {
+        let mut tracker = Tracker::default();
+        let mut ids = Vec::new();
+        for frame in 0..10 {
+            let shift = frame as f32 * 5.0;
+            let tracked = tracker.update(&[storage(shift, 0), storage(300.0 - shift, 1)]);
+            assert_eq!(tracked.len(), 2);
+            ids.push((tracked[0].track_id, tracked[1].track_id));
+
+            // The filtered box stays on the detection
+            assert!(tracked[0].bbox.iou(&storage(shift, 0)) > 0.8);
+        }
+        assert!(ids.iter().all(|&pair| pair == ids[0]));
+        assert_ne!(ids[0].0, ids[0].1);
+    }
+
+    #[test]
+    fn test_lost_track_is_replaced_and_new_tracks_wait_for_hits() {
+        let mut tracker = Tracker::default();
+        for _ in 0..4 {
+            tracker.update(&[storage(0.0, 0)]);
+        }
+        let first_id = tracker.update(&[storage(0.0, 0)])[0].track_id;
+
+        // One missed frame is tolerated, two drop the track
+        assert!(tracker.update(&[]).is_empty());
+        assert!(tracker.update(&[]).is_empty());
+        assert_eq!(tracker.track_count(), 0);
+
+        // Past the first frames, a new track is only reported after `min_hits` matches
+        assert!(tracker.update(&[storage(0.0, 0)]).is_empty());
+        assert!(tracker.update(&[storage(0.0, 0)]).is_empty());
+        let tracked = tracker.update(&[storage(0.0, 0)]);
+        assert_eq!(tracked.len(), 1);
+        assert_ne!(tracked[0].track_id, first_id);
+        assert_eq!(tracked[0].hits, 3);
+    }
+
+    #[test]
+    fn test_classes_are_never_matched_together() {
+        let mut tracker = Tracker::default();
+        let first = tracker.update(&[storage(0.0, 0)]);
+        let second = tracker.update(&[storage(0.0, 1)]);
+        assert_ne!(first[0].track_id, second[0].track_id);
+    }
+}
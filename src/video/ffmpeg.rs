@@ -0,0 +1,129 @@
+//! Video files decoded into frames by the `ffmpeg` and `ffprobe` executables
+
+use crate::source::{Frame, ImageSource, SourceError};
+use image::{DynamicImage, RgbImage};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdout, Command, Stdio};
+
+/// Source yielding the frames of a video file, decoded by an `ffmpeg` child process
+pub struct VideoFile {
+    path: PathBuf,
+    width: u32,
+    height: u32,
+    index: usize,
+    child: Child,
+    stdout: ChildStdout,
+}
+
+impl VideoFile {
+    /// Probes the video dimensions and starts decoding it to raw RGB frames
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, SourceError> {
+        let path = path.as_ref();
+        let probe = Command::new("ffprobe")
+            .args([
+                "-v",
+                "error",
+                "-select_streams",
+                "v:0",
+                "-show_entries",
+                "stream=width,height",
+                "-of",
+                "csv=p=0:s=x",
+            ])
+            .arg(path)
+            .output()?;
+        if !probe.status.success() {
+            return Err(io::Error::other(format!(
+                "ffprobe failed on {}: {}",
+                path.display(),
+                String::from_utf8_lossy(&probe.stderr).trim()
+            ))
+            .into());
+        }
+        let (width, height) = parse_dimensions(&String::from_utf8_lossy(&probe.stdout))
+            .ok_or_else(|| io::Error::other(format!("No video stream in {}", path.display())))?;
+
+        let mut child = Command::new("ffmpeg")
+            .args(["-v", "error", "-i"])
+            .arg(path)
+            .args(["-f", "rawvideo", "-pix_fmt", "rgb24", "-"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| io::Error::other("ffmpeg stdout is not piped"))?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            width,
+            height,
+            index: 0,
+            child,
+            stdout,
+        })
+    }
+
+    /// Returns the frame dimensions
+    #[inline]
+    #[must_use]
+    pub const fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+}
+
+impl ImageSource for VideoFile {
+    fn next(&mut self) -> Option<Result<Frame, SourceError>> {
+        let mut buffer = vec![0u8; self.width as usize * self.height as usize * 3];
+        match self.stdout.read_exact(&mut buffer) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return None,
+            Err(e) => return Some(Err(e.into())),
+        }
+
+        let image = RgbImage::from_raw(self.width, self.height, buffer)?;
+        let stem = self
+            .path
+            .file_stem()
+            .map_or_else(|| "video".into(), |stem| stem.to_string_lossy());
+        let frame = Frame::new(
+            format!("{stem}_{:06}", self.index),
+            DynamicImage::ImageRgb8(image),
+        );
+        self.index += 1;
+        Some(Ok(frame))
+    }
+}
+
+impl Drop for VideoFile {
+    fn drop(&mut self) {
+        // Stop decoding frames nobody will read
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Parses the `WIDTHxHEIGHT` line printed by `ffprobe`
+fn parse_dimensions(output: &str) -> Option<(u32, u32)> {
+    let (width, height) = output.lines().next()?.trim().split_once('x')?;
+    let dimensions = (
+        width.parse().ok()?,
+        height.trim_end_matches('x').parse().ok()?,
+    );
+    (dimensions.0 > 0 && dimensions.1 > 0).then_some(dimensions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_dimensions() {
+        assert_eq!(parse_dimensions("1920x1080\n"), Some((1920, 1080)));
+        assert_eq!(parse_dimensions("1280x720x\n"), Some((1280, 720)));
+        assert_eq!(parse_dimensions(""), None);
+        assert_eq!(parse_dimensions("0x0"), None);
+    }
+}
@@ -0,0 +1,324 @@
+//! Frame-sequence processing for recorded attacks: per-frame detections, frame skipping and FPS
+
+#[cfg(feature = "ffmpeg")]
+pub mod ffmpeg;
+
+use crate::detection::BoundingBox;
+use crate::session::SessionError;
+use crate::session::yolo_session::YoloSession;
+use crate::source::{Frame, ImageSource, SourceError};
+use std::collections::VecDeque;
+use std::time::Instant;
+
+/// Number of processed frames the rolling FPS is averaged over by default
+pub const DEFAULT_FPS_WINDOW: usize = 30;
+
+/// Errors that can occur while processing a frame sequence
+#[derive(Debug, thiserror::Error)]
+pub enum VideoError {
+    #[error("Frame source error: {0}")]
+    Source(#[from] SourceError),
+    #[error("Detection failed: {0}")]
+    Session(#[from] SessionError),
+}
+
+/// Detector run on every processed frame
+pub trait FrameDetector {
+    /// Returns the detections of a frame in original image coordinates
+    fn detect(&mut self, frame: &Frame) -> Result<Vec<BoundingBox>, SessionError>;
+}
+
+impl FrameDetector for YoloSession {
+    fn detect(&mut self, frame: &Frame) -> Result<Vec<BoundingBox>, SessionError> {
+        self.process_frame(frame)
+    }
+}
+
+impl<F> FrameDetector for F
+where
+    F: FnMut(&Frame) -> Result<Vec<BoundingBox>, SessionError>,
+{
+    fn detect(&mut self, frame: &Frame) -> Result<Vec<BoundingBox>, SessionError> {
+        self(frame)
+    }
+}
+
+/// How the confidence of detections reused on skipped frames decays with their age
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ConfidenceDecay {
+    /// Reused detections keep their confidence
+    #[default]
+    None,
+    /// Confidence drops by `per_frame` for every frame of age
+    Linear { per_frame: f32 },
+    /// Confidence halves every `half_life` frames of age
+    Exponential { half_life: f32 },
+}
+
+impl ConfidenceDecay {
+    /// Returns the factor applied to the confidence of detections `age` frames old
+    #[must_use]
+    pub fn factor(&self, age: usize) -> f32 {
+        match *self {
+            Self::None => 1.0,
+            Self::Linear { per_frame } => (1.0 - per_frame * age as f32).clamp(0.0, 1.0),
+            Self::Exponential { half_life } if half_life > 0.0 => {
+                0.5f32.powf(age as f32 / half_life)
+            }
+            Self::Exponential { .. } => f32::from(u8::from(age == 0)),
+        }
+    }
+
+    /// Returns the boxes with their confidence decayed, dropping those decayed to zero
+    #[must_use]
+    pub fn apply(&self, boxes: &[BoundingBox], age: usize) -> Vec<BoundingBox> {
+        let factor = self.factor(age);
+        boxes
+            .iter()
+            .map(|bbox| BoundingBox {
+                confidence: bbox.confidence * factor,
+                ..*bbox
+            })
+            .filter(|bbox| bbox.confidence > 0.0)
+            .collect()
+    }
+}
+
+/// Detections of one processed or reused frame
+#[derive(Debug)]
+pub struct FrameDetections {
+    /// Position of the frame in the sequence, skipped frames included
+    pub index: usize,
+    pub name: String,
+    pub boxes: Result<Vec<BoundingBox>, VideoError>,
+    /// Frames since the boxes were detected, 0 for fresh detections
+    pub age: usize,
+    /// Rolling processing rate when the frame was done, in frames per second
+    pub fps: f32,
+}
+
+/// Runs a detector over a sequence of frames, optionally skipping frames between detections
+pub struct FrameProcessor<D> {
+    detector: D,
+    frame_skip: usize,
+    fps_window: usize,
+    finished_at: VecDeque<Instant>,
+    reuse: Option<ConfidenceDecay>,
+    last: Option<(usize, Vec<BoundingBox>)>,
+}
+
+impl<D: FrameDetector> FrameProcessor<D> {
+    /// Creates a processor running `detector` on every frame
+    pub fn new(detector: D) -> Self {
+        Self {
+            detector,
+            frame_skip: 0,
+            fps_window: DEFAULT_FPS_WINDOW,
+            finished_at: VecDeque::new(),
+            reuse: None,
+            last: None,
+        }
+    }
+
+    /// Skips `frame_skip` frames after every processed frame
+    #[must_use]
+    pub const fn with_frame_skip(mut self, frame_skip: usize) -> Self {
+        self.frame_skip = frame_skip;
+        self
+    }
+
+    /// Yields skipped frames too, with the boxes of the last processed frame decayed by age
+    #[must_use]
+    pub const fn with_reuse(mut self, decay: ConfidenceDecay) -> Self {
+        self.reuse = Some(decay);
+        self
+    }
+
+    /// Averages the FPS over the last `fps_window` processed frames
+    #[must_use]
+    pub fn with_fps_window(mut self, fps_window: usize) -> Self {
+        self.fps_window = fps_window.max(2);
+        self
+    }
+
+    /// Returns the detector, e.g. to flush the sinks of a session
+    pub fn detector_mut(&mut self) -> &mut D {
+        &mut self.detector
+    }
+
+    /// Returns the rolling processing rate over the last processed frames
+    #[must_use]
+    pub fn fps(&self) -> f32 {
+        match (self.finished_at.front(), self.finished_at.back()) {
+            (Some(first), Some(last)) if self.finished_at.len() > 1 => {
+                let elapsed = last.duration_since(*first).as_secs_f32();
+                if elapsed > 0.0 {
+                    (self.finished_at.len() - 1) as f32 / elapsed
+                } else {
+                    0.0
+                }
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// Lazily detects the frames of a sequence, yielding the processed frames in order.
+    ///
+    /// With `with_reuse`, skipped frames are yielded as well, with aged detections.
+    pub fn process<I>(&mut self, frames: I) -> impl Iterator<Item = FrameDetections>
+    where
+        I: IntoIterator<Item = Result<Frame, SourceError>>,
+    {
+        let step = self.frame_skip + 1;
+        frames
+            .into_iter()
+            .enumerate()
+            .filter_map(move |(index, frame)| {
+                if index % step == 0 {
+                    Some(self.process_one(index, frame))
+                } else {
+                    self.reuse_last(index, frame)
+                }
+            })
+    }
+
+    /// Lazily detects the frames of an image source, such as a decoded video file
+    pub fn process_source<S: ImageSource>(
+        &mut self,
+        mut source: S,
+    ) -> impl Iterator<Item = FrameDetections> {
+        self.process(std::iter::from_fn(move || source.next()))
+    }
+
+    /// Detects one frame and records when it was done
+    fn process_one(&mut self, index: usize, frame: Result<Frame, SourceError>) -> FrameDetections {
+        let (name, boxes) = match frame {
+            Ok(frame) => {
+                let boxes = self.detector.detect(&frame).map_err(VideoError::from);
+                (frame.name, boxes)
+            }
+            Err(e) => (format!("frame_{index}"), Err(e.into())),
+        };
+
+        self.finished_at.push_back(Instant::now());
+        while self.finished_at.len() > self.fps_window {
+            self.finished_at.pop_front();
+        }
+        if self.reuse.is_some() {
+            self.last = boxes.as_ref().ok().map(|boxes| (index, boxes.clone()));
+        }
+
+        FrameDetections {
+            index,
+            name,
+            boxes,
+            age: 0,
+            fps: self.fps(),
+        }
+    }
+
+    /// Returns the aged boxes of the last processed frame for a skipped frame, if reused
+    fn reuse_last(
+        &self,
+        index: usize,
+        frame: Result<Frame, SourceError>,
+    ) -> Option<FrameDetections> {
+        let decay = self.reuse?;
+        let (detected_at, boxes) = self.last.as_ref()?;
+        let age = index - detected_at;
+        Some(FrameDetections {
+            index,
+            name: frame.map_or_else(|_| format!("frame_{index}"), |frame| frame.name),
+            boxes: Ok(decay.apply(boxes, age)),
+            age,
+            fps: self.fps(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::DynamicImage;
+
+    fn frames(count: usize) -> Vec<Result<Frame, SourceError>> {
+        (0..count)
+            .map(|i| {
+                Ok(Frame::new(
+                    format!("frame_{i}"),
+                    DynamicImage::new_rgb8(4, 4),
+                ))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_frame_skip_and_results() {
+        let mut calls = 0;
+        let detector = |frame: &Frame| {
+            calls += 1;
+            if frame.name == "frame_4" {
+                return Err(SessionError::Inference("no output".to_string()));
+            }
+            Ok(vec![BoundingBox::new(0.0, 0.0, 1.0, 1.0, 0, 0.9)])
+        };
+        let mut processor = FrameProcessor::new(detector).with_frame_skip(1);
+
+        let mut frames = frames(6);
+        frames[2] = Err(SourceError::Io(std::io::Error::other("truncated")));
+        let results: Vec<FrameDetections> = processor.process(frames).collect();
+
+        let indices: Vec<usize> = results.iter().map(|result| result.index).collect();
+        assert_eq!(indices, vec![0, 2, 4]);
+        assert_eq!(results[0].boxes.as_ref().unwrap().len(), 1);
+        assert!(matches!(results[1].boxes, Err(VideoError::Source(_))));
+        assert!(matches!(results[2].boxes, Err(VideoError::Session(_))));
+        assert_eq!(results[2].name, "frame_4");
+        drop(processor);
+        assert_eq!(calls, 2);
+    }
+
+    #[test]
+    fn test_rolling_fps() {
+        let mut processor = FrameProcessor::new(|_: &Frame| {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+            Ok(Vec::new())
+        })
+        .with_fps_window(3);
+        assert_eq!(processor.fps(), 0.0);
+
+        let last = processor.process(frames(5)).last().unwrap();
+        assert_eq!(processor.finished_at.len(), 3);
+        assert!(last.fps > 0.0 && last.fps <= 200.0);
+    }
+
+    #[test]
+    fn test_reused_detections_decay_with_age() {
+        let detector = |frame: &Frame| {
+            if frame.name == "frame_3" {
+                return Err(SessionError::Inference("no output".to_string()));
+            }
+            Ok(vec![BoundingBox::new(0.0, 0.0, 1.0, 1.0, 0, 0.8)])
+        };
+        let mut processor = FrameProcessor::new(detector)
+            .with_frame_skip(2)
+            .with_reuse(ConfidenceDecay::Exponential { half_life: 1.0 });
+        let results: Vec<FrameDetections> = processor.process(frames(6)).collect();
+
+        // Frames 4 and 5 follow a failed detection, so there is nothing to reuse
+        let ages: Vec<(usize, usize)> = results.iter().map(|r| (r.index, r.age)).collect();
+        assert_eq!(ages, vec![(0, 0), (1, 1), (2, 2), (3, 0)]);
+        assert_eq!(results[1].name, "frame_1");
+        assert_eq!(results[1].boxes.as_ref().unwrap()[0].confidence, 0.4);
+        assert_eq!(results[2].boxes.as_ref().unwrap()[0].confidence, 0.2);
+
+        let linear = ConfidenceDecay::Linear { per_frame: 0.5 };
+        assert_eq!(linear.factor(1), 0.5);
+        assert!(
+            linear
+                .apply(&[BoundingBox::new(0.0, 0.0, 1.0, 1.0, 0, 0.9)], 2)
+                .is_empty()
+        );
+        assert_eq!(ConfidenceDecay::None.factor(100), 1.0);
+    }
+}